@@ -18,14 +18,62 @@ extern crate test;
 use test::Bencher;
 
 use fix_rs::byte_buffer::ByteBuffer;
+use fix_rs::dictionary::fields::{Account,ClOrdID,Currency,HandlInst,MaxFloor,MinQty,MsgSeqNum,OrdType,OrderQty,Price,SecurityID,SecurityIDSource,SenderCompID,SendingTime,SettlDate,SettlType,SideField,Symbol,TargetCompID,TimeInForce,TransactTime};
 use fix_rs::dictionary::messages::NewOrderSingle;
 use fix_rs::fix::Parser;
 use fix_rs::fix_version::FIXVersion;
 use fix_rs::message::Message;
 use fix_rs::message_version::MessageVersion;
+use fix_rs::sbe::{SBECodec,SBEType};
 
 const MESSAGE_BYTES: &'static [u8] = b"8=FIX.4.2\x019=206\x0135=D\x0149=AFUNDMGR\x0156=ABROKER\x0134=2\x0152=20170101-01:01:01.001\x0111=12345\x011=111111\x0163=0\x0164=20170101\x0121=3\x01110=1000\x01111=50000\x0155=IBM\x0148=459200101\x0122=1\x0154=1\x0160=20170101-01:01:01.001\x0138=5000\x0140=1\x0144=15.75\x0115=USD\x0159=0\x0110=092\x01";
 
+define_sbe_schema!(BenchSchema: 1, 0);
+
+//Same fields as MESSAGE_BYTES so SBE and tag=value benchmarks can be compared.
+define_sbe_message!(BenchSchema, NewOrderSingle: 14 => {
+    FIELDS {
+        msg_seq_num: MsgSeqNum => SBEType::UInt32,
+        sending_time: SendingTime => SBEType::UTCTimestampNanos,
+        cl_ord_id: ClOrdID => SBEType::CharArray(20),
+        settl_type: SettlType => SBEType::CharArray(3),
+        settl_date: SettlDate => SBEType::CharArray(8),
+        handl_inst: HandlInst => SBEType::Char,
+        min_qty: MinQty => SBEType::Decimal(-4),
+        max_floor: MaxFloor => SBEType::Decimal(-4),
+        symbol: Symbol => SBEType::CharArray(8),
+        security_id: SecurityID => SBEType::CharArray(12),
+        security_id_source: SecurityIDSource => SBEType::Char,
+        side: SideField => SBEType::Char,
+        transact_time: TransactTime => SBEType::UTCTimestampNanos,
+        order_qty: OrderQty => SBEType::Decimal(-4),
+        ord_type: OrdType => SBEType::Char,
+        price: Price => SBEType::Decimal(-4),
+        currency: Currency => SBEType::CharArray(3),
+        time_in_force: TimeInForce => SBEType::Char,
+    }
+    GROUPS {}
+    DATA {
+        sender_comp_id: SenderCompID,
+        target_comp_id: TargetCompID,
+        account: Account,
+    }
+});
+
+fn parse_simple_message() -> NewOrderSingle {
+    define_dictionary!(
+        NewOrderSingle,
+    );
+
+    let mut parser = Parser::new(build_dictionary(),4096);
+    let (bytes_read,result) = parser.parse(MESSAGE_BYTES);
+    assert!(result.is_ok());
+    assert!(bytes_read == MESSAGE_BYTES.len());
+    match message_to_enum(parser.messages.remove(0)) {
+        MessageEnum::NewOrderSingle(message) => *message,
+    }
+}
+
 #[bench]
 fn parse_simple_message_bench(b: &mut Bencher) {
     define_dictionary!(
@@ -63,3 +111,30 @@ fn serialize_simple_message_bench(b: &mut Bencher) {
         },
     }
 }
+
+#[bench]
+fn sbe_decode_simple_message_bench(b: &mut Bencher) {
+    let message = parse_simple_message();
+    let mut codec = SBECodec::new();
+    let mut data = Vec::with_capacity(512);
+    codec.encode::<BenchSchema,NewOrderSingle>(&message,&mut data).unwrap();
+
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let (decoded_message,bytes_read) = codec.decode::<BenchSchema,NewOrderSingle>(&data).unwrap();
+        assert!(bytes_read == data.len());
+        decoded_message
+    });
+}
+
+#[bench]
+fn sbe_encode_simple_message_bench(b: &mut Bencher) {
+    let message = parse_simple_message();
+    let mut codec = SBECodec::new();
+    let mut data = Vec::with_capacity(512);
+    b.bytes = codec.encode::<BenchSchema,NewOrderSingle>(&message,&mut data).unwrap() as u64;
+    b.iter(|| {
+        data.clear();
+        codec.encode::<BenchSchema,NewOrderSingle>(&message,&mut data).unwrap()
+    });
+}
@@ -60,7 +60,7 @@ fn main() {
                 logon_message.encrypt_method = EncryptMethod::None;
                 logon_message.heart_bt_int = 5;
                 logon_message.default_appl_ver_id = message_version;
-                client.send_message(connection_id,logon_message);
+                client.send_message(connection_id,logon_message).unwrap();
             },
             //Connection could not open TCP stream to server.
             EngineEvent::ConnectionFailed(connection_id,err) => {
@@ -97,8 +97,8 @@ fn main() {
                 };
             },
             //Connection received a message that could not be parsed correctly.
-            EngineEvent::MessageReceivedGarbled(connection_id,parse_error) => {
-                println!("({})Could not parse message: {}",connection_id,parse_error);
+            EngineEvent::MessageReceivedGarbled(connection_id,parse_error,error_context,_,_) => {
+                println!("({})Could not parse message: {} ({})",connection_id,parse_error,error_context);
             },
             //Connection received a message with a MsgSeqNum matching another message that was
             //already received.
@@ -107,8 +107,8 @@ fn main() {
                 };
             },
             //Connection received a message that could not be parsed correctly.
-            EngineEvent::MessageReceivedGarbled(connection_id,parse_error) => {
-                println!("({})Could not parse message: {}",connection_id,parse_error);
+            EngineEvent::MessageReceivedGarbled(connection_id,parse_error,error_context,_,_) => {
+                println!("({})Could not parse message: {} ({})",connection_id,parse_error,error_context);
             },
             //Connection received a message with a MsgSeqNum matching another message that was
             //already received.
@@ -0,0 +1,107 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//Command line front end for fix_rs::codegen. Reads one data dictionary per FIX version and writes
+//field_types.rs, fields.rs, and messages.rs to the output directory. When an SBE XML schema is
+//given, sbe.rs is written too.
+
+extern crate clap;
+extern crate fix_rs;
+
+use clap::{App,Arg};
+use std::fs::File;
+use std::io::{self,Read,Write};
+use std::path::Path;
+use std::process;
+
+use fix_rs::codegen::{Codegen,versions_for_data_dictionary};
+use fix_rs::data_dictionary::DataDictionary;
+
+fn write_file(directory: &Path,file_name: &str,contents: &str) -> io::Result<()> {
+    let mut file = try!(File::create(directory.join(file_name)));
+    file.write_all(contents.as_bytes())
+}
+
+fn main() {
+    let matches = App::new("fix-rs-codegen")
+                       .version(env!("CARGO_PKG_VERSION"))
+                       .author(env!("CARGO_PKG_AUTHORS"))
+                       .about("Generates fix-rs field and message definitions from QuickFIX XML data dictionaries or FIX Orchestra repositories")
+                       .arg(Arg::with_name("output")
+                                 .short("o")
+                                 .long("output")
+                                 .takes_value(true)
+                                 .required(true)
+                                 .help("Directory to write field_types.rs, fields.rs, and messages.rs into"))
+                       .arg(Arg::with_name("sbe")
+                                 .long("sbe")
+                                 .takes_value(true)
+                                 .help("SBE XML schema to generate sbe.rs from"))
+                       .arg(Arg::with_name("dictionaries")
+                                 .required(true)
+                                 .multiple(true)
+                                 .index(1)
+                                 .help("One data dictionary or Orchestra repository per FIX version, ie. FIX40.xml ... FIX50SP2.xml and FIXT11.xml"))
+                       .get_matches();
+
+    let mut data_dictionaries = Vec::new();
+    for path in matches.values_of("dictionaries").unwrap() {
+        let data_dictionary = match DataDictionary::from_file(path) {
+            Ok(data_dictionary) => data_dictionary,
+            Err(error) => {
+                writeln!(io::stderr(),"Could not load {}: {}",path,error).unwrap();
+                process::exit(1);
+            },
+        };
+        let versions = match versions_for_data_dictionary(&data_dictionary) {
+            Some(versions) => versions,
+            None => {
+                writeln!(io::stderr(),"Unsupported FIX version in {}: {}",path,String::from_utf8_lossy(data_dictionary.begin_string())).unwrap();
+                process::exit(1);
+            },
+        };
+        let source = Path::new(path).file_name().map_or(path.to_string(),|file_name| file_name.to_string_lossy().into_owned());
+        data_dictionaries.push((versions,source,data_dictionary));
+    }
+    data_dictionaries.sort_by_key(|&(versions,_,_)| versions.trailing_zeros());
+
+    let mut codegen = Codegen::new();
+    for (versions,source,data_dictionary) in data_dictionaries {
+        codegen.add_data_dictionary(source,&data_dictionary,versions);
+    }
+
+    let mut files = vec![("field_types.rs",codegen.generate_field_types()),
+                         ("fields.rs",codegen.generate_fields()),
+                         ("messages.rs",codegen.generate_messages())];
+    if let Some(path) = matches.value_of("sbe") {
+        let source = Path::new(path).file_name().map_or(path.to_string(),|file_name| file_name.to_string_lossy().into_owned());
+        let mut document = String::new();
+        if let Err(error) = File::open(path).and_then(|mut file| file.read_to_string(&mut document)) {
+            writeln!(io::stderr(),"Could not load {}: {}",path,error).unwrap();
+            process::exit(1);
+        }
+        match codegen.generate_sbe(&source[..],&document[..]) {
+            Ok(contents) => files.push(("sbe.rs",contents)),
+            Err(error) => {
+                writeln!(io::stderr(),"Could not generate SBE from {}: {}",path,error).unwrap();
+                process::exit(1);
+            },
+        }
+    }
+
+    let output_directory = Path::new(matches.value_of("output").unwrap());
+    for &(file_name,ref contents) in &files {
+        if let Err(error) = write_file(output_directory,file_name,&contents[..]) {
+            writeln!(io::stderr(),"Could not write {}: {}",file_name,error).unwrap();
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//Compares the messages in two files field by field. Each file holds one or more tag=value
+//messages delimited by SOH, '|', '^A', or any other single byte as found in most logs. The Nth
+//message of the left file is compared to the Nth message of the right file.
+//
+//Messages known to fix-rs are parsed into their message definitions. Everything else is parsed
+//into a GenericMessage. A QuickFIX XML data dictionary can be given to find repeating groups and
+//names for messages fix-rs doesn't know about.
+//
+//Exits with 0 when every message matches, 1 when there is a difference, and 2 on error.
+
+extern crate clap;
+#[macro_use]
+extern crate fix_rs;
+
+use clap::{App,Arg};
+use std::fs::File;
+use std::io::{self,Read,Write};
+use std::process;
+use std::sync::Arc;
+
+use fix_rs::data_dictionary::DataDictionary;
+use fix_rs::dictionary::messages::{AllocationInstruction,BusinessMessageReject,Email,Heartbeat,ListStatus,Logon,Logout,News,NewOrderList,NewOrderSingle,Reject,ResendRequest,SequenceReset,TestRequest};
+use fix_rs::fix::{FieldDelimiter,Parser};
+use fix_rs::fixt::message::FIXTMessage;
+use fix_rs::message_diff::write_message_diffs;
+
+const MAX_MESSAGE_SIZE: u64 = 1024 * 1024;
+
+fn read_messages(path: &str,data_dictionary: &Option<Arc<DataDictionary>>) -> Result<Vec<Box<FIXTMessage + Send>>,String> {
+    define_dictionary!(
+        AllocationInstruction,
+        BusinessMessageReject,
+        Email,
+        Heartbeat,
+        ListStatus,
+        Logon,
+        Logout,
+        News,
+        NewOrderList,
+        NewOrderSingle,
+        Reject,
+        ResendRequest,
+        SequenceReset,
+        TestRequest,
+    );
+
+    let mut bytes = Vec::new();
+    if let Err(error) = File::open(path).and_then(|mut file| file.read_to_end(&mut bytes)) {
+        return Err(format!("Could not read {}: {}",path,error));
+    }
+
+    let mut parser = Parser::new(build_dictionary(),MAX_MESSAGE_SIZE);
+    parser.set_field_delimiter(FieldDelimiter::AutoDetect);
+    parser.set_generic_message_fallback(true);
+    parser.set_data_dictionary(data_dictionary.clone());
+
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let (bytes_parsed,result) = parser.parse(&bytes[offset..]);
+        if let Err(error) = result {
+            return Err(format!("Could not parse message {} in {}: {}",parser.messages.len() + 1,path,error));
+        }
+        offset += bytes_parsed;
+    }
+
+    Ok(parser.messages)
+}
+
+fn main() {
+    let matches = App::new("fix-rs-diff")
+                       .version(env!("CARGO_PKG_VERSION"))
+                       .author(env!("CARGO_PKG_AUTHORS"))
+                       .about("Compares FIX messages field by field")
+                       .arg(Arg::with_name("dictionary")
+                                 .short("d")
+                                 .long("dictionary")
+                                 .takes_value(true)
+                                 .help("QuickFIX XML data dictionary used to parse messages fix-rs doesn't know about"))
+                       .arg(Arg::with_name("left")
+                                 .required(true)
+                                 .index(1)
+                                 .help("File holding the original messages"))
+                       .arg(Arg::with_name("right")
+                                 .required(true)
+                                 .index(2)
+                                 .help("File holding the messages to compare against"))
+                       .get_matches();
+
+    let data_dictionary = match matches.value_of("dictionary") {
+        Some(path) => match DataDictionary::from_file(path) {
+            Ok(data_dictionary) => Some(Arc::new(data_dictionary)),
+            Err(error) => {
+                writeln!(io::stderr(),"Could not load {}: {}",path,error).unwrap();
+                process::exit(2);
+            },
+        },
+        None => None,
+    };
+
+    let mut all_messages = Vec::new();
+    for path in &[matches.value_of("left").unwrap(),matches.value_of("right").unwrap()] {
+        match read_messages(path,&data_dictionary) {
+            Ok(messages) => all_messages.push(messages),
+            Err(error) => {
+                writeln!(io::stderr(),"{}",error).unwrap();
+                process::exit(2);
+            },
+        }
+    }
+    let right_messages = all_messages.pop().unwrap();
+    let left_messages = all_messages.pop().unwrap();
+
+    let data_dictionary = data_dictionary.as_ref().map(|data_dictionary| &**data_dictionary);
+    let is_different = match write_message_diffs(&left_messages[..],&right_messages[..],data_dictionary,&mut io::stdout()) {
+        Ok(is_different) => is_different,
+        Err(error) => {
+            writeln!(io::stderr(),"Could not write differences: {}",error).unwrap();
+            process::exit(2);
+        },
+    };
+
+    process::exit(if is_different { 1 } else { 0 });
+}
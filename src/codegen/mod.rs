@@ -0,0 +1,549 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//Generates field, enumerated field type, and message definitions from one data dictionary per FIX
+//version. Data dictionaries can be QuickFIX XML or FIX Orchestra repositories. The dictionaries are
+//merged so each field of each message gets the range of message versions it's defined in along
+//with which of those versions require it.
+//
+//Three files are generated and are meant to be used as sibling modules:
+//  field_types.rs - define_enum_field_type! for every field with enumerated values.
+//  fields.rs - define_fields! for every field and define_message! for every repeating group.
+//  messages.rs - define_fixt_message! for every message.
+//An SBE XML schema can also be turned into a fourth sibling module, see sbe.rs.
+//
+//Some number of groups tags hold differently laid out groups depending on the message, ie.
+//NoOrders (73) in NewOrderList and ListStatus. These are written to
+//define_context_dependent_group_fields! and each layout gets its own group type named after the
+//first message that uses it, ie. NewOrderListOrdersGrp.
+
+pub mod sbe;
+
+use std::collections::{BTreeMap,HashSet};
+use std::fmt::Write;
+
+use data_dictionary::{DataDictionary,FieldReference};
+use field_tag::FieldTag;
+use message_version::MessageVersion;
+
+pub type VersionSet = u8; //Bit N is set when defined in MessageVersion::all()[N].
+
+struct CodegenField {
+    name: String,
+    field_type: String,
+    values: BTreeMap<Vec<u8>,String>, //Value -> Description
+    data_tag: Option<FieldTag>, //Set when this is the length field of a data field.
+    length_tag: Option<FieldTag>, //Set when this is a data field with a length field.
+}
+
+#[derive(Clone)]
+struct CodegenFieldReference {
+    tag: FieldTag,
+    versions: VersionSet,
+    required_versions: VersionSet,
+}
+
+struct CodegenMessage {
+    name: String,
+    is_admin: bool,
+    fields: Vec<CodegenFieldReference>,
+}
+
+struct CodegenGroup {
+    name: String,
+    msg_types: Vec<Vec<u8>>, //Messages that use this layout.
+    fields: Vec<CodegenFieldReference>,
+}
+
+type GroupLayouts = BTreeMap<u64,Vec<CodegenGroup>>; //Number of groups tag -> Distinct layouts.
+
+pub struct Codegen {
+    sources: Vec<String>,
+    fields: BTreeMap<u64,CodegenField>,
+    groups: BTreeMap<u64,BTreeMap<Vec<u8>,Vec<CodegenFieldReference>>>, //Number of groups tag -> MsgType -> Fields of each group.
+    messages: BTreeMap<Vec<u8>,CodegenMessage>,
+    header_tags: HashSet<u64>,
+}
+
+impl Codegen {
+    pub fn new() -> Codegen {
+        Codegen {
+            sources: Vec::new(),
+            fields: BTreeMap::new(),
+            groups: BTreeMap::new(),
+            messages: BTreeMap::new(),
+            header_tags: [8,9,35,10].iter().cloned().collect(), //Built into the parser.
+        }
+    }
+
+    pub fn add_data_dictionary(&mut self,source: String,data_dictionary: &DataDictionary,versions: VersionSet) {
+        //Dictionaries are added from oldest to newest so names and descriptions end up matching
+        //the newest version that defines them.
+        self.sources.push(source);
+
+        for field in data_dictionary.fields() {
+            let codegen_field = self.fields.entry(field.tag.0).or_insert_with(|| {
+                CodegenField {
+                    name: String::new(),
+                    field_type: String::new(),
+                    values: BTreeMap::new(),
+                    data_tag: None,
+                    length_tag: None,
+                }
+            });
+            codegen_field.name = field.name.clone();
+            codegen_field.field_type = field.field_type.clone();
+            for value in &field.values {
+                codegen_field.values.insert(value.value.clone(),value.description.clone());
+            }
+        }
+
+        for field in data_dictionary.fields() {
+            if let Some(data_tag) = data_dictionary.data_tag(field.tag) {
+                self.fields.get_mut(&field.tag.0).unwrap().data_tag = Some(data_tag);
+                self.fields.get_mut(&data_tag.0).unwrap().length_tag = Some(field.tag);
+            }
+        }
+
+        //The standard header and trailer are provided by define_fixt_message!.
+        for field in data_dictionary.header().iter().chain(data_dictionary.trailer().iter()) {
+            self.header_tags.insert(field.tag.0);
+        }
+
+        for message in data_dictionary.messages() {
+            let mut codegen_message = self.messages.remove(&message.msg_type).unwrap_or_else(|| {
+                CodegenMessage {
+                    name: String::new(),
+                    is_admin: false,
+                    fields: Vec::new(),
+                }
+            });
+            codegen_message.name = message.name.clone();
+            codegen_message.is_admin = message.is_admin;
+            merge_field_references(&mut codegen_message.fields,&message.fields[..],versions,&message.msg_type[..],&mut self.groups);
+            self.messages.insert(message.msg_type.clone(),codegen_message);
+        }
+    }
+
+    fn field_name(&self,tag: FieldTag) -> &str {
+        &self.fields[&tag.0].name[..]
+    }
+
+    fn group_name(&self,tag: FieldTag) -> String {
+        group_name(self.field_name(tag))
+    }
+
+    fn group_layouts(&self) -> GroupLayouts {
+        //Messages that use the same fields for a group share a single group type. The versions of
+        //each field are merged so the shared type covers every message.
+        let mut group_layouts = BTreeMap::new();
+        for (tag,message_groups) in &self.groups {
+            let mut layouts: Vec<CodegenGroup> = Vec::new();
+            for (msg_type,fields) in message_groups {
+                let is_same_layout = |layout: &CodegenGroup| layout.fields.len() == fields.len() && layout.fields.iter().zip(fields.iter()).all(|(a,b)| a.tag == b.tag);
+                match layouts.iter().position(is_same_layout) {
+                    Some(index) => {
+                        let layout = &mut layouts[index];
+                        layout.msg_types.push(msg_type.clone());
+                        for (layout_field,field) in layout.fields.iter_mut().zip(fields.iter()) {
+                            layout_field.versions |= field.versions;
+                            layout_field.required_versions |= field.required_versions;
+                        }
+                    },
+                    None => layouts.push(CodegenGroup {
+                        name: String::new(),
+                        msg_types: vec![msg_type.clone()],
+                        fields: fields.clone(),
+                    }),
+                }
+            }
+
+            let group_name = self.group_name(FieldTag(*tag));
+            if layouts.len() == 1 {
+                layouts[0].name = group_name;
+            }
+            else {
+                for layout in &mut layouts {
+                    layout.name = format!("{}{}",self.messages[&layout.msg_types[0]].name,group_name);
+                }
+            }
+            group_layouts.insert(*tag,layouts);
+        }
+
+        group_layouts
+    }
+
+    fn has_enum(&self,field: &CodegenField) -> bool {
+        //Multiple value fields are space separated lists of values so they can't be represented by
+        //a single enum variant.
+        !field.values.is_empty() && match &field.field_type[..] {
+            "CHAR" | "INT" | "STRING" => true,
+            _ => false,
+        }
+    }
+
+    fn write_header(&self,output: &mut String) {
+        writeln!(output,"//Generated by fix-rs-codegen from {}. Do not edit by hand.",self.sources.join(", ")).unwrap();
+        writeln!(output,"").unwrap();
+    }
+
+    pub fn generate_field_types(&self) -> String {
+        let mut output = String::new();
+        self.write_header(&mut output);
+        output.push_str("#![allow(non_camel_case_types)]\n\n");
+        output.push_str("use std::io::Write;\n");
+        output.push_str("use std::str::FromStr;\n\n");
+        output.push_str("use field_type::FieldType;\n");
+        output.push_str("use fix_version::FIXVersion;\n");
+        output.push_str("use message::SetValueError;\n");
+        output.push_str("use message_version::MessageVersion;\n\n");
+        output.push_str("//Enumerated Fields (Sorted Alphabetically)\n");
+
+        let mut fields: Vec<&CodegenField> = self.fields.values().filter(|field| self.has_enum(field)).collect();
+        fields.sort_by(|a,b| a.name.cmp(&b.name));
+        for field in fields {
+            let must_be = match &field.field_type[..] {
+                "INT" if field.values.keys().all(|value| is_integer(value)) => "MUST_BE_INT",
+                "CHAR" if field.values.keys().all(|value| value.len() == 1) => "MUST_BE_CHAR",
+                _ => "MUST_BE_STRING",
+            };
+
+            writeln!(output,"").unwrap();
+            writeln!(output,"define_enum_field_type!(").unwrap();
+            writeln!(output,"    FIELD {} {{",field.name).unwrap();
+            let mut variant_names = HashSet::new();
+            for (value,description) in &field.values {
+                let mut name = variant_name(description,value);
+                if !variant_names.insert(name.clone()) {
+                    name = format!("{}{}",name,variant_name_suffix(value));
+                    variant_names.insert(name.clone());
+                }
+                writeln!(output,"        {} => b\"{}\",",name,escape_bytes(value)).unwrap();
+            }
+            writeln!(output,"    }},").unwrap();
+            writeln!(output,"    FIELD_TYPE [NOT_REQUIRED,{}] {}FieldType",must_be,field.name).unwrap();
+            writeln!(output,");").unwrap();
+        }
+
+        output
+    }
+
+    pub fn generate_fields(&self) -> String {
+        let group_layouts = self.group_layouts();
+        let mut output = String::new();
+        self.write_header(&mut output);
+
+        let enum_field_types: Vec<String> = self.fields.values().filter(|field| self.has_enum(field)).map(|field| format!("{}FieldType",field.name)).collect();
+        output.push_str("#![allow(unused_imports)]\n\n");
+        output.push_str("use dictionary::field_types::generic::{CharFieldType,CountryFieldType,CurrencyFieldType,DataFieldType,DayOfMonthFieldType,IntFieldType,LengthFieldType,LocalMktDateFieldType,MonthYearFieldType,NoneFieldType,RepeatingGroupFieldType,SeqNumFieldType,StringFieldType,UTCTimeOnlyFieldType,UTCTimestampFieldType};\n");
+        if !enum_field_types.is_empty() {
+            writeln!(output,"use super::field_types::{{{}}};",enum_field_types.join(",")).unwrap();
+        }
+        output.push_str("use field_tag;\n");
+        output.push_str("use fix_version::FIXVersion;\n");
+        output.push_str("use message::{self,REQUIRED,NOT_REQUIRED};\n");
+        output.push_str("use message_version::{self,MessageVersion};\n");
+        output.push_str("use rule::Rule;\n\n");
+
+        let mut context_dependent_tags = Vec::new();
+        output.push_str("define_fields!(\n");
+        for (tag,field) in &self.fields {
+            if is_context_dependent(&group_layouts,FieldTag(*tag)) {
+                context_dependent_tags.push(*tag);
+                continue;
+            }
+
+            let (field_type,rule,comment) = self.field_type(FieldTag(*tag),field,&group_layouts);
+            write!(output,"    {}: {} = {}",field.name,field_type,tag).unwrap();
+            if let Some(rule) = rule {
+                write!(output," => {}",rule).unwrap();
+            }
+            output.push(',');
+            if let Some(comment) = comment {
+                write!(output," //{}",comment).unwrap();
+            }
+            output.push('\n');
+        }
+        output.push_str(");\n\n");
+
+        if !context_dependent_tags.is_empty() {
+            output.push_str("//Repeating group fields where the group type depends on the message using them.\n");
+            output.push_str("define_context_dependent_group_fields!(\n");
+            for tag in context_dependent_tags {
+                let layout_names: Vec<&str> = group_layouts[&tag].iter().map(|layout| &layout.name[..]).collect();
+                writeln!(output,"    {} = {}, //{}.",self.field_name(FieldTag(tag)),tag,layout_names.join(", ")).unwrap();
+            }
+            output.push_str(");\n\n");
+        }
+
+        output.push_str("//Repeating Groups (Sorted Alphabetically)\n");
+        let mut groups: Vec<&CodegenGroup> = group_layouts.values().flat_map(|layouts| layouts.iter()).collect();
+        groups.sort_by(|a,b| a.name.cmp(&b.name));
+        for group in groups {
+            writeln!(output,"").unwrap();
+            writeln!(output,"define_message!({} {{",group.name).unwrap();
+            self.write_field_references(&mut output,&group.name[..],&group.msg_types[0][..],&group.fields,&group_layouts);
+            writeln!(output,"}});").unwrap();
+        }
+
+        output
+    }
+
+    pub fn generate_messages(&self) -> String {
+        let group_layouts = self.group_layouts();
+        let mut output = String::new();
+        self.write_header(&mut output);
+        output.push_str("#![allow(unused_imports)]\n\n");
+        output.push_str("use std::any::Any;\n\n");
+        output.push_str("use super::fields::*;\n");
+        output.push_str("use field::Field;\n");
+        output.push_str("use field_tag::{self,FieldTag};\n");
+        output.push_str("use field_type::FieldType;\n");
+        output.push_str("use fix_version::FIXVersion;\n");
+        output.push_str("use fixt;\n");
+        output.push_str("use fixt::message::FIXTMessage;\n");
+        output.push_str("use message::{self,REQUIRED,NOT_REQUIRED,Message,Meta,SetValueError};\n");
+        output.push_str("use message_version::{self,MessageVersion};\n");
+
+        //Session level messages first like the FIX specification.
+        let mut messages: Vec<(&Vec<u8>,&CodegenMessage)> = self.messages.iter().collect();
+        messages.sort_by(|a,b| (!a.1.is_admin,&a.1.name).cmp(&(!b.1.is_admin,&b.1.name)));
+        for (msg_type,message) in messages {
+            writeln!(output,"").unwrap();
+            writeln!(output,"define_fixt_message!({}: {}b\"{}\" => {{",message.name,if message.is_admin { "ADMIN " } else { "" },escape_bytes(msg_type)).unwrap();
+            let fields: Vec<&CodegenFieldReference> = message.fields.iter().filter(|field| !self.header_tags.contains(&field.tag.0)).collect();
+            self.write_field_references(&mut output,&message.name[..],&msg_type[..],fields,&group_layouts);
+            writeln!(output,"}});").unwrap();
+        }
+
+        output
+    }
+
+    fn write_field_references<'a,I: IntoIterator<Item=&'a CodegenFieldReference>>(&self,output: &mut String,message_name: &str,msg_type: &[u8],fields: I,group_layouts: &GroupLayouts) {
+        let all_versions = MessageVersion::all();
+        for field in fields {
+            let field_name = self.field_name(field.tag);
+
+            //Context dependent groups are bound to the layout used by this message.
+            let field_type = if is_context_dependent(group_layouts,field.tag) {
+                let layout = group_layouts[&field.tag.0].iter().find(|layout| layout.msg_types.iter().any(|layout_msg_type| &layout_msg_type[..] == msg_type)).unwrap();
+                format!("{}<{}>",field_name,layout.name)
+            }
+            else {
+                field_name.to_string()
+            };
+
+            write!(output,"    {}, {}: {} [{}]",
+                   if field.required_versions == field.versions { "REQUIRED" } else { "NOT_REQUIRED" },
+                   snake_case(field_name),
+                   field_type,
+                   versions_to_range(field.versions)).unwrap();
+
+            //Fields that are only required in some versions are required by version instead.
+            if field.required_versions != 0 && field.required_versions != field.versions {
+                let required_versions: Vec<String> = (0..all_versions.len()).filter(|index| field.required_versions & (1 << index) != 0).map(|index| format!("MessageVersion::{:?}",all_versions[index])).collect();
+                write!(output," => REQUIRED_WHEN |_: &{},version: MessageVersion| {{ match version {{ {} => true, _ => false }} }}",message_name,required_versions.join(" | ")).unwrap();
+            }
+            output.push(',');
+
+            //Version ranges can't have holes so point out the versions that don't actually define
+            //the field.
+            let missing_versions = range_mask(field.versions) & !field.versions;
+            if missing_versions != 0 {
+                let missing_versions: Vec<String> = (0..all_versions.len()).filter(|index| missing_versions & (1 << index) != 0).map(|index| format!("{:?}",all_versions[index])).collect();
+                write!(output," //TODO: Not defined in {}.",missing_versions.join(", ")).unwrap();
+            }
+            output.push('\n');
+        }
+    }
+
+    fn field_type(&self,tag: FieldTag,field: &CodegenField,group_layouts: &GroupLayouts) -> (String,Option<String>,Option<String>) {
+        if self.has_enum(field) {
+            return (format!("{}FieldType",field.name),None,None);
+        }
+
+        match &field.field_type[..] {
+            "NUMINGROUP" if group_layouts.contains_key(&tag.0) => (format!("RepeatingGroupFieldType<{}>",group_layouts[&tag.0][0].name),None,None),
+            "NUMINGROUP" => (String::from("IntFieldType"),None,Some(String::from("TODO: No group definition found."))),
+            "LENGTH" => match field.data_tag {
+                Some(data_tag) => (String::from("NoneFieldType"),Some(format!("Rule::PrepareForBytes{{ bytes_tag: {}::tag() }}",self.field_name(data_tag))),None),
+                None => (String::from("LengthFieldType"),None,None),
+            },
+            "DATA" | "XMLDATA" => match field.length_tag {
+                Some(length_tag) => (String::from("DataFieldType"),Some(format!("Rule::ConfirmPreviousTag{{ previous_tag: {}::tag() }}",self.field_name(length_tag))),None),
+                None => (String::from("StringFieldType"),None,Some(String::from("TODO: No length field found."))),
+            },
+            "CHAR" => (String::from("CharFieldType"),None,None),
+            "INT" | "TAGNUM" => (String::from("IntFieldType"),None,None),
+            "SEQNUM" => (String::from("SeqNumFieldType"),None,None),
+            "DAYOFMONTH" => (String::from("DayOfMonthFieldType"),None,None),
+            "COUNTRY" => (String::from("CountryFieldType"),None,None),
+            "CURRENCY" => (String::from("CurrencyFieldType"),None,None),
+            "LOCALMKTDATE" => (String::from("LocalMktDateFieldType"),None,None),
+            "MONTHYEAR" => (String::from("MonthYearFieldType"),None,None),
+            "UTCTIMEONLY" => (String::from("UTCTimeOnlyFieldType"),None,None),
+            "UTCTIMESTAMP" => (String::from("UTCTimestampFieldType"),None,None),
+            "STRING" => (String::from("StringFieldType"),None,None),
+            //TODO: Create implementations for the remaining types.
+            _ => (String::from("StringFieldType"),None,Some(camel_case(&field.field_type[..]))),
+        }
+    }
+}
+
+fn merge_field_references(existing_fields: &mut Vec<CodegenFieldReference>,fields: &[FieldReference],versions: VersionSet,msg_type: &[u8],groups: &mut BTreeMap<u64,BTreeMap<Vec<u8>,Vec<CodegenFieldReference>>>) {
+    //Fields that are new in this version are placed after the field that comes before them in
+    //this version so the order matches the specification.
+    let mut insert_index = 0;
+    for field in fields {
+        let index = match existing_fields.iter().position(|existing_field| existing_field.tag == field.tag) {
+            Some(index) => index,
+            None => {
+                existing_fields.insert(insert_index,CodegenFieldReference {
+                    tag: field.tag,
+                    versions: 0,
+                    required_versions: 0,
+                });
+                insert_index
+            },
+        };
+        existing_fields[index].versions |= versions;
+        if field.required {
+            existing_fields[index].required_versions |= versions;
+        }
+        insert_index = index + 1;
+
+        //Groups are kept per message until generation so groups that share a number of groups
+        //tag but have different fields depending on the message can be told apart.
+        if let Some(ref group) = field.group {
+            let mut group_fields = groups.entry(field.tag.0).or_insert_with(BTreeMap::new).remove(msg_type).unwrap_or_else(Vec::new);
+            merge_field_references(&mut group_fields,&group[..],versions,msg_type,groups);
+            groups.get_mut(&field.tag.0).unwrap().insert(msg_type.to_vec(),group_fields);
+        }
+    }
+}
+
+fn group_name(field_name: &str) -> String {
+    //NoPartyIDs -> PartyIDsGrp
+    let name = if field_name.starts_with("No") && field_name[2..].starts_with(|c: char| c.is_uppercase()) { &field_name[2..] } else { field_name };
+    format!("{}Grp",name)
+}
+
+fn is_context_dependent(group_layouts: &GroupLayouts,tag: FieldTag) -> bool {
+    group_layouts.get(&tag.0).map_or(false,|layouts| layouts.len() > 1)
+}
+
+pub fn versions_for_data_dictionary(data_dictionary: &DataDictionary) -> Option<VersionSet> {
+    //FIXT.1.1 session dictionaries apply to every FIX 5.0 and later application version.
+    let all_versions = MessageVersion::all();
+    let index_of = |message_version: MessageVersion| all_versions.iter().position(|version| *version == message_version).unwrap();
+    if data_dictionary.begin_string() == b"FIXT.1.1" {
+        return Some((1 << index_of(MessageVersion::FIX50)) | (1 << index_of(MessageVersion::FIX50SP1)) | (1 << index_of(MessageVersion::FIX50SP2)));
+    }
+
+    data_dictionary.message_version().map(|message_version| 1 << index_of(message_version))
+}
+
+pub fn range_mask(versions: VersionSet) -> VersionSet {
+    //Every version from the first to the last version in the set.
+    let first = versions.trailing_zeros();
+    let last = 7 - versions.leading_zeros();
+    (((1u16 << (last + 1)) - 1) as VersionSet) & !((1 << first) - 1)
+}
+
+pub fn versions_to_range(versions: VersionSet) -> String {
+    let all_versions = MessageVersion::all();
+    let first = versions.trailing_zeros() as usize;
+    let last = 7 - versions.leading_zeros() as usize;
+    if last == all_versions.len() - 1 {
+        format!("{:?}..",all_versions[first])
+    }
+    else if first == last {
+        format!("{:?}",all_versions[first])
+    }
+    else {
+        format!("{:?}..{:?}",all_versions[first],all_versions[last])
+    }
+}
+
+fn is_integer(value: &[u8]) -> bool {
+    !value.is_empty() && value.iter().all(|c| *c >= b'0' && *c <= b'9')
+}
+
+fn escape_bytes(value: &[u8]) -> String {
+    let mut result = String::new();
+    for c in value {
+        match *c {
+            b'"' => result.push_str("\\\""),
+            b'\\' => result.push_str("\\\\"),
+            c if c >= 0x20 && c < 0x7F => result.push(c as char),
+            c => write!(result,"\\x{:02X}",c).unwrap(),
+        }
+    }
+
+    result
+}
+
+pub fn camel_case(text: &str) -> String {
+    //PROPRIETARY_CUSTOM_CODE -> ProprietaryCustomCode
+    let mut result = String::new();
+    for word in text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()) {
+        let mut chars = word.chars();
+        result.extend(chars.next().unwrap().to_uppercase());
+        result.extend(chars.flat_map(|c| c.to_lowercase()));
+    }
+
+    result
+}
+
+fn variant_name_suffix(value: &[u8]) -> String {
+    camel_case(&String::from_utf8_lossy(value)[..])
+}
+
+fn variant_name(description: &str,value: &[u8]) -> String {
+    let name = camel_case(description);
+    if name.is_empty() {
+        format!("Value{}",variant_name_suffix(value))
+    }
+    else if name.starts_with(|c: char| c.is_numeric()) {
+        format!("Value{}",name)
+    }
+    else {
+        name
+    }
+}
+
+pub fn snake_case(name: &str) -> String {
+    //ClOrdID -> cl_ord_id, SecurityIDSource -> security_id_source, NoPartyIDs -> no_party_ids
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::new();
+    for (index,c) in chars.iter().enumerate() {
+        if c.is_uppercase() && index > 0 {
+            let previous = chars[index - 1];
+            let next = chars.get(index + 1).cloned();
+            let next_is_plural = next == Some('s') && chars.get(index + 2).map_or(true,|c| c.is_uppercase());
+            let starts_word = previous.is_lowercase() || previous.is_numeric() ||
+                              (previous.is_uppercase() && next.map_or(false,|c| c.is_lowercase()) && !next_is_plural);
+            if starts_word {
+                result.push('_');
+            }
+        }
+        result.extend(c.to_lowercase());
+    }
+
+    match &result[..] {
+        "as" | "box" | "break" | "const" | "continue" | "crate" | "else" | "enum" | "extern" | "false" | "fn" | "for" |
+        "if" | "impl" | "in" | "let" | "loop" | "match" | "mod" | "move" | "mut" | "pub" | "ref" | "return" | "static" |
+        "struct" | "super" | "trait" | "true" | "type" | "unsafe" | "use" | "where" | "while" => result.push('_'),
+        _ => {},
+    }
+
+    result
+}
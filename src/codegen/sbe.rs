@@ -0,0 +1,346 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//Generates define_sbe_schema!, define_sbe_message!, and define_sbe_block! from an SBE XML schema.
+//Field names in the schema are used as the Field type and, in snake case, as the message member
+//just like the messages generated from data dictionaries. Repeating groups use the same group
+//types as the generated messages, including context dependent groups.
+//
+//Only layouts that SBECodec can encode are accepted: little-endian byte order, uint16 message
+//headers, groupSizeEncoding and var-data lengths, char and integer primitives, char arrays,
+//enums, Decimal composites with a constant exponent, and UTCTimestamp composites with a constant
+//nanosecond unit. Anything else, such as sets or padding between fields, is an error instead of
+//being generated incorrectly.
+
+use std::collections::HashMap;
+use std::fmt::{self,Write};
+
+use sbe::SBEType;
+use super::{Codegen,camel_case,group_name,snake_case};
+use xml::{self,Element};
+
+pub enum SBESchemaError {
+    Xml(usize,&'static str), //Byte offset, Description
+    NotSchema, //Root element is not <messageSchema>.
+    MissingAttribute(String,&'static str), //Element name, Attribute name
+    InvalidAttribute(String,&'static str,String), //Element name, Attribute name, Attribute value
+    UnknownType(String),
+    UnsupportedType(String), //Type can't be represented by SBEType.
+    UnsupportedEncoding(String), //Schema or composite isn't encoded the way SBECodec expects.
+    UnsupportedOffset(String), //Message, group, or field that needs padding.
+    ConflictingGroup(String), //Group type is laid out differently in different messages.
+}
+
+impl fmt::Display for SBESchemaError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SBESchemaError::Xml(offset,description) => write!(f,"SBESchemaError::Xml({},{})",offset,description),
+            SBESchemaError::NotSchema => write!(f,"SBESchemaError::NotSchema"),
+            SBESchemaError::MissingAttribute(ref element,attribute) => write!(f,"SBESchemaError::MissingAttribute({},{})",element,attribute),
+            SBESchemaError::InvalidAttribute(ref element,attribute,ref value) => write!(f,"SBESchemaError::InvalidAttribute({},{},{})",element,attribute,value),
+            SBESchemaError::UnknownType(ref name) => write!(f,"SBESchemaError::UnknownType({})",name),
+            SBESchemaError::UnsupportedType(ref name) => write!(f,"SBESchemaError::UnsupportedType({})",name),
+            SBESchemaError::UnsupportedEncoding(ref name) => write!(f,"SBESchemaError::UnsupportedEncoding({})",name),
+            SBESchemaError::UnsupportedOffset(ref name) => write!(f,"SBESchemaError::UnsupportedOffset({})",name),
+            SBESchemaError::ConflictingGroup(ref name) => write!(f,"SBESchemaError::ConflictingGroup({})",name),
+        }
+    }
+}
+
+impl fmt::Debug for SBESchemaError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        <SBESchemaError as fmt::Display>::fmt(self,f)
+    }
+}
+
+struct Schema<'a> {
+    name: String,
+    types: HashMap<&'a str,&'a Element>, //Type name -> <type>, <composite>, <enum>, or <set>
+    sections: Vec<(String,String)>, //Block type name -> Generated macro
+}
+
+impl Codegen {
+    pub fn generate_sbe(&self,source: &str,document: &str) -> Result<String,SBESchemaError> {
+        let root = match xml::parse(document) {
+            Ok(root) => root,
+            Err(error) => return Err(SBESchemaError::Xml(error.offset,error.description)),
+        };
+        if local_name(&root.name[..]) != "messageSchema" {
+            return Err(SBESchemaError::NotSchema);
+        }
+
+        let mut schema = Schema {
+            name: format!("{}Schema",camel_case(root.attribute("package").unwrap_or(""))),
+            types: HashMap::new(),
+            sections: Vec::new(),
+        };
+        for types in root.children.iter().filter(|child| local_name(&child.name[..]) == "types") {
+            for schema_type in &types.children {
+                schema.types.insert(try!(required_attribute(schema_type,"name")),schema_type);
+            }
+        }
+
+        if root.attribute("byteOrder").unwrap_or("littleEndian") != "littleEndian" {
+            return Err(SBESchemaError::UnsupportedEncoding(schema.name));
+        }
+        try!(check_uint16_composite(&schema,root.attribute("headerType").unwrap_or("messageHeader"),&["blockLength","templateId","schemaId","version"]));
+
+        for message in root.children.iter().filter(|child| local_name(&child.name[..]) == "message") {
+            let name = try!(required_attribute(message,"name"));
+            let template_id = try!(parse_attribute::<u16>(message,"id"));
+
+            //Messages are matched to the data dictionaries by MsgType when the schema gives it
+            //and otherwise by name.
+            let msg_type = match message.attribute("semanticType") {
+                Some(semantic_type) => Some(semantic_type.as_bytes()),
+                None => self.messages.iter().find(|&(_,codegen_message)| codegen_message.name == name).map(|(msg_type,_)| &msg_type[..]),
+            };
+
+            let header = format!("define_sbe_message!({}, {}: {} => {{",schema.name,name,template_id);
+            try!(self.write_sbe_block(&mut schema,name,header,message,msg_type));
+        }
+
+        let mut output = String::new();
+        writeln!(output,"//Generated by fix-rs-codegen from {}. Do not edit by hand.",source).unwrap();
+        writeln!(output,"").unwrap();
+        output.push_str("#![allow(unused_imports)]\n\n");
+        output.push_str("use super::fields::*;\n");
+        output.push_str("use super::messages::*;\n");
+        output.push_str("use sbe::SBEType;\n\n");
+        writeln!(output,"define_sbe_schema!({}: {}, {});",schema.name,try!(parse_attribute::<u16>(&root,"id")),match root.attribute("version") {
+            Some(_) => try!(parse_attribute::<u16>(&root,"version")),
+            None => 0,
+        }).unwrap();
+        for &(_,ref section) in &schema.sections {
+            writeln!(output,"").unwrap();
+            output.push_str(section);
+        }
+
+        Ok(output)
+    }
+
+    fn write_sbe_block(&self,schema: &mut Schema,name: &str,header: String,element: &Element,msg_type: Option<&[u8]>) -> Result<(),SBESchemaError> {
+        //The block is reserved ahead of time so it comes before the groups it uses.
+        let index = schema.sections.len();
+        schema.sections.push((name.to_string(),String::new()));
+
+        let mut fields = Vec::new();
+        let mut groups = Vec::new();
+        let mut data = Vec::new();
+        let mut offset = 0;
+        for child in &element.children {
+            match local_name(&child.name[..]) {
+                "field" => {
+                    //Constant fields are not sent.
+                    let field_name = try!(required_attribute(child,"name"));
+                    if child.attribute("presence") == Some("constant") {
+                        continue;
+                    }
+
+                    let sbe_type = try!(resolve_type(schema,try!(required_attribute(child,"type"))));
+                    if child.attribute("offset").is_some() && try!(parse_attribute::<usize>(child,"offset")) != offset {
+                        return Err(SBESchemaError::UnsupportedOffset(field_name.to_string()));
+                    }
+                    offset += sbe_type.len();
+                    fields.push(format!("{}: {} => SBEType::{:?}",snake_case(field_name),field_name,sbe_type));
+                },
+                "group" => {
+                    let group_field_name = try!(required_attribute(child,"name"));
+                    try!(check_uint16_composite(schema,child.attribute("dimensionType").unwrap_or("groupSizeEncoding"),&["blockLength","numInGroup"]));
+
+                    let (field_type,block_name) = self.sbe_group_type(try!(parse_attribute::<u64>(child,"id")),group_field_name,msg_type);
+                    groups.push(format!("{}: {}",snake_case(group_field_name),field_type));
+                    let header = format!("define_sbe_block!({}, {} => {{",schema.name,block_name);
+                    try!(self.write_sbe_block(schema,&block_name[..],header,child,msg_type));
+                },
+                "data" => {
+                    let data_field_name = try!(required_attribute(child,"name"));
+                    try!(check_data_composite(schema,try!(required_attribute(child,"type"))));
+                    data.push(format!("{}: {}",snake_case(data_field_name),data_field_name));
+                },
+                _ => {},
+            }
+        }
+        if element.attribute("blockLength").is_some() && try!(parse_attribute::<usize>(element,"blockLength")) != offset {
+            return Err(SBESchemaError::UnsupportedOffset(name.to_string()));
+        }
+
+        let mut section = String::new();
+        writeln!(section,"{}",header).unwrap();
+        for &(label,ref lines) in &[("FIELDS",fields),("GROUPS",groups),("DATA",data)] {
+            if lines.is_empty() {
+                writeln!(section,"    {} {{}}",label).unwrap();
+                continue;
+            }
+
+            writeln!(section,"    {} {{",label).unwrap();
+            for line in lines {
+                writeln!(section,"        {},",line).unwrap();
+            }
+            writeln!(section,"    }}").unwrap();
+        }
+        writeln!(section,"}});").unwrap();
+
+        //Groups used by more than one message only need to be defined once but they must be laid
+        //out the same way every time.
+        let existing_section = schema.sections.iter().enumerate().find(|&(existing_index,&(ref existing_name,_))| existing_index != index && existing_name == name).map(|(_,&(_,ref existing_section))| *existing_section == section);
+        match existing_section {
+            Some(true) => {
+                schema.sections.remove(index);
+            },
+            Some(false) => return Err(SBESchemaError::ConflictingGroup(name.to_string())),
+            None => schema.sections[index].1 = section,
+        }
+
+        Ok(())
+    }
+
+    fn sbe_group_type(&self,tag: u64,field_name: &str,msg_type: Option<&[u8]>) -> (String,String) {
+        //Use the same group types as the generated messages. Returns the type used in GROUPS along
+        //with the type of the block.
+        let group_layouts = self.group_layouts();
+        if let Some(layouts) = group_layouts.get(&tag) {
+            if layouts.len() == 1 {
+                return (field_name.to_string(),layouts[0].name.clone());
+            }
+
+            let layout = msg_type.and_then(|msg_type| layouts.iter().find(|layout| layout.msg_types.iter().any(|layout_msg_type| &layout_msg_type[..] == msg_type)));
+            if let Some(layout) = layout {
+                return (format!("{}<{}>",field_name,layout.name),layout.name.clone());
+            }
+        }
+
+        (field_name.to_string(),group_name(field_name))
+    }
+}
+
+fn resolve_type(schema: &Schema,type_name: &str) -> Result<SBEType,SBESchemaError> {
+    if let Some(sbe_type) = primitive_type(type_name,1) {
+        return Ok(sbe_type);
+    }
+
+    let element = match schema.types.get(type_name) {
+        Some(element) => *element,
+        None => return Err(SBESchemaError::UnknownType(type_name.to_string())),
+    };
+    match local_name(&element.name[..]) {
+        "type" => {
+            let length = match element.attribute("length") {
+                Some(_) => try!(parse_attribute::<usize>(element,"length")),
+                None => 1,
+            };
+            match primitive_type(try!(required_attribute(element,"primitiveType")),length) {
+                Some(sbe_type) => Ok(sbe_type),
+                None => Err(SBESchemaError::UnsupportedType(type_name.to_string())),
+            }
+        },
+        //Enums are sent as their encoding type and the field type turns them back into variants.
+        "enum" => resolve_type(schema,try!(required_attribute(element,"encodingType"))),
+        "composite" => {
+            let members = composite_members(element);
+            let member = |member_name: &str| members.iter().find(|member| member.attribute("name") == Some(member_name)).cloned();
+            let is_constant = |member: &Element| member.attribute("presence") == Some("constant");
+            if members.len() != 2 {
+                return Err(SBESchemaError::UnsupportedType(type_name.to_string()));
+            }
+
+            if let (Some(mantissa),Some(exponent)) = (member("mantissa"),member("exponent")) {
+                if mantissa.attribute("primitiveType") == Some("int64") && is_constant(exponent) {
+                    if let Ok(exponent) = exponent.text.parse::<i8>() {
+                        return Ok(SBEType::Decimal(exponent));
+                    }
+                }
+            }
+            else if let (Some(time),Some(unit)) = (member("time"),member("unit")) {
+                let is_nanoseconds = unit.text == "9" || unit.attribute("valueRef").map_or(false,|value_ref| value_ref.ends_with(".nanosecond"));
+                if time.attribute("primitiveType") == Some("uint64") && is_constant(unit) && is_nanoseconds {
+                    return Ok(SBEType::UTCTimestampNanos);
+                }
+            }
+
+            Err(SBESchemaError::UnsupportedType(type_name.to_string()))
+        },
+        _ => Err(SBESchemaError::UnsupportedType(type_name.to_string())),
+    }
+}
+
+fn primitive_type(name: &str,length: usize) -> Option<SBEType> {
+    match (name,length) {
+        ("char",1) => Some(SBEType::Char),
+        ("char",length) if length > 1 => Some(SBEType::CharArray(length)),
+        ("int8",1) => Some(SBEType::Int8),
+        ("int16",1) => Some(SBEType::Int16),
+        ("int32",1) => Some(SBEType::Int32),
+        ("int64",1) => Some(SBEType::Int64),
+        ("uint8",1) => Some(SBEType::UInt8),
+        ("uint16",1) => Some(SBEType::UInt16),
+        ("uint32",1) => Some(SBEType::UInt32),
+        ("uint64",1) => Some(SBEType::UInt64),
+        _ => None,
+    }
+}
+
+fn check_uint16_composite(schema: &Schema,type_name: &str,member_names: &[&str]) -> Result<(),SBESchemaError> {
+    //Message headers and group dimensions are read by SBECodec as a fixed list of uint16s.
+    let element = match schema.types.get(type_name) {
+        Some(element) => *element,
+        None => return Err(SBESchemaError::UnknownType(type_name.to_string())),
+    };
+
+    let members = composite_members(element);
+    let is_supported = members.len() == member_names.len() && members.iter().zip(member_names.iter()).all(|(member,member_name)| {
+        member.attribute("name") == Some(*member_name) && member.attribute("primitiveType") == Some("uint16")
+    });
+    if !is_supported {
+        return Err(SBESchemaError::UnsupportedEncoding(type_name.to_string()));
+    }
+
+    Ok(())
+}
+
+fn check_data_composite(schema: &Schema,type_name: &str) -> Result<(),SBESchemaError> {
+    //Var-data is a uint16 length followed by the bytes.
+    let element = match schema.types.get(type_name) {
+        Some(element) => *element,
+        None => return Err(SBESchemaError::UnknownType(type_name.to_string())),
+    };
+
+    let members = composite_members(element);
+    if members.len() != 2 || members[0].attribute("name") != Some("length") || members[0].attribute("primitiveType") != Some("uint16") || members[1].attribute("name") != Some("varData") {
+        return Err(SBESchemaError::UnsupportedEncoding(type_name.to_string()));
+    }
+
+    Ok(())
+}
+
+fn composite_members(element: &Element) -> Vec<&Element> {
+    element.children.iter().filter(|child| local_name(&child.name[..]) == "type").collect()
+}
+
+fn parse_attribute<T: ::std::str::FromStr>(element: &Element,name: &'static str) -> Result<T,SBESchemaError> {
+    let value = try!(required_attribute(element,name));
+    value.parse::<T>().map_err(|_| SBESchemaError::InvalidAttribute(element.name.clone(),name,value.to_string()))
+}
+
+fn required_attribute<'a>(element: &'a Element,name: &'static str) -> Result<&'a str,SBESchemaError> {
+    match element.attribute(name) {
+        Some(value) => Ok(value),
+        None => Err(SBESchemaError::MissingAttribute(element.name.clone(),name)),
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    //Schema elements are usually namespaced, ie. <sbe:message>.
+    match name.find(':') {
+        Some(index) => &name[index + 1..],
+        None => name,
+    }
+}
@@ -0,0 +1,514 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod orchestra;
+
+use std::collections::{HashMap,HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{self,Read};
+use std::path::Path;
+
+use field_tag::FieldTag;
+use fix_version::FIXVersion;
+use fixt::message::FIXTMessage;
+use generic_message::{GenericField,GenericFieldValue,GenericMessage};
+use hash::BuildFieldHasher;
+use message::FieldHashSet;
+use message_version::MessageVersion;
+use message_view::MessageViewTables;
+use xml::{self,Element};
+
+//Tags that are built into the parser or always decoded by GenericMessage. They never show up in
+//GenericMessage::fields so they can't be checked here.
+const BUILT_IN_TAGS: &'static [FieldTag] = &[
+    FieldTag(8),   //BeginString
+    FieldTag(9),   //BodyLength
+    FieldTag(35),  //MsgType
+    FieldTag(49),  //SenderCompID
+    FieldTag(56),  //TargetCompID
+    FieldTag(34),  //MsgSeqNum
+    FieldTag(43),  //PossDupFlag
+    FieldTag(52),  //SendingTime
+    FieldTag(122), //OrigSendingTime
+    FieldTag(10),  //CheckSum
+];
+
+pub enum DataDictionaryError {
+    Io(io::Error),
+    Xml(usize,&'static str), //Byte offset, Description
+    NotDataDictionary, //Root element is not <fix> or an Orchestra <repository>.
+    MissingAttribute(String,&'static str), //Element name, Attribute name
+    InvalidAttribute(String,&'static str,String), //Element name, Attribute name, Attribute value
+    DuplicateField(String),
+    DuplicateMessage(String),
+    UnknownField(String), //Field is used but not defined in <fields>.
+    UnknownComponent(String), //Component is used but not defined in <components>.
+    RecursiveComponent(String),
+    EmptyGroup(String),
+}
+
+impl fmt::Display for DataDictionaryError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DataDictionaryError::Io(ref error) => write!(f,"DataDictionaryError::Io({})",error),
+            DataDictionaryError::Xml(offset,description) => write!(f,"DataDictionaryError::Xml({},{})",offset,description),
+            DataDictionaryError::NotDataDictionary => write!(f,"DataDictionaryError::NotDataDictionary"),
+            DataDictionaryError::MissingAttribute(ref element,attribute) => write!(f,"DataDictionaryError::MissingAttribute({},{})",element,attribute),
+            DataDictionaryError::InvalidAttribute(ref element,attribute,ref value) => write!(f,"DataDictionaryError::InvalidAttribute({},{},{})",element,attribute,value),
+            DataDictionaryError::DuplicateField(ref name) => write!(f,"DataDictionaryError::DuplicateField({})",name),
+            DataDictionaryError::DuplicateMessage(ref msg_type) => write!(f,"DataDictionaryError::DuplicateMessage({})",msg_type),
+            DataDictionaryError::UnknownField(ref name) => write!(f,"DataDictionaryError::UnknownField({})",name),
+            DataDictionaryError::UnknownComponent(ref name) => write!(f,"DataDictionaryError::UnknownComponent({})",name),
+            DataDictionaryError::RecursiveComponent(ref name) => write!(f,"DataDictionaryError::RecursiveComponent({})",name),
+            DataDictionaryError::EmptyGroup(ref name) => write!(f,"DataDictionaryError::EmptyGroup({})",name),
+        }
+    }
+}
+
+impl fmt::Debug for DataDictionaryError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        <DataDictionaryError as fmt::Display>::fmt(self,f)
+    }
+}
+
+//Reasons a message does not match its definition in the data dictionary. These mirror the
+//ParseError variants that the parser reports.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum DataDictionaryViolation {
+    MissingRequiredTag(FieldTag),
+    UnknownTag(FieldTag), //Tag isn't defined anywhere in the data dictionary.
+    UnexpectedTag(FieldTag), //Tag is defined but doesn't belong to the message or group.
+    WrongFormatTag(FieldTag),
+    OutOfRangeTag(FieldTag),
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct FieldValueDefinition {
+    pub value: Vec<u8>,
+    pub description: String,
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct FieldDefinition {
+    pub tag: FieldTag,
+    pub name: String,
+    pub field_type: String, //QuickFIX type name, ie. STRING, INT, NUMINGROUP, etc.
+    pub values: Vec<FieldValueDefinition>, //Allowed values. Empty means any value is allowed.
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct FieldReference {
+    pub tag: FieldTag,
+    pub required: bool,
+    pub group: Option<Vec<FieldReference>>, //Fields of each repeating group when this is a number of groups field. The first field starts each group.
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct MessageDefinition {
+    pub name: String,
+    pub msg_type: Vec<u8>,
+    pub is_admin: bool,
+    pub fields: Vec<FieldReference>, //Components are flattened into their fields.
+}
+
+pub struct DataDictionary {
+    begin_string: Vec<u8>,
+    service_pack: u32,
+    fields: HashMap<FieldTag,FieldDefinition,BuildFieldHasher>,
+    field_tags: HashMap<String,FieldTag>,
+    data_tags: HashMap<FieldTag,FieldTag,BuildFieldHasher>, //Length tag -> Data tag.
+    header: Vec<FieldReference>,
+    trailer: Vec<FieldReference>,
+    messages: HashMap<Vec<u8>,MessageDefinition>,
+}
+
+impl DataDictionary {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<DataDictionary,DataDictionaryError> {
+        let mut document = String::new();
+        let mut file = try!(File::open(path).map_err(DataDictionaryError::Io));
+        try!(file.read_to_string(&mut document).map_err(DataDictionaryError::Io));
+
+        DataDictionary::from_xml(&document[..])
+    }
+
+    pub fn from_xml(document: &str) -> Result<DataDictionary,DataDictionaryError> {
+        let root = match xml::parse(document) {
+            Ok(root) => root,
+            Err(error) => return Err(DataDictionaryError::Xml(error.offset,error.description)),
+        };
+        if orchestra::is_repository(&root) {
+            return orchestra::from_repository(&root);
+        }
+        else if root.name != "fix" {
+            return Err(DataDictionaryError::NotDataDictionary);
+        }
+
+        //Version is stored as <fix type="FIX" major="4" minor="4" servicepack="0">.
+        let mut begin_string = root.attribute("type").unwrap_or("FIX").as_bytes().to_vec();
+        begin_string.push(b'.');
+        begin_string.extend_from_slice(try!(required_attribute(&root,"major")).as_bytes());
+        begin_string.push(b'.');
+        begin_string.extend_from_slice(try!(required_attribute(&root,"minor")).as_bytes());
+        let service_pack = match root.attribute("servicepack") {
+            Some(service_pack) => try!(service_pack.parse::<u32>().map_err(|_| DataDictionaryError::InvalidAttribute(root.name.clone(),"servicepack",service_pack.to_string()))),
+            None => 0,
+        };
+
+        let mut data_dictionary = DataDictionary {
+            begin_string: begin_string,
+            service_pack: service_pack,
+            fields: HashMap::with_hasher(BuildFieldHasher),
+            field_tags: HashMap::new(),
+            data_tags: HashMap::with_hasher(BuildFieldHasher),
+            header: Vec::new(),
+            trailer: Vec::new(),
+            messages: HashMap::new(),
+        };
+
+        //Fields must be known before anything that refers to them can be resolved.
+        if let Some(fields) = root.child("fields") {
+            for field in fields.children.iter().filter(|child| child.name == "field") {
+                try!(data_dictionary.add_field(field));
+            }
+        }
+        data_dictionary.pair_data_fields();
+
+        let mut components = HashMap::new();
+        if let Some(component_elements) = root.child("components") {
+            for component in component_elements.children.iter().filter(|child| child.name == "component") {
+                components.insert(try!(required_attribute(component,"name")),component);
+            }
+        }
+
+        if let Some(header) = root.child("header") {
+            data_dictionary.header = try!(data_dictionary.resolve_fields(header,true,&components,&mut Vec::new()));
+        }
+        if let Some(trailer) = root.child("trailer") {
+            data_dictionary.trailer = try!(data_dictionary.resolve_fields(trailer,true,&components,&mut Vec::new()));
+        }
+        if let Some(messages) = root.child("messages") {
+            for message in messages.children.iter().filter(|child| child.name == "message") {
+                let message_definition = MessageDefinition {
+                    name: try!(required_attribute(message,"name")).to_string(),
+                    msg_type: try!(required_attribute(message,"msgtype")).as_bytes().to_vec(),
+                    is_admin: message.attribute("msgcat") == Some("admin"),
+                    fields: try!(data_dictionary.resolve_fields(message,true,&components,&mut Vec::new())),
+                };
+                if data_dictionary.messages.contains_key(&message_definition.msg_type) {
+                    return Err(DataDictionaryError::DuplicateMessage(String::from_utf8_lossy(&message_definition.msg_type[..]).into_owned()));
+                }
+                data_dictionary.messages.insert(message_definition.msg_type.clone(),message_definition);
+            }
+        }
+
+        Ok(data_dictionary)
+    }
+
+    fn add_field(&mut self,element: &Element) -> Result<(),DataDictionaryError> {
+        let name = try!(required_attribute(element,"name")).to_string();
+        let number = try!(required_attribute(element,"number"));
+        let tag = match number.parse::<u64>() {
+            Ok(tag) if tag > 0 => FieldTag(tag),
+            _ => return Err(DataDictionaryError::InvalidAttribute(element.name.clone(),"number",number.to_string())),
+        };
+
+        let mut values = Vec::new();
+        for value in element.children.iter().filter(|child| child.name == "value") {
+            values.push(FieldValueDefinition {
+                value: try!(required_attribute(value,"enum")).as_bytes().to_vec(),
+                description: value.attribute("description").unwrap_or("").to_string(),
+            });
+        }
+
+        if self.fields.contains_key(&tag) || self.field_tags.contains_key(&name) {
+            return Err(DataDictionaryError::DuplicateField(name));
+        }
+        self.field_tags.insert(name.clone(),tag);
+        self.fields.insert(tag,FieldDefinition {
+            tag: tag,
+            name: name,
+            field_type: try!(required_attribute(element,"type")).to_uppercase(),
+            values: values,
+        });
+
+        Ok(())
+    }
+
+    fn pair_data_fields(&mut self) {
+        //QuickFIX doesn't say which length field belongs to which data field. By convention, the
+        //length field is named after the data field, ie. RawData and RawDataLength or SecureData
+        //and SecureDataLen.
+        for field in self.fields.values() {
+            if field.field_type != "DATA" {
+                continue;
+            }
+
+            for suffix in &["Length","Len"] {
+                let length_name = format!("{}{}",field.name,suffix);
+                if let Some(length_tag) = self.field_tags.get(&length_name) {
+                    if self.fields[length_tag].field_type == "LENGTH" {
+                        self.data_tags.insert(*length_tag,field.tag);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    fn resolve_fields<'a>(&self,parent: &'a Element,is_required: bool,components: &HashMap<&'a str,&'a Element>,component_stack: &mut Vec<&'a str>) -> Result<Vec<FieldReference>,DataDictionaryError> {
+        //Fields are only required if every enclosing component is also required.
+        let mut fields = Vec::new();
+        for child in &parent.children {
+            let required = is_required && child.attribute("required") == Some("Y");
+            match &child.name[..] {
+                "field" => {
+                    fields.push(FieldReference {
+                        tag: try!(self.field_tag(try!(required_attribute(child,"name")))),
+                        required: required,
+                        group: None,
+                    });
+                },
+                "group" => {
+                    let name = try!(required_attribute(child,"name"));
+                    let group_fields = try!(self.resolve_fields(child,true,components,component_stack));
+                    if group_fields.is_empty() {
+                        return Err(DataDictionaryError::EmptyGroup(name.to_string()));
+                    }
+
+                    fields.push(FieldReference {
+                        tag: try!(self.field_tag(name)),
+                        required: required,
+                        group: Some(group_fields),
+                    });
+                },
+                "component" => {
+                    let name = try!(required_attribute(child,"name"));
+                    let component = match components.get(name) {
+                        Some(component) => *component,
+                        None => return Err(DataDictionaryError::UnknownComponent(name.to_string())),
+                    };
+                    if component_stack.contains(&name) {
+                        return Err(DataDictionaryError::RecursiveComponent(name.to_string()));
+                    }
+
+                    component_stack.push(name);
+                    fields.extend(try!(self.resolve_fields(component,required,components,component_stack)));
+                    component_stack.pop();
+                },
+                _ => {}, //Ignore anything QuickFIX might add in the future.
+            }
+        }
+
+        Ok(fields)
+    }
+
+    fn field_tag(&self,name: &str) -> Result<FieldTag,DataDictionaryError> {
+        match self.field_tags.get(name) {
+            Some(tag) => Ok(*tag),
+            None => Err(DataDictionaryError::UnknownField(name.to_string())),
+        }
+    }
+
+    pub fn begin_string(&self) -> &[u8] {
+        &self.begin_string[..]
+    }
+
+    pub fn fix_version(&self) -> Option<FIXVersion> {
+        FIXVersion::all().into_iter().find(|fix_version| fix_version.begin_string() == &self.begin_string[..])
+    }
+
+    pub fn message_version(&self) -> Option<MessageVersion> {
+        //FIX 5.0 and later application dictionaries don't have a matching FIXVersion because they
+        //are sent using FIXT.1.1.
+        match &self.begin_string[..] {
+            b"FIX.5.0" => match self.service_pack {
+                0 => Some(MessageVersion::FIX50),
+                1 => Some(MessageVersion::FIX50SP1),
+                _ => Some(MessageVersion::FIX50SP2),
+            },
+            b"FIXT.1.1" => None,
+            _ => self.fix_version().map(|fix_version| fix_version.max_message_version()),
+        }
+    }
+
+    pub fn field(&self,tag: FieldTag) -> Option<&FieldDefinition> {
+        self.fields.get(&tag)
+    }
+
+    pub fn field_by_name(&self,name: &str) -> Option<&FieldDefinition> {
+        self.field_tags.get(name).and_then(|tag| self.fields.get(tag))
+    }
+
+    pub fn fields(&self) -> Vec<&FieldDefinition> {
+        self.fields.values().collect()
+    }
+
+    pub fn data_tag(&self,length_tag: FieldTag) -> Option<FieldTag> {
+        //Data field that the length field describes.
+        self.data_tags.get(&length_tag).cloned()
+    }
+
+    pub fn header(&self) -> &[FieldReference] {
+        &self.header[..]
+    }
+
+    pub fn trailer(&self) -> &[FieldReference] {
+        &self.trailer[..]
+    }
+
+    pub fn message(&self,msg_type: &[u8]) -> Option<&MessageDefinition> {
+        self.messages.get(msg_type)
+    }
+
+    pub fn messages(&self) -> Vec<&MessageDefinition> {
+        self.messages.values().collect()
+    }
+
+    pub fn extend_message_view_tables(&self,tables: &mut MessageViewTables) {
+        //Teach the tables about every data field and repeating group in the data dictionary so
+        //messages defined here can be walked without a compile time definition.
+        for (length_tag,data_tag) in &self.data_tags {
+            tables.insert_data_tag(*length_tag,*data_tag);
+        }
+
+        fn add_groups(fields: &[FieldReference],tables: &mut MessageViewTables) -> FieldHashSet {
+            let mut tags = HashSet::with_hasher(BuildFieldHasher);
+            for field in fields {
+                tags.insert(field.tag);
+                if let Some(ref group) = field.group {
+                    let group_tags = add_groups(group,tables);
+                    tags.extend(group_tags.iter());
+                    tables.insert_group_tags(field.tag,group_tags);
+                }
+            }
+
+            tags
+        }
+
+        add_groups(&self.header[..],tables);
+        add_groups(&self.trailer[..],tables);
+        for message in self.messages.values() {
+            add_groups(&message.fields[..],tables);
+        }
+    }
+
+    pub fn validate(&self,message: &GenericMessage,ignore_unknown_fields: bool) -> Result<(),DataDictionaryViolation> {
+        //Messages that aren't in the data dictionary are not checked.
+        let message_definition = match self.messages.get(message.msg_type()) {
+            Some(message_definition) => message_definition,
+            None => return Ok(()),
+        };
+
+        //Header and trailer fields can appear in any message.
+        self.validate_fields(&message.fields[..],&[&self.header[..],&message_definition.fields[..],&self.trailer[..]],BUILT_IN_TAGS,ignore_unknown_fields)
+    }
+
+    fn validate_fields(&self,fields: &[GenericField],definitions: &[&[FieldReference]],skip_tags: &[FieldTag],ignore_unknown_fields: bool) -> Result<(),DataDictionaryViolation> {
+        for field_reference in definitions.iter().flat_map(|definition| definition.iter()) {
+            if field_reference.required && !skip_tags.contains(&field_reference.tag) && !fields.iter().any(|field| field.tag == field_reference.tag) {
+                return Err(DataDictionaryViolation::MissingRequiredTag(field_reference.tag));
+            }
+        }
+
+        for field in fields {
+            let field_reference = match definitions.iter().flat_map(|definition| definition.iter()).find(|field_reference| field_reference.tag == field.tag) {
+                Some(field_reference) => field_reference,
+                None if ignore_unknown_fields => continue,
+                None if self.fields.contains_key(&field.tag) => return Err(DataDictionaryViolation::UnexpectedTag(field.tag)),
+                None => return Err(DataDictionaryViolation::UnknownTag(field.tag)),
+            };
+
+            match (&field.value,&field_reference.group) {
+                (&GenericFieldValue::Value(ref value),_) => try!(self.validate_value(field.tag,value)),
+                (&GenericFieldValue::Groups(ref groups),&Some(ref group_definition)) => {
+                    for group in groups {
+                        try!(self.validate_fields(&group[..],&[&group_definition[..]],&[],ignore_unknown_fields));
+                    }
+                },
+                (&GenericFieldValue::Groups(_),&None) => return Err(DataDictionaryViolation::WrongFormatTag(field.tag)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_value(&self,tag: FieldTag,value: &[u8]) -> Result<(),DataDictionaryViolation> {
+        let field_definition = match self.fields.get(&tag) {
+            Some(field_definition) => field_definition,
+            None => return Ok(()),
+        };
+
+        if !is_valid_format(&field_definition.field_type[..],value) {
+            return Err(DataDictionaryViolation::WrongFormatTag(tag));
+        }
+
+        if !field_definition.values.is_empty() {
+            let is_allowed = |value: &[u8]| field_definition.values.iter().any(|allowed_value| &allowed_value.value[..] == value);
+            let is_in_range = match &field_definition.field_type[..] {
+                "MULTIPLEVALUESTRING" | "MULTIPLESTRINGVALUE" | "MULTIPLECHARVALUE" => value.split(|c| *c == b' ').all(is_allowed),
+                _ => is_allowed(value),
+            };
+            if !is_in_range {
+                return Err(DataDictionaryViolation::OutOfRangeTag(tag));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for DataDictionary {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"DataDictionary({},{} fields,{} messages)",String::from_utf8_lossy(&self.begin_string[..]),self.fields.len(),self.messages.len())
+    }
+}
+
+fn required_attribute<'a>(element: &'a Element,name: &'static str) -> Result<&'a str,DataDictionaryError> {
+    match element.attribute(name) {
+        Some(value) => Ok(value),
+        None => Err(DataDictionaryError::MissingAttribute(element.name.clone(),name)),
+    }
+}
+
+fn is_valid_format(field_type: &str,value: &[u8]) -> bool {
+    fn is_unsigned_integer(value: &[u8]) -> bool {
+        !value.is_empty() && value.iter().all(|c| *c >= b'0' && *c <= b'9')
+    }
+
+    fn is_integer(value: &[u8]) -> bool {
+        is_unsigned_integer(if value.first() == Some(&b'-') { &value[1..] } else { value })
+    }
+
+    fn is_decimal(value: &[u8]) -> bool {
+        let digits = if value.first() == Some(&b'-') { &value[1..] } else { value };
+        let mut found_digit = false;
+        let mut found_point = false;
+        for c in digits {
+            match *c {
+                b'0'...b'9' => found_digit = true,
+                b'.' if !found_point => found_point = true,
+                _ => return false,
+            }
+        }
+
+        found_digit
+    }
+
+    match field_type {
+        "INT" => is_integer(value),
+        "LENGTH" | "NUMINGROUP" | "SEQNUM" | "TAGNUM" | "DAYOFMONTH" => is_unsigned_integer(value),
+        "FLOAT" | "QTY" | "PRICE" | "PRICEOFFSET" | "AMT" | "PERCENTAGE" => is_decimal(value),
+        "CHAR" => value.len() == 1,
+        "BOOLEAN" => value == b"Y" || value == b"N",
+        _ => true,
+    }
+}
@@ -0,0 +1,280 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//Reads a FIX Orchestra repository into the same definitions as a QuickFIX data dictionary.
+//Orchestra refers to fields, components, and groups by id and keeps enumerated values in separate
+//code sets so these are resolved here. The StandardHeader and StandardTrailer components become
+//the header and trailer and are left out of each message just like in a QuickFIX data dictionary.
+//Only the base scenario is read.
+
+use std::collections::HashMap;
+
+use field_tag::FieldTag;
+use hash::BuildFieldHasher;
+use xml::Element;
+use super::{DataDictionary,DataDictionaryError,FieldDefinition,FieldReference,FieldValueDefinition,MessageDefinition,required_attribute};
+
+struct Structures<'a> {
+    components: HashMap<&'a str,&'a Element>, //Component id -> Component
+    groups: HashMap<&'a str,&'a Element>, //Group id -> Group
+    header_id: Option<&'a str>,
+    trailer_id: Option<&'a str>,
+}
+
+pub fn is_repository(root: &Element) -> bool {
+    local_name(&root.name[..]) == "repository"
+}
+
+pub fn from_repository(root: &Element) -> Result<DataDictionary,DataDictionaryError> {
+    let version = try!(required_attribute(root,"version"));
+    let (begin_string,service_pack) = match parse_version(version) {
+        Some(version) => version,
+        None => return Err(DataDictionaryError::InvalidAttribute(root.name.clone(),"version",version.to_string())),
+    };
+
+    let mut data_dictionary = DataDictionary {
+        begin_string: begin_string,
+        service_pack: service_pack,
+        fields: HashMap::with_hasher(BuildFieldHasher),
+        field_tags: HashMap::new(),
+        data_tags: HashMap::with_hasher(BuildFieldHasher),
+        header: Vec::new(),
+        trailer: Vec::new(),
+        messages: HashMap::new(),
+    };
+
+    let mut code_sets = HashMap::new();
+    if let Some(code_set_elements) = child(root,"codeSets") {
+        for code_set in children(code_set_elements,"codeSet") {
+            code_sets.insert(try!(required_attribute(code_set,"name")),code_set);
+        }
+    }
+
+    //Data fields can name their length field directly. Otherwise fall back to the same naming
+    //convention used for QuickFIX data dictionaries.
+    let mut length_tags = Vec::new();
+    if let Some(fields) = child(root,"fields") {
+        for field in children(fields,"field") {
+            let tag = try!(add_field(&mut data_dictionary,field,&code_sets));
+            if field.attribute("lengthId").is_some() {
+                length_tags.push((try!(parse_tag(field,"lengthId")),tag));
+            }
+        }
+    }
+    data_dictionary.pair_data_fields();
+    for (length_tag,data_tag) in length_tags {
+        data_dictionary.data_tags.insert(length_tag,data_tag);
+    }
+
+    let mut structures = Structures {
+        components: HashMap::new(),
+        groups: HashMap::new(),
+        header_id: None,
+        trailer_id: None,
+    };
+    if let Some(components) = child(root,"components") {
+        for component in children(components,"component") {
+            let id = try!(required_attribute(component,"id"));
+            match try!(required_attribute(component,"name")) {
+                "StandardHeader" => structures.header_id = Some(id),
+                "StandardTrailer" => structures.trailer_id = Some(id),
+                _ => {},
+            }
+            structures.components.insert(id,component);
+        }
+    }
+    if let Some(groups) = child(root,"groups") {
+        for group in children(groups,"group") {
+            structures.groups.insert(try!(required_attribute(group,"id")),group);
+        }
+    }
+
+    if let Some(header_id) = structures.header_id {
+        data_dictionary.header = try!(resolve_fields(&data_dictionary,structures.components[header_id],true,&structures,&mut Vec::new()));
+    }
+    if let Some(trailer_id) = structures.trailer_id {
+        data_dictionary.trailer = try!(resolve_fields(&data_dictionary,structures.components[trailer_id],true,&structures,&mut Vec::new()));
+    }
+    if let Some(messages) = child(root,"messages") {
+        for message in children(messages,"message") {
+            let structure = child(message,"structure").unwrap_or(message);
+            let message_definition = MessageDefinition {
+                name: try!(required_attribute(message,"name")).to_string(),
+                msg_type: try!(required_attribute(message,"msgType")).as_bytes().to_vec(),
+                is_admin: message.attribute("category") == Some("Session"),
+                fields: try!(resolve_fields(&data_dictionary,structure,true,&structures,&mut Vec::new())),
+            };
+            if data_dictionary.messages.contains_key(&message_definition.msg_type) {
+                return Err(DataDictionaryError::DuplicateMessage(String::from_utf8_lossy(&message_definition.msg_type[..]).into_owned()));
+            }
+            data_dictionary.messages.insert(message_definition.msg_type.clone(),message_definition);
+        }
+    }
+
+    Ok(data_dictionary)
+}
+
+fn parse_version(version: &str) -> Option<(Vec<u8>,u32)> {
+    //ie. FIX.4.4, FIX.5.0SP2, FIXT.1.1, or FIX.Latest which extends FIX.5.0SP2.
+    if version == "FIX.Latest" {
+        return Some((b"FIX.5.0".to_vec(),2));
+    }
+
+    let (begin_string,service_pack) = match version.find("SP") {
+        Some(index) => match version[index + 2..].parse::<u32>() {
+            Ok(service_pack) => (&version[..index],service_pack),
+            Err(_) => return None,
+        },
+        None => (version,0),
+    };
+    if !begin_string.starts_with("FIX") || !begin_string.contains('.') {
+        return None;
+    }
+
+    Some((begin_string.as_bytes().to_vec(),service_pack))
+}
+
+fn add_field(data_dictionary: &mut DataDictionary,element: &Element,code_sets: &HashMap<&str,&Element>) -> Result<FieldTag,DataDictionaryError> {
+    let name = try!(required_attribute(element,"name")).to_string();
+    let tag = try!(parse_tag(element,"id"));
+
+    //Fields with enumerated values use a code set as their type. The code set has the actual
+    //data type.
+    let type_name = try!(required_attribute(element,"type"));
+    let mut values = Vec::new();
+    let field_type = match code_sets.get(type_name) {
+        Some(code_set) => {
+            for code in children(code_set,"code") {
+                values.push(FieldValueDefinition {
+                    value: try!(required_attribute(code,"value")).as_bytes().to_vec(),
+                    description: code.attribute("name").unwrap_or("").to_string(),
+                });
+            }
+
+            try!(required_attribute(code_set,"type")).to_uppercase()
+        },
+        None => type_name.to_uppercase(),
+    };
+
+    if data_dictionary.fields.contains_key(&tag) || data_dictionary.field_tags.contains_key(&name) {
+        return Err(DataDictionaryError::DuplicateField(name));
+    }
+    data_dictionary.field_tags.insert(name.clone(),tag);
+    data_dictionary.fields.insert(tag,FieldDefinition {
+        tag: tag,
+        name: name,
+        field_type: field_type,
+        values: values,
+    });
+
+    Ok(tag)
+}
+
+fn resolve_fields<'a>(data_dictionary: &DataDictionary,parent: &'a Element,is_required: bool,structures: &Structures<'a>,stack: &mut Vec<&'a str>) -> Result<Vec<FieldReference>,DataDictionaryError> {
+    //Fields are only required if every enclosing component is also required.
+    let mut fields = Vec::new();
+    for child_element in parent.children.iter().filter(|child_element| is_base_scenario(child_element)) {
+        let required = is_required && child_element.attribute("presence") == Some("required");
+        match local_name(&child_element.name[..]) {
+            "fieldRef" => {
+                let tag = try!(parse_tag(child_element,"id"));
+                if !data_dictionary.fields.contains_key(&tag) {
+                    return Err(DataDictionaryError::UnknownField(tag.to_string()));
+                }
+
+                fields.push(FieldReference {
+                    tag: tag,
+                    required: required,
+                    group: None,
+                });
+            },
+            "groupRef" => {
+                let id = try!(required_attribute(child_element,"id"));
+                let group = match structures.groups.get(id) {
+                    Some(group) => *group,
+                    None => return Err(DataDictionaryError::UnknownComponent(id.to_string())),
+                };
+                let name = try!(required_attribute(group,"name"));
+                if stack.contains(&id) {
+                    return Err(DataDictionaryError::RecursiveComponent(name.to_string()));
+                }
+                let tag = match child(group,"numInGroup") {
+                    Some(num_in_group) => try!(parse_tag(num_in_group,"id")),
+                    None => return Err(DataDictionaryError::EmptyGroup(name.to_string())),
+                };
+                if !data_dictionary.fields.contains_key(&tag) {
+                    return Err(DataDictionaryError::UnknownField(tag.to_string()));
+                }
+
+                stack.push(id);
+                let group_fields = try!(resolve_fields(data_dictionary,group,true,structures,stack));
+                stack.pop();
+                if group_fields.is_empty() {
+                    return Err(DataDictionaryError::EmptyGroup(name.to_string()));
+                }
+
+                fields.push(FieldReference {
+                    tag: tag,
+                    required: required,
+                    group: Some(group_fields),
+                });
+            },
+            "componentRef" => {
+                let id = try!(required_attribute(child_element,"id"));
+                if Some(id) == structures.header_id || Some(id) == structures.trailer_id {
+                    continue;
+                }
+                let component = match structures.components.get(id) {
+                    Some(component) => *component,
+                    None => return Err(DataDictionaryError::UnknownComponent(id.to_string())),
+                };
+                if stack.contains(&id) {
+                    return Err(DataDictionaryError::RecursiveComponent(try!(required_attribute(component,"name")).to_string()));
+                }
+
+                stack.push(id);
+                fields.extend(try!(resolve_fields(data_dictionary,component,required,structures,stack)));
+                stack.pop();
+            },
+            _ => {}, //numInGroup, annotation, and anything else that doesn't describe a field.
+        }
+    }
+
+    Ok(fields)
+}
+
+fn parse_tag(element: &Element,attribute: &'static str) -> Result<FieldTag,DataDictionaryError> {
+    let id = try!(required_attribute(element,attribute));
+    match id.parse::<u64>() {
+        Ok(tag) if tag > 0 => Ok(FieldTag(tag)),
+        _ => Err(DataDictionaryError::InvalidAttribute(element.name.clone(),attribute,id.to_string())),
+    }
+}
+
+fn is_base_scenario(element: &Element) -> bool {
+    element.attribute("scenario").map_or(true,|scenario| scenario == "base")
+}
+
+fn local_name(name: &str) -> &str {
+    //Orchestra elements are namespaced, ie. <fixr:field>.
+    match name.find(':') {
+        Some(index) => &name[index + 1..],
+        None => name,
+    }
+}
+
+fn child<'a>(element: &'a Element,name: &str) -> Option<&'a Element> {
+    element.children.iter().find(|child_element| local_name(&child_element.name[..]) == name)
+}
+
+fn children<'a>(element: &'a Element,name: &str) -> Vec<&'a Element> {
+    element.children.iter().filter(|child_element| local_name(&child_element.name[..]) == name && is_base_scenario(child_element)).collect()
+}
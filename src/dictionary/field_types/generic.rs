@@ -14,14 +14,17 @@ use chrono::datetime::DateTime;
 use chrono::offset::utc::UTC;
 use chrono::naive::datetime::NaiveDateTime;
 use std::any::Any;
+use std::borrow::Cow;
 use std::marker::PhantomData;
-use std::io::Write;
+use std::io::{self,Write};
 use std::str::FromStr;
 
-use constant::VALUE_END;
+use constant::{TAG_END,VALUE_END};
+use encoder::write_integer;
+use field_tag::FieldTag;
 use field_type::FieldType;
 use fix_version::FIXVersion;
-use message::{Message,MessageBuildable,SetValueError};
+use message::{GroupIter,InvalidFieldValue,InvalidValuePolicy,Message,MessageBuildable,MessageField,MissingRequiredField,SetValueError,sanitize_bytes};
 use message_version::MessageVersion;
 use rule::Rule;
 
@@ -75,6 +78,11 @@ impl FieldType for BoolTrueOrBlankFieldType {
     fn read(field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,buf: &mut Vec<u8>) -> usize {
         buf.write(if *field { b"Y" } else { b"N" }).unwrap()
     }
+
+    fn write(field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,writer: &mut Write,_scratch: &mut Vec<u8>) -> io::Result<usize> {
+        try!(writer.write_all(if *field { b"Y" } else { b"N" }));
+        Ok(1)
+    }
 }
 
 pub struct CharFieldType;
@@ -104,9 +112,28 @@ impl FieldType for CharFieldType {
         1
     }
 
+    fn sanitize_value(field: &mut Self::Type,policy: InvalidValuePolicy) -> bool {
+        if !policy.is_invalid_value_byte(*field) {
+            return true;
+        }
+
+        //Stripping the only byte leaves the field empty.
+        let mut value = vec![*field];
+        if !sanitize_bytes(&mut value,policy) {
+            return false;
+        }
+        *field = value.first().cloned().unwrap_or(0);
+        true
+    }
+
     fn read(field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,buf: &mut Vec<u8>) -> usize {
         buf.write(&[*field]).unwrap()
     }
+
+    fn write(field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,writer: &mut Write,_scratch: &mut Vec<u8>) -> io::Result<usize> {
+        try!(writer.write_all(&[*field]));
+        Ok(1)
+    }
 }
 
 //Country names and codes are from https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2
@@ -579,6 +606,15 @@ impl FieldType for DataFieldType {
     fn read(field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,buf: &mut Vec<u8>) -> usize {
         buf.write(field).unwrap()
     }
+
+    fn write(field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,writer: &mut Write,_scratch: &mut Vec<u8>) -> io::Result<usize> {
+        try!(writer.write_all(&field[..]));
+        Ok(field.len())
+    }
+
+    fn read_value<'a>(field: &'a Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion) -> Cow<'a,[u8]> {
+        Cow::Borrowed(&field[..])
+    }
 }
 
 pub struct DayOfMonthFieldType;
@@ -617,6 +653,14 @@ impl FieldType for DayOfMonthFieldType {
 
         0
     }
+
+    fn write(field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,writer: &mut Write,_scratch: &mut Vec<u8>) -> io::Result<usize> {
+        if let Some(value) = *field {
+            return write_integer(value,writer);
+        }
+
+        Ok(0)
+    }
 }
 
 pub struct IntFieldType;
@@ -649,6 +693,10 @@ impl FieldType for IntFieldType {
         let value_string = field.to_string();
         buf.write(value_string.as_bytes()).unwrap()
     }
+
+    fn write(field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,writer: &mut Write,_scratch: &mut Vec<u8>) -> io::Result<usize> {
+        write_integer(*field,writer)
+    }
 }
 
 //LengthFieldType is used identically to SeqNumFieldType.
@@ -724,6 +772,10 @@ impl FieldType for NoneFieldType {
     fn read(_field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,_buf: &mut Vec<u8>) -> usize {
         0
     }
+
+    fn write(_field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,_writer: &mut Write,_scratch: &mut Vec<u8>) -> io::Result<usize> {
+        Ok(0)
+    }
 }
 
 #[derive(Clone,PartialEq)]
@@ -897,6 +949,10 @@ impl FieldType for SeqNumFieldType {
         let value_string = field.to_string();
         buf.write(value_string.as_bytes()).unwrap()
     }
+
+    fn write(field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,writer: &mut Write,_scratch: &mut Vec<u8>) -> io::Result<usize> {
+        write_integer(*field,writer)
+    }
 }
 
 pub struct StringFieldType;
@@ -922,9 +978,97 @@ impl FieldType for StringFieldType {
         field.len()
     }
 
+    fn sanitize_value(field: &mut Self::Type,policy: InvalidValuePolicy) -> bool {
+        sanitize_bytes(field,policy)
+    }
+
     fn read(field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,buf: &mut Vec<u8>) -> usize {
         buf.write(&field[..]).unwrap()
     }
+
+    fn write(field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,writer: &mut Write,_scratch: &mut Vec<u8>) -> io::Result<usize> {
+        try!(writer.write_all(&field[..]));
+        Ok(field.len())
+    }
+
+    fn read_value<'a>(field: &'a Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion) -> Cow<'a,[u8]> {
+        Cow::Borrowed(&field[..])
+    }
+}
+
+pub struct RepeatableStringFieldType;
+
+impl FieldType for RepeatableStringFieldType {
+    type Type = Vec<Vec<u8>>;
+
+    fn rule() -> Option<Rule> {
+        Some(Rule::RepeatableField)
+    }
+
+    fn default_value() -> Self::Type {
+        Default::default()
+    }
+
+    fn set_value(field: &mut Self::Type,bytes: &[u8]) -> Result<(),SetValueError> {
+        //Each occurrence of the tag is kept in the order it was received.
+        field.push(bytes.to_vec());
+        Ok(())
+    }
+
+    fn is_empty(field: &Self::Type) -> bool {
+        field.is_empty()
+    }
+
+    fn len(_field: &Self::Type) -> usize {
+        0
+    }
+
+    fn sanitize_value(field: &mut Self::Type,policy: InvalidValuePolicy) -> bool {
+        field.iter_mut().all(|value| sanitize_bytes(value,policy))
+    }
+
+    fn read(field: &Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion,buf: &mut Vec<u8>) -> usize {
+        //Only the first value can be written without knowing the tag. Use read_repeated() to
+        //write all of them.
+        if let Some(value) = field.first() {
+            buf.write(&value[..]).unwrap()
+        }
+        else {
+            0
+        }
+    }
+
+    fn read_repeated(field: &Self::Type,tag_bytes: &[u8],_fix_version: FIXVersion,_message_version: MessageVersion,buf: &mut Vec<u8>) -> usize {
+        let mut result = 0;
+        for value in field {
+            result += buf.write(tag_bytes).unwrap();
+            buf.push(TAG_END);
+            result += buf.write(&value[..]).unwrap();
+            buf.push(VALUE_END);
+            result += 2;
+        }
+
+        result
+    }
+
+    fn read_value<'a>(field: &'a Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion) -> Cow<'a,[u8]> {
+        //Same as read(), only the first value.
+        match field.first() {
+            Some(value) => Cow::Borrowed(&value[..]),
+            None => Cow::Borrowed(&[]),
+        }
+    }
+
+    fn read_message_fields<'a>(field: &'a Self::Type,tag: FieldTag,_fix_version: FIXVersion,_message_version: MessageVersion,fields: &mut Vec<MessageField<'a>>) {
+        //Every occurrence is written with its own tag.
+        for value in field {
+            fields.push(MessageField {
+                tag: tag,
+                value: Cow::Borrowed(&value[..]),
+                groups: GroupIter::empty(),
+            });
+        }
+    }
 }
 
 pub struct RepeatingGroupFieldType<T: Message + PartialEq> {
@@ -969,6 +1113,22 @@ impl<T: Message + MessageBuildable + Any + Clone + Default + PartialEq + Send +
         field.len()
     }
 
+    fn sanitize_groups(field: &mut Self::Type,policy: InvalidValuePolicy) -> Result<(),InvalidFieldValue> {
+        for group in field.iter_mut() {
+            try!(group.sanitize_values(policy));
+        }
+
+        Ok(())
+    }
+
+    fn validate_groups(field: &Self::Type,message_version: MessageVersion) -> Result<(),MissingRequiredField> {
+        for group in field {
+            try!(group.validate(message_version));
+        }
+
+        Ok(())
+    }
+
     fn read(field: &Self::Type,fix_version: FIXVersion,message_version: MessageVersion,buf: &mut Vec<u8>) -> usize {
         let group_count_str = field.len().to_string();
         let mut result = 1;
@@ -982,6 +1142,32 @@ impl<T: Message + MessageBuildable + Any + Clone + Default + PartialEq + Send +
 
         result
     }
+
+    fn write(field: &Self::Type,fix_version: FIXVersion,message_version: MessageVersion,writer: &mut Write,scratch: &mut Vec<u8>) -> io::Result<usize> {
+        let mut result = try!(write_integer(field.len(),writer));
+        try!(writer.write_all(&[VALUE_END]));
+        result += 1;
+
+        for group in field {
+            result += try!(group.write_body(fix_version,message_version,writer,scratch));
+        }
+
+        Ok(result)
+    }
+
+    fn read_value<'a>(field: &'a Self::Type,_fix_version: FIXVersion,_message_version: MessageVersion) -> Cow<'a,[u8]> {
+        //Only the NumInGroup count. The groups themselves are listed lazily through
+        //read_message_fields().
+        Cow::Owned(field.len().to_string().into_bytes())
+    }
+
+    fn read_message_fields<'a>(field: &'a Self::Type,tag: FieldTag,fix_version: FIXVersion,message_version: MessageVersion,fields: &mut Vec<MessageField<'a>>) {
+        fields.push(MessageField {
+            tag: tag,
+            value: Self::read_value(field,fix_version,message_version),
+            groups: GroupIter::new(field,fix_version,message_version),
+        });
+    }
 }
 
 pub struct UTCTimeOnlyFieldType;
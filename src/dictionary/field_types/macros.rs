@@ -68,6 +68,53 @@ macro_rules! define_enum_field_type_impl {
         }
     };
 
+    ( WRITE_FUNC_DEF $field_type_type:ident ) => {
+        fn write(field: &Self::Type,_fix_version: $crate::fix_version::FIXVersion,_message_version: $crate::message_version::MessageVersion,writer: &mut ::std::io::Write,_scratch: &mut Vec<u8>) -> ::std::io::Result<usize> {
+            let bytes = field.to_bytes();
+            try!(writer.write_all(bytes));
+            Ok(bytes.len())
+        }
+    };
+
+    ( WRITE_FUNC_DEF $field_type_type:ty ) => {
+        fn write(field: &Self::Type,_fix_version: $crate::fix_version::FIXVersion,_message_version: $crate::message_version::MessageVersion,writer: &mut ::std::io::Write,_scratch: &mut Vec<u8>) -> ::std::io::Result<usize> {
+            if let Some(ref field) = *field {
+                let bytes = field.to_bytes();
+                try!(writer.write_all(bytes));
+                return Ok(bytes.len())
+            }
+
+            Ok(0)
+        }
+    };
+
+    ( READ_SBE_FUNC_DEF $field_type_type:ident ) => {
+        fn read_sbe<'a>(field: &'a Self::Type,_scratch: &'a mut Vec<u8>) -> $crate::sbe::SBEValue<'a> {
+            $crate::sbe::SBEValue::Bytes(field.to_bytes())
+        }
+    };
+
+    ( READ_SBE_FUNC_DEF $field_type_type:ty ) => {
+        fn read_sbe<'a>(field: &'a Self::Type,_scratch: &'a mut Vec<u8>) -> $crate::sbe::SBEValue<'a> {
+            match *field {
+                Some(ref field) => $crate::sbe::SBEValue::Bytes(field.to_bytes()),
+                None => $crate::sbe::SBEValue::Null,
+            }
+        }
+    };
+
+    ( DESCRIPTION_FUNC_DEF $field_type_type:ident ) => {
+        fn value_description(field: &Self::Type) -> Option<String> {
+            field.description()
+        }
+    };
+
+    ( DESCRIPTION_FUNC_DEF $field_type_type:ty ) => {
+        fn value_description(field: &Self::Type) -> Option<String> {
+            field.as_ref().and_then(|field| field.description())
+        }
+    };
+
     ( 1=> $base_type:ident { $( $base_type_field:path => $base_type_value:expr ),* $(),* } ) => {
        impl $base_type {
             fn new(bytes: &[u8]) -> Option<$base_type> {
@@ -83,6 +130,10 @@ macro_rules! define_enum_field_type_impl {
                     $( $base_type_field => $base_type_value, )*
                 }
             }
+
+            fn description(&self) -> Option<String> {
+                Some(format!("{:?}",self))
+            }
         }
     };
 
@@ -115,6 +166,14 @@ macro_rules! define_enum_field_type_impl {
             }
 
             define_enum_field_type_impl!( READ_FUNC_DEF $( $field_type_type )* );
+
+            define_enum_field_type_impl!( WRITE_FUNC_DEF $( $field_type_type )* );
+
+            define_enum_field_type_impl!( DESCRIPTION_FUNC_DEF $( $field_type_type )* );
+        }
+
+        impl $crate::sbe::SBEFieldType for $field_type {
+            define_enum_field_type_impl!( READ_SBE_FUNC_DEF $( $field_type_type )* );
         }
     };
 
@@ -282,6 +341,14 @@ macro_rules! define_enum_field_type_with_reserved {
                     $base_type_reserved_field(value) => value,
                 }
             }
+
+            fn description(&self) -> Option<String> {
+                match *self {
+                    //Reserved values don't have a name.
+                    $base_type_reserved_field(_) => None,
+                    _ => Some(format!("{:?}",self)),
+                }
+            }
         }
 
         pub struct $field_type;
@@ -320,7 +387,11 @@ macro_rules! define_enum_field_type_with_reserved {
             }
 
             define_enum_field_type_with_reserved!( READ_FUNC_DEF $( $field_type_type )* );
+
+            define_enum_field_type_impl!( DESCRIPTION_FUNC_DEF $( $field_type_type )* );
         }
+
+        impl $crate::sbe::SBEFieldType for $field_type {}
     };
 
     ( AS_BYTES_REQUIRED $base_type:ident, $field_type:ident { $( $base_type_field:path => $base_type_value:expr ),* $(),* } ) => {
@@ -352,11 +423,21 @@ macro_rules! define_enum_field_type_with_reserved {
                 0 //Unused for this type
             }
 
+            fn sanitize_value(field: &mut Self::Type,policy: $crate::message::InvalidValuePolicy) -> bool {
+                field.sanitize(policy)
+            }
+
             fn read(field: &Self::Type,_fix_version: $crate::fix_version::FIXVersion,_message_version: $crate::message_version::MessageVersion,buf: &mut Vec<u8>) -> usize {
                 let value_bytes = field.as_bytes();
                 return buf.write(value_bytes).unwrap()
             }
+
+            fn value_description(field: &Self::Type) -> Option<String> {
+                field.description()
+            }
         }
+
+        impl $crate::sbe::SBEFieldType for $field_type {}
     };
 
     ( AS_BYTES_NOT_REQUIRED $base_type:ident, $field_type:ident { $( $base_type_field:path => $base_type_value:expr ),* $(),* } ) => {
@@ -384,6 +465,10 @@ macro_rules! define_enum_field_type_with_reserved {
                 0 //Unused for this type.
             }
 
+            fn sanitize_value(field: &mut Self::Type,policy: $crate::message::InvalidValuePolicy) -> bool {
+                field.as_mut().map_or(true,|field| field.sanitize(policy))
+            }
+
             fn read(field: &Self::Type,_fix_version: $crate::fix_version::FIXVersion,_message_version: $crate::message_version::MessageVersion,buf: &mut Vec<u8>) -> usize {
                 if let Some(ref field) = *field {
                     let value_bytes = field.as_bytes();
@@ -392,7 +477,13 @@ macro_rules! define_enum_field_type_with_reserved {
 
                 0
             }
+
+            fn value_description(field: &Self::Type) -> Option<String> {
+                field.as_ref().and_then(|field| field.description())
+            }
         }
+
+        impl $crate::sbe::SBEFieldType for $field_type {}
     };
 
     ( REQUIRED, $base_type:ident, $field_type:ident { $( $base_type_field:path => $base_type_value:expr ),* $(),* } $base_type_reserved_field:path => WITH_MINIMUM $base_type_reserved_field_minimum:expr ) => {
@@ -419,6 +510,22 @@ macro_rules! define_enum_field_type_with_reserved {
                     $base_type_reserved_field(ref value) => &value[..],
                 }
             }
+
+            fn sanitize(&mut self,policy: $crate::message::InvalidValuePolicy) -> bool {
+                //Only reserved values hold bytes that weren't checked when parsed.
+                match *self {
+                    $base_type_reserved_field(ref mut value) => $crate::message::sanitize_bytes(value,policy),
+                    _ => true,
+                }
+            }
+
+            fn description(&self) -> Option<String> {
+                match *self {
+                    //Reserved values don't have a name.
+                    $base_type_reserved_field(_) => None,
+                    _ => Some(format!("{:?}",self)),
+                }
+            }
         }
 
         define_enum_field_type_with_reserved!( AS_BYTES_REQUIRED $base_type, $required_field_type { $( $base_type_field => $base_type_value,)* } );
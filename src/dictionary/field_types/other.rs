@@ -840,6 +840,23 @@ define_enum_field_type!(
     FIELD_TYPE [NOT_REQUIRED] SessionRejectReasonFieldType
 );
 
+define_enum_field_type!(
+    FIELD SessionStatus {
+        SessionActive => 0,
+        SessionPasswordChanged => 1,
+        SessionPasswordDueToExpire => 2,
+        NewSessionPasswordDoesNotComplyWithPolicy => 3,
+        SessionLogoutComplete => 4,
+        InvalidUsernameOrPassword => 5,
+        AccountLocked => 6,
+        LogonsAreNotAllowedAtThisTime => 7,
+        PasswordExpired => 8,
+        ReceivedMsgSeqNumTooLow => 9,
+        ReceivedNextExpectedMsgSeqNumTooHigh => 10,
+    } Reserved100Plus => WITH_MINIMUM 100,
+    FIELD_TYPE [NOT_REQUIRED] SessionStatusFieldType
+);
+
 define_enum_field_type!(
     FIELD SettlMethod {
         CashSettlementRequired => b"C",
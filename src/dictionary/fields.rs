@@ -9,9 +9,9 @@
 // at your option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use dictionary::field_types::generic::{BoolTrueOrBlankFieldType,CharFieldType,CountryFieldType,CurrencyFieldType,DataFieldType,DayOfMonthFieldType,IntFieldType,LengthFieldType,LocalMktDateFieldType,MonthYearFieldType,NoneFieldType,RepeatingGroupFieldType,SeqNumFieldType,StringFieldType,UTCTimeOnlyFieldType,UTCTimestampFieldType};
+use dictionary::field_types::generic::{BoolTrueOrBlankFieldType,CharFieldType,CountryFieldType,CurrencyFieldType,DataFieldType,DayOfMonthFieldType,IntFieldType,LengthFieldType,LocalMktDateFieldType,MonthYearFieldType,NoneFieldType,RepeatableStringFieldType,RepeatingGroupFieldType,SeqNumFieldType,StringFieldType,UTCTimeOnlyFieldType,UTCTimestampFieldType};
 use dictionary::field_types::other as other_field_types;
-use dictionary::field_types::other::{ApplVerIDFieldType,BusinessRejectReasonFieldType,ComplexEventConditionFieldType,ComplexEventPriceBoundaryMethodFieldType,ComplexEventPriceTimeTypeFieldType,ComplexEventTypeFieldType,ContractMultiplierUnitFieldType,CPProgramFieldType,DefaultApplVerIDFieldType,EmailTypeFieldType,EncryptMethodFieldType,EventTypeFieldType,ExerciseStyleFieldType,FlowScheduleTypeFieldType,HandlInstFieldType,InstrmtAssignmentMethodFieldType,IssuerFieldType,ListMethodFieldType,MsgDirectionFieldType,NotRequiredSecurityIDSourceFieldType,NotRequiredSecurityTypeFieldType as SecurityTypeFieldType,NotRequiredSideFieldType,NotRequiredSymbolSfxFieldType as SymbolSfxFieldType,NotRequiredTimeUnitFieldType as TimeUnitFieldType,OptPayoutTypeFieldType,OrdTypeFieldType,PartyIDSourceFieldType,PartyRoleFieldType,PartySubIDTypeFieldType,PriceQuoteMethodFieldType,ProductFieldType,PutOrCallFieldType,RateSourceFieldType,RateSourceTypeFieldType,RequiredSecurityIDSourceFieldType,RequiredSideFieldType,RequiredStipulationTypeFieldType as StipulationTypeFieldType,RestructuringTypeFieldType,RoutingTypeFieldType,SecurityStatusFieldType,SeniorityFieldType,SessionRejectReasonFieldType,SettlMethodFieldType,SettlTypeFieldType,StrikePriceBoundaryMethodFieldType,StrikePriceDeterminationMethodFieldType,TimeInForceFieldType,UnderlyingCashTypeFieldType,UnderlyingFXRateCalcFieldType,UnderlyingPriceDeterminationMethodFieldType,UnderlyingSettlementTypeFieldType,UnitOfMeasureFieldType,ValuationMethodFieldType};
+use dictionary::field_types::other::{ApplVerIDFieldType,BusinessRejectReasonFieldType,ComplexEventConditionFieldType,ComplexEventPriceBoundaryMethodFieldType,ComplexEventPriceTimeTypeFieldType,ComplexEventTypeFieldType,ContractMultiplierUnitFieldType,CPProgramFieldType,DefaultApplVerIDFieldType,EmailTypeFieldType,EncryptMethodFieldType,EventTypeFieldType,ExerciseStyleFieldType,FlowScheduleTypeFieldType,HandlInstFieldType,InstrmtAssignmentMethodFieldType,IssuerFieldType,ListMethodFieldType,MsgDirectionFieldType,NotRequiredSecurityIDSourceFieldType,NotRequiredSecurityTypeFieldType as SecurityTypeFieldType,NotRequiredSideFieldType,NotRequiredSymbolSfxFieldType as SymbolSfxFieldType,NotRequiredTimeUnitFieldType as TimeUnitFieldType,OptPayoutTypeFieldType,OrdTypeFieldType,PartyIDSourceFieldType,PartyRoleFieldType,PartySubIDTypeFieldType,PriceQuoteMethodFieldType,ProductFieldType,PutOrCallFieldType,RateSourceFieldType,RateSourceTypeFieldType,RequiredSecurityIDSourceFieldType,RequiredSideFieldType,RequiredStipulationTypeFieldType as StipulationTypeFieldType,RestructuringTypeFieldType,RoutingTypeFieldType,SecurityStatusFieldType,SeniorityFieldType,SessionRejectReasonFieldType,SessionStatusFieldType,SettlMethodFieldType,SettlTypeFieldType,StrikePriceBoundaryMethodFieldType,StrikePriceDeterminationMethodFieldType,TimeInForceFieldType,UnderlyingCashTypeFieldType,UnderlyingFXRateCalcFieldType,UnderlyingPriceDeterminationMethodFieldType,UnderlyingSettlementTypeFieldType,UnitOfMeasureFieldType,ValuationMethodFieldType};
 use field_tag;
 use fix_version::FIXVersion;
 use message::{self,REQUIRED,NOT_REQUIRED};
@@ -28,8 +28,10 @@ type ExchangeFieldType = StringFieldType; //See ISO 10383 for a complete list: h
 
 define_fields!(
     Account: StringFieldType = 1,
+    AvgPx: PriceFieldType = 6,
     BeginSeqNo: SeqNumFieldType = 7,
     ClOrdID: StringFieldType = 11,
+    CumQty: QtyFieldType = 14,
     Currency: CurrencyFieldType = 15,
     EndSeqNo: SeqNumFieldType = 16,
     HandlInst: HandlInstFieldType = 21,
@@ -39,29 +41,42 @@ define_fields!(
     NewSeqNo: SeqNumFieldType = 36,
     OrderID: StringFieldType = 37,
     OrderQty: StringFieldType = 38, //Qty
+    OrdStatus: CharFieldType = 39,
     OrdType: OrdTypeFieldType = 40,
     OrigTime: UTCTimestampFieldType = 42,
     PossDupFlag: BoolTrueOrBlankFieldType = 43,
     Price: StringFieldType = 44, //Price
     RefSeqNum: SeqNumFieldType = 45,
-    RelatedSym: StringFieldType = 46, //TODO: Old special field that can be repeated without a repeating group.
+    RelatedSym: RepeatableStringFieldType = 46,
     SecurityID: StringFieldType = 48,
     SenderCompID: StringFieldType = 49,
     SenderSubID: StringFieldType = 50,
     SendingTime: UTCTimestampFieldType = 52,
-    SideField: RequiredSideFieldType = 54,
+    Quantity: QtyFieldType = 53,
+    SideField("Side"): RequiredSideFieldType = 54,
     Symbol: StringFieldType = 55,
     TargetCompID: StringFieldType = 56,
     TargetSubID: StringFieldType = 57,
     Text: StringFieldType = 58,
     TimeInForce: TimeInForceFieldType = 59,
     TransactTime: UTCTimestampFieldType = 60,
+    Urgency: CharFieldType = 61,
     SettlType: SettlTypeFieldType = 63,
     SettlDate: LocalMktDateFieldType = 64,
     SymbolSfx: SymbolSfxFieldType = 65,
-    //NoOrders: RepeatingGroupFieldType<Order> = 73, //TODO: The repeating group type here depends on the message using NoOrders.
-    //NoAllocs: RepeatingGroupFieldType<Alloc> = 78, //TODO: The repeating group type here depends on the message using NoAllocs.
+    ListID: StringFieldType = 66,
+    ListSeqNo: IntFieldType = 67,
+    TotNoOrders: IntFieldType = 68,
+    ListExecInst: StringFieldType = 69,
+    AllocID: StringFieldType = 70,
+    AllocTransType: CharFieldType = 71,
+    RefAllocID: StringFieldType = 72,
+    TradeDate: LocalMktDateFieldType = 75,
     AllocAccount: StringFieldType = 79,
+    AllocQty: QtyFieldType = 80,
+    NoRpts: IntFieldType = 82,
+    RptSeq: IntFieldType = 83,
+    CxlQty: QtyFieldType = 84,
     Signature: DataFieldType = 89 => Rule::ConfirmPreviousTag{ previous_tag: SignatureLength::tag() },
     SecureDataLen: NoneFieldType = 90 => Rule::PrepareForBytes{ bytes_tag: SecureData::tag() },
     SecureData: DataFieldType = 91 => Rule::ConfirmPreviousTag{ previous_tag: SecureDataLen::tag() },
@@ -91,9 +106,13 @@ define_fields!(
     DeliverToLocationID: StringFieldType = 145,
     NoRelatedSym: RepeatingGroupFieldType<Instrument> = 146,
     Subject: StringFieldType = 147,
+    Headline: StringFieldType = 148,
+    URLLink: StringFieldType = 149,
+    LeavesQty: QtyFieldType = 151,
     CashOrderQty: StringFieldType = 152, //Qty
     EmailThreadID: StringFieldType = 164,
     SecurityType: SecurityTypeFieldType = 167,
+    SecondaryOrderID: StringFieldType = 198,
     MaturityMonthYear: MonthYearFieldType = 200,
     PutOrCall: PutOrCallFieldType = 201,
     StrikePrice: StringFieldType = 202, //Price
@@ -153,10 +172,13 @@ define_fields!(
     EncodedText: DataFieldType = 355 => Rule::ConfirmPreviousTag{ previous_tag: EncodedTextLen::tag() },
     EncodedSubjectLen: NoneFieldType = 356 => Rule::PrepareForBytes{ bytes_tag: EncodedSubject::tag() },
     EncodedSubject: DataFieldType = 357 => Rule::ConfirmPreviousTag{ previous_tag: EncodedSubjectLen::tag() },
+    EncodedHeadlineLen: NoneFieldType = 358 => Rule::PrepareForBytes{ bytes_tag: EncodedHeadline::tag() },
+    EncodedHeadline: DataFieldType = 359 => Rule::ConfirmPreviousTag{ previous_tag: EncodedHeadlineLen::tag() },
     EncodedUnderlyingIssuerLen: NoneFieldType = 362 => Rule::PrepareForBytes{ bytes_tag: EncodedUnderlyingIssuer::tag() },
     EncodedUnderlyingIssuer: DataFieldType = 363 => Rule::ConfirmPreviousTag{ previous_tag: EncodedUnderlyingIssuerLen::tag() },
     EncodedUnderlyingSecurityDescLen: NoneFieldType = 364 => Rule::PrepareForBytes{ bytes_tag: EncodedUnderlyingSecurityDesc::tag() },
     EncodedUnderlyingSecurityDesc: DataFieldType = 365 => Rule::ConfirmPreviousTag{ previous_tag: EncodedUnderlyingSecurityDescLen::tag() },
+    AllocPrice: PriceFieldType = 366,
     LastMsgSeqNumProcessed: SeqNumFieldType = 369,
     OnBehalfOfSendingTime: UTCTimestampFieldType = 370,
     RefTagID: StringFieldType = 371, //int
@@ -165,10 +187,18 @@ define_fields!(
     BusinessRejectRefID: StringFieldType = 379,
     BusinessRejectReason: BusinessRejectReasonFieldType = 380,
     MaxMessageSize: LengthFieldType = 383,
-    NoMsgTypeGrp: RepeatingGroupFieldType<MsgTypeGrp> = 384,
+    NoMsgTypeGrp("NoMsgTypes"): RepeatingGroupFieldType<MsgTypeGrp> = 384,
     MsgDirection: MsgDirectionFieldType = 385,
+    BidID: StringFieldType = 390,
+    ClientBidID: StringFieldType = 391,
+    BidType: IntFieldType = 394,
+    ProgRptReqs: IntFieldType = 414,
+    ProgPeriodInterval: IntFieldType = 415,
+    ListStatusType: IntFieldType = 429,
+    ListOrderStatus: IntFieldType = 431,
     UnderlyingCouponRate: PercentageFieldType = 435,
     UnderlyingContractMultiplier: StringFieldType = 436, //Float
+    ListStatusText: StringFieldType = 444,
     NoSecurityAltID: RepeatingGroupFieldType<SecAltIDGrp> = 454,
     SecurityAltID: StringFieldType = 455,
     SecurityAltIDSource: RequiredSecurityIDSourceFieldType = 456,
@@ -180,9 +210,11 @@ define_fields!(
     UnderlyingProduct: ProductFieldType = 462,
     UnderlyingCFICode: StringFieldType = 463,
     TestMessageIndicator: StringFieldType = 464, //Bool
+    IndividualAllocID: StringFieldType = 467,
     CountryOfIssue: CountryFieldType = 470,
     StateOrProvinceOfIssue: StringFieldType = 471,
     LocaleOfIssue: StringFieldType = 472, //Full code list is available for purchase here: http://www.iata.org/publications/store/Pages/airline-coding-directory.aspx
+    SecondaryClOrdID: StringFieldType = 526,
     MaturityDate: LocalMktDateFieldType = 541,
     UnderlyingMaturityDate: LocalMktDateFieldType = 542,
     InstrRegistry: StringFieldType = 543,
@@ -224,19 +256,24 @@ define_fields!(
     EncodedLegSecurityDesc: DataFieldType = 622 => Rule::ConfirmPreviousTag{ previous_tag: EncodedLegSecurityDescLen::tag() },
     LegRatioQty: StringFieldType = 623, //Float
     LegSide: NotRequiredSideFieldType = 624,
+    AllocType: IntFieldType = 626,
     NoHops: RepeatingGroupFieldType<HopGrp> = 627,
     HopCompID: StringFieldType = 628,
     HopSendingTime: UTCTimestampFieldType = 629,
     HopRefID: SeqNumFieldType = 630,
+    AllocAcctIDSource: IntFieldType = 661,
     ContractSettlMonth: MonthYearFieldType = 667,
     Pool: StringFieldType = 691,
     NoUnderlyings: RepeatingGroupFieldType<UnderlyingInstrument> = 711,
+    AllocSettlCurrency: CurrencyFieldType = 736,
     LegDatedDate: LocalMktDateFieldType = 739,
     LegPool: StringFieldType = 740,
     SecuritySubType: StringFieldType = 762,
     UnderlyingSecuritySubType: StringFieldType = 763,
     LegSecuritySubType: StringFieldType = 764,
     NextExpectedMsgSeqNum: SeqNumFieldType = 789,
+    OrderAvgPx: PriceFieldType = 799,
+    OrderBookingQty: QtyFieldType = 800,
     UnderlyingPx: PriceFieldType = 810,
     NoEvents: RepeatingGroupFieldType<EvntGrp> = 864,
     EventType: EventTypeFieldType = 865,
@@ -339,7 +376,7 @@ define_fields!(
     RefApplExtID: StringFieldType = 1406, //int
     DefaultApplExtID: StringFieldType = 1407, //int
     DefaultCstmApplVerID: StringFieldType = 1408,
-    SessionStatus: StringFieldType = 1409, //int
+    SessionStatus: SessionStatusFieldType = 1409,
     DefaultVerIndicator: BoolTrueOrBlankFieldType = 1410,
     UnderlyingExerciseStyle: ExerciseStyleFieldType = 1419,
     LegExerciseStyle: ExerciseStyleFieldType = 1420,
@@ -391,8 +428,23 @@ define_fields!(
     ComplexEventEndTime: UTCTimeOnlyFieldType = 1496, //TODO: Must always be greater than start time.
 );
 
+//Repeating group fields where the group type depends on the message using them.
+define_context_dependent_group_fields!(
+    NoOrders = 73, //ListOrdGrp, OrdAllocGrp, or OrdListStatGrp.
+    NoAllocs = 78, //AllocGrp or PreAllocGrp.
+);
+
 //Repeating Groups (Sorted Alphabetically)
 
+define_message!(AllocGrp {
+    REQUIRED, alloc_account: AllocAccount [FIX40..],
+    NOT_REQUIRED, alloc_acct_id_source: AllocAcctIDSource [FIX44..],
+    NOT_REQUIRED, alloc_price: AllocPrice [FIX43..],
+    NOT_REQUIRED, alloc_qty: AllocQty [FIX40..], //TODO: Required before FIX44 when it was known as AllocShares.
+    NOT_REQUIRED, individual_alloc_id: IndividualAllocID [FIX41..],
+    NOT_REQUIRED, alloc_settl_currency: AllocSettlCurrency [FIX44..],
+});
+
 define_message!(ComplexEvent {
     REQUIRED, complex_event_type: ComplexEventType [FIX50SP2..],
     NOT_REQUIRED, complex_opt_payout_amount: ComplexOptPayoutAmount [FIX50SP2..],
@@ -602,6 +654,29 @@ define_message!(LinesOfTextGrp {
     NOT_REQUIRED, encoded_text: EncodedText [FIX42..],
 });
 
+define_message!(ListOrdGrp {
+    REQUIRED, cl_ord_id: ClOrdID [FIX42..],
+    NOT_REQUIRED, secondary_cl_ord_id: SecondaryClOrdID [FIX43..],
+    REQUIRED, list_seq_no: ListSeqNo [FIX42..],
+    NOT_REQUIRED, account: Account [FIX42..],
+    NOT_REQUIRED, allocs: NoAllocs<PreAllocGrp> [FIX42..],
+    NOT_REQUIRED, settl_type: SettlType [FIX42..],
+    NOT_REQUIRED, settl_date: SettlDate [FIX42..],
+    NOT_REQUIRED, handl_inst: HandlInst [FIX42..],
+    NOT_REQUIRED, min_qty: MinQty [FIX42..],
+    NOT_REQUIRED, max_floor: MaxFloor [FIX42..],
+    REQUIRED, symbol: Symbol [FIX42..], //TODO: Part of the Instrument block.
+    NOT_REQUIRED, security_id: SecurityID [FIX42..], //TODO: Part of the Instrument block.
+    NOT_REQUIRED, security_id_source: SecurityIDSource [FIX42..], //TODO: Part of the Instrument block.
+    REQUIRED, side: SideField [FIX42..],
+    NOT_REQUIRED, transact_time: TransactTime [FIX42..],
+    NOT_REQUIRED, order_qty: OrderQty [FIX42..],
+    NOT_REQUIRED, ord_type: OrdType [FIX42..],
+    NOT_REQUIRED, price: Price [FIX42..],
+    NOT_REQUIRED, time_in_force: TimeInForce [FIX42..],
+    NOT_REQUIRED, text: Text [FIX42..],
+});
+
 define_message!(MsgTypeGrp {
     REQUIRED, ref_msg_type: RefMsgType [FIX42..],
     REQUIRED, msg_direction: MsgDirection [FIX42..],
@@ -611,6 +686,36 @@ define_message!(MsgTypeGrp {
     NOT_REQUIRED, default_ver_indicator: DefaultVerIndicator [FIX50SP1..],
 });
 
+define_message!(OrdAllocGrp {
+    REQUIRED, cl_ord_id: ClOrdID [FIX40..],
+    NOT_REQUIRED, order_id: OrderID [FIX41..],
+    NOT_REQUIRED, secondary_order_id: SecondaryOrderID [FIX42..],
+    NOT_REQUIRED, secondary_cl_ord_id: SecondaryClOrdID [FIX43..],
+    NOT_REQUIRED, list_id: ListID [FIX42..],
+    NOT_REQUIRED, order_qty: OrderQty [FIX44..],
+    NOT_REQUIRED, order_avg_px: OrderAvgPx [FIX44..],
+    NOT_REQUIRED, order_booking_qty: OrderBookingQty [FIX44..],
+});
+
+define_message!(OrdListStatGrp {
+    REQUIRED, cl_ord_id: ClOrdID [FIX40..],
+    NOT_REQUIRED, secondary_cl_ord_id: SecondaryClOrdID [FIX43..],
+    REQUIRED, cum_qty: CumQty [FIX40..],
+    REQUIRED, ord_status: OrdStatus [FIX42..],
+    REQUIRED, leaves_qty: LeavesQty [FIX42..],
+    REQUIRED, cxl_qty: CxlQty [FIX40..],
+    REQUIRED, avg_px: AvgPx [FIX40..],
+    NOT_REQUIRED, text: Text [FIX40..],
+});
+
+define_message!(PreAllocGrp {
+    REQUIRED, alloc_account: AllocAccount [FIX42..],
+    NOT_REQUIRED, alloc_acct_id_source: AllocAcctIDSource [FIX44..],
+    NOT_REQUIRED, alloc_settl_currency: AllocSettlCurrency [FIX44..],
+    NOT_REQUIRED, individual_alloc_id: IndividualAllocID [FIX42..],
+    NOT_REQUIRED, alloc_qty: AllocQty [FIX42..],
+});
+
 define_message!(RateSourceGrp {
     REQUIRED, rate_source: RateSource [FIX50SP2..],
     REQUIRED, rate_source_type: RateSourceType [FIX50SP2..],
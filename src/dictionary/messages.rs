@@ -202,6 +202,25 @@ define_fixt_message!(Email: b"C" => {
     NOT_REQUIRED, raw_data: RawData [FIX40..],
 });
 
+//TODO: Only the fields shared with Email are currently included. The news-specific components
+//(ie. NewsRefGrp and InstrmtGrp) still need to be added.
+define_fixt_message!(News: b"B" => {
+    NOT_REQUIRED, orig_time: OrigTime [FIX40..],
+    NOT_REQUIRED, urgency: Urgency [FIX40..],
+    REQUIRED, headline: Headline [FIX41..],
+    NOT_REQUIRED, encoded_headline_len: EncodedHeadlineLen [FIX42..],
+    NOT_REQUIRED, encoded_headline: EncodedHeadline [FIX42..],
+    NOT_REQUIRED, no_routing_ids: NoRoutingIDs [FIX42..],
+    NOT_REQUIRED, related_sym: RelatedSym [FIX40..FIX41],
+    NOT_REQUIRED, no_related_sym: NoRelatedSym [FIX41..],
+    NOT_REQUIRED, no_underlyings: NoUnderlyings [FIX44..],
+    NOT_REQUIRED, no_legs: NoLegs [FIX44..],
+    REQUIRED, no_lines_of_text: NoLinesOfText [FIX40..],
+    NOT_REQUIRED, url_link: URLLink [FIX42..],
+    NOT_REQUIRED, raw_data_length: RawDataLength [FIX40..],
+    NOT_REQUIRED, raw_data: RawData [FIX40..],
+});
+
 define_fixt_message!(BusinessMessageReject: b"j" => {
     NOT_REQUIRED, ref_seq_num: RefSeqNum [FIX42..],
     REQUIRED, ref_msg_type: RefMsgType [FIX42..],
@@ -215,6 +234,47 @@ define_fixt_message!(BusinessMessageReject: b"j" => {
     NOT_REQUIRED, encoded_text: EncodedText [FIX42..],
 });
 
+define_fixt_message!(AllocationInstruction: b"J" => { //TODO: Only the core fields are implemented.
+    REQUIRED, alloc_id: AllocID [FIX40..],
+    REQUIRED, alloc_trans_type: AllocTransType [FIX40..],
+    REQUIRED, alloc_type: AllocType [FIX44..],
+    NOT_REQUIRED, ref_alloc_id: RefAllocID [FIX40..],
+    NOT_REQUIRED, orders: NoOrders<OrdAllocGrp> [FIX40..],
+    REQUIRED, side: SideField [FIX40..],
+    REQUIRED, symbol: Symbol [FIX40..], //TODO: Part of the Instrument block.
+    REQUIRED, quantity: Quantity [FIX40..],
+    REQUIRED, avg_px: AvgPx [FIX40..],
+    NOT_REQUIRED, currency: Currency [FIX40..],
+    REQUIRED, trade_date: TradeDate [FIX40..],
+    NOT_REQUIRED, transact_time: TransactTime [FIX40..],
+    NOT_REQUIRED, allocs: NoAllocs<AllocGrp> [FIX40..],
+    NOT_REQUIRED, text: Text [FIX40..],
+});
+
+define_fixt_message!(ListStatus: b"N" => { //TODO: Only the core fields are implemented.
+    REQUIRED, list_id: ListID [FIX40..],
+    REQUIRED, list_status_type: ListStatusType [FIX42..],
+    REQUIRED, no_rpts: NoRpts [FIX40..],
+    REQUIRED, list_order_status: ListOrderStatus [FIX42..],
+    REQUIRED, rpt_seq: RptSeq [FIX40..],
+    NOT_REQUIRED, list_status_text: ListStatusText [FIX42..],
+    NOT_REQUIRED, transact_time: TransactTime [FIX42..],
+    REQUIRED, tot_no_orders: TotNoOrders [FIX40..],
+    REQUIRED, orders: NoOrders<OrdListStatGrp> [FIX40..],
+});
+
+define_fixt_message!(NewOrderList: b"E" => { //TODO: Only the core fields are implemented. FIX 4.0 and 4.1 sent one order per message instead of using NoOrders.
+    REQUIRED, list_id: ListID [FIX40..],
+    NOT_REQUIRED, bid_id: BidID [FIX42..],
+    NOT_REQUIRED, client_bid_id: ClientBidID [FIX42..],
+    NOT_REQUIRED, prog_rpt_reqs: ProgRptReqs [FIX42..],
+    REQUIRED, bid_type: BidType [FIX42..],
+    NOT_REQUIRED, prog_period_interval: ProgPeriodInterval [FIX42..],
+    NOT_REQUIRED, list_exec_inst: ListExecInst [FIX40..],
+    REQUIRED, tot_no_orders: TotNoOrders [FIX40..],
+    REQUIRED, orders: NoOrders<ListOrdGrp> [FIX42..],
+});
+
 define_fixt_message!(NewOrderSingle: b"D" => { //TODO: All version info for this message is wrong.
     REQUIRED, cl_ord_id: ClOrdID [FIX40..],
     /*NOT_REQUIRED, secondary_cl_ord_id: SecondaryClOrdID,
@@ -228,8 +288,8 @@ define_fixt_message!(NewOrderSingle: b"D" => { //TODO: All version info for this
     NOT_REQUIRED, day_booking_inst: DayBookingInst,
     NOT_REQUIRED, booking_unit: BookingUnit,
     NOT_REQUIRED, prealloc_method: PreallocMethod,
-    NOT_REQUIRED, alloc_id: AllocID,
-    NOT_REQUIRED, pre_alloc_grp: NoPreAllocGrp,*/
+    NOT_REQUIRED, alloc_id: AllocID,*/
+    NOT_REQUIRED, allocs: NoAllocs<PreAllocGrp> [FIX42..],
     NOT_REQUIRED, settl_type: SettlType [FIX40..],
     NOT_REQUIRED, settl_date: SettlDate [FIX40..],
     /*NOT_REQUIRED, cash_margin: CashMargin,
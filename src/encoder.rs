@@ -0,0 +1,199 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+use std::io::{self,Write};
+
+use constant::VALUE_END;
+use fix_version::FIXVersion;
+use message::Message;
+use message_version::MessageVersion;
+
+const CHECKSUM_LEN: usize = 7; //10=000\x01
+
+pub enum EncodeError {
+    BufferTooSmall(usize), //Number of bytes needed to hold the entire message.
+    Io(io::Error),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EncodeError::BufferTooSmall(required_len) => write!(f,"EncodeError::BufferTooSmall({})",required_len),
+            EncodeError::Io(ref error) => write!(f,"EncodeError::Io({})",error),
+        }
+    }
+}
+
+impl fmt::Debug for EncodeError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        <EncodeError as fmt::Display>::fmt(self,f)
+    }
+}
+
+//Sums every byte written so the checksum is ready as soon as the body has been written.
+struct ChecksumWriter<'a,W: Write + 'a> {
+    writer: &'a mut W,
+    checksum: u8,
+    len: usize,
+}
+
+impl<'a,W: Write> Write for ChecksumWriter<'a,W> {
+    fn write(&mut self,bytes: &[u8]) -> io::Result<usize> {
+        let len = try!(self.writer.write(bytes));
+        for byte in &bytes[..len] {
+            self.checksum = self.checksum.wrapping_add(*byte);
+        }
+        self.len += len;
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+//Throws away everything written and only keeps track of how many bytes there were. Used to find
+//BodyLength without serializing the body into a buffer.
+pub struct LengthCounter {
+    pub len: usize,
+}
+
+impl LengthCounter {
+    pub fn new() -> LengthCounter {
+        LengthCounter {
+            len: 0,
+        }
+    }
+}
+
+impl Write for LengthCounter {
+    fn write(&mut self,bytes: &[u8]) -> io::Result<usize> {
+        self.len += bytes.len();
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+//Serializes messages straight into a caller provided slice or io::Write. Unlike Message::read(),
+//the header is written in front of the body instead of being copied into padding reserved in a
+//ByteBuffer and the checksum is calculated while writing. BodyLength is found with a first pass
+//that only counts the bytes the body would take. The second pass writes the body directly to the
+//output. Field types that can't write themselves directly are formatted through a scratch buffer
+//that's kept between calls so an Encoder that's reused stops allocating.
+pub struct Encoder {
+    scratch: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn new() -> Encoder {
+        Encoder::with_capacity(64)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Encoder {
+        Encoder {
+            scratch: Vec::with_capacity(capacity),
+        }
+    }
+
+    fn prepare<T: Message + ?Sized>(&mut self,message: &T,fix_version: FIXVersion,message_version: MessageVersion) -> (usize,usize) {
+        let mut counter = LengthCounter::new();
+        message.write_body(fix_version,message_version,&mut counter,&mut self.scratch).unwrap(); //LengthCounter never fails.
+
+        //BodyLength counts everything after the BodyLength field up to the CheckSum field. This
+        //includes the MsgType field.
+        let body_length = message.msg_type_header().len() + counter.len;
+        let message_len = 2 + fix_version.begin_string().len() + 1 + //8=<FIXVersion>\x01
+                          2 + digit_count(body_length) + 1 +        //9=<BodyLength>\x01
+                          body_length +                             //35=<MessageType>\x01<Body>
+                          CHECKSUM_LEN;                             //10=<CheckSum>\x01
+
+        (body_length,message_len)
+    }
+
+    fn write_message<T: Message + ?Sized,W: Write>(&mut self,message: &T,fix_version: FIXVersion,message_version: MessageVersion,body_length: usize,writer: &mut W) -> io::Result<usize> {
+        let mut writer = ChecksumWriter {
+            writer: writer,
+            checksum: 0,
+            len: 0,
+        };
+
+        //Header.
+        try!(writer.write_all(b"8="));
+        try!(writer.write_all(fix_version.begin_string()));
+        try!(writer.write_all(b"\x019="));
+        try!(write_integer(body_length,&mut writer));
+        try!(writer.write_all(&[VALUE_END]));
+        try!(writer.write_all(message.msg_type_header()));
+
+        //Body.
+        try!(message.write_body(fix_version,message_version,&mut writer,&mut self.scratch));
+
+        //Trailer. The checksum must always have a length of 3. FIXT version 1.1, page 55.
+        let checksum = writer.checksum;
+        let checksum_bytes = [b'1',b'0',b'=',b'0' + checksum / 100,b'0' + (checksum / 10) % 10,b'0' + checksum % 10,VALUE_END];
+        try!(writer.writer.write_all(&checksum_bytes));
+
+        Ok(writer.len + CHECKSUM_LEN)
+    }
+
+    pub fn encode_to_slice<T: Message + ?Sized>(&mut self,message: &T,fix_version: FIXVersion,message_version: MessageVersion,buf: &mut [u8]) -> Result<usize,EncodeError> {
+        //Nothing is written to buf unless the entire message fits.
+        let (body_length,message_len) = self.prepare(message,fix_version,message_version);
+        if message_len > buf.len() {
+            return Err(EncodeError::BufferTooSmall(message_len));
+        }
+
+        let mut slice = &mut buf[..message_len];
+        self.write_message(message,fix_version,message_version,body_length,&mut slice).map_err(EncodeError::Io)
+    }
+
+    pub fn encode_to_writer<T: Message + ?Sized,W: Write>(&mut self,message: &T,fix_version: FIXVersion,message_version: MessageVersion,writer: &mut W) -> Result<usize,EncodeError> {
+        //Writer is given the message in several pieces. Wrap it with an io::BufWriter or similar
+        //if each write is expensive.
+        let (body_length,_) = self.prepare(message,fix_version,message_version);
+        self.write_message(message,fix_version,message_version,body_length,writer).map_err(EncodeError::Io)
+    }
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Encoder::new()
+    }
+}
+
+//Same as writing value.to_string() but without allocating. Returns the number of bytes written.
+pub fn write_integer<T: fmt::Display>(value: T,writer: &mut Write) -> io::Result<usize> {
+    //Every integer type, including the sign, fits in 20 bytes.
+    let mut bytes = [0u8;20];
+    let len = {
+        let mut slice = &mut bytes[..];
+        try!(write!(slice,"{}",value));
+        20 - slice.len()
+    };
+    try!(writer.write_all(&bytes[..len]));
+
+    Ok(len)
+}
+
+fn digit_count(mut value: usize) -> usize {
+    let mut count = 1;
+    while value >= 10 {
+        value /= 10;
+        count += 1;
+    }
+
+    count
+}
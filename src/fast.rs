@@ -0,0 +1,1160 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//FAST (FIX Adapted for STreaming) 1.1 decoder driven by an XML template file. Each decoded message
+//is first turned into a list of fields using the id attribute of each template instruction as the
+//tag. Instructions without an id are decoded but not kept. Sequences become repeating groups using
+//the id of their <length> element and groups are flattened into the enclosing message.
+//
+//Messages are then built using the message dictionary based on MsgType (35), which is usually a
+//constant in the template. When the template has no MsgType, the template name is used instead.
+//Messages that aren't in the dictionary are returned as a GenericMessage.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self,Read};
+use std::path::Path;
+
+use field_tag::FieldTag;
+use fixt::message::{BuildFIXTMessage,FIXTMessage};
+use generic_message::{GenericField,GenericFieldValue,GenericMessage};
+use message::{BuildMessage,Message,SetValueError};
+use message_version::MessageVersion;
+use rule::Rule;
+use xml::{self,Element};
+
+//Fields that are part of every message and are not set like other fields.
+const BUILT_IN_TAGS: &'static [FieldTag] = &[
+    FieldTag(8),  //BeginString
+    FieldTag(9),  //BodyLength
+    FieldTag(35), //MsgType
+    FieldTag(10), //CheckSum
+];
+const MSG_TYPE_TAG: FieldTag = FieldTag(35);
+
+//The template identifier is always decoded as if it used the copy operator with its own entry in
+//the global dictionary.
+const TEMPLATE_ID_DICTIONARY_INDEX: usize = 0;
+
+const MAX_EXPONENT: i64 = 63;
+
+const DEFAULT_MAX_SEQUENCE_LENGTH: u64 = 1024;
+const DEFAULT_MAX_TEMPLATE_DEPTH: usize = 16;
+
+pub enum FASTError {
+    Io(io::Error),
+    Xml(usize,&'static str), //Byte offset, Description
+    NotTemplates, //Root element is not <templates>.
+    MissingAttribute(String,&'static str), //Element name, Attribute name
+    InvalidAttribute(String,&'static str,String), //Element name, Attribute name, Attribute value
+    UnknownInstruction(String), //Element name
+    DuplicateTemplate(String),
+    UnknownTemplate(String), //Static <templateRef> names a template that isn't defined.
+    RecursiveTemplate(String),
+    BufferTooSmall, //Message is incomplete. Decode again once more bytes have been received.
+    MissingTemplateID, //Message doesn't include a template ID and no previous message did either.
+    TemplateIDUnknown(u32),
+    IntegerOverflow(String), //Field name
+    MissingValue(String), //Mandatory field has no value. Field name
+    InvalidValue(String), //Field name
+    UnknownTag(FieldTag), //Tag is not part of the message and the message can't store extra fields.
+    WrongFormat(FieldTag),
+    OutOfRange(FieldTag),
+    SequenceTooLong(String,u64), //Field name, Length
+    TemplateDepthExceeded, //Dynamic template references are nested deeper than allowed.
+}
+
+impl fmt::Display for FASTError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FASTError::Io(ref error) => write!(f,"FASTError::Io({})",error),
+            FASTError::Xml(offset,description) => write!(f,"FASTError::Xml({},{})",offset,description),
+            FASTError::NotTemplates => write!(f,"FASTError::NotTemplates"),
+            FASTError::MissingAttribute(ref element,attribute) => write!(f,"FASTError::MissingAttribute({},{})",element,attribute),
+            FASTError::InvalidAttribute(ref element,attribute,ref value) => write!(f,"FASTError::InvalidAttribute({},{},{})",element,attribute,value),
+            FASTError::UnknownInstruction(ref element) => write!(f,"FASTError::UnknownInstruction({})",element),
+            FASTError::DuplicateTemplate(ref name) => write!(f,"FASTError::DuplicateTemplate({})",name),
+            FASTError::UnknownTemplate(ref name) => write!(f,"FASTError::UnknownTemplate({})",name),
+            FASTError::RecursiveTemplate(ref name) => write!(f,"FASTError::RecursiveTemplate({})",name),
+            FASTError::BufferTooSmall => write!(f,"FASTError::BufferTooSmall"),
+            FASTError::MissingTemplateID => write!(f,"FASTError::MissingTemplateID"),
+            FASTError::TemplateIDUnknown(template_id) => write!(f,"FASTError::TemplateIDUnknown({})",template_id),
+            FASTError::IntegerOverflow(ref name) => write!(f,"FASTError::IntegerOverflow({})",name),
+            FASTError::MissingValue(ref name) => write!(f,"FASTError::MissingValue({})",name),
+            FASTError::InvalidValue(ref name) => write!(f,"FASTError::InvalidValue({})",name),
+            FASTError::UnknownTag(tag) => write!(f,"FASTError::UnknownTag({})",tag),
+            FASTError::WrongFormat(tag) => write!(f,"FASTError::WrongFormat({})",tag),
+            FASTError::OutOfRange(tag) => write!(f,"FASTError::OutOfRange({})",tag),
+            FASTError::SequenceTooLong(ref name,len) => write!(f,"FASTError::SequenceTooLong({},{})",name,len),
+            FASTError::TemplateDepthExceeded => write!(f,"FASTError::TemplateDepthExceeded"),
+        }
+    }
+}
+
+impl fmt::Debug for FASTError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        <FASTError as fmt::Display>::fmt(self,f)
+    }
+}
+
+#[derive(Clone,PartialEq)]
+enum Value {
+    Int(i64),
+    UInt(u64),
+    Decimal(i64,i64), //Mantissa, Exponent
+    Bytes(Vec<u8>),
+}
+
+#[derive(Clone)]
+enum DictionaryValue {
+    Undefined,
+    Empty,
+    Assigned(Value),
+}
+
+#[derive(Clone,Copy,PartialEq)]
+enum OperatorKind {
+    None,
+    Constant,
+    Default,
+    Copy,
+    Increment,
+    Delta,
+    Tail,
+}
+
+#[derive(Clone)]
+struct Operator {
+    kind: OperatorKind,
+    initial_value: Option<Value>,
+    dictionary_index: usize, //Only used by operators that keep the previous value.
+}
+
+impl Operator {
+    fn presence_map_bits(&self,is_optional: bool) -> usize {
+        match self.kind {
+            OperatorKind::None | OperatorKind::Delta => 0,
+            OperatorKind::Constant => if is_optional { 1 } else { 0 },
+            OperatorKind::Default | OperatorKind::Copy | OperatorKind::Increment | OperatorKind::Tail => 1,
+        }
+    }
+}
+
+#[derive(Clone,Copy,PartialEq)]
+enum ScalarType {
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Decimal,
+    AsciiString,
+    UnicodeString,
+    ByteVector,
+}
+
+#[derive(Clone)]
+enum InstructionType {
+    Scalar(ScalarType,Operator),
+    SplitDecimal(Operator,Operator), //Exponent, Mantissa
+    Group(Vec<Instruction>,bool), //Instructions, Has presence map
+    Sequence(Box<Instruction>,Vec<Instruction>,bool), //Length, Instructions, Has presence map
+    DynamicTemplateRef,
+}
+
+#[derive(Clone)]
+struct Instruction {
+    name: String,
+    tag: Option<FieldTag>,
+    is_optional: bool,
+    instruction_type: InstructionType,
+}
+
+impl Instruction {
+    fn presence_map_bits(&self) -> usize {
+        match self.instruction_type {
+            InstructionType::Scalar(_,ref operator) => operator.presence_map_bits(self.is_optional),
+            InstructionType::SplitDecimal(ref exponent,ref mantissa) => exponent.presence_map_bits(self.is_optional) + mantissa.presence_map_bits(false),
+            InstructionType::Group(..) => if self.is_optional { 1 } else { 0 },
+            InstructionType::Sequence(ref length,_,_) => length.presence_map_bits(),
+            InstructionType::DynamicTemplateRef => 0,
+        }
+    }
+}
+
+struct Template {
+    name: String,
+    instructions: Vec<Instruction>,
+}
+
+#[derive(Clone,Copy)]
+struct Context<'a> {
+    template_name: &'a str,
+    type_name: &'a str,
+    dictionary: &'a str,
+}
+
+struct TemplateParser<'a> {
+    root_dictionary: &'a str,
+    template_elements: HashMap<&'a str,&'a Element>,
+    dictionary_keys: HashMap<String,usize>,
+}
+
+fn required_attribute<'a>(element: &'a Element,name: &'static str) -> Result<&'a str,FASTError> {
+    element.attribute(name).ok_or_else(|| FASTError::MissingAttribute(element.name.clone(),name))
+}
+
+fn parse_tag(element: &Element) -> Result<Option<FieldTag>,FASTError> {
+    match element.attribute("id") {
+        Some(id) => match id.parse::<u64>() {
+            Ok(tag) if tag > 0 => Ok(Some(FieldTag(tag))),
+            _ => Err(FASTError::InvalidAttribute(element.name.clone(),"id",id.to_string())),
+        },
+        None => Ok(None),
+    }
+}
+
+fn parse_initial_value(scalar_type: ScalarType,value: &str) -> Option<Value> {
+    match scalar_type {
+        ScalarType::Int32 | ScalarType::Int64 => value.trim().parse::<i64>().ok().map(Value::Int),
+        ScalarType::UInt32 | ScalarType::UInt64 => value.trim().parse::<u64>().ok().map(Value::UInt),
+        ScalarType::Decimal => {
+            //Normalized so "1.50" becomes a mantissa of 15 and an exponent of -1.
+            let value = value.trim();
+            let (integer,fraction) = match value.find('.') {
+                Some(index) => (&value[..index],value[index + 1..].trim_right_matches('0')),
+                None => (value,""),
+            };
+            let mut mantissa = match format!("{}{}",integer,fraction).parse::<i64>() {
+                Ok(mantissa) => mantissa,
+                Err(_) => return None,
+            };
+            let mut exponent = -(fraction.len() as i64);
+            while mantissa != 0 && mantissa % 10 == 0 {
+                mantissa /= 10;
+                exponent += 1;
+            }
+            if mantissa == 0 {
+                exponent = 0;
+            }
+            Some(Value::Decimal(mantissa,exponent))
+        },
+        ScalarType::AsciiString | ScalarType::UnicodeString | ScalarType::ByteVector => Some(Value::Bytes(value.as_bytes().to_vec())),
+    }
+}
+
+impl<'a> TemplateParser<'a> {
+    fn dictionary_index(&mut self,context: Context,operator_element: &Element,key: &str) -> usize {
+        let dictionary = operator_element.attribute("dictionary").unwrap_or(context.dictionary);
+        let key = operator_element.attribute("key").unwrap_or(key);
+        let full_key = match dictionary {
+            "global" => format!("global:{}",key),
+            "template" => format!("template:{}:{}",context.template_name,key),
+            "type" => format!("type:{}:{}",context.type_name,key),
+            _ => format!("user:{}:{}",dictionary,key),
+        };
+
+        let next_index = self.dictionary_keys.len() + 1; //Index 0 is used by the template ID.
+        *self.dictionary_keys.entry(full_key).or_insert(next_index)
+    }
+
+    fn parse_operator(&mut self,context: Context,element: &Element,scalar_type: ScalarType,is_optional: bool,key: &str) -> Result<Operator,FASTError> {
+        let mut operator = Operator {
+            kind: OperatorKind::None,
+            initial_value: None,
+            dictionary_index: 0,
+        };
+
+        let operator_element = match element.children.iter().find(|child| child.name != "length" && child.name != "exponent" && child.name != "mantissa") {
+            Some(operator_element) => operator_element,
+            None => return Ok(operator),
+        };
+        operator.kind = match &operator_element.name[..] {
+            "constant" => OperatorKind::Constant,
+            "default" => OperatorKind::Default,
+            "copy" => OperatorKind::Copy,
+            "increment" => OperatorKind::Increment,
+            "delta" => OperatorKind::Delta,
+            "tail" => OperatorKind::Tail,
+            _ => return Err(FASTError::UnknownInstruction(operator_element.name.clone())),
+        };
+
+        if let Some(value) = operator_element.attribute("value") {
+            operator.initial_value = match parse_initial_value(scalar_type,value) {
+                Some(initial_value) => Some(initial_value),
+                None => return Err(FASTError::InvalidAttribute(operator_element.name.clone(),"value",value.to_string())),
+            };
+        }
+        else if operator.kind == OperatorKind::Constant || (operator.kind == OperatorKind::Default && !is_optional) {
+            return Err(FASTError::MissingAttribute(operator_element.name.clone(),"value"));
+        }
+
+        match operator.kind {
+            OperatorKind::Copy | OperatorKind::Increment | OperatorKind::Delta | OperatorKind::Tail => {
+                operator.dictionary_index = self.dictionary_index(context,operator_element,key);
+            },
+            _ => {},
+        }
+
+        Ok(operator)
+    }
+
+    fn parse_instruction(&mut self,context: Context<'a>,element: &'a Element,stack: &mut Vec<String>) -> Result<Instruction,FASTError> {
+        let name = try!(required_attribute(element,"name")).to_string();
+        let tag = try!(parse_tag(element));
+        let is_optional = match element.attribute("presence") {
+            None | Some("mandatory") => false,
+            Some("optional") => true,
+            Some(presence) => return Err(FASTError::InvalidAttribute(element.name.clone(),"presence",presence.to_string())),
+        };
+
+        let scalar_type = match &element.name[..] {
+            "int32" => Some(ScalarType::Int32),
+            "uInt32" => Some(ScalarType::UInt32),
+            "int64" => Some(ScalarType::Int64),
+            "uInt64" => Some(ScalarType::UInt64),
+            "decimal" => Some(ScalarType::Decimal),
+            "string" if element.attribute("charset") == Some("unicode") => Some(ScalarType::UnicodeString),
+            "string" => Some(ScalarType::AsciiString),
+            "byteVector" => Some(ScalarType::ByteVector),
+            _ => None,
+        };
+
+        let instruction_type = if scalar_type == Some(ScalarType::Decimal) && (element.child("exponent").is_some() || element.child("mantissa").is_some()) {
+            let exponent = match element.child("exponent") {
+                Some(exponent_element) => try!(self.parse_operator(context,exponent_element,ScalarType::Int32,is_optional,&format!("{}.exponent",name))),
+                None => try!(self.parse_operator(context,&Element::new("exponent"),ScalarType::Int32,is_optional,&name)),
+            };
+            let mantissa = match element.child("mantissa") {
+                Some(mantissa_element) => try!(self.parse_operator(context,mantissa_element,ScalarType::Int64,false,&format!("{}.mantissa",name))),
+                None => try!(self.parse_operator(context,&Element::new("mantissa"),ScalarType::Int64,false,&name)),
+            };
+            InstructionType::SplitDecimal(exponent,mantissa)
+        }
+        else if let Some(scalar_type) = scalar_type {
+            InstructionType::Scalar(scalar_type,try!(self.parse_operator(context,element,scalar_type,is_optional,&name)))
+        }
+        else if element.name == "group" {
+            let instructions = try!(self.parse_instructions(context,element,stack));
+            let has_presence_map = instructions.iter().any(|instruction| instruction.presence_map_bits() > 0);
+            InstructionType::Group(instructions,has_presence_map)
+        }
+        else if element.name == "sequence" {
+            //The length of a sequence is an implicit uInt32 field unless described using <length>.
+            let mut length_context = context;
+            length_context.dictionary = element.attribute("dictionary").unwrap_or(context.dictionary);
+            let implicit_length_element = Element::new("length");
+            let length_element = element.child("length").unwrap_or(&implicit_length_element);
+            let length_name = length_element.attribute("name").map(|length_name| length_name.to_string()).unwrap_or_else(|| format!("{}.length",name));
+            let length_operator = try!(self.parse_operator(length_context,length_element,ScalarType::UInt32,is_optional,&length_name));
+            let length = Instruction {
+                name: length_name,
+                tag: try!(parse_tag(length_element)),
+                is_optional: is_optional,
+                instruction_type: InstructionType::Scalar(ScalarType::UInt32,length_operator),
+            };
+
+            let instructions = try!(self.parse_instructions(context,element,stack));
+            let has_presence_map = instructions.iter().any(|instruction| instruction.presence_map_bits() > 0);
+            InstructionType::Sequence(Box::new(length),instructions,has_presence_map)
+        }
+        else {
+            return Err(FASTError::UnknownInstruction(element.name.clone()));
+        };
+
+        Ok(Instruction {
+            name: name,
+            tag: tag,
+            is_optional: is_optional,
+            instruction_type: instruction_type,
+        })
+    }
+
+    fn parse_instructions(&mut self,context: Context<'a>,element: &'a Element,stack: &mut Vec<String>) -> Result<Vec<Instruction>,FASTError> {
+        let mut context = context;
+        if let Some(dictionary) = element.attribute("dictionary") {
+            context.dictionary = dictionary;
+        }
+        if let Some(type_ref) = element.child("typeRef") {
+            context.type_name = try!(required_attribute(type_ref,"name"));
+        }
+
+        let mut instructions = Vec::new();
+        for child in &element.children {
+            match &child.name[..] {
+                "typeRef" | "length" => {},
+                "templateRef" => match child.attribute("name") {
+                    //Static references are replaced by the referenced template's instructions.
+                    Some(template_name) => {
+                        let template_element = match self.template_elements.get(template_name) {
+                            Some(template_element) => *template_element,
+                            None => return Err(FASTError::UnknownTemplate(template_name.to_string())),
+                        };
+                        if stack.iter().any(|name| name == template_name) {
+                            return Err(FASTError::RecursiveTemplate(template_name.to_string()));
+                        }
+
+                        stack.push(template_name.to_string());
+                        instructions.extend(try!(self.parse_template(template_element,stack)));
+                        stack.pop();
+                    },
+                    None => instructions.push(Instruction {
+                        name: child.name.clone(),
+                        tag: None,
+                        is_optional: false,
+                        instruction_type: InstructionType::DynamicTemplateRef,
+                    }),
+                },
+                _ => instructions.push(try!(self.parse_instruction(context,child,stack))),
+            }
+        }
+
+        Ok(instructions)
+    }
+
+    fn parse_template(&mut self,element: &'a Element,stack: &mut Vec<String>) -> Result<Vec<Instruction>,FASTError> {
+        let context = Context {
+            template_name: try!(required_attribute(element,"name")),
+            type_name: "",
+            dictionary: self.root_dictionary,
+        };
+
+        self.parse_instructions(context,element,stack)
+    }
+}
+
+//A set of templates loaded from a FAST XML template file.
+pub struct FASTTemplates {
+    templates: HashMap<u32,Template>,
+    dictionary_len: usize,
+}
+
+impl FASTTemplates {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<FASTTemplates,FASTError> {
+        let mut document = String::new();
+        let mut file = try!(File::open(path).map_err(FASTError::Io));
+        try!(file.read_to_string(&mut document).map_err(FASTError::Io));
+
+        FASTTemplates::from_xml(&document[..])
+    }
+
+    pub fn from_xml(document: &str) -> Result<FASTTemplates,FASTError> {
+        let root = match xml::parse(document) {
+            Ok(root) => root,
+            Err(error) => return Err(FASTError::Xml(error.offset,error.description)),
+        };
+        if root.name != "templates" {
+            return Err(FASTError::NotTemplates);
+        }
+
+        //Templates can refer to each other in any order so they must all be known first.
+        let mut parser = TemplateParser {
+            root_dictionary: root.attribute("dictionary").unwrap_or("global"),
+            template_elements: HashMap::new(),
+            dictionary_keys: HashMap::new(),
+        };
+        for template_element in root.children.iter().filter(|child| child.name == "template") {
+            let name = try!(required_attribute(template_element,"name"));
+            if parser.template_elements.insert(name,template_element).is_some() {
+                return Err(FASTError::DuplicateTemplate(name.to_string()));
+            }
+        }
+
+        let mut templates = HashMap::new();
+        for template_element in root.children.iter().filter(|child| child.name == "template") {
+            let name = try!(required_attribute(template_element,"name"));
+            let id = try!(required_attribute(template_element,"id"));
+            let id = try!(id.parse::<u32>().map_err(|_| FASTError::InvalidAttribute(template_element.name.clone(),"id",id.to_string())));
+            let template = Template {
+                name: name.to_string(),
+                instructions: try!(parser.parse_template(template_element,&mut vec![name.to_string()])),
+            };
+            if templates.insert(id,template).is_some() {
+                return Err(FASTError::DuplicateTemplate(name.to_string()));
+            }
+        }
+
+        Ok(FASTTemplates {
+            templates: templates,
+            dictionary_len: parser.dictionary_keys.len() + 1,
+        })
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn read_stop_bit_bytes(&mut self) -> Result<&'a [u8],FASTError> {
+        let start = self.offset;
+        loop {
+            if self.offset >= self.buf.len() {
+                return Err(FASTError::BufferTooSmall);
+            }
+
+            let byte = self.buf[self.offset];
+            self.offset += 1;
+            if byte & 0x80 != 0 {
+                let buf = self.buf;
+                return Ok(&buf[start..self.offset]);
+            }
+        }
+    }
+
+    fn read_bytes(&mut self,len: usize) -> Result<&'a [u8],FASTError> {
+        if self.buf.len() - self.offset < len {
+            return Err(FASTError::BufferTooSmall);
+        }
+
+        let buf = self.buf;
+        let start = self.offset;
+        self.offset += len;
+        Ok(&buf[start..self.offset])
+    }
+
+    fn read_uint(&mut self,is_nullable: bool,name: &str) -> Result<Option<u64>,FASTError> {
+        let mut value: u64 = 0;
+        for byte in try!(self.read_stop_bit_bytes()) {
+            if value > u64::max_value() >> 7 {
+                return Err(FASTError::IntegerOverflow(name.to_string()));
+            }
+            value = (value << 7) | (byte & 0x7F) as u64;
+        }
+
+        //Nullable integers are stored one higher so zero can mean null.
+        Ok(if !is_nullable { Some(value) } else if value == 0 { None } else { Some(value - 1) })
+    }
+
+    fn read_int(&mut self,is_nullable: bool,name: &str) -> Result<Option<i64>,FASTError> {
+        let bytes = try!(self.read_stop_bit_bytes());
+
+        //Sign is the second highest bit of the first byte.
+        let mut value: i64 = if bytes[0] & 0x40 != 0 { -1 } else { 0 };
+        for byte in bytes {
+            if value > i64::max_value() >> 7 || value < i64::min_value() >> 7 {
+                return Err(FASTError::IntegerOverflow(name.to_string()));
+            }
+            value = (value << 7) | (byte & 0x7F) as i64;
+        }
+
+        //Nullable integers have non-negative values stored one higher so zero can mean null.
+        Ok(if !is_nullable || value < 0 { Some(value) } else if value == 0 { None } else { Some(value - 1) })
+    }
+
+    fn read_ascii(&mut self,is_nullable: bool) -> Result<Option<Vec<u8>>,FASTError> {
+        let mut value: Vec<u8> = try!(self.read_stop_bit_bytes()).iter().map(|byte| byte & 0x7F).collect();
+
+        //A leading zero byte is used to encode empty and NUL strings. A nullable string needs one
+        //extra leading zero byte because a lone zero byte means null.
+        if is_nullable {
+            if value == [0] {
+                return Ok(None);
+            }
+            else if value[0] == 0 {
+                value.remove(0);
+            }
+        }
+        if value == [0] {
+            value.clear();
+        }
+        else if value[0] == 0 {
+            value.remove(0);
+        }
+
+        Ok(Some(value))
+    }
+
+    fn read_byte_vector(&mut self,is_nullable: bool,name: &str) -> Result<Option<Vec<u8>>,FASTError> {
+        match try!(self.read_uint(is_nullable,name)) {
+            Some(len) => {
+                if len > (self.buf.len() - self.offset) as u64 {
+                    return Err(FASTError::BufferTooSmall);
+                }
+                Ok(Some(try!(self.read_bytes(len as usize)).to_vec()))
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+struct PresenceMap<'a> {
+    bytes: &'a [u8],
+    index: usize,
+}
+
+impl<'a> PresenceMap<'a> {
+    fn new(bytes: &'a [u8]) -> PresenceMap<'a> {
+        PresenceMap {
+            bytes: bytes,
+            index: 0,
+        }
+    }
+
+    fn next_bit(&mut self) -> bool {
+        //Each byte holds seven bits, most significant first. Bits past the end are zero.
+        let byte_index = self.index / 7;
+        let bit = 6 - self.index % 7;
+        self.index += 1;
+
+        byte_index < self.bytes.len() && self.bytes[byte_index] & (1 << bit) != 0
+    }
+}
+
+//Dictionary entries changed while decoding are journaled so a message that turns out to be
+//incomplete can be decoded again once the rest of it arrives.
+struct Dictionary {
+    values: Vec<DictionaryValue>,
+    journal: Vec<(usize,DictionaryValue)>,
+}
+
+impl Dictionary {
+    fn get(&self,index: usize) -> &DictionaryValue {
+        &self.values[index]
+    }
+
+    fn set(&mut self,index: usize,value: DictionaryValue) {
+        let previous_value = ::std::mem::replace(&mut self.values[index],value);
+        self.journal.push((index,previous_value));
+    }
+
+    fn commit(&mut self) {
+        self.journal.clear();
+    }
+
+    fn rollback(&mut self) {
+        while let Some((index,previous_value)) = self.journal.pop() {
+            self.values[index] = previous_value;
+        }
+    }
+}
+
+fn check_range(scalar_type: ScalarType,value: &Value,name: &str) -> Result<(),FASTError> {
+    let is_in_range = match (scalar_type,value) {
+        (ScalarType::Int32,&Value::Int(value)) => value >= i32::min_value() as i64 && value <= i32::max_value() as i64,
+        (ScalarType::UInt32,&Value::UInt(value)) => value <= u32::max_value() as u64,
+        (ScalarType::Decimal,&Value::Decimal(_,exponent)) => exponent >= -MAX_EXPONENT && exponent <= MAX_EXPONENT,
+        _ => true,
+    };
+
+    if is_in_range { Ok(()) } else { Err(FASTError::IntegerOverflow(name.to_string())) }
+}
+
+fn read_value(reader: &mut Reader,scalar_type: ScalarType,is_nullable: bool,name: &str) -> Result<Option<Value>,FASTError> {
+    let value = match scalar_type {
+        ScalarType::Int32 | ScalarType::Int64 => try!(reader.read_int(is_nullable,name)).map(Value::Int),
+        ScalarType::UInt32 | ScalarType::UInt64 => try!(reader.read_uint(is_nullable,name)).map(Value::UInt),
+        ScalarType::Decimal => match try!(reader.read_int(is_nullable,name)) {
+            Some(exponent) => Some(Value::Decimal(try!(reader.read_int(false,name)).unwrap(),exponent)),
+            None => None,
+        },
+        ScalarType::AsciiString => try!(reader.read_ascii(is_nullable)).map(Value::Bytes),
+        ScalarType::UnicodeString | ScalarType::ByteVector => try!(reader.read_byte_vector(is_nullable,name)).map(Value::Bytes),
+    };
+
+    if let Some(ref value) = value {
+        try!(check_range(scalar_type,value,name));
+    }
+
+    Ok(value)
+}
+
+fn base_value(scalar_type: ScalarType) -> Value {
+    match scalar_type {
+        ScalarType::Int32 | ScalarType::Int64 => Value::Int(0),
+        ScalarType::UInt32 | ScalarType::UInt64 => Value::UInt(0),
+        ScalarType::Decimal => Value::Decimal(0,0),
+        ScalarType::AsciiString | ScalarType::UnicodeString | ScalarType::ByteVector => Value::Bytes(Vec::new()),
+    }
+}
+
+fn increment_value(scalar_type: ScalarType,value: Value) -> Value {
+    //Incrementing the largest value wraps around to the smallest value.
+    match (scalar_type,value) {
+        (ScalarType::Int32,Value::Int(value)) => Value::Int(if value >= i32::max_value() as i64 { i32::min_value() as i64 } else { value + 1 }),
+        (ScalarType::UInt32,Value::UInt(value)) => Value::UInt(if value >= u32::max_value() as u64 { 0 } else { value + 1 }),
+        (_,Value::Int(value)) => Value::Int(value.wrapping_add(1)),
+        (_,Value::UInt(value)) => Value::UInt(value.wrapping_add(1)),
+        (_,value) => value,
+    }
+}
+
+fn apply_delta(reader: &mut Reader,scalar_type: ScalarType,is_nullable: bool,base: Value,name: &str) -> Result<Option<Value>,FASTError> {
+    let overflow = || FASTError::IntegerOverflow(name.to_string());
+
+    let value = match (scalar_type,base) {
+        (ScalarType::Int32,Value::Int(base)) | (ScalarType::Int64,Value::Int(base)) => match try!(reader.read_int(is_nullable,name)) {
+            Some(delta) => Value::Int(try!(base.checked_add(delta).ok_or_else(&overflow))),
+            None => return Ok(None),
+        },
+        (ScalarType::UInt32,Value::UInt(base)) | (ScalarType::UInt64,Value::UInt(base)) => match try!(reader.read_int(is_nullable,name)) {
+            Some(delta) if delta >= 0 => Value::UInt(try!(base.checked_add(delta as u64).ok_or_else(&overflow))),
+            Some(delta) => Value::UInt(try!(base.checked_sub((delta as u64).wrapping_neg()).ok_or_else(&overflow))),
+            None => return Ok(None),
+        },
+        (ScalarType::Decimal,Value::Decimal(mantissa,exponent)) => match try!(reader.read_int(is_nullable,name)) {
+            Some(exponent_delta) => {
+                let mantissa_delta = try!(reader.read_int(false,name)).unwrap();
+                Value::Decimal(try!(mantissa.checked_add(mantissa_delta).ok_or_else(&overflow)),try!(exponent.checked_add(exponent_delta).ok_or_else(&overflow)))
+            },
+            None => return Ok(None),
+        },
+        (_,Value::Bytes(base)) => {
+            //Characters are removed from the end of the previous value and the difference is
+            //appended. A negative subtraction length removes characters from the front instead and
+            //prepends the difference.
+            let subtraction_len = match try!(reader.read_int(is_nullable,name)) {
+                Some(subtraction_len) => subtraction_len,
+                None => return Ok(None),
+            };
+            let difference = match scalar_type {
+                ScalarType::AsciiString => try!(reader.read_ascii(false)).unwrap(),
+                _ => try!(reader.read_byte_vector(false,name)).unwrap(),
+            };
+
+            let (is_front,remove_len) = if subtraction_len >= 0 { (false,subtraction_len as u64) } else { (true,(-(subtraction_len + 1)) as u64) };
+            if remove_len > base.len() as u64 {
+                return Err(FASTError::InvalidValue(name.to_string()));
+            }
+            let remove_len = remove_len as usize;
+
+            let mut value = Vec::with_capacity(base.len() - remove_len + difference.len());
+            if is_front {
+                value.extend_from_slice(&difference[..]);
+                value.extend_from_slice(&base[remove_len..]);
+            }
+            else {
+                value.extend_from_slice(&base[..base.len() - remove_len]);
+                value.extend_from_slice(&difference[..]);
+            }
+            Value::Bytes(value)
+        },
+        _ => return Err(FASTError::InvalidValue(name.to_string())),
+    };
+
+    try!(check_range(scalar_type,&value,name));
+    Ok(Some(value))
+}
+
+fn decode_scalar(reader: &mut Reader,presence_map: &mut PresenceMap,dictionary: &mut Dictionary,scalar_type: ScalarType,operator: &Operator,is_optional: bool,name: &str) -> Result<Option<Value>,FASTError> {
+    let missing_value = || FASTError::MissingValue(name.to_string());
+
+    match operator.kind {
+        OperatorKind::None => read_value(reader,scalar_type,is_optional,name),
+        OperatorKind::Constant => {
+            if !is_optional || presence_map.next_bit() {
+                Ok(operator.initial_value.clone())
+            }
+            else {
+                Ok(None)
+            }
+        },
+        OperatorKind::Default => {
+            if presence_map.next_bit() {
+                read_value(reader,scalar_type,is_optional,name)
+            }
+            else {
+                Ok(operator.initial_value.clone())
+            }
+        },
+        OperatorKind::Copy | OperatorKind::Increment | OperatorKind::Tail => {
+            let index = operator.dictionary_index;
+            if presence_map.next_bit() {
+                let value = if operator.kind == OperatorKind::Tail {
+                    match try!(read_value(reader,scalar_type,is_optional,name)) {
+                        Some(Value::Bytes(tail)) => {
+                            //The tail replaces the same number of characters at the end of the
+                            //previous value.
+                            let base = match *dictionary.get(index) {
+                                DictionaryValue::Assigned(Value::Bytes(ref base)) => base.clone(),
+                                _ => match operator.initial_value {
+                                    Some(Value::Bytes(ref initial_value)) => initial_value.clone(),
+                                    _ => Vec::new(),
+                                },
+                            };
+                            let mut value = if tail.len() < base.len() { base[..base.len() - tail.len()].to_vec() } else { Vec::new() };
+                            value.extend_from_slice(&tail[..]);
+                            Some(Value::Bytes(value))
+                        },
+                        _ => None,
+                    }
+                }
+                else {
+                    try!(read_value(reader,scalar_type,is_optional,name))
+                };
+
+                dictionary.set(index,match value {
+                    Some(ref value) => DictionaryValue::Assigned(value.clone()),
+                    None => DictionaryValue::Empty,
+                });
+                Ok(value)
+            }
+            else {
+                let previous_value = dictionary.get(index).clone();
+                match previous_value {
+                    DictionaryValue::Undefined => match operator.initial_value {
+                        Some(ref initial_value) => {
+                            dictionary.set(index,DictionaryValue::Assigned(initial_value.clone()));
+                            Ok(Some(initial_value.clone()))
+                        },
+                        None if is_optional => {
+                            dictionary.set(index,DictionaryValue::Empty);
+                            Ok(None)
+                        },
+                        None => Err(missing_value()),
+                    },
+                    DictionaryValue::Empty => if is_optional { Ok(None) } else { Err(missing_value()) },
+                    DictionaryValue::Assigned(value) => {
+                        if operator.kind == OperatorKind::Increment {
+                            let value = increment_value(scalar_type,value);
+                            dictionary.set(index,DictionaryValue::Assigned(value.clone()));
+                            Ok(Some(value))
+                        }
+                        else {
+                            Ok(Some(value))
+                        }
+                    },
+                }
+            }
+        },
+        OperatorKind::Delta => {
+            let index = operator.dictionary_index;
+            let base = match *dictionary.get(index) {
+                DictionaryValue::Assigned(ref value) => value.clone(),
+                DictionaryValue::Undefined => operator.initial_value.clone().unwrap_or_else(|| base_value(scalar_type)),
+                DictionaryValue::Empty => return Err(missing_value()),
+            };
+
+            //A null delta leaves the previous value alone.
+            let value = try!(apply_delta(reader,scalar_type,is_optional,base,name));
+            if let Some(ref value) = value {
+                dictionary.set(index,DictionaryValue::Assigned(value.clone()));
+            }
+            Ok(value)
+        },
+    }
+}
+
+fn format_value(value: Value) -> Vec<u8> {
+    match value {
+        Value::Int(value) => value.to_string().into_bytes(),
+        Value::UInt(value) => value.to_string().into_bytes(),
+        Value::Decimal(mantissa,exponent) => {
+            let mut digits = if mantissa < 0 { (mantissa as u64).wrapping_neg() } else { mantissa as u64 }.to_string();
+            if exponent >= 0 {
+                if mantissa != 0 {
+                    for _ in 0..exponent {
+                        digits.push('0');
+                    }
+                }
+            }
+            else {
+                let fraction_len = -exponent as usize;
+                if digits.len() <= fraction_len {
+                    digits = format!("{:0>width$}",digits,width = fraction_len + 1);
+                }
+                let point_index = digits.len() - fraction_len;
+                digits.insert(point_index,'.');
+            }
+
+            if mantissa < 0 {
+                digits.insert(0,'-');
+            }
+            digits.into_bytes()
+        },
+        Value::Bytes(value) => value,
+    }
+}
+
+struct Decoder<'a> {
+    templates: &'a FASTTemplates,
+    dictionary: &'a mut Dictionary,
+    max_sequence_length: u64,
+    max_template_depth: usize,
+    template_depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn decode_segment(&mut self,reader: &mut Reader) -> Result<(u32,Vec<GenericField>),FASTError> {
+        let presence_map_bytes = try!(reader.read_stop_bit_bytes());
+        let mut presence_map = PresenceMap::new(presence_map_bytes);
+
+        let template_id = if presence_map.next_bit() {
+            let template_id = try!(reader.read_uint(false,"TemplateID")).unwrap();
+            if template_id > u32::max_value() as u64 {
+                return Err(FASTError::IntegerOverflow("TemplateID".to_string()));
+            }
+            self.dictionary.set(TEMPLATE_ID_DICTIONARY_INDEX,DictionaryValue::Assigned(Value::UInt(template_id)));
+            template_id as u32
+        }
+        else {
+            match *self.dictionary.get(TEMPLATE_ID_DICTIONARY_INDEX) {
+                DictionaryValue::Assigned(Value::UInt(template_id)) => template_id as u32,
+                _ => return Err(FASTError::MissingTemplateID),
+            }
+        };
+        let templates = self.templates;
+        let template = match templates.templates.get(&template_id) {
+            Some(template) => template,
+            None => return Err(FASTError::TemplateIDUnknown(template_id)),
+        };
+
+        let mut fields = Vec::new();
+        try!(self.decode_instructions(reader,&mut presence_map,&template.instructions,&mut fields));
+
+        Ok((template_id,fields))
+    }
+
+    fn decode_instructions(&mut self,reader: &mut Reader,presence_map: &mut PresenceMap,instructions: &[Instruction],fields: &mut Vec<GenericField>) -> Result<(),FASTError> {
+        for instruction in instructions {
+            let value = match instruction.instruction_type {
+                InstructionType::Scalar(scalar_type,ref operator) => {
+                    try!(decode_scalar(reader,presence_map,self.dictionary,scalar_type,operator,instruction.is_optional,&instruction.name))
+                },
+                InstructionType::SplitDecimal(ref exponent_operator,ref mantissa_operator) => {
+                    //Mantissa is only present when the exponent is.
+                    match try!(decode_scalar(reader,presence_map,self.dictionary,ScalarType::Int32,exponent_operator,instruction.is_optional,&instruction.name)) {
+                        Some(Value::Int(exponent)) => match try!(decode_scalar(reader,presence_map,self.dictionary,ScalarType::Int64,mantissa_operator,false,&instruction.name)) {
+                            Some(Value::Int(mantissa)) => {
+                                let value = Value::Decimal(mantissa,exponent);
+                                try!(check_range(ScalarType::Decimal,&value,&instruction.name));
+                                Some(value)
+                            },
+                            _ => return Err(FASTError::MissingValue(instruction.name.clone())),
+                        },
+                        _ => None,
+                    }
+                },
+                InstructionType::Group(ref group_instructions,has_presence_map) => {
+                    if !instruction.is_optional || presence_map.next_bit() {
+                        try!(self.decode_group(reader,group_instructions,has_presence_map,fields));
+                    }
+                    continue;
+                },
+                InstructionType::Sequence(ref length_instruction,ref group_instructions,has_presence_map) => {
+                    let len = match length_instruction.instruction_type {
+                        InstructionType::Scalar(scalar_type,ref operator) => try!(decode_scalar(reader,presence_map,self.dictionary,scalar_type,operator,instruction.is_optional,&length_instruction.name)),
+                        _ => None,
+                    };
+                    let len = match len {
+                        Some(Value::UInt(len)) => len,
+                        _ => continue,
+                    };
+
+                    //The length comes straight from the wire. Groups made up of constant fields
+                    //don't use any bytes so the length has to be capped explicitly.
+                    if len > self.max_sequence_length {
+                        return Err(FASTError::SequenceTooLong(length_instruction.name.clone(),len));
+                    }
+                    if has_presence_map && len > (reader.buf.len() - reader.offset) as u64 {
+                        //Every group starts with at least one presence map byte.
+                        return Err(FASTError::BufferTooSmall);
+                    }
+
+                    let mut groups = Vec::new();
+                    for _ in 0..len {
+                        let mut group_fields = Vec::new();
+                        try!(self.decode_group(reader,group_instructions,has_presence_map,&mut group_fields));
+                        groups.push(group_fields);
+                    }
+                    if let Some(tag) = length_instruction.tag {
+                        fields.push(GenericField::new_groups(tag,groups));
+                    }
+                    continue;
+                },
+                InstructionType::DynamicTemplateRef => {
+                    if self.template_depth >= self.max_template_depth {
+                        return Err(FASTError::TemplateDepthExceeded);
+                    }
+
+                    self.template_depth += 1;
+                    let result = self.decode_segment(reader);
+                    self.template_depth -= 1;
+                    let (_,template_fields) = try!(result);
+                    fields.extend(template_fields);
+                    continue;
+                },
+            };
+
+            if let (Some(tag),Some(value)) = (instruction.tag,value) {
+                fields.push(GenericField::new(tag,format_value(value)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn decode_group(&mut self,reader: &mut Reader,instructions: &[Instruction],has_presence_map: bool,fields: &mut Vec<GenericField>) -> Result<(),FASTError> {
+        let presence_map_bytes = if has_presence_map { try!(reader.read_stop_bit_bytes()) } else { &[][..] };
+        let mut presence_map = PresenceMap::new(presence_map_bytes);
+
+        self.decode_instructions(reader,&mut presence_map,instructions,fields)
+    }
+}
+
+fn set_fields<T: Message + ?Sized>(message: &mut T,builder: &mut BuildMessage,fields: Vec<GenericField>) -> Result<(),FASTError> {
+    //Templates don't say which message version they describe so every field that was ever part of
+    //the message is accepted.
+    let mut rules = HashMap::new();
+    for message_version in MessageVersion::all() {
+        for (tag,rule) in builder.fields(message_version) {
+            rules.insert(tag,rule);
+        }
+    }
+
+    for field in fields {
+        let tag = field.tag;
+        if BUILT_IN_TAGS.contains(&tag) {
+            continue;
+        }
+
+        match (field.value,rules.get(&tag)) {
+            //Length is implied by the data field that follows.
+            (GenericFieldValue::Value(_),Some(&Rule::PrepareForBytes{ .. })) => {},
+            (GenericFieldValue::Value(value),Some(_)) => {
+                try!(message.set_value(tag,&value[..]).map_err(|err| match err {
+                    SetValueError::WrongFormat => FASTError::WrongFormat(tag),
+                    SetValueError::OutOfRange => FASTError::OutOfRange(tag),
+                }));
+            },
+            (GenericFieldValue::Value(value),None) => match message.extra_fields_mut() {
+                Some(extra_fields) => extra_fields.push((tag,value)),
+                None => return Err(FASTError::UnknownTag(tag)),
+            },
+            (GenericFieldValue::Groups(groups),Some(&Rule::BeginGroup{ builder_func })) => {
+                let mut group_builder = builder_func();
+                let mut group_messages: Vec<Box<Message>> = Vec::with_capacity(groups.len());
+                for group_fields in groups {
+                    let mut group_message = group_builder.build();
+                    try!(set_fields(&mut *group_message,&mut *group_builder,group_fields));
+                    group_messages.push(group_message);
+                }
+                if !message.set_groups(tag,group_messages) {
+                    return Err(FASTError::WrongFormat(tag));
+                }
+            },
+            (GenericFieldValue::Groups(_),Some(_)) => return Err(FASTError::WrongFormat(tag)),
+            (GenericFieldValue::Groups(_),None) => return Err(FASTError::UnknownTag(tag)),
+        }
+    }
+
+    Ok(())
+}
+
+//Decodes a stream of FAST messages. Dictionary state carries over from one message to the next
+//until reset() is called, which feeds usually require at the start of each packet.
+pub struct FASTDecoder {
+    templates: FASTTemplates,
+    dictionary: Dictionary,
+    message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>>,
+    max_sequence_length: u64,
+    max_template_depth: usize,
+}
+
+impl FASTDecoder {
+    pub fn new(templates: FASTTemplates,message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>>) -> FASTDecoder {
+        let dictionary_len = templates.dictionary_len;
+        FASTDecoder {
+            templates: templates,
+            dictionary: Dictionary {
+                values: vec![DictionaryValue::Undefined;dictionary_len],
+                journal: Vec::new(),
+            },
+            message_dictionary: message_dictionary,
+            max_sequence_length: DEFAULT_MAX_SEQUENCE_LENGTH,
+            max_template_depth: DEFAULT_MAX_TEMPLATE_DEPTH,
+        }
+    }
+
+    pub fn set_max_sequence_length(&mut self,max_sequence_length: u64) {
+        //Largest sequence accepted before decoding fails with FASTError::SequenceTooLong.
+        self.max_sequence_length = max_sequence_length;
+    }
+
+    pub fn set_max_template_depth(&mut self,max_template_depth: usize) {
+        //Deepest dynamic template references can be nested before decoding fails with
+        //FASTError::TemplateDepthExceeded.
+        self.max_template_depth = max_template_depth;
+    }
+
+    pub fn reset(&mut self) {
+        for value in &mut self.dictionary.values {
+            *value = DictionaryValue::Undefined;
+        }
+        self.dictionary.journal.clear();
+    }
+
+    //Returns the template ID, the fields in template order, and the number of bytes used.
+    pub fn decode_fields(&mut self,buf: &[u8]) -> Result<(u32,Vec<GenericField>,usize),FASTError> {
+        let mut reader = Reader {
+            buf: buf,
+            offset: 0,
+        };
+        let result = Decoder {
+            templates: &self.templates,
+            dictionary: &mut self.dictionary,
+            max_sequence_length: self.max_sequence_length,
+            max_template_depth: self.max_template_depth,
+            template_depth: 0,
+        }.decode_segment(&mut reader);
+
+        match result {
+            Ok((template_id,fields)) => {
+                self.dictionary.commit();
+                Ok((template_id,fields,reader.offset))
+            },
+            Err(err) => {
+                self.dictionary.rollback();
+                Err(err)
+            },
+        }
+    }
+
+    //Returns the message and the number of bytes used.
+    pub fn decode(&mut self,buf: &[u8]) -> Result<(Box<FIXTMessage + Send>,usize),FASTError> {
+        let (template_id,fields,len) = try!(self.decode_fields(buf));
+
+        let msg_type = match fields.iter().find(|field| field.tag == MSG_TYPE_TAG) {
+            Some(&GenericField { value: GenericFieldValue::Value(ref msg_type), .. }) => msg_type.clone(),
+            _ => self.templates.templates[&template_id].name.as_bytes().to_vec(),
+        };
+
+        if let Some(builder) = self.message_dictionary.get(&msg_type[..]) {
+            let mut message = builder.build();
+            let mut builder = BuildMessage::new_into_box(&**builder);
+            try!(set_fields(&mut *message,&mut *builder,fields));
+            return Ok((message,len));
+        }
+
+        let mut message = GenericMessage::new(&msg_type[..]);
+        for field in fields {
+            if BUILT_IN_TAGS.contains(&field.tag) {
+                continue;
+            }
+
+            let tag = field.tag;
+            let result = match field.value {
+                GenericFieldValue::Value(ref value) => message.set_value(tag,&value[..]),
+                GenericFieldValue::Groups(_) => Ok(()),
+            };
+            try!(result.map_err(|err| match err {
+                SetValueError::WrongFormat => FASTError::WrongFormat(tag),
+                SetValueError::OutOfRange => FASTError::OutOfRange(tag),
+            }));
+            if let GenericFieldValue::Groups(_) = field.value {
+                message.push(field);
+            }
+        }
+
+        Ok((Box::new(message),len))
+    }
+}
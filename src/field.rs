@@ -9,24 +9,142 @@
 // at your option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::borrow::Cow;
+use std::io::{self,Write};
+
+use constant::{TAG_END,VALUE_END};
+use encoder::write_integer;
 use field_tag::FieldTag;
 use field_type::FieldType;
 use fix_version::FIXVersion;
+use message::{InvalidFieldValue,InvalidValuePolicy,MessageField,MissingRequiredField};
 use message_version::MessageVersion;
 use rule::Rule;
 
 pub trait Field {
     type Type;
     fn rule() -> Rule;
+    fn name() -> &'static str;
     fn tag_bytes() -> &'static [u8];
     fn tag() -> FieldTag;
     fn read(field: &<<Self as Field>::Type as FieldType>::Type,fix_version: FIXVersion,message_version: MessageVersion,buf: &mut Vec<u8>,required: bool) -> usize
         where <Self as Field>::Type: FieldType;
+
+    fn write(field: &<<Self as Field>::Type as FieldType>::Type,fix_version: FIXVersion,message_version: MessageVersion,writer: &mut Write,scratch: &mut Vec<u8>,required: bool) -> io::Result<usize>
+        where <Self as Field>::Type: FieldType {
+        //Same bytes as read() but written straight to writer. Returns the number of bytes written.
+        if !required && <<Self as Field>::Type as FieldType>::is_empty(field) {
+            return Ok(0);
+        }
+
+        let mut result = 0;
+
+        match Self::rule() {
+            Rule::PrepareForBytes{ .. } => {
+                return Ok(0);
+            },
+            Rule::ConfirmPreviousTag{ previous_tag } => {
+                result += try!(write_integer(previous_tag,writer));
+                try!(writer.write_all(&[TAG_END]));
+                result += try!(write_integer(<<Self as Field>::Type as FieldType>::len(field),writer));
+                try!(writer.write_all(&[VALUE_END]));
+                result += 2;
+            },
+            Rule::RequiresFIXVersion{ fix_version: required_fix_version } => {
+                if fix_version != required_fix_version {
+                    return Ok(0);
+                }
+            },
+            Rule::RepeatableField => {
+                scratch.clear();
+                <<Self as Field>::Type as FieldType>::read_repeated(field,Self::tag_bytes(),fix_version,message_version,scratch);
+                try!(writer.write_all(&scratch[..]));
+                return Ok(scratch.len());
+            },
+            _ => {},
+        }
+
+        try!(writer.write_all(Self::tag_bytes()));
+        try!(writer.write_all(&[TAG_END]));
+        result += Self::tag_bytes().len() + 1;
+        result += try!(<<Self as Field>::Type as FieldType>::write(field,fix_version,message_version,writer,scratch));
+
+        //Repeating groups write their own VALUE_END after the group count. See read().
+        if let Rule::BeginGroup{ .. } = Self::rule() {}
+        else {
+            try!(writer.write_all(&[VALUE_END]));
+            result += 1;
+        }
+
+        Ok(result)
+    }
+
+    fn sanitize(field: &mut <<Self as Field>::Type as FieldType>::Type,policy: InvalidValuePolicy) -> Result<(),InvalidFieldValue>
+        where <Self as Field>::Type: FieldType {
+        if !<<Self as Field>::Type as FieldType>::sanitize_value(field,policy) {
+            return Err(InvalidFieldValue {
+                tag: Self::tag(),
+                name: Self::name(),
+            });
+        }
+
+        <<Self as Field>::Type as FieldType>::sanitize_groups(field,policy)
+    }
+
+    fn validate(field: &<<Self as Field>::Type as FieldType>::Type,message_version: MessageVersion,required: bool) -> Result<(),MissingRequiredField>
+        where <Self as Field>::Type: FieldType {
+        if required && <<Self as Field>::Type as FieldType>::is_empty(field) {
+            match Self::rule() {
+                //Length fields are filled in from the data field when serialized.
+                Rule::PrepareForBytes{ .. } => {},
+                _ => {
+                    return Err(MissingRequiredField {
+                        tag: Self::tag(),
+                        name: Self::name(),
+                    });
+                },
+            }
+        }
+
+        <<Self as Field>::Type as FieldType>::validate_groups(field,message_version)
+    }
+
+    fn is_set(field: &<<Self as Field>::Type as FieldType>::Type,fix_version: FIXVersion) -> bool
+        where <Self as Field>::Type: FieldType {
+        //Whether the field has a value that would be serialized with fix_version. Length fields are
+        //implied by the data field.
+        if <<Self as Field>::Type as FieldType>::is_empty(field) {
+            return false;
+        }
+
+        match Self::rule() {
+            Rule::PrepareForBytes{ .. } => false,
+            Rule::RequiresFIXVersion{ fix_version: required_fix_version } => fix_version == required_fix_version,
+            _ => true,
+        }
+    }
+
+    fn get_value<'a>(field: &'a <<Self as Field>::Type as FieldType>::Type,fix_version: FIXVersion,message_version: MessageVersion) -> Option<Cow<'a,[u8]>>
+        where <Self as Field>::Type: FieldType {
+        if !Self::is_set(field,fix_version) {
+            return None;
+        }
+
+        Some(<<Self as Field>::Type as FieldType>::read_value(field,fix_version,message_version))
+    }
+
+    fn read_message_fields<'a>(field: &'a <<Self as Field>::Type as FieldType>::Type,fix_version: FIXVersion,message_version: MessageVersion,fields: &mut Vec<MessageField<'a>>)
+        where <Self as Field>::Type: FieldType {
+        if Self::is_set(field,fix_version) {
+            <<Self as Field>::Type as FieldType>::read_message_fields(field,Self::tag(),fix_version,message_version,fields);
+        }
+    }
+
 }
 
 #[macro_export]
 macro_rules! define_fields {
-    ( $( $field_name:ident : $field_type:ty = $tag:expr $( => $rule:expr )* ),* $(),* ) => { $(
+    ( $( $field_name:ident $( ( $fix_name:expr ) )* : $field_type:ty = $tag:expr $( => $rule:expr )* ),* $(),* ) => { $(
         #[derive(BuildField)]
         pub struct $field_name {
             #[tag=$tag]
@@ -55,6 +173,17 @@ macro_rules! define_fields {
                 }
             }
 
+            #[allow(unreachable_code)]
+            fn name() -> &'static str {
+                //If the type's name had to be different from the standard FIX name, prefer the FIX
+                //name so it's used everywhere a field is named, ie. JSON, FIXML, and pretty-printing.
+                $(
+                    return $fix_name //A maximum of one name may be specified.
+                )*;
+
+                stringify!($field_name)
+            }
+
             fn tag_bytes() -> &'static [u8] {
                 Self::tag_bytes()
             }
@@ -95,6 +224,11 @@ macro_rules! define_fields {
                             return 0;
                         }
                     },
+                    //If this tag can be repeated without a repeating group, the field type writes
+                    //each occurrence including the tag.
+                    $crate::rule::Rule::RepeatableField => {
+                        return <$field_type as $crate::field_type::FieldType>::read_repeated(field,Self::tag_bytes(),fix_version,message_version,buf);
+                    },
                     _ => {},
                 };
 
@@ -118,3 +252,53 @@ macro_rules! define_fields {
     )*};
 }
 
+
+//Some tags are used for different repeating groups depending on the message. For example,
+//NoOrders (73) holds a ListOrdGrp in NewOrderList but an OrdListStatGrp in ListStatus. These
+//fields take the group type as a type parameter so each message can bind the tag to its own group
+//type, ie. `REQUIRED, orders: NoOrders<ListOrdGrp> [FIX42..]`.
+#[macro_export]
+macro_rules! define_context_dependent_group_fields {
+    ( $( $field_name:ident = $tag:expr ),* $(),* ) => { $(
+        pub struct $field_name<T> {
+            _group_type: ::std::marker::PhantomData<T>,
+        }
+
+        impl<T: $crate::message::Message + $crate::message::MessageBuildable + ::std::any::Any + Clone + Default + PartialEq + Send + Sized> $crate::field::Field for $field_name<T> {
+            type Type = $crate::dictionary::field_types::generic::RepeatingGroupFieldType<T>;
+
+            fn rule() -> $crate::rule::Rule {
+                <$crate::dictionary::field_types::generic::RepeatingGroupFieldType<T> as $crate::field_type::FieldType>::rule().unwrap()
+            }
+
+            fn name() -> &'static str {
+                stringify!($field_name)
+            }
+
+            fn tag_bytes() -> &'static [u8] {
+                stringify!($tag).as_bytes()
+            }
+
+            fn tag() -> $crate::field_tag::FieldTag {
+                $crate::field_tag::FieldTag($tag)
+            }
+
+            fn read(field: &<<Self as $crate::field::Field>::Type as $crate::field_type::FieldType>::Type,fix_version: $crate::fix_version::FIXVersion,message_version: $crate::message_version::MessageVersion,buf: &mut Vec<u8>,required: bool) -> usize {
+                use ::std::io::Write;
+
+                if !required && <$crate::dictionary::field_types::generic::RepeatingGroupFieldType<T> as $crate::field_type::FieldType>::is_empty(field) {
+                    return 0;
+                }
+
+                //Same as a repeating group from define_fields!: the field type writes the
+                //VALUE_END symbol itself so it can append the groups.
+                let mut result = 1;
+                result += buf.write(<Self as $crate::field::Field>::tag_bytes()).unwrap();
+                buf.push($crate::constant::TAG_END);
+                result += <$crate::dictionary::field_types::generic::RepeatingGroupFieldType<T> as $crate::field_type::FieldType>::read(field,fix_version,message_version,buf);
+
+                result
+            }
+        }
+    )*};
+}
@@ -9,8 +9,12 @@
 // at your option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::borrow::Cow;
+use std::io::{self,Write};
+
+use field_tag::FieldTag;
 use fix_version::FIXVersion;
-use message::{Message,SetValueError};
+use message::{GroupIter,InvalidFieldValue,InvalidValuePolicy,Message,MessageField,MissingRequiredField,SetValueError};
 use message_version::MessageVersion;
 use rule::Rule;
 
@@ -34,5 +38,56 @@ pub trait FieldType {
     fn is_empty(field: &Self::Type) -> bool;
     fn len(field: &Self::Type) -> usize;
     fn read(field: &Self::Type,fix_version: FIXVersion,message_version: MessageVersion,buf: &mut Vec<u8>) -> usize;
+
+    //Same bytes as read() but written straight to writer. Returns the number of bytes written.
+    //Field types that can't write their value directly fall back to read() into the scratch buffer.
+    fn write(field: &Self::Type,fix_version: FIXVersion,message_version: MessageVersion,writer: &mut Write,scratch: &mut Vec<u8>) -> io::Result<usize> {
+        scratch.clear();
+        Self::read(field,fix_version,message_version,scratch);
+        try!(writer.write_all(&scratch[..]));
+        Ok(scratch.len())
+    }
+
+    //Returns false if the value contains a byte that would corrupt the message when serialized
+    //and the policy can't fix it. Field types that hold unchecked bytes (text, chars and reserved
+    //enum values) need to check. Data fields can hold any byte and are never sanitized.
+    fn sanitize_value(_field: &mut Self::Type,_policy: InvalidValuePolicy) -> bool {
+        true
+    }
+
+    fn sanitize_groups(_field: &mut Self::Type,_policy: InvalidValuePolicy) -> Result<(),InvalidFieldValue> {
+        Ok(())
+    }
+
+    fn validate_groups(_field: &Self::Type,_message_version: MessageVersion) -> Result<(),MissingRequiredField> {
+        Ok(())
+    }
+
+    //Only used by field types that specify Rule::RepeatableField. Every occurrence must be written
+    //with its own tag and VALUE_END.
+    fn read_repeated(_field: &Self::Type,_tag_bytes: &[u8],_fix_version: FIXVersion,_message_version: MessageVersion,_buf: &mut Vec<u8>) -> usize {
+        0
+    }
+
+    //Value as it would be written on the wire. Field types that keep the value's bytes as is can
+    //borrow them instead of serializing.
+    fn read_value<'a>(field: &'a Self::Type,fix_version: FIXVersion,message_version: MessageVersion) -> Cow<'a,[u8]> {
+        let mut buf = Vec::new();
+        Self::read(field,fix_version,message_version,&mut buf);
+        Cow::Owned(buf)
+    }
+
+    fn read_message_fields<'a>(field: &'a Self::Type,tag: FieldTag,fix_version: FIXVersion,message_version: MessageVersion,fields: &mut Vec<MessageField<'a>>) {
+        fields.push(MessageField {
+            tag: tag,
+            value: Self::read_value(field,fix_version,message_version),
+            groups: GroupIter::empty(),
+        });
+    }
+
+    //Human readable name of the value for enumerated field types. Ie. "Buy" for a Side of 1.
+    fn value_description(_field: &Self::Type) -> Option<String> {
+        None
+    }
 }
 
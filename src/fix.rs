@@ -10,24 +10,29 @@
 // except according to those terms.
 
 use std::borrow::Borrow;
+use std::cmp;
 use std::collections::{HashMap,HashSet};
 use std::collections::hash_map::Entry;
 use std::fmt;
 use std::iter::FromIterator;
 use std::mem;
 use std::str::FromStr;
+use std::sync::Arc;
 
+use data_dictionary::{DataDictionary,DataDictionaryViolation};
 use constant::{FIX_4_0_BEGIN_STRING,FIX_4_1_BEGIN_STRING,FIX_4_2_BEGIN_STRING,FIX_4_3_BEGIN_STRING,FIX_4_4_BEGIN_STRING,FIXT_1_1_BEGIN_STRING,TAG_END,VALUE_END};
 use dictionary::messages::{Logon,NullMessage};
-use dictionary::fields::{ApplVerID,SenderCompID,TargetCompID};
+use dictionary::fields::{ApplVerID,MsgSeqNum,SenderCompID,SendingTime,TargetCompID};
 use dictionary::field_types::other::DefaultApplVerIDFieldType;
 use field::Field;
 use field_tag::FieldTag;
 use field_type::FieldType;
 use fix_version::FIXVersion;
 use fixt::message::{BuildFIXTMessage,FIXTMessage};
+use generic_message::GenericMessage;
 use hash::BuildFieldHasher;
 use message::{BuildMessage,FieldHashMap,FieldHashSet,Meta,Message,SetValueError};
+use message_view::{MessageView,MessageViewTables,parse_message_view};
 use message_version::MessageVersion;
 use rule::Rule;
 
@@ -68,6 +73,9 @@ pub enum ParseError {
     RepeatingGroupTagWithNoRepeatingGroup(FieldTag), //Repeating group tag was found outside of a repeating group.
     MissingFirstRepeatingGroupTagAfterNumberOfRepeatingGroupTag(FieldTag), //Tag indicating start of a repeating group was not found immediatelly after tag indicating the number of repeating groups.
     MessageSizeTooBig,
+    RepeatingGroupCountTooLarge(FieldTag), //Number of repeating groups tag states more groups than ParseLimits::max_group_count.
+    RepeatingGroupNestedTooDeep(FieldTag), //Repeating group is nested deeper than ParseLimits::max_group_depth.
+    TooManyFields, //Message has more fields than ParseLimits::max_field_count.
 }
 
 fn tag_to_string(tag: &[u8]) -> String {
@@ -102,6 +110,9 @@ impl fmt::Display for ParseError {
             ParseError::RepeatingGroupTagWithNoRepeatingGroup(ref tag) => write!(f,"ParseError::RepeatingGroupTagWithNoRepeatingGroup({})",tag),
             ParseError::MissingFirstRepeatingGroupTagAfterNumberOfRepeatingGroupTag(ref number_of_tag) => write!(f,"ParseError::MissingFirstRepeatingGroupTagAfterNumberOfRepeatingGroupTag({})",number_of_tag),
             ParseError::MessageSizeTooBig => write!(f,"ParseError::MessageSizeTooBig"),
+            ParseError::RepeatingGroupCountTooLarge(ref number_of_tag) => write!(f,"ParseError::RepeatingGroupCountTooLarge({})",number_of_tag),
+            ParseError::RepeatingGroupNestedTooDeep(ref number_of_tag) => write!(f,"ParseError::RepeatingGroupNestedTooDeep({})",number_of_tag),
+            ParseError::TooManyFields => write!(f,"ParseError::TooManyFields"),
         }
     }
 }
@@ -112,6 +123,107 @@ impl fmt::Debug for ParseError {
     }
 }
 
+#[derive(Clone,Debug,PartialEq)]
+pub struct GroupPathEntry {
+    pub number_of_groups_tag: FieldTag,
+    pub name: Option<&'static str>, //Name of the number of groups field if the message defines it.
+    pub index: usize, //Zero based index of the group being parsed.
+}
+
+impl fmt::Display for GroupPathEntry {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name {
+            Some(name) => write!(f,"{}[{}]",name,self.index),
+            None => write!(f,"{}[{}]",self.number_of_groups_tag,self.index),
+        }
+    }
+}
+
+//Where a ParseError was detected. Fields that weren't reached before the error are left empty.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct ParseErrorContext {
+    pub byte_offset: u64, //Offset of the offending byte counted from the first byte parsed. Counts translated bytes when the field delimiter isn't SOH.
+    pub field_ordinal: usize, //One based position of the offending field in the message. BeginStr is 1.
+    pub group_path: Vec<GroupPathEntry>, //Repeating groups enclosing the offending field, outermost first.
+    pub msg_type: Option<Vec<u8>>,
+    pub msg_seq_num: Option<u64>,
+}
+
+impl fmt::Display for ParseErrorContext {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f,"byte {}, field {}",self.byte_offset,self.field_ordinal));
+        if !self.group_path.is_empty() {
+            let group_path: Vec<String> = self.group_path.iter().map(|entry| entry.to_string()).collect();
+            try!(write!(f,", group {}",group_path.join(".")));
+        }
+        if let Some(ref msg_type) = self.msg_type {
+            try!(write!(f,", MsgType {}",tag_to_string(msg_type)));
+        }
+        if let Some(msg_seq_num) = self.msg_seq_num {
+            try!(write!(f,", MsgSeqNum {}",msg_seq_num));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct ValidationConfig {
+    pub ignore_unknown_fields: bool, //Skip tags not defined for the message instead of returning UnknownTag or UnexpectedTag.
+    pub relax_header_ordering: bool, //SenderCompID, TargetCompID, and ApplVerID do not have to be the 4th, 5th, and 6th tags.
+    pub allow_empty_values: bool, //Tags without a value are treated as if they were never specified.
+    pub skip_checksum_verification: bool, //CheckSum must still be the last tag but its value is not checked.
+    pub allow_fields_from_other_message_versions: bool, //Accept tags that belong to the message in any message version.
+    pub capture_unknown_fields: bool, //Store tags not defined for the message in its extra fields. Takes precedence over ignore_unknown_fields.
+}
+
+impl ValidationConfig {
+    pub fn strict() -> ValidationConfig {
+        ValidationConfig::default()
+    }
+
+    pub fn lenient() -> ValidationConfig {
+        ValidationConfig {
+            ignore_unknown_fields: true,
+            relax_header_ordering: true,
+            allow_empty_values: true,
+            skip_checksum_verification: true,
+            allow_fields_from_other_message_versions: true,
+            capture_unknown_fields: false,
+        }
+    }
+}
+
+//Upper bounds on the structure of a single message. Without these, a peer can make the parser do
+//a lot of work by stating a huge NumInGroup count or nesting groups deeply in a message that is
+//still within the maximum message size.
+#[derive(Clone,Debug,PartialEq)]
+pub struct ParseLimits {
+    pub max_group_count: usize, //Largest number of groups accepted for a single repeating group.
+    pub max_group_depth: usize, //Deepest a repeating group can be nested. A group directly in the message has a depth of 1.
+    pub max_field_count: usize, //Most fields accepted in a message, including the header and trailer.
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        //Defaults are well above what the FIX specification uses but low enough to be safe when
+        //accepting connections from the internet.
+        ParseLimits {
+            max_group_count: 1000,
+            max_group_depth: 8,
+            max_field_count: 10000,
+        }
+    }
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum FieldDelimiter {
+    Soh, //Standard delimiter used on the wire.
+    Byte(u8), //Single byte used in place of SOH, ie. b'|' in most logs.
+    CaretA, //Two byte "^A" as printed by terminals and some logging tools.
+    AutoDetect, //Use the byte(s) following the BeginString of the first message found.
+}
+
 struct ParseGroupState {
     remaining_fields: FieldHashMap,
     remaining_required_fields: FieldHashSet,
@@ -202,9 +314,55 @@ fn set_message_value<T: Message + ?Sized>(message: &mut T,tag: FieldTag,bytes: &
     Ok(())
 }
 
+fn set_message_extra_value<T: Message + ?Sized>(message: &mut T,tag: FieldTag,bytes: &[u8]) -> Result<bool,ParseError> {
+    //Returns false if the message doesn't support extra fields so the tag can be handled like any
+    //other unknown tag.
+    if let Some(extra_fields) = message.extra_fields_mut() {
+        if extra_fields.iter().any(|&(extra_tag,_)| extra_tag == tag) {
+            return Err(ParseError::DuplicateTag(tag));
+        }
+
+        extra_fields.push((tag,bytes.to_vec()));
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+fn detect_field_delimiter(bytes: &[u8],max_message_length: u64) -> Option<FieldDelimiter> {
+    //BeginString values are only made of letters, digits, and periods so whatever comes right
+    //after is the delimiter.
+    if let Some(start) = bytes.windows(5).position(|window| window == b"8=FIX") {
+        let mut index = start + 2;
+        while index < bytes.len() {
+            match bytes[index] {
+                b'0'...b'9' | b'A'...b'Z' | b'a'...b'z' | b'.' => {},
+                VALUE_END => return Some(FieldDelimiter::Soh),
+                b'^' if index + 1 == bytes.len() => return None,
+                b'^' if bytes[index + 1] == b'A' => return Some(FieldDelimiter::CaretA),
+                c => return Some(FieldDelimiter::Byte(c)),
+            }
+            index += 1;
+        }
+    }
+    else if bytes.len() as u64 > max_message_length {
+        //No message in sight. Let the parser skip over the garbage as usual.
+        return Some(FieldDelimiter::Soh);
+    }
+
+    None
+}
+
 pub struct Parser {
     message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>>,
     max_message_length: u64,
+    validation_config: ValidationConfig,
+    parse_limits: ParseLimits,
+    field_delimiter: FieldDelimiter,
+    pending_delimiter_bytes: Vec<u8>, //Input held back until the delimiter can be determined.
+    generic_message_fallback: bool, //Parse messages with an unknown MsgType into a GenericMessage.
+    data_dictionary: Option<Arc<DataDictionary>>, //Runtime message definitions parsed into a GenericMessage and validated.
+    message_view_tables: Option<MessageViewTables>, //Built the first time parse_view(), the generic message fallback, or a data dictionary is used.
     default_message_version: MessageVersion,
     default_message_type_version: HashMap<&'static [u8],MessageVersion>,
     value_to_length_tags: HashMap<FieldTag,FieldTag>,
@@ -215,6 +373,7 @@ pub struct Parser {
     message_version: MessageVersion,
     body_length: u64,
     message_type: Vec<u8>,
+    msg_seq_num: Option<u64>, //Only used to describe where a ParseError occurred.
     checksum: u8,
     sender_comp_id: Vec<u8>,
     target_comp_id: Vec<u8>,
@@ -229,6 +388,14 @@ pub struct Parser {
     missing_tag: FieldTag,
     missing_conditional_tag: FieldTag,
     current_message: Box<FIXTMessage + Send>,
+    is_message_prepared: bool, //Message has been built and fields from the body can be stored.
+    is_generic_message: bool, //Message type is unknown and is being parsed into a GenericMessage.
+    generic_message_tags: FieldHashSet, //Tags found outside of repeating groups in the GenericMessage being parsed.
+    current_message_bytes: Vec<u8>, //Raw bytes of the message being parsed, starting with BeginStr.
+    garbled_bytes: Vec<u8>, //Raw bytes of the message that triggered the last ParseError.
+    error_context: ParseErrorContext, //Where the last ParseError occurred.
+    stream_offset: u64, //Total bytes consumed by previous calls to parse().
+    message_start_offset: u64, //Stream offset of the BeginStr tag of the current message.
     pub messages: Vec<Box<FIXTMessage + Send>>,
 }
 
@@ -264,6 +431,13 @@ impl Parser {
         Parser {
             message_dictionary: message_dictionary,
             max_message_length: max_message_length,
+            validation_config: ValidationConfig::default(),
+            parse_limits: ParseLimits::default(),
+            field_delimiter: FieldDelimiter::Soh,
+            pending_delimiter_bytes: Vec::new(),
+            generic_message_fallback: false,
+            data_dictionary: None,
+            message_view_tables: None,
             default_message_version: DefaultApplVerIDFieldType::default_value(),
             default_message_type_version: HashMap::new(),
             value_to_length_tags: value_to_length_tags,
@@ -274,6 +448,7 @@ impl Parser {
             message_version: MessageVersion::FIX40,
             body_length: 0,
             message_type: Vec::new(),
+            msg_seq_num: None,
             checksum: 0,
             sender_comp_id: Vec::new(),
             target_comp_id: Vec::new(),
@@ -288,6 +463,14 @@ impl Parser {
             missing_tag: FieldTag::empty(),
             missing_conditional_tag: FieldTag::empty(),
             current_message: Box::new(NullMessage {}),
+            is_message_prepared: false,
+            is_generic_message: false,
+            generic_message_tags: HashSet::with_hasher(BuildFieldHasher),
+            current_message_bytes: Vec::with_capacity(64),
+            garbled_bytes: Vec::new(),
+            error_context: ParseErrorContext::default(),
+            stream_offset: 0,
+            message_start_offset: 0,
             messages: Vec::new(),
         }
     }
@@ -298,6 +481,7 @@ impl Parser {
         self.current_bytes.clear();
         self.body_length = 0;
         self.message_type.clear();
+        self.msg_seq_num = None;
         self.checksum = 0;
         self.sender_comp_id.clear();
         self.target_comp_id.clear();
@@ -312,6 +496,10 @@ impl Parser {
         self.missing_tag = FieldTag::empty();
         self.missing_conditional_tag = FieldTag::empty();
         self.current_message = Box::new(NullMessage {});
+        self.is_message_prepared = false;
+        self.is_generic_message = false;
+        self.generic_message_tags.clear();
+        self.current_message_bytes.clear();
     }
 
     pub fn set_default_message_version(&mut self,message_version: MessageVersion) {
@@ -338,10 +526,81 @@ impl Parser {
         }
     }
 
+    pub fn set_validation_config(&mut self,validation_config: ValidationConfig) {
+        self.validation_config = validation_config;
+    }
+
+    pub fn validation_config(&self) -> &ValidationConfig {
+        &self.validation_config
+    }
+
+    pub fn set_parse_limits(&mut self,parse_limits: ParseLimits) {
+        self.parse_limits = parse_limits;
+    }
+
+    pub fn parse_limits(&self) -> &ParseLimits {
+        &self.parse_limits
+    }
+
+    pub fn set_field_delimiter(&mut self,field_delimiter: FieldDelimiter) {
+        //Accept messages copied from logs where SOH was replaced by something printable. The
+        //delimiter is translated back to SOH before parsing so BodyLength and CheckSum are
+        //validated exactly like wire data. When auto detecting, the detected delimiter replaces
+        //FieldDelimiter::AutoDetect once the first BeginString is found.
+        self.field_delimiter = field_delimiter;
+        self.pending_delimiter_bytes.clear();
+    }
+
+    pub fn field_delimiter(&self) -> FieldDelimiter {
+        self.field_delimiter
+    }
+
+    pub fn set_generic_message_fallback(&mut self,generic_message_fallback: bool) {
+        //When enabled, messages with a MsgType that isn't in the message dictionary are parsed into
+        //a GenericMessage instead of returning ParseError::MsgTypeUnknown. The dictionary is still
+        //used to find data fields and repeating groups.
+        self.generic_message_fallback = generic_message_fallback;
+        if generic_message_fallback && self.message_view_tables.is_none() {
+            self.message_view_tables = Some(MessageViewTables::new(&mut self.message_dictionary));
+        }
+    }
+
+    pub fn generic_message_fallback(&self) -> bool {
+        self.generic_message_fallback
+    }
+
+    pub fn set_data_dictionary(&mut self,data_dictionary: Option<Arc<DataDictionary>>) {
+        //Messages defined in the data dictionary are parsed into a GenericMessage and then
+        //validated against their definition. Session level messages that are also in the message
+        //dictionary keep using the message dictionary so the engine can still handle them.
+        let mut message_view_tables = MessageViewTables::new(&mut self.message_dictionary);
+        if let Some(ref data_dictionary) = data_dictionary {
+            data_dictionary.extend_message_view_tables(&mut message_view_tables);
+        }
+        self.message_view_tables = Some(message_view_tables);
+        self.data_dictionary = data_dictionary;
+    }
+
+    pub fn data_dictionary(&self) -> Option<&Arc<DataDictionary>> {
+        self.data_dictionary.as_ref()
+    }
+
     pub fn max_message_size(&self) -> u64 {
         self.max_message_length
     }
 
+    pub fn garbled_bytes(&self) -> &[u8] {
+        //Bytes of the message that caused the most recent ParseError, starting at BeginStr and
+        //ending with the byte where the error was detected. Any remaining bytes of the message are
+        //skipped by the next call to parse() while scanning for the next message.
+        &self.garbled_bytes[..]
+    }
+
+    pub fn error_context(&self) -> &ParseErrorContext {
+        //Location of the most recent ParseError.
+        &self.error_context
+    }
+
     pub fn validate_message_dictionary(message_dictionary: &HashMap<&'static [u8],Box<BuildFIXTMessage + Send>>) {
         enum MessageType {
             Standard,
@@ -472,6 +731,10 @@ impl Parser {
     }
 
     fn update_book_keeping(&mut self,c: u8) -> Result<(),ParseError> {
+        //Keep a copy of the raw message so it can be reported if the message turns out to be
+        //garbled.
+        self.current_message_bytes.push(c);
+
         //Update checksum.
         self.checksum = self.checksum.overflowing_add(c).0;
 
@@ -489,10 +752,51 @@ impl Parser {
     }
 
     fn prepare_for_message(&mut self) -> Result<(),ParseError> {
-        if let Some(builder) = self.message_dictionary.get_mut(&self.message_type[..]) {
-            self.current_message = BuildFIXTMessage::build(&**builder);
-            self.remaining_fields = builder.fields(self.message_version);
-            self.remaining_required_fields = builder.required_fields(self.message_version);
+        let use_data_dictionary = match self.data_dictionary {
+            Some(ref data_dictionary) => match data_dictionary.message(&self.message_type[..]) {
+                Some(message_definition) => !message_definition.is_admin || !self.message_dictionary.contains_key(&self.message_type[..]),
+                None => false,
+            },
+            None => false,
+        };
+
+        if !use_data_dictionary {
+            if let Some(builder) = self.message_dictionary.get_mut(&self.message_type[..]) {
+                self.current_message = BuildFIXTMessage::build(&**builder);
+                self.remaining_fields = builder.fields(self.message_version);
+                self.remaining_required_fields = builder.required_fields(self.message_version);
+                self.is_message_prepared = true;
+
+                //Accept fields that only exist in other versions of the message. The message version's
+                //own rules take precedence and only its required fields are enforced.
+                if self.validation_config.allow_fields_from_other_message_versions {
+                    for message_version in MessageVersion::all() {
+                        if message_version == self.message_version {
+                            continue;
+                        }
+
+                        for (tag,rule) in builder.fields(message_version) {
+                            self.remaining_fields.entry(tag).or_insert(rule);
+                        }
+                    }
+                }
+
+                return Ok(());
+            }
+        }
+
+        //Any tag is accepted by a generic message but the standard header must still be
+        //complete. Messages from a data dictionary are validated once they are complete.
+        if use_data_dictionary || self.generic_message_fallback {
+            self.current_message = Box::new(GenericMessage::new(&self.message_type[..]));
+            self.remaining_fields.clear();
+            self.remaining_required_fields.clear();
+            self.remaining_required_fields.insert(SenderCompID::tag());
+            self.remaining_required_fields.insert(TargetCompID::tag());
+            self.remaining_required_fields.insert(MsgSeqNum::tag());
+            self.remaining_required_fields.insert(SendingTime::tag());
+            self.is_message_prepared = true;
+            self.is_generic_message = true;
 
             return Ok(());
         }
@@ -521,6 +825,12 @@ impl Parser {
             checksum = checksum.overflowing_sub(*c).0;
         }
 
+        //Counterparty is trusted to send the message intact.
+        if self.validation_config.skip_checksum_verification {
+            self.checksum = checksum;
+            return Ok(());
+        }
+
         match ascii_to_integer::<u8>(checksum_bytes) {
             Ok(stated_checksum) => if checksum != stated_checksum {
                 return Err(ParseError::ChecksumDoesNotMatch(checksum,stated_checksum));
@@ -568,6 +878,10 @@ impl Parser {
             //Act like the BeginStr tag was parsed so we don't duplicate work.
             self.current_tag = BEGINSTR_TAG;
             self.checksum = BEGINSTR_TAG_BYTES[0] + TAG_END;
+            self.message_start_offset = (self.stream_offset + *index as u64).saturating_sub(BEGINSTR_TAG_BYTES.len() as u64);
+            self.current_message_bytes.clear();
+            self.current_message_bytes.extend_from_slice(BEGINSTR_TAG_BYTES);
+            self.current_message_bytes.push(TAG_END);
             *index += 1;
         }
         else if previous_byte == b'8' && *index == message_bytes.len() {
@@ -604,7 +918,17 @@ impl Parser {
             &Rule::BeginGroup{ builder_func: repeating_group_builder_func } => {
                 let repeating_group_builder = repeating_group_builder_func();
                 match ascii_to_integer::<usize>(&self.current_bytes) {
+                    Ok(group_count) if group_count > self.parse_limits.max_group_count => {
+                        return Err(ParseError::RepeatingGroupCountTooLarge(self.current_tag));
+                    },
                     Ok(group_count) if group_count > 0 => {
+                        let group_depth = self.tag_rule_mode_stack.iter().filter(|tag_rule_mode| {
+                            if let TagRuleMode::RepeatingGroups(_) = ***tag_rule_mode { true } else { false }
+                        }).count() + 1;
+                        if group_depth > self.parse_limits.max_group_depth {
+                            return Err(ParseError::RepeatingGroupNestedTooDeep(self.current_tag));
+                        }
+
                         let first_field = repeating_group_builder.first_field(self.message_version);
                         self.tag_rule_mode_stack.push(Box::new(TagRuleMode::RepeatingGroups(Box::new(ParseRepeatingGroupState {
                             number_of_tag: self.current_tag,
@@ -631,6 +955,7 @@ impl Parser {
             },
             &Rule::ConfirmPreviousTag{ .. } => {}, //Must be checked after parsing tag and before parsing value.
             &Rule::RequiresFIXVersion{ .. } => {}, //Unused by parser.
+            &Rule::RepeatableField => {}, //Later occurrences are allowed when checking for duplicate tags.
         }
 
        Ok(skip_set_value)
@@ -718,6 +1043,18 @@ impl Parser {
     }
 
     fn match_value_end(&mut self,index: &mut usize,message_bytes: &[u8]) -> Result<MessageEnd,ParseError> {
+        //Remember MsgSeqNum as soon as it's found so it can be reported if the rest of the message
+        //turns out to be garbled.
+        if self.current_tag == MsgSeqNum::tag() && self.found_tag_count > 2 {
+            self.msg_seq_num = ascii_to_integer::<u64>(&self.current_bytes).ok();
+        }
+
+        //Stop parsing a message with an unreasonable number of fields before it uses up any more
+        //memory. The current tag isn't included in found_tag_count yet.
+        if self.found_tag_count >= self.parse_limits.max_field_count {
+            return Err(ParseError::TooManyFields);
+        }
+
         //Validate that the first three tags of a message are, in order: BeginStr,
         //BodyLength, and MsgType.
         if self.found_tag_count == 0 {
@@ -782,16 +1119,19 @@ impl Parser {
                 try!(self.prepare_for_message());
             }
         }
-        else if self.found_tag_count == 3 && self.fix_version == FIXVersion::FIXT_1_1 {
+        else if self.found_tag_count == 3 && self.fix_version == FIXVersion::FIXT_1_1 &&
+                (self.current_tag == SenderCompID::tag() || !self.validation_config.relax_header_ordering) {
             //FIXT.1.1 requires the fourth field to be SenderCompID. Older FIX versions use generic
             //field handling because the order doesn't matter but the field is stil required.
+            //When header ordering is relaxed, any other tag starts the body of the message early.
             if self.current_tag != SenderCompID::tag() {
                 return Err(ParseError::SenderCompIDNotFourthTag);
             }
 
             self.sender_comp_id = self.current_bytes.clone();
         }
-        else if self.found_tag_count == 4 && self.fix_version == FIXVersion::FIXT_1_1 {
+        else if self.found_tag_count == 4 && self.fix_version == FIXVersion::FIXT_1_1 && !self.is_message_prepared &&
+                (self.current_tag == TargetCompID::tag() || !self.validation_config.relax_header_ordering) {
             //FIXT.1.1 requires the fifth field to be TargetCompID. Older FIX versions use generic
             //field handling because the order doesn't matter but the field is stil required.
             if self.current_tag != TargetCompID::tag() {
@@ -801,13 +1141,18 @@ impl Parser {
             self.target_comp_id = self.current_bytes.clone();
         }
         else if self.current_bytes.is_empty() {
-            //Tag was provided without a value.
-            return Err(ParseError::NoValueAfterTag(self.current_tag.clone()));
+            //Tag was provided without a value. When allowed, the tag is treated as if it was never
+            //specified. CheckSum is always required to end the message though.
+            if !self.validation_config.allow_empty_values || self.current_tag == CHECKSUM_TAG {
+                return Err(ParseError::NoValueAfterTag(self.current_tag.clone()));
+            }
         }
         else {
             //FIXT.1.1 requires that if the ApplVerID tag is specified, it must be the sixth field.
+            //This is the first tag after SenderCompID and TargetCompID unless header ordering is
+            //relaxed.
             let mut skip_set_value = false;
-            if self.found_tag_count == 5 && self.fix_version == FIXVersion::FIXT_1_1 {
+            if !self.is_message_prepared && self.fix_version == FIXVersion::FIXT_1_1 {
                 //Handle if this is the optional ApplVerID field. This can override all other
                 //methods for determining what FIX version this message is expected to adhere to.
                 if self.current_tag == ApplVerID::tag() {
@@ -830,20 +1175,30 @@ impl Parser {
                 try!(self.prepare_for_message());
 
                 //Start the message by filling out the SenderCompID and TargetCompID portions of
-                //message. These fields are always required for FIXT.1.1 messages.
-                try!(set_message_value(&mut *self.current_message,SenderCompID::tag(),&self.sender_comp_id[..]));
-                self.remaining_fields.remove(&SenderCompID::tag());
-                self.remaining_required_fields.remove(&SenderCompID::tag());
-                try!(set_message_value(&mut *self.current_message,TargetCompID::tag(),&self.target_comp_id[..]));
-                self.remaining_fields.remove(&TargetCompID::tag());
-                self.remaining_required_fields.remove(&TargetCompID::tag());
+                //message. These fields are always required for FIXT.1.1 messages. If header
+                //ordering is relaxed, they might not have been found yet and will be handled like
+                //any other field when they are.
+                if self.found_tag_count > 3 {
+                    try!(set_message_value(&mut *self.current_message,SenderCompID::tag(),&self.sender_comp_id[..]));
+                    self.remaining_fields.remove(&SenderCompID::tag());
+                    self.remaining_required_fields.remove(&SenderCompID::tag());
+                }
+                if self.found_tag_count > 4 {
+                    try!(set_message_value(&mut *self.current_message,TargetCompID::tag(),&self.target_comp_id[..]));
+                    self.remaining_fields.remove(&TargetCompID::tag());
+                    self.remaining_required_fields.remove(&TargetCompID::tag());
+                }
 
                 //Mark ApplVerID as found so we produce an error if it's encountered anywhere else
-                //in the message.
+                //in the message. When header ordering is relaxed, a later ApplVerID is stored like
+                //any other field but does not change the message version.
                 if self.current_tag == ApplVerID::tag() {
                     try!(set_message_value(&mut *self.current_message,ApplVerID::tag(),&self.current_bytes[..]));
+                    self.remaining_fields.remove(&ApplVerID::tag());
+                }
+                else if !self.validation_config.relax_header_ordering {
+                    self.remaining_fields.remove(&ApplVerID::tag());
                 }
-                self.remaining_fields.remove(&ApplVerID::tag());
             }
 
             //Make sure checksum checks out when done reading a message.
@@ -904,10 +1259,49 @@ impl Parser {
 
                         if !tag_in_group {
                             //Figure out if this is an error or the end of the group.
-                            if prgs.group_builder.fields(self.message_version).contains_key(&self.current_tag) {
+                            let group_fields = prgs.group_builder.fields(self.message_version);
+                            if let Some(&Rule::RepeatableField) = group_fields.get(&self.current_tag) {
+                                //Legacy field that can be repeated without a repeating group.
+                                //Append the value to the current group.
+                                if let Some(group) = prgs.groups.last_mut() {
+                                    try!(set_message_value(&mut *group.message,self.current_tag,&self.current_bytes[..]));
+                                    tag_in_group = true;
+                                    break;
+                                }
+                            }
+
+                            if group_fields.contains_key(&self.current_tag) {
                                 return Err(ParseError::DuplicateTag(self.current_tag.clone()));
                             }
                             else if prgs.groups.len() < prgs.group_count {
+                                //A tag belonging to the message that was already found can't
+                                //show up again inside of the group.
+                                let message_version = self.message_version;
+                                let current_tag = self.current_tag;
+                                let is_message_field = self.message_dictionary.get_mut(&self.message_type[..]).map_or(false,|builder| builder.fields(message_version).contains_key(&current_tag));
+                                if is_message_field && !self.remaining_fields.contains_key(&current_tag) {
+                                    return Err(ParseError::DuplicateTag(current_tag));
+                                }
+                                let is_unknown_tag = !is_message_field && !self.remaining_fields.contains_key(&current_tag);
+
+                                //When capturing unknown fields, a tag that doesn't belong anywhere
+                                //in the message is stored with the current group.
+                                if self.validation_config.capture_unknown_fields && is_unknown_tag {
+                                    if let Some(group) = prgs.groups.last_mut() {
+                                        if try!(set_message_extra_value(&mut *group.message,self.current_tag,&self.current_bytes[..])) {
+                                            tag_in_group = true;
+                                            break;
+                                        }
+                                    }
+                                }
+
+                                //When ignoring unknown fields, a tag that doesn't belong anywhere
+                                //in the message is dropped without ending the group early.
+                                if self.validation_config.ignore_unknown_fields && is_unknown_tag {
+                                    tag_in_group = true;
+                                    break;
+                                }
+
                                 return Err(ParseError::NonRepeatingGroupTagInRepeatingGroup(self.current_tag));
                             }
 
@@ -941,16 +1335,35 @@ impl Parser {
                 //fields were found once we are done parsing the message.
                 self.remaining_required_fields.remove(&self.current_tag);
 
+                //Generic messages accept any tag in any order. Only tags that can belong to a
+                //repeating group are allowed to repeat. Data fields still need to be read by
+                //length so the data can contain SOH.
+                if self.is_generic_message {
+                    let is_group_tag = self.message_view_tables.as_ref().unwrap().is_group_tag(self.current_tag);
+                    if !is_group_tag && !self.generic_message_tags.insert(self.current_tag) {
+                        return Err(ParseError::DuplicateTag(self.current_tag));
+                    }
+
+                    let data_tag = self.message_view_tables.as_ref().unwrap().data_tag(self.current_tag);
+                    if let Some(bytes_tag) = data_tag {
+                        try!(self.handle_rule_after_value(&Rule::PrepareForBytes{ bytes_tag: bytes_tag }));
+                    }
+                }
                 //Mark field as found so we can quickly check if a duplicate tag was
                 //encountered. As a side effect, we also handle any tag specific
                 //rules in consequence of being encountered.
-                if let Some(rule) = self.remaining_fields.remove(&self.current_tag) {
+                else if let Some(rule) = self.remaining_fields.remove(&self.current_tag) {
                     skip_set_value = try!(self.handle_rule_after_value(&rule));
                 }
                 else {
                     if self.is_current_tag_known() {
                         let current_message_builder = self.message_dictionary.get_mut(&self.message_type[..]).unwrap();
-                        if current_message_builder.fields(self.message_version).contains_key(&self.current_tag) {
+                        let message_fields = current_message_builder.fields(self.message_version);
+                        if let Some(&Rule::RepeatableField) = message_fields.get(&self.current_tag) {
+                            //Legacy field that can be repeated without a repeating group (ie.
+                            //RelatedSym in FIX 4.0 and 4.1). The value is appended below.
+                        }
+                        else if message_fields.contains_key(&self.current_tag) {
                             //Special case where if ApplVerID tag is encountered after the sixth
                             //tag. This needs its own error so the correct SessionRejectReason can
                             //be specified in a Reject message.
@@ -960,10 +1373,22 @@ impl Parser {
 
                             return Err(ParseError::DuplicateTag(self.current_tag.clone()));
                         }
+                        else if self.validation_config.capture_unknown_fields && try!(set_message_extra_value(&mut *self.current_message,self.current_tag,&self.current_bytes[..])) {
+                            skip_set_value = true;
+                        }
+                        else if self.validation_config.ignore_unknown_fields {
+                            skip_set_value = true;
+                        }
                         else {
                             return Err(ParseError::UnexpectedTag(self.current_tag.clone()));
                         }
                     }
+                    else if self.validation_config.capture_unknown_fields && try!(set_message_extra_value(&mut *self.current_message,self.current_tag,&self.current_bytes[..])) {
+                        skip_set_value = true;
+                    }
+                    else if self.validation_config.ignore_unknown_fields {
+                        skip_set_value = true;
+                    }
                     else {
                         return Err(ParseError::UnknownTag(self.current_tag.clone()));
                     }
@@ -1005,6 +1430,34 @@ impl Parser {
                     }
                 }
 
+                //Generic messages are parsed flat. Now that all of the fields are known, move
+                //repeating groups under their number of groups field.
+                if self.is_generic_message {
+                    let tables = self.message_view_tables.as_ref().unwrap();
+                    try!(self.current_message.as_any_mut().downcast_mut::<GenericMessage>().unwrap().nest_groups(tables,&self.parse_limits));
+
+                    let violation = match self.data_dictionary {
+                        Some(ref data_dictionary) => {
+                            let message = self.current_message.as_any().downcast_ref::<GenericMessage>().unwrap();
+                            data_dictionary.validate(message,self.validation_config.ignore_unknown_fields).err()
+                        },
+                        None => None,
+                    };
+                    match violation {
+                        Some(DataDictionaryViolation::MissingRequiredTag(tag)) => {
+                            return Err(
+                                ParseError::MissingRequiredTag(
+                                    tag,
+                                    mem::replace(&mut self.current_message,Box::new(NullMessage {}))));
+                        },
+                        Some(DataDictionaryViolation::UnknownTag(tag)) => return Err(ParseError::UnknownTag(tag)),
+                        Some(DataDictionaryViolation::UnexpectedTag(tag)) => return Err(ParseError::UnexpectedTag(tag)),
+                        Some(DataDictionaryViolation::WrongFormatTag(tag)) => return Err(ParseError::WrongFormatTag(tag)),
+                        Some(DataDictionaryViolation::OutOfRangeTag(tag)) => return Err(ParseError::OutOfRangeTag(tag)),
+                        None => {},
+                    }
+                }
+
                 //Store meta info about the message. Mainly for debugging.
                 self.current_message.set_meta(Meta {
                     begin_string: self.fix_version,
@@ -1044,6 +1497,35 @@ impl Parser {
         Ok(MessageEnd::No)
     }
 
+    fn build_error_context(&self) -> ParseErrorContext {
+        //Walk the repeating groups being parsed, outermost first. Each group's name comes from the
+        //message or group that contains it.
+        let mut group_path = Vec::new();
+        let mut parent_group: Option<&Box<Message>> = None;
+        for tag_rule_mode in &self.tag_rule_mode_stack {
+            if let TagRuleMode::RepeatingGroups(ref prgs) = **tag_rule_mode {
+                let name = match parent_group {
+                    Some(group) => group.field_name(prgs.number_of_tag),
+                    None => self.current_message.field_name(prgs.number_of_tag),
+                };
+                group_path.push(GroupPathEntry {
+                    number_of_groups_tag: prgs.number_of_tag,
+                    name: name,
+                    index: prgs.groups.len().saturating_sub(1),
+                });
+                parent_group = prgs.groups.last().map(|group| &group.message);
+            }
+        }
+
+        ParseErrorContext {
+            byte_offset: self.message_start_offset + (self.current_message_bytes.len() as u64).saturating_sub(1),
+            field_ordinal: self.found_tag_count + 1,
+            group_path: group_path,
+            msg_type: if self.message_type.is_empty() { None } else { Some(self.message_type.clone()) },
+            msg_seq_num: self.msg_seq_num,
+        }
+    }
+
     fn is_current_tag_known(&mut self) -> bool {
         for message in self.message_dictionary.values_mut() {
             if message.fields(self.message_version).contains_key(&self.current_tag) {
@@ -1054,13 +1536,107 @@ impl Parser {
         false
     }
 
+    pub fn parse_view<'a>(&'a mut self,message_bytes: &'a [u8]) -> (usize,Result<Option<MessageView<'a>>,ParseError>) {
+        //Alternative to parse() that borrows the message from message_bytes instead of building
+        //a FIXTMessage. Only the framing, BodyLength, CheckSum, and ParseLimits are validated.
+        //Unlike parse(), no state is kept between calls so an entire message must be available at
+        //once. Ok(None) means more bytes are needed.
+        if self.message_view_tables.is_none() {
+            self.message_view_tables = Some(MessageViewTables::new(&mut self.message_dictionary));
+        }
+
+        parse_message_view(self.message_view_tables.as_ref().unwrap(),self.max_message_length,&self.validation_config,&self.parse_limits,message_bytes)
+    }
+
     pub fn parse(&mut self,message_bytes: &[u8]) -> (usize,Result<(),ParseError>) {
         //Parse and bytes as possible. Either all bytes will be consumed or all bytes up until a
         //parse error is triggered -- whatever happens first.
+        if self.field_delimiter != FieldDelimiter::Soh {
+            return self.parse_with_field_delimiter(message_bytes);
+        }
+
+        self.parse_soh_delimited(message_bytes)
+    }
+
+    fn parse_with_field_delimiter(&mut self,message_bytes: &[u8]) -> (usize,Result<(),ParseError>) {
+        let mut input_bytes = mem::replace(&mut self.pending_delimiter_bytes,Vec::new());
+        let pending_len = input_bytes.len();
+        input_bytes.extend_from_slice(message_bytes);
+
+        let field_delimiter = match self.field_delimiter {
+            FieldDelimiter::AutoDetect => match detect_field_delimiter(&input_bytes[..],self.max_message_length) {
+                Some(field_delimiter) => {
+                    self.field_delimiter = field_delimiter;
+                    field_delimiter
+                },
+                None => {
+                    //Not enough bytes to tell yet.
+                    self.pending_delimiter_bytes = input_bytes;
+                    return (message_bytes.len(),Ok(()));
+                },
+            },
+            field_delimiter => field_delimiter,
+        };
+
+        //Translate into SOH delimited bytes while remembering where each translated byte ends in
+        //the input so the number of bytes parsed can be reported in terms of the input.
+        //ParseErrorContext::byte_offset is in terms of the translated bytes.
+        let mut translated_bytes = Vec::with_capacity(input_bytes.len());
+        let mut input_offsets = Vec::with_capacity(input_bytes.len());
+        let mut index = 0;
+        while index < input_bytes.len() {
+            let c = input_bytes[index];
+            match field_delimiter {
+                FieldDelimiter::Byte(delimiter) if c == delimiter => translated_bytes.push(VALUE_END),
+                FieldDelimiter::CaretA if c == b'^' => {
+                    if index + 1 == input_bytes.len() {
+                        //Delimiter might be split between calls.
+                        self.pending_delimiter_bytes.push(c);
+                        break;
+                    }
+                    else if input_bytes[index + 1] == b'A' {
+                        translated_bytes.push(VALUE_END);
+                        index += 1;
+                    }
+                    else {
+                        translated_bytes.push(c);
+                    }
+                },
+                _ => translated_bytes.push(c),
+            }
+
+            index += 1;
+            input_offsets.push(index);
+        }
+
+        let (translated_index,result) = self.parse_soh_delimited(&translated_bytes[..]);
+        let input_index = if translated_index == translated_bytes.len() {
+            input_bytes.len()
+        }
+        else {
+            //Parsing stopped early. Bytes held back from this call will be passed in again but
+            //bytes held back from previous calls were already reported as parsed, so keep any that
+            //weren't reached for the next call.
+            let input_index = if translated_index == 0 { 0 } else { input_offsets[translated_index - 1] };
+            self.pending_delimiter_bytes = input_bytes[cmp::min(input_index,pending_len)..pending_len].to_vec();
+            input_index
+        };
+
+        (input_index.saturating_sub(pending_len),result)
+    }
+
+    fn parse_soh_delimited(&mut self,message_bytes: &[u8]) -> (usize,Result<(),ParseError>) {
         let mut index = 0;
-        match self.parse_private(&mut index,message_bytes) {
+        let result = self.parse_private(&mut index,message_bytes);
+        self.stream_offset += index as u64;
+        match result {
             Ok(_) => (index,Ok(())),
             Err(err) => {
+                //Hold onto the offending bytes and where the error occurred so the owner of the
+                //parser can see exactly what was received.
+                self.error_context = self.build_error_context();
+                mem::swap(&mut self.garbled_bytes,&mut self.current_message_bytes);
+
                 //Reset automatically so the next parse won't fail immediatelly.
                 self.reset_parser();
 
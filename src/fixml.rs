@@ -0,0 +1,528 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//FIXML encoding using the abbreviated element and attribute names from the FIXML schema. A message
+//is written as `<FIXML v="..."><Order ID="..."><Hdr SID="..." .../><Instrmt Sym="..."/></Order>
+//</FIXML>`. Fields become attributes, component blocks become a single child element, and each
+//repeating group becomes one child element per group.
+//
+//Abbreviations are only known for the fields listed in FIELD_ABBREVIATIONS: the standard header,
+//orders and executions, lists, allocations, the Instrument, InstrumentLeg, and
+//UnderlyingInstrument component blocks, Email, News, and BusinessMessageReject. Any other field is
+//written using its standard FIX name, which is NOT conformant FIXML, and user-defined fields use
+//their tag number. Session level messages aren't part of FIXML at all.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use fix_version::FIXVersion;
+use fixt::message::{BuildFIXTMessage,FIXTMessage};
+use json::{JsonError,JsonValue,read_json_fields,set_json_fields};
+use message::{BuildMessage,Message};
+use message_version::MessageVersion;
+use rule::Rule;
+use xml::{self,Element};
+
+//Used for message types that don't have a FIXML name.
+const UNKNOWN_MESSAGE_ELEMENT: &'static str = "Msg";
+const UNKNOWN_MESSAGE_TYPE_ATTRIBUTE: &'static str = "MsgTyp";
+
+//Attribute used for each occurrence of a field that can be repeated without a repeating group.
+const REPEATABLE_VALUE_ATTRIBUTE: &'static str = "Val";
+
+static MESSAGE_NAMES: ::phf::Map<&'static [u8],&'static str> = phf_map! {
+    //Session level messages aren't part of FIXML so their full names are used instead.
+    b"0" => "Heartbeat",
+    b"1" => "TestRequest",
+    b"2" => "ResendRequest",
+    b"3" => "Reject",
+    b"4" => "SequenceReset",
+    b"5" => "Logout",
+    b"A" => "Logon",
+
+    b"8" => "ExecRpt",
+    b"9" => "OrdCxlRej",
+    b"B" => "News",
+    b"C" => "Email",
+    b"D" => "Order",
+    b"E" => "NewOrdList",
+    b"F" => "OrdCxlReq",
+    b"G" => "OrdCxlRplcReq",
+    b"H" => "OrdStatReq",
+    b"J" => "AllocInstrctn",
+    b"N" => "ListStat",
+    b"P" => "AllocInstrctnAck",
+    b"j" => "BizMsgRej",
+    b"AE" => "TrdCaptRpt",
+    b"AR" => "TrdCaptRptAck",
+    b"AS" => "AllocRpt",
+};
+
+//Dictionary field name => (Component block element or None, Abbreviation). Repeating groups use
+//the abbreviation as the element name of each group.
+static FIELD_ABBREVIATIONS: ::phf::Map<&'static str,(Option<&'static str>,&'static str)> = phf_map! {
+    //Standard header.
+    "ApplVerID" => (Some("Hdr"),"ApplVerID"),
+    "ApplExtID" => (Some("Hdr"),"ApplExtID"),
+    "CstmApplVerID" => (Some("Hdr"),"CstmApplVerID"),
+    "SenderCompID" => (Some("Hdr"),"SID"),
+    "TargetCompID" => (Some("Hdr"),"TID"),
+    "OnBehalfOfCompID" => (Some("Hdr"),"OBID"),
+    "DeliverToCompID" => (Some("Hdr"),"D2ID"),
+    "SecureData" => (Some("Hdr"),"SecDat"),
+    "MsgSeqNum" => (Some("Hdr"),"SeqNum"),
+    "SenderSubID" => (Some("Hdr"),"SSub"),
+    "SenderLocationID" => (Some("Hdr"),"SLoc"),
+    "TargetSubID" => (Some("Hdr"),"TSub"),
+    "TargetLocationID" => (Some("Hdr"),"TLoc"),
+    "OnBehalfOfSubID" => (Some("Hdr"),"OBSub"),
+    "OnBehalfOfLocationID" => (Some("Hdr"),"OBLoc"),
+    "DeliverToSubID" => (Some("Hdr"),"D2Sub"),
+    "DeliverToLocationID" => (Some("Hdr"),"D2Loc"),
+    "PossDupFlag" => (Some("Hdr"),"PosDup"),
+    "PossResend" => (Some("Hdr"),"PosRsnd"),
+    "SendingTime" => (Some("Hdr"),"Snt"),
+    "OrigSendingTime" => (Some("Hdr"),"OrigSnt"),
+    "XmlData" => (Some("Hdr"),"XmlData"),
+    "MessageEncoding" => (Some("Hdr"),"MsgEncd"),
+    "LastMsgSeqNumProcessed" => (Some("Hdr"),"LastMsgSeqNumProcd"),
+    "OnBehalfOfSendingTime" => (Some("Hdr"),"OBSnt"),
+    "NoHops" => (Some("Hdr"),"Hop"),
+    "HopCompID" => (None,"ID"),
+    "HopSendingTime" => (None,"Snt"),
+    "HopRefID" => (None,"Ref"),
+
+    //Orders.
+    "Account" => (None,"Acct"),
+    "AvgPx" => (None,"AvgPx"),
+    "ClOrdID" => (None,"ID"),
+    "Currency" => (None,"Ccy"),
+    "CumQty" => (None,"CumQty"),
+    "CxlQty" => (None,"CxlQty"),
+    "HandlInst" => (None,"HandlInst"),
+    "LeavesQty" => (None,"LeavesQty"),
+    "MaxFloor" => (None,"MaxFloor"),
+    "MinQty" => (None,"MinQty"),
+    "OrderID" => (None,"OrdID"),
+    "OrderQty" => (Some("OrdQty"),"Qty"),
+    "OrdStatus" => (None,"Stat"),
+    "OrdType" => (None,"Typ"),
+    "Price" => (None,"Px"),
+    "SecondaryClOrdID" => (None,"ID2"),
+    "SecondaryOrderID" => (None,"OrdID2"),
+    "SettlDate" => (None,"SettlDt"),
+    "SettlType" => (None,"SettlTyp"),
+    "Side" => (None,"Side"),
+    "TimeInForce" => (None,"TmInForce"),
+    "TransactTime" => (None,"TxnTm"),
+
+    //Lists.
+    "BidID" => (None,"BidID"),
+    "BidType" => (None,"BidTyp"),
+    "ClientBidID" => (None,"ClBidID"),
+    "ListExecInst" => (None,"ListExecInst"),
+    "ListID" => (None,"ListID"),
+    "ListOrderStatus" => (None,"ListOrdStat"),
+    "ListSeqNo" => (None,"ListSeqNo"),
+    "ListStatusText" => (None,"ListStatTxt"),
+    "ListStatusType" => (None,"ListStatTyp"),
+    "NoOrders" => (None,"Ord"),
+    "NoRpts" => (None,"NoRpts"),
+    "ProgPeriodInterval" => (None,"ProgPrdIntvl"),
+    "ProgRptReqs" => (None,"ProgRptReqs"),
+    "RptSeq" => (None,"RptSeq"),
+    "TotNoOrders" => (None,"TotNoOrds"),
+
+    //Allocations.
+    "AllocAccount" => (None,"Acct"),
+    "AllocAcctIDSource" => (None,"ActIDSrc"),
+    "AllocID" => (None,"ID"),
+    "AllocPrice" => (None,"Px"),
+    "AllocQty" => (None,"Qty"),
+    "AllocSettlCurrency" => (None,"SettlCcy"),
+    "AllocTransType" => (None,"TransTyp"),
+    "AllocType" => (None,"Typ"),
+    "IndividualAllocID" => (None,"IndAllocID"),
+    "NoAllocs" => (None,"Alloc"),
+    "OrderAvgPx" => (None,"AvgPx"),
+    "OrderBookingQty" => (None,"OrdBkngQty"),
+    "Quantity" => (None,"Qty"),
+    "RefAllocID" => (None,"RefID"),
+    "TradeDate" => (None,"TrdDt"),
+
+    //Instrument component block.
+    "CFICode" => (Some("Instrmt"),"CFI"),
+    "ContractMultiplier" => (Some("Instrmt"),"Mult"),
+    "CouponRate" => (Some("Instrmt"),"CpnRt"),
+    "EncodedIssuer" => (Some("Instrmt"),"EncIssr"),
+    "EncodedSecurityDesc" => (Some("Instrmt"),"EncSecDesc"),
+    "Issuer" => (Some("Instrmt"),"Issr"),
+    "MaturityDate" => (Some("Instrmt"),"MatDt"),
+    "MaturityMonthYear" => (Some("Instrmt"),"MMY"),
+    "NoEvents" => (Some("Instrmt"),"Evnt"),
+    "NoInstrumentParties" => (Some("Instrmt"),"Pty"),
+    "NoSecurityAltID" => (Some("Instrmt"),"AID"),
+    "Product" => (Some("Instrmt"),"Prod"),
+    "PutOrCall" => (Some("Instrmt"),"PutCall"),
+    "SecurityDesc" => (Some("Instrmt"),"Desc"),
+    "SecurityExchange" => (Some("Instrmt"),"Exch"),
+    "SecurityID" => (Some("Instrmt"),"ID"),
+    "SecurityIDSource" => (Some("Instrmt"),"Src"),
+    "SecuritySubType" => (Some("Instrmt"),"SubTyp"),
+    "SecurityType" => (Some("Instrmt"),"SecTyp"),
+    "StrikePrice" => (Some("Instrmt"),"StrkPx"),
+    "Symbol" => (Some("Instrmt"),"Sym"),
+    "SymbolSfx" => (Some("Instrmt"),"Sfx"),
+    "EventDate" => (None,"Dt"),
+    "EventPx" => (None,"Px"),
+    "EventText" => (None,"Txt"),
+    "EventTime" => (None,"Tm"),
+    "EventType" => (None,"EventTyp"),
+    "InstrumentPartyID" => (None,"ID"),
+    "InstrumentPartyIDSource" => (None,"Src"),
+    "InstrumentPartyRole" => (None,"R"),
+    "InstrumentPartySubID" => (None,"ID"),
+    "InstrumentPartySubIDType" => (None,"Typ"),
+    "NoInstrumentPartySubIDs" => (None,"Sub"),
+    "SecurityAltID" => (None,"AltID"),
+    "SecurityAltIDSource" => (None,"AltIDSrc"),
+
+    //InstrumentLeg component block.
+    "LegCFICode" => (None,"CFI"),
+    "LegCurrency" => (None,"Ccy"),
+    "LegMaturityDate" => (None,"MatDt"),
+    "LegMaturityMonthYear" => (None,"MMY"),
+    "LegPrice" => (None,"Px"),
+    "LegProduct" => (None,"Prod"),
+    "LegPutOrCall" => (None,"PutCall"),
+    "LegRatioQty" => (None,"RatioQty"),
+    "LegSecurityAltID" => (None,"AltID"),
+    "LegSecurityAltIDSource" => (None,"AltIDSrc"),
+    "LegSecurityDesc" => (None,"Desc"),
+    "LegSecurityExchange" => (None,"Exch"),
+    "LegSecurityID" => (None,"ID"),
+    "LegSecurityIDSource" => (None,"Src"),
+    "LegSecuritySubType" => (None,"SubTyp"),
+    "LegSecurityType" => (None,"SecTyp"),
+    "LegSide" => (None,"Side"),
+    "LegStrikePrice" => (None,"StrkPx"),
+    "LegSymbol" => (None,"Sym"),
+    "LegSymbolSfx" => (None,"Sfx"),
+    "NoLegSecurityAltID" => (None,"AID"),
+
+    //UnderlyingInstrument component block.
+    "NoUnderlyingSecurityAltID" => (None,"AID"),
+    "NoUnderlyingStips" => (None,"Stip"),
+    "NoUndlyInstrumentParties" => (None,"Pty"),
+    "UnderlyingCFICode" => (None,"CFI"),
+    "UnderlyingCurrency" => (None,"Ccy"),
+    "UnderlyingMaturityDate" => (None,"MatDt"),
+    "UnderlyingMaturityMonthYear" => (None,"MMY"),
+    "UnderlyingProduct" => (None,"Prod"),
+    "UnderlyingPutOrCall" => (None,"PutCall"),
+    "UnderlyingPx" => (None,"Px"),
+    "UnderlyingQty" => (None,"Qty"),
+    "UnderlyingSecurityAltID" => (None,"AltID"),
+    "UnderlyingSecurityAltIDSource" => (None,"AltIDSrc"),
+    "UnderlyingSecurityDesc" => (None,"Desc"),
+    "UnderlyingSecurityExchange" => (None,"Exch"),
+    "UnderlyingSecurityID" => (None,"ID"),
+    "UnderlyingSecurityIDSource" => (None,"Src"),
+    "UnderlyingSecuritySubType" => (None,"SubTyp"),
+    "UnderlyingSecurityType" => (None,"SecTyp"),
+    "UnderlyingStipType" => (None,"Typ"),
+    "UnderlyingStipValue" => (None,"Val"),
+    "UnderlyingStrikePrice" => (None,"StrkPx"),
+    "UnderlyingSymbol" => (None,"Sym"),
+    "UnderlyingSymbolSfx" => (None,"Sfx"),
+
+    //Email, News, and rejects.
+    "BusinessRejectReason" => (None,"BizRejRsn"),
+    "BusinessRejectRefID" => (None,"BizRejRefID"),
+    "EmailThreadID" => (None,"EmailThreadID"),
+    "EmailType" => (None,"EmailTyp"),
+    "EncodedHeadline" => (None,"EncHeadline"),
+    "EncodedSubject" => (None,"EncSubj"),
+    "EncodedText" => (None,"EncTxt"),
+    "Headline" => (None,"Headline"),
+    "NoLegs" => (None,"Leg"),
+    "NoLinesOfText" => (None,"TxtLn"),
+    "NoRelatedSym" => (None,"Instrmt"),
+    "NoRoutingIDs" => (None,"Rtg"),
+    "NoUnderlyings" => (None,"Undly"),
+    "OrigTime" => (None,"OrigTm"),
+    "RefMsgType" => (None,"RefMsgTyp"),
+    "RefSeqNum" => (None,"RefSeqNum"),
+    "RelatedSym" => (None,"RelSym"),
+    "RoutingID" => (None,"ID"),
+    "RoutingType" => (None,"Typ"),
+    "Subject" => (None,"Subj"),
+    "Text" => (None,"Txt"),
+    "URLLink" => (None,"URLLink"),
+    "Urgency" => (None,"Urgency"),
+};
+
+pub enum FIXMLError {
+    Syntax(usize,&'static str), //Byte offset, Description
+    MissingMessage, //FIXML element does not contain a message element.
+    MessageUnknown(String), //Message element name not in dictionary passed to from_fixml().
+    UnknownField(String), //Attribute or element isn't defined for the message and isn't a tag number.
+    WrongFormat(String), //Field name
+    OutOfRange(String), //Field name
+}
+
+impl fmt::Display for FIXMLError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FIXMLError::Syntax(offset,description) => write!(f,"FIXMLError::Syntax({},{})",offset,description),
+            FIXMLError::MissingMessage => write!(f,"FIXMLError::MissingMessage"),
+            FIXMLError::MessageUnknown(ref name) => write!(f,"FIXMLError::MessageUnknown({})",name),
+            FIXMLError::UnknownField(ref name) => write!(f,"FIXMLError::UnknownField({})",name),
+            FIXMLError::WrongFormat(ref name) => write!(f,"FIXMLError::WrongFormat({})",name),
+            FIXMLError::OutOfRange(ref name) => write!(f,"FIXMLError::OutOfRange({})",name),
+        }
+    }
+}
+
+impl fmt::Debug for FIXMLError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        <FIXMLError as fmt::Display>::fmt(self,f)
+    }
+}
+
+impl From<JsonError> for FIXMLError {
+    fn from(error: JsonError) -> FIXMLError {
+        match error {
+            JsonError::UnknownField(name) => FIXMLError::UnknownField(name),
+            JsonError::WrongFormat(name) => FIXMLError::WrongFormat(name),
+            JsonError::OutOfRange(name) => FIXMLError::OutOfRange(name),
+            //Remaining errors only come from parsing JSON text.
+            JsonError::Syntax(offset,description) => FIXMLError::Syntax(offset,description),
+            JsonError::MissingSection(_) | JsonError::MissingMsgType => FIXMLError::MissingMessage,
+            JsonError::MsgTypeUnknown(msg_type) => FIXMLError::MessageUnknown(String::from_utf8_lossy(&msg_type).into_owned()),
+        }
+    }
+}
+
+fn abbreviation(name: &'static str) -> (Option<&'static str>,&'static str) {
+    match FIELD_ABBREVIATIONS.get(name) {
+        Some(&(component,abbreviation)) => (component,abbreviation),
+        None => (None,name),
+    }
+}
+
+fn version_attribute(message_version: MessageVersion) -> &'static str {
+    match message_version {
+        MessageVersion::FIX40 => "4.0",
+        MessageVersion::FIX41 => "4.1",
+        MessageVersion::FIX42 => "4.2",
+        MessageVersion::FIX43 => "4.3",
+        MessageVersion::FIX44 => "4.4",
+        MessageVersion::FIX50 => "5.0",
+        MessageVersion::FIX50SP1 => "5.0 SP1",
+        MessageVersion::FIX50SP2 => "5.0 SP2",
+    }
+}
+
+fn write_fields(element: &mut Element,members: Vec<(String,JsonValue)>) {
+    for (name,value) in members {
+        let found = FIELD_ABBREVIATIONS.get(&name[..]).cloned();
+        let (component,abbreviation) = match found {
+            Some((component,abbreviation)) => (component,abbreviation.to_string()),
+            None => (None,name),
+        };
+
+        //Fields are placed in their component block's element unless the element being written is
+        //that component block. For example, Symbol inside of an Instrmt repeating group.
+        let target = match component {
+            Some(component) if component != element.name => {
+                let index = match element.children.iter().position(|child| child.name == component) {
+                    Some(index) => index,
+                    None => {
+                        element.children.push(Element::new(component));
+                        element.children.len() - 1
+                    },
+                };
+                &mut element.children[index]
+            },
+            _ => &mut *element,
+        };
+
+        match value {
+            JsonValue::String(value) => target.attributes.push((abbreviation,value)),
+            JsonValue::Array(values) => {
+                for value in values {
+                    match value {
+                        //Repeating group.
+                        JsonValue::Object(group_members) => {
+                            let mut group_element = Element::new(&abbreviation[..]);
+                            write_fields(&mut group_element,group_members);
+                            target.children.push(group_element);
+                        },
+                        //Field repeated without a repeating group.
+                        JsonValue::String(value) => {
+                            let mut value_element = Element::new(&abbreviation[..]);
+                            value_element.attributes.push((REPEATABLE_VALUE_ATTRIBUTE.to_string(),value));
+                            target.children.push(value_element);
+                        },
+                        JsonValue::Array(_) => {},
+                    }
+                }
+            },
+            JsonValue::Object(_) => {},
+        }
+    }
+}
+
+pub fn to_fixml<T: FIXTMessage + ?Sized>(message: &T,fix_version: FIXVersion,message_version: MessageVersion) -> String {
+    let fields = read_json_fields(message.fields_with_version(fix_version,message_version));
+
+    let mut message_element = match MESSAGE_NAMES.get(message.msg_type()) {
+        Some(name) => Element::new(*name),
+        None => {
+            let mut element = Element::new(UNKNOWN_MESSAGE_ELEMENT);
+            element.attributes.push((UNKNOWN_MESSAGE_TYPE_ATTRIBUTE.to_string(),String::from_utf8_lossy(message.msg_type()).into_owned()));
+            element
+        },
+    };
+    write_fields(&mut message_element,fields.into_iter().map(|field| (field.name,field.value)).collect());
+
+    let mut root = Element::new("FIXML");
+    root.attributes.push((String::from("v"),version_attribute(message_version).to_string()));
+    root.children.push(message_element);
+
+    let mut buf = String::new();
+    root.write(&mut buf);
+    buf
+}
+
+struct FIXMLField {
+    name: &'static str,
+    rule: Rule,
+    component: Option<&'static str>,
+    abbreviation: &'static str,
+}
+
+fn message_fields<T: Message + ?Sized>(message: &T,builder: &mut BuildMessage) -> Vec<FIXMLField> {
+    let mut fields = HashMap::new();
+    for message_version in MessageVersion::all() {
+        for (tag,rule) in builder.fields(message_version) {
+            if let Some(name) = message.field_name(tag) {
+                let (component,abbreviation) = abbreviation(name);
+                fields.insert(tag,FIXMLField {
+                    name: name,
+                    rule: rule,
+                    component: component,
+                    abbreviation: abbreviation,
+                });
+            }
+        }
+    }
+
+    fields.into_iter().map(|(_,field)| field).collect()
+}
+
+fn read_fields(element: &Element,scopes: &[Option<&str>],fields: &[FIXMLField],members: &mut Vec<(String,JsonValue)>) -> Result<(),FIXMLError> {
+    fn push_array_value(members: &mut Vec<(String,JsonValue)>,name: &str,value: JsonValue) {
+        for &mut (ref member_name,ref mut member_value) in members.iter_mut() {
+            if member_name == name {
+                if let JsonValue::Array(ref mut values) = *member_value {
+                    values.push(value);
+                    return;
+                }
+            }
+        }
+
+        members.push((name.to_string(),JsonValue::Array(vec![value])));
+    }
+
+    let find_field = |abbreviation: &str| {
+        fields.iter().find(|field| field.abbreviation == abbreviation && scopes.contains(&field.component))
+    };
+
+    for &(ref name,ref value) in &element.attributes {
+        if let Some(field) = find_field(name) {
+            members.push((field.name.to_string(),JsonValue::String(value.clone())));
+        }
+        else if name.parse::<u64>().is_ok() {
+            //User-defined field.
+            members.push((name.clone(),JsonValue::String(value.clone())));
+        }
+        else if element.name == UNKNOWN_MESSAGE_ELEMENT && name == UNKNOWN_MESSAGE_TYPE_ATTRIBUTE {
+            //Already used to find the message type.
+        }
+        else {
+            return Err(FIXMLError::UnknownField(name.clone()));
+        }
+    }
+
+    for child in &element.children {
+        match find_field(&child.name) {
+            Some(&FIXMLField { name, rule: Rule::BeginGroup{ builder_func }, .. }) => {
+                let mut group_builder = builder_func();
+                let group_message = group_builder.build();
+                let group_fields = message_fields(&*group_message,&mut *group_builder);
+
+                let mut group_members = Vec::new();
+                try!(read_fields(child,&[None,Some(&child.name[..])],&group_fields,&mut group_members));
+                push_array_value(members,name,JsonValue::Object(group_members));
+            },
+            Some(&FIXMLField { name, rule: Rule::RepeatableField, .. }) => {
+                match child.attribute(REPEATABLE_VALUE_ATTRIBUTE) {
+                    Some(value) => push_array_value(members,name,JsonValue::String(value.to_string())),
+                    None => return Err(FIXMLError::WrongFormat(name.to_string())),
+                }
+            },
+            Some(field) => return Err(FIXMLError::WrongFormat(field.name.to_string())),
+            None => {
+                //Component block.
+                if !fields.iter().any(|field| field.component == Some(&child.name[..])) {
+                    return Err(FIXMLError::UnknownField(child.name.clone()));
+                }
+                try!(read_fields(child,&[Some(&child.name[..])],fields,members));
+            },
+        }
+    }
+
+    Ok(())
+}
+
+pub fn from_fixml(fixml: &str,message_dictionary: &HashMap<&'static [u8],Box<BuildFIXTMessage + Send>>) -> Result<Box<FIXTMessage + Send>,FIXMLError> {
+    let root = try!(xml::parse(fixml).map_err(|err| FIXMLError::Syntax(err.offset,err.description)));
+    if root.name != "FIXML" {
+        return Err(FIXMLError::Syntax(0,"Expected FIXML element"));
+    }
+
+    //Batches of messages are not supported. Only the first message is used.
+    let message_element = match root.children.first() {
+        Some(message_element) => message_element,
+        None => return Err(FIXMLError::MissingMessage),
+    };
+    let msg_type = if message_element.name == UNKNOWN_MESSAGE_ELEMENT {
+        message_element.attribute(UNKNOWN_MESSAGE_TYPE_ATTRIBUTE).map(|msg_type| msg_type.as_bytes())
+    }
+    else {
+        MESSAGE_NAMES.entries().find(|&(_,name)| *name == message_element.name).map(|(msg_type,_)| *msg_type)
+    };
+    let builder = match msg_type.and_then(|msg_type| message_dictionary.get(msg_type)) {
+        Some(builder) => builder,
+        None => return Err(FIXMLError::MessageUnknown(message_element.name.clone())),
+    };
+
+    let mut message = builder.build();
+    let mut builder = BuildMessage::new_into_box(&**builder);
+    let fields = message_fields(&*message,&mut *builder);
+    let mut members = Vec::new();
+    try!(read_fields(message_element,&[None,Some(&message_element.name[..])],&fields,&mut members));
+    try!(set_json_fields(&mut *message,&mut *builder,&members));
+
+    Ok(message)
+}
@@ -11,6 +11,7 @@
 
 #![allow(deprecated)]
 
+use chrono;
 use mio::{Events,Poll,PollOpt,Ready,Token};
 use mio::channel::{channel,Receiver,Sender};
 use std::collections::HashMap;
@@ -25,11 +26,14 @@ use std::sync::mpsc::TryRecvError;
 use std::thread;
 use std::time::{Duration,Instant};
 
+use data_dictionary::DataDictionary;
+use dictionary::field_types::other::SessionStatus;
 use dictionary::messages::Logon;
 use fixt::engine_thread::{CONNECTION_COUNT_MAX,BASE_CONNECTION_TOKEN,INTERNAL_ENGINE_EVENT_TOKEN,InternalEngineToThreadEvent,internal_engine_thread};
 use fixt::message::{BuildFIXTMessage,FIXTMessage};
-use fix::ParseError;
+use fix::{ParseError,ParseErrorContext,ParseLimits,ValidationConfig};
 use fix_version::FIXVersion;
+use message::{InvalidFieldValue,InvalidValuePolicy,MissingRequiredField};
 use message_version::MessageVersion;
 use token_generator::TokenGenerator;
 
@@ -55,10 +59,12 @@ impl fmt::Display for Listener {
 
 pub enum ConnectionTerminatedReason {
     BeginStrWrongError{ received: FIXVersion, expected: FIXVersion },
+    InboundGarbledMessageThresholdExceededError,
     InboundMsgSeqNumMaxExceededError,
     InboundMsgSeqNumLowerThanExpectedError,
     InboundResendRequestLoopError,
     LocalRequested,
+    LogonAuthenticationFailedError,
     LogonHeartBtIntNegativeError,
     LogonParseError(ParseError),
     LogonNeverReceivedError,
@@ -84,10 +90,12 @@ impl fmt::Debug for ConnectionTerminatedReason {
                 let expected_str = String::from_utf8_lossy(expected.begin_string()).into_owned();
                 write!(f,"Received message with BeginStr containing '{}' but expected '{}'.",received_str,expected_str)
             },
+            ConnectionTerminatedReason::InboundGarbledMessageThresholdExceededError => write!(f,"Received too many garbled messages within the configured time window."),
             ConnectionTerminatedReason::InboundMsgSeqNumMaxExceededError => write!(f,"Expected inbound MsgSeqNum exceeded maximum allowed."),
             ConnectionTerminatedReason::InboundMsgSeqNumLowerThanExpectedError => write!(f,"Received message with lower MsgSeqNum than expected."),
             ConnectionTerminatedReason::InboundResendRequestLoopError => write!(f,"Received too many ResendRequests with the same BeginSeqNo."),
             ConnectionTerminatedReason::LocalRequested => write!(f,"Local requested logout and it was performed cleanly."),
+            ConnectionTerminatedReason::LogonAuthenticationFailedError => write!(f,"Listener's logon authenticator rejected logon."),
             ConnectionTerminatedReason::LogonHeartBtIntNegativeError => write!(f,"Response to logon included negative HeartBtInt."),
             ConnectionTerminatedReason::LogonParseError(_) => write!(f,"Could not parse logon response."), //Did you connect to a server not running a FIX engine?
             ConnectionTerminatedReason::LogonNeverReceivedError => write!(f,"Never received logon from new connection."),
@@ -107,6 +115,48 @@ impl fmt::Debug for ConnectionTerminatedReason {
     }
 }
 
+#[derive(Clone,PartialEq)]
+pub enum SendMessageError {
+    InvalidFieldValue(InvalidFieldValue), //Value contains an invalid byte and InvalidValueAction::Reject is used.
+    MissingRequiredField(MissingRequiredField), //Required or conditionally required field is not set for the message version.
+}
+
+impl fmt::Debug for SendMessageError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SendMessageError::InvalidFieldValue(ref invalid_field_value) => {
+                if invalid_field_value.name.is_empty() {
+                    write!(f,"Value of tag {} contains SOH or '='.",invalid_field_value.tag)
+                }
+                else {
+                    write!(f,"Value of {} ({}) contains SOH or '='.",invalid_field_value.name,invalid_field_value.tag)
+                }
+            },
+            SendMessageError::MissingRequiredField(ref missing_required_field) => {
+                write!(f,"Required field {} ({}) is not set.",missing_required_field.name,missing_required_field.tag)
+            },
+        }
+    }
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum SendingTimePolicy {
+    Reject, //Respond with a Reject and do not pass the message on as received.
+    Warn, //Issue an EngineEvent but otherwise process the message as normal.
+    Ignore, //Do not check at all.
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub enum LogonAuthenticationResult {
+    Accept, //Continue logon. The Logon response will include SessionStatus::SessionActive.
+    PasswordChanged, //Continue logon. The Logon response will include SessionStatus::SessionPasswordChanged.
+    Reject(SessionStatus), //Respond with a Logout including the SessionStatus and then disconnect.
+}
+
+pub trait LogonAuthenticator {
+    fn authenticate(&mut self,listener: Listener,connection: Connection,message: &Logon) -> LogonAuthenticationResult;
+}
+
 pub enum EngineEvent {
     ConnectionFailed(Connection,io::Error), //Could not setup connection.
     ConnectionSucceeded(Connection), //Connection completed and ready to begin logon.
@@ -118,12 +168,15 @@ pub enum EngineEvent {
     ListenerFailed(Listener,io::Error), //Could not setup listener.
     ListenerAcceptFailed(Listener,io::Error), //Could not accept a connection with listener.
     MessageReceived(Connection,Box<FIXTMessage + Send>), //New valid message was received.
-    MessageReceivedGarbled(Connection,ParseError), //New message could not be parsed correctly. (If not garbled (FIXT 1.1, page 40), a Reject will be issued first)
+    MessageReceivedGarbled(Connection,ParseError,ParseErrorContext,Vec<u8>,u64), //New message could not be parsed correctly. Includes where the error occurred, the raw bytes received up to the error, and the total number of truly garbled messages (ignored without a Reject) received on the connection. (If not garbled (FIXT 1.1, page 40), a Reject will be issued first)
     MessageReceivedDuplicate(Connection,Box<FIXTMessage + Send>), //Message with MsgSeqNum already seen was received.
     MessageRejected(Connection,Box<FIXTMessage + Send>), //New message breaks session rules and was rejected.
+    MessageNotSent(Connection,Box<FIXTMessage + Send>,SendMessageError), //Message sent without a MessageVersion could not be serialized using the connection's default message version and was dropped.
     ResendRequested(Connection,Range<u64>), //Range of messages by MsgSeqNum that are requested to be resent. [Range::start,Range::end)
     SequenceResetResetHasNoEffect(Connection),
     SequenceResetResetInThePast(Connection),
+    SendingTimeOutsideTolerance(Connection,u64,chrono::Duration), //Message with MsgSeqNum had a SendingTime that drifted from local time by more than allowed but was accepted anyway. Positive drift means SendingTime is in the past.
+    OrigSendingTimeAfterSendingTime(Connection,u64), //Message with MsgSeqNum had PossDupFlag set and OrigSendingTime > SendingTime but was accepted anyway.
     FatalError(&'static str,io::Error), //A critical error has occurred. No more events can be received and no more messages will be sent.
 }
 
@@ -140,12 +193,15 @@ impl fmt::Debug for EngineEvent {
             EngineEvent::ListenerFailed(listener,ref error) => write!(f,"EngineEvent::ListenerFailed({:?},{:?})",listener,error),
             EngineEvent::ListenerAcceptFailed(listener,ref error) => write!(f,"EngineEvent::ListenerAcceptFailed({:?},{:?})",listener,error),
             EngineEvent::MessageReceived(connection,ref message) => write!(f,"EngineEvent::MessageReceived({:?},{:?})",connection,message),
-            EngineEvent::MessageReceivedGarbled(connection,ref parse_error) => write!(f,"EngineEvent::MessageReceivedGarbled({:?},{:?})",connection,parse_error),
+            EngineEvent::MessageReceivedGarbled(connection,ref parse_error,ref error_context,ref bytes,garbled_count) => write!(f,"EngineEvent::MessageReceivedGarbled({:?},{:?},{},{:?},{:?})",connection,parse_error,error_context,String::from_utf8_lossy(bytes).replace("\x01","|"),garbled_count),
             EngineEvent::MessageReceivedDuplicate(connection,ref message) => write!(f,"EngineEvent::MessageReceivedDuplicate({:?},{:?})",connection,message),
             EngineEvent::MessageRejected(connection,ref message) => write!(f,"EngineEvent::MessageRejected({:?},{:?})",connection,message),
+            EngineEvent::MessageNotSent(connection,ref message,ref error) => write!(f,"EngineEvent::MessageNotSent({:?},{:?},{:?})",connection,message,error),
             EngineEvent::ResendRequested(connection,ref range) => write!(f,"EngineEvent::ResendRequested({:?},{:?})",connection,range),
             EngineEvent::SequenceResetResetHasNoEffect(connection) => write!(f,"EngineEvent:SequenceResetResetHasNoEffect({:?})",connection),
             EngineEvent::SequenceResetResetInThePast(connection) => write!(f,"EngineEvent:SequenceResetResetInThePast({:?})",connection),
+            EngineEvent::SendingTimeOutsideTolerance(connection,msg_seq_num,ref drift) => write!(f,"EngineEvent::SendingTimeOutsideTolerance({:?},{:?},{:?})",connection,msg_seq_num,drift),
+            EngineEvent::OrigSendingTimeAfterSendingTime(connection,msg_seq_num) => write!(f,"EngineEvent::OrigSendingTimeAfterSendingTime({:?},{:?})",connection,msg_seq_num),
             EngineEvent::FatalError(description,ref error) => write!(f,"EngineEvent::FatalError({:?},{:?})",description,error),
         }
     }
@@ -170,6 +226,7 @@ pub struct Engine {
     rx: Receiver<EngineEvent>,
     poll: Poll,
     thread_handle: Option<thread::JoinHandle<()>>,
+    invalid_value_policy: InvalidValuePolicy,
 }
 
 impl Engine {
@@ -193,6 +250,7 @@ impl Engine {
             thread_handle: Some(thread::spawn(move || {
                 internal_engine_thread(poll,token_generator,thread_to_engine_tx,engine_to_thread_rx,message_dictionary,max_message_size);
             })),
+            invalid_value_policy: InvalidValuePolicy::default(),
         })
     }
 
@@ -249,17 +307,39 @@ impl Engine {
         Ok(Some(listener))
     }
 
-    pub fn send_message<T: 'static + FIXTMessage + Send>(&mut self,connection: Connection,message: T) {
+    pub fn send_message<T: 'static + FIXTMessage + Send>(&mut self,connection: Connection,message: T) -> Result<(),SendMessageError> {
         let message = Box::new(message);
-        self.send_message_box(connection,message);
+        self.send_message_box(connection,message)
     }
 
-    pub fn send_message_box(&mut self,connection: Connection,message: Box<FIXTMessage + Send>) {
-        self.send_message_box_with_message_version(connection,None,message);
+    pub fn send_message_box(&mut self,connection: Connection,message: Box<FIXTMessage + Send>) -> Result<(),SendMessageError> {
+        self.send_message_box_with_message_version(connection,None,message)
+    }
+
+    pub fn send_message_box_with_message_version<MV: Into<Option<MessageVersion>>>(&mut self,connection: Connection,message_version: MV,mut message: Box<FIXTMessage + Send>) -> Result<(),SendMessageError> {
+        //Make sure the message can't corrupt the outbound stream before handing it off. Otherwise,
+        //the counterparty would see extra or broken fields and respond with Rejects.
+        try!(message.sanitize_values(self.invalid_value_policy).map_err(SendMessageError::InvalidFieldValue));
+
+        //Make sure the counterparty won't receive empty required fields and that serializing won't
+        //panic. The connection's default message version is only known by the engine thread so
+        //messages without a message version are checked there instead and reported using
+        //EngineEvent::MessageNotSent.
+        let message_version = message_version.into();
+        if let Some(message_version) = message_version {
+            try!(message.validate(message_version).map_err(SendMessageError::MissingRequiredField));
+        }
+
+        self.tx.send(InternalEngineToThreadEvent::SendMessage(Token(connection.0),message_version,message)).unwrap();
+
+        Ok(())
     }
 
-    pub fn send_message_box_with_message_version<MV: Into<Option<MessageVersion>>>(&mut self,connection: Connection,message_version: MV,message: Box<FIXTMessage + Send>) {
-        self.tx.send(InternalEngineToThreadEvent::SendMessage(Token(connection.0),message_version.into(),message)).unwrap();
+    pub fn set_invalid_value_policy(&mut self,invalid_value_policy: InvalidValuePolicy) {
+        //Choose what send_message() does when a string value contains SOH, or '=' if
+        //InvalidValuePolicy::include_tag_end is set. Defaults to InvalidValueAction::Reject, which
+        //returns SendMessageError::InvalidFieldValue.
+        self.invalid_value_policy = invalid_value_policy;
     }
 
     pub fn send_resend_response(&mut self,connection: Connection,response: Vec<ResendResponse>) {
@@ -300,6 +380,76 @@ impl Engine {
         self.tx.send(InternalEngineToThreadEvent::RejectNewConnection(connection,reason)).unwrap();
     }
 
+    pub fn set_logon_authenticator(&mut self,listener: Listener,authenticator: Option<Box<LogonAuthenticator + Send>>) {
+        //Every Logon received by the listener's connections is passed to the authenticator before
+        //EngineEvent::ConnectionLoggingOn is issued. Rejected connections are logged out by the
+        //engine with ConnectionTerminatedReason::LogonAuthenticationFailedError and are never
+        //passed on to be approved. Use None to remove (the default).
+        self.tx.send(InternalEngineToThreadEvent::SetLogonAuthenticator(Token(listener.0),authenticator)).unwrap();
+    }
+
+    pub fn set_sending_time_tolerance(&mut self,connection: Connection,tolerance: Duration,policy: SendingTimePolicy) {
+        //SendingTime is compared against the local time each message was received. Use
+        //SendingTimePolicy::Ignore to disable the check. Messages held in the out of order queue
+        //are checked once the gap is filled but still against when they were received.
+        self.tx.send(InternalEngineToThreadEvent::SetSendingTimeTolerance(Token(connection.0),tolerance,policy)).unwrap();
+    }
+
+    pub fn set_orig_sending_time_policy(&mut self,connection: Connection,policy: SendingTimePolicy) {
+        self.tx.send(InternalEngineToThreadEvent::SetOrigSendingTimePolicy(Token(connection.0),policy)).unwrap();
+    }
+
+    pub fn set_garbled_message_threshold<T: Into<Option<(usize,Duration)>>>(&mut self,connection: Connection,threshold: T) {
+        //Logout when the specified number of garbled messages are received within the specified
+        //time window. Use None to disable (the default).
+        self.tx.send(InternalEngineToThreadEvent::SetGarbledMessageThreshold(Token(connection.0),threshold.into())).unwrap();
+    }
+
+    pub fn set_inbound_out_of_order_queue_len_max(&mut self,connection: Connection,len_max: usize) {
+        //Hold onto up to len_max messages received with a MsgSeqNum higher than expected and
+        //deliver them in order once the gap is filled. When the queue is full, messages are
+        //discarded and must be resent just like when the queue is disabled (len_max == 0, the
+        //default).
+        self.tx.send(InternalEngineToThreadEvent::SetInboundOutOfOrderQueueLenMax(Token(connection.0),len_max)).unwrap();
+    }
+
+    pub fn set_validation_config(&mut self,connection: Connection,validation_config: ValidationConfig) {
+        //Relax how strictly inbound messages are parsed for counterparties that don't quite follow
+        //the specification. Defaults to strict validation.
+        self.tx.send(InternalEngineToThreadEvent::SetValidationConfig(Token(connection.0),validation_config)).unwrap();
+    }
+
+    pub fn set_parse_limits(&mut self,connection: Connection,parse_limits: ParseLimits) {
+        //Limit how many repeating groups, how deeply nested repeating groups, and how many fields
+        //an inbound message can have. Messages that exceed a limit are rejected.
+        self.tx.send(InternalEngineToThreadEvent::SetParseLimits(Token(connection.0),parse_limits)).unwrap();
+    }
+
+    pub fn set_data_dictionary(&mut self,connection: Connection,data_dictionary: Option<Arc<DataDictionary>>) {
+        //Parse and validate messages defined in a runtime data dictionary. They are delivered as
+        //GenericMessages through EngineEvent::MessageReceived. Pass None to go back to only using
+        //the compiled message definitions.
+        self.tx.send(InternalEngineToThreadEvent::SetDataDictionary(Token(connection.0),data_dictionary)).unwrap();
+    }
+
+    pub fn set_listener_validation_config(&mut self,listener: Listener,validation_config: ValidationConfig) {
+        //Same as set_validation_config() but used for every connection the listener accepts from
+        //then on, starting with its Logon message.
+        self.tx.send(InternalEngineToThreadEvent::SetListenerValidationConfig(Token(listener.0),validation_config)).unwrap();
+    }
+
+    pub fn set_listener_parse_limits(&mut self,listener: Listener,parse_limits: ParseLimits) {
+        //Same as set_parse_limits() but used for every connection the listener accepts from then
+        //on, starting with its Logon message.
+        self.tx.send(InternalEngineToThreadEvent::SetListenerParseLimits(Token(listener.0),parse_limits)).unwrap();
+    }
+
+    pub fn set_listener_data_dictionary(&mut self,listener: Listener,data_dictionary: Option<Arc<DataDictionary>>) {
+        //Same as set_data_dictionary() but used for every connection the listener accepts from
+        //then on, starting with its Logon message.
+        self.tx.send(InternalEngineToThreadEvent::SetListenerDataDictionary(Token(listener.0),data_dictionary)).unwrap();
+    }
+
     pub fn logout(&mut self,connection: Connection) {
         self.tx.send(InternalEngineToThreadEvent::Logout(Token(connection.0))).unwrap();
     }
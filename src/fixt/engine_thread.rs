@@ -11,6 +11,8 @@
 
 #![allow(deprecated)]
 
+use chrono::datetime::DateTime;
+use chrono::offset::utc::UTC;
 use mio::{Event,Events,Poll,PollOpt,Ready,Token};
 use mio::channel::{Receiver,Sender};
 use mio::tcp::{Shutdown,TcpListener,TcpStream};
@@ -18,26 +20,27 @@ use mio::unix::UnixReady;
 use mio::timer::{Timeout,Timer};
 use mio::timer::Builder as TimerBuilder;
 use std::cmp;
-use std::collections::HashMap;
+use std::collections::{BTreeMap,HashMap,VecDeque};
 use std::collections::hash_map::Entry;
 use std::fmt;
 use std::io::{self,Write};
 use std::mem;
 use std::net::SocketAddr;
 use std::sync::{Arc,Mutex};
-use std::time::Duration;
+use std::time::{Duration,Instant};
 
 use byte_buffer::ByteBuffer;
+use data_dictionary::DataDictionary;
 use dictionary::{CloneDictionary,administrative_msg_types,standard_msg_types};
 use dictionary::field_types::generic::UTCTimestampFieldType;
-use dictionary::field_types::other::{BusinessRejectReason,MsgDirection,SessionRejectReason};
+use dictionary::field_types::other::{BusinessRejectReason,MsgDirection,SessionRejectReason,SessionStatus};
 use dictionary::fields::{ApplVerID,MsgSeqNum,SenderCompID,TargetCompID,OrigSendingTime};
 use dictionary::messages::{Logon,Logout,ResendRequest,TestRequest,Heartbeat,SequenceReset,Reject,BusinessMessageReject};
 use field::Field;
 use field_type::FieldType;
-use fix::{Parser,ParseError};
+use fix::{Parser,ParseError,ParseErrorContext,ParseLimits,ValidationConfig};
 use fix_version::FIXVersion;
-use fixt::engine::{EngineEvent,Connection,ConnectionTerminatedReason,Listener,ResendResponse};
+use fixt::engine::{EngineEvent,Connection,ConnectionTerminatedReason,Listener,LogonAuthenticationResult,LogonAuthenticator,ResendResponse,SendMessageError,SendingTimePolicy};
 use fixt::message::{BuildFIXTMessage,FIXTMessage};
 use message_version::MessageVersion;
 use network_read_retry::NetworkReadRetry;
@@ -52,7 +55,6 @@ use token_generator::TokenGenerator;
 //TODO: Need to make inbound and outbound MsgSeqNums adjustable at connection setup and available
 //on connection termination to support persistent sessions.
 //TODO: Stop allowing outgoing messages when performing an emergency logout.
-//TODO: Need to sanitize output strings when serializing.
 
 const NO_INBOUND_TIMEOUT_PADDING_MS: u64 = 250;
 const AUTO_DISCONNECT_AFTER_LOGOUT_RESPONSE_SECS: u64 = 10;
@@ -61,6 +63,7 @@ const AUTO_CONTINUE_AFTER_LOGOUT_RESEND_REQUEST_SECS: u64 = 10;
 const AUTO_DISCONNECT_AFTER_WRITE_BLOCKS_SECS: u64 = 10;
 pub const AUTO_DISCONNECT_AFTER_INBOUND_RESEND_REQUEST_LOOP_COUNT: u64 = 5;
 pub const AUTO_DISCONNECT_AFTER_NO_LOGON_RECEIVED_SECONDS: u64 = 10;
+pub const DEFAULT_SENDING_TIME_TOLERANCE_SECS: u64 = 120; //FIXT v1.1, page 52 recommends two minutes.
 const EVENT_POLL_CAPACITY: usize = 1024;
 pub const INBOUND_MESSAGES_BUFFER_LEN_MAX: usize = 10;
 pub const INBOUND_BYTES_BUFFER_CAPACITY: usize = 2048;
@@ -226,6 +229,7 @@ struct OutboundMessage {
     message: Box<FIXTMessage + Send>,
     message_version: Option<MessageVersion>,
     auto_msg_seq_num: bool,
+    validate: bool, //Check for missing required fields using the message version it's serialized with.
 }
 
 impl OutboundMessage {
@@ -234,6 +238,7 @@ impl OutboundMessage {
             message: Box::new(message),
             message_version: None,
             auto_msg_seq_num: auto_msg_seq_num,
+            validate: false,
         }
     }
 
@@ -242,6 +247,7 @@ impl OutboundMessage {
             message: Box::new(message),
             message_version: None,
             auto_msg_seq_num: true,
+            validate: false,
         }
     }
 
@@ -250,6 +256,7 @@ impl OutboundMessage {
             message: message,
             message_version: None,
             auto_msg_seq_num: true,
+            validate: false,
         }
     }
 }
@@ -297,6 +304,17 @@ pub enum InternalEngineToThreadEvent {
     NewListener(Token,<<SenderCompID as Field>::Type as FieldType>::Type,TcpListener),
     SendMessage(Token,Option<MessageVersion>,Box<FIXTMessage + Send>),
     ResendMessages(Token,Vec<ResendResponse>),
+    SetSendingTimeTolerance(Token,Duration,SendingTimePolicy),
+    SetOrigSendingTimePolicy(Token,SendingTimePolicy),
+    SetGarbledMessageThreshold(Token,Option<(usize,Duration)>),
+    SetInboundOutOfOrderQueueLenMax(Token,usize),
+    SetValidationConfig(Token,ValidationConfig),
+    SetParseLimits(Token,ParseLimits),
+    SetDataDictionary(Token,Option<Arc<DataDictionary>>),
+    SetLogonAuthenticator(Token,Option<Box<LogonAuthenticator + Send>>),
+    SetListenerValidationConfig(Token,ValidationConfig),
+    SetListenerParseLimits(Token,ParseLimits),
+    SetListenerDataDictionary(Token,Option<Arc<DataDictionary>>),
     ApproveNewConnection(Connection,Box<Logon>,u64),
     RejectNewConnection(Connection,Option<Vec<u8>>),
     Logout(Token),
@@ -318,7 +336,7 @@ enum ConnectionEventError {
 
 enum ConnectionReadMessage {
     Message(Box<FIXTMessage + Send>),
-    Error(ParseError),
+    Error(ParseError,ParseErrorContext,Vec<u8>),
 }
 
 struct LastSeenResendRequest {
@@ -344,7 +362,17 @@ struct InternalConnection {
     inbound_last_seen_resend_request: LastSeenResendRequest,
     inbound_blocked: bool,
     inbound_blocked_timeout: Option<Timeout>,
+    inbound_sending_time_tolerance: Duration,
+    inbound_sending_time_policy: SendingTimePolicy,
+    inbound_orig_sending_time_policy: SendingTimePolicy,
+    inbound_garbled_count: u64,
+    inbound_garbled_threshold: Option<(usize,Duration)>,
+    inbound_garbled_times: VecDeque<Instant>, //When garbled messages were received within the threshold's time window.
+    inbound_out_of_order_messages: BTreeMap<MsgSeqNumType,(Box<FIXTMessage + Send>,DateTime<UTC>)>, //Messages (and when they were received) with MsgSeqNum > expected that are waiting for the gap to be filled.
+    inbound_out_of_order_messages_len_max: usize, //Zero disables queueing and out of order messages are discarded.
+    inbound_out_of_order_resend_request_end_seq_no: MsgSeqNumType, //Highest MsgSeqNum requested while queueing out of order messages.
     logout_timeout: Option<Timeout>,
+    logon_session_status: Option<SessionStatus>, //Included in the Logon response when the connection is approved.
     parser: Parser,
     is_connected: bool, //TODO: Might belong better as part of ConnectionStatus if the state machine design works well.
     status: ConnectionStatus,
@@ -390,7 +418,17 @@ impl InternalConnection {
             },
             inbound_blocked: false,
             inbound_blocked_timeout: None,
+            inbound_sending_time_tolerance: Duration::from_secs(DEFAULT_SENDING_TIME_TOLERANCE_SECS),
+            inbound_sending_time_policy: SendingTimePolicy::Ignore,
+            inbound_orig_sending_time_policy: SendingTimePolicy::Reject,
+            inbound_garbled_count: 0,
+            inbound_garbled_threshold: None,
+            inbound_garbled_times: VecDeque::new(),
+            inbound_out_of_order_messages: BTreeMap::new(),
+            inbound_out_of_order_messages_len_max: 0,
+            inbound_out_of_order_resend_request_end_seq_no: 0,
             logout_timeout: None,
+            logon_session_status: None,
             parser: parser,
             is_connected: false,
             status: ConnectionStatus::SendingLogon,
@@ -399,7 +437,7 @@ impl InternalConnection {
         }
     }
 
-    fn write(&mut self,timer: &mut Timer<(TimeoutType,Token)>,network_read_retry: &mut NetworkReadRetry) -> Result<(),ConnectionTerminatedReason> {
+    fn write(&mut self,tx: &Sender<EngineEvent>,timer: &mut Timer<(TimeoutType,Token)>,network_read_retry: &mut NetworkReadRetry) -> Result<(),ConnectionTerminatedReason> {
         //Send data until no more messages are available or until the socket returns WouldBlock.
         let mut sent_data = false;
         loop { //TODO: This loop might make this function too greedy. Maybe not?
@@ -437,8 +475,19 @@ impl InternalConnection {
                     break;
                 }
 
-                //Setup message to go out and serialize it.
+                //Messages sent without a message version are checked against the default message
+                //version now instead of when they were queued because the default can change in
+                //between. Messages that fail are handed back without using up a MsgSeqNum.
                 let mut message = self.outbound_messages.remove(0);
+                let message_version = if let Some(message_version) = message.message_version { message_version } else { self.default_message_version };
+                if message.validate {
+                    if let Err(missing_required_field) = message.message.validate(message_version) {
+                        tx.send(EngineEvent::MessageNotSent(self.as_connection(),message.message,SendMessageError::MissingRequiredField(missing_required_field))).unwrap();
+                        continue;
+                    }
+                }
+
+                //Setup message to go out and serialize it.
                 message.message.setup_fixt_session_header(
                     if message.auto_msg_seq_num {
                         let result = Some(self.outbound_msg_seq_num);
@@ -449,7 +498,6 @@ impl InternalConnection {
                     self.target_comp_id.clone()
                 );
                 let fix_version = self.fix_version;
-                let message_version = if let Some(message_version) = message.message_version { message_version } else { self.default_message_version };
                 message.message.read(fix_version,message_version,&mut self.outbound_buffer);
 
                 //TODO: Hold onto message and pass it off to the engine or some callback so the
@@ -510,7 +558,7 @@ impl InternalConnection {
                     messages.push(ConnectionReadMessage::Message(message));
                 }
                 if let Err(e) = result {
-                    messages.push(ConnectionReadMessage::Error(e));
+                    messages.push(ConnectionReadMessage::Error(e,connection.parser.error_context().clone(),connection.parser.garbled_bytes().to_vec()));
                 }
 
                 //Stop reading once INBOUND_MESSAGES_BUFFER_LEN_MAX messages have been read.
@@ -581,6 +629,10 @@ impl InternalConnection {
     }
 
     fn initiate_logout(&mut self,timer: &mut Timer<(TimeoutType,Token)>,logging_out_type: LoggingOutType,text: &[u8]) {
+        self.initiate_logout_with_session_status(timer,logging_out_type,None,text);
+    }
+
+    fn initiate_logout_with_session_status(&mut self,timer: &mut Timer<(TimeoutType,Token)>,logging_out_type: LoggingOutType,session_status: Option<SessionStatus>,text: &[u8]) {
         //Begin the logout process. Use respond_to_logout() to respond to a logout message.
 
         assert!(match logging_out_type {
@@ -590,6 +642,7 @@ impl InternalConnection {
         });
 
         let mut logout = Logout::new();
+        logout.session_status = session_status;
         logout.text = text.to_vec();
 
         //TODO: The clearing of outbound messages might be optional. Probably need a receipt or
@@ -645,6 +698,7 @@ impl InternalConnection {
 
     fn clear_inbound_resend_request_msg_seq_num(&mut self,timer: &mut Timer<(TimeoutType,Token)>) {
         self.inbound_resend_request_msg_seq_num = None;
+        self.inbound_out_of_order_resend_request_end_seq_no = 0;
 
         //If remote started a logout, we noticed missing messaged, and have now
         //received all of those messages, finally respond to logout.
@@ -689,6 +743,27 @@ impl InternalConnection {
         network_read_retry.queue(self.token);
     }
 
+    fn clear_out_of_order_messages(&mut self) {
+        //Anything requested for the queued messages is either no longer needed or will be
+        //requested again.
+        self.inbound_out_of_order_messages.clear();
+        self.inbound_out_of_order_resend_request_end_seq_no = 0;
+    }
+
+    fn can_queue_out_of_order_message(&self,message: &FIXTMessage,msg_seq_num: MsgSeqNumType) -> bool {
+        //ResendRequest and Logout messages are acted upon immediately even when out of order and
+        //Logon is only ever processed out of order. So these are never queued.
+        if self.inbound_out_of_order_messages_len_max == 0 ||
+           message.as_any().is::<ResendRequest>() ||
+           message.as_any().is::<Logout>() ||
+           message.as_any().is::<Logon>() {
+            return false;
+        }
+
+        self.inbound_out_of_order_messages.len() < self.inbound_out_of_order_messages_len_max ||
+        self.inbound_out_of_order_messages.contains_key(&msg_seq_num)
+    }
+
     fn as_connection(&self) -> Connection {
         Connection(self.token.0)
     }
@@ -696,7 +771,7 @@ impl InternalConnection {
 
 macro_rules! try_write_connection_or_terminate {
     ( $connection_entry:ident, $internal_thread:ident ) => {
-        if let Err(e) = $connection_entry.get_mut().write(&mut $internal_thread.timer,&mut $internal_thread.network_read_retry) {
+        if let Err(e) = $connection_entry.get_mut().write(&$internal_thread.tx,&mut $internal_thread.timer,&mut $internal_thread.network_read_retry) {
             return Err(ConnectionEventError::TerminateConnection($connection_entry.remove(),e));
         }
     }
@@ -706,6 +781,10 @@ struct InternalListener {
     socket: TcpListener,
     token: Token,
     sender_comp_id: <<SenderCompID as Field>::Type as FieldType>::Type,
+    logon_authenticator: Option<Box<LogonAuthenticator + Send>>,
+    validation_config: ValidationConfig, //Copied to the parser of every accepted connection.
+    parse_limits: ParseLimits, //Copied to the parser of every accepted connection.
+    data_dictionary: Option<Arc<DataDictionary>>, //Copied to the parser of every accepted connection.
 }
 
 impl InternalListener {
@@ -771,6 +850,10 @@ impl InternalThread {
                     socket: socket,
                     token: token,
                     sender_comp_id: sender_comp_id,
+                    logon_authenticator: None,
+                    validation_config: ValidationConfig::default(),
+                    parse_limits: ParseLimits::default(),
+                    data_dictionary: None,
                 };
 
                 if let Err(e) = self.poll.register(&listener.socket,listener.token,Ready::readable(),PollOpt::edge()) {
@@ -783,8 +866,11 @@ impl InternalThread {
             //Engine wants to send a message over a connection.
             InternalEngineToThreadEvent::SendMessage(token,message_version,message) => {
                 if let Entry::Occupied(mut connection_entry) = self.connections.entry(token) {
+                    //Messages with a message version were already validated by the engine. The rest
+                    //are validated when they're serialized.
                     let mut outbound_message = OutboundMessage::from_box(message);
                     outbound_message.message_version = message_version;
+                    outbound_message.validate = message_version.is_none();
                     connection_entry.get_mut().outbound_messages.push(outbound_message);
                     try_write_connection_or_terminate!(connection_entry,self);
                 }
@@ -845,8 +931,118 @@ impl InternalThread {
                     //TODO: Maybe submit this to a logging system or something?
                 }
             },
+            //Engine wants to change how strictly SendingTime is checked on a connection.
+            InternalEngineToThreadEvent::SetSendingTimeTolerance(token,tolerance,policy) => {
+                if let Some(connection) = self.connections.get_mut(&token) {
+                    connection.inbound_sending_time_tolerance = tolerance;
+                    connection.inbound_sending_time_policy = policy;
+                }
+                else {
+                    //Silently ignore for an invalid connection.
+                    //TODO: Maybe submit this to a logging system or something?
+                }
+            },
+            //Engine wants to change how PossDup messages with OrigSendingTime > SendingTime are
+            //handled on a connection.
+            InternalEngineToThreadEvent::SetOrigSendingTimePolicy(token,policy) => {
+                if let Some(connection) = self.connections.get_mut(&token) {
+                    connection.inbound_orig_sending_time_policy = policy;
+                }
+                else {
+                    //Silently ignore for an invalid connection.
+                    //TODO: Maybe submit this to a logging system or something?
+                }
+            },
+            //Engine wants to logout connection when too many garbled messages are received.
+            InternalEngineToThreadEvent::SetGarbledMessageThreshold(token,threshold) => {
+                if let Some(connection) = self.connections.get_mut(&token) {
+                    connection.inbound_garbled_threshold = threshold;
+                    connection.inbound_garbled_times.clear();
+                }
+                else {
+                    //Silently ignore for an invalid connection.
+                    //TODO: Maybe submit this to a logging system or something?
+                }
+            },
+            //Engine wants to hold onto out of order messages until the gap is filled instead of
+            //discarding them.
+            InternalEngineToThreadEvent::SetInboundOutOfOrderQueueLenMax(token,len_max) => {
+                if let Some(connection) = self.connections.get_mut(&token) {
+                    connection.inbound_out_of_order_messages_len_max = len_max;
+                    if len_max == 0 {
+                        connection.clear_out_of_order_messages();
+                    }
+                }
+                else {
+                    //Silently ignore for an invalid connection.
+                    //TODO: Maybe submit this to a logging system or something?
+                }
+            },
+            InternalEngineToThreadEvent::SetValidationConfig(token,validation_config) => {
+                if let Some(connection) = self.connections.get_mut(&token) {
+                    connection.parser.set_validation_config(validation_config);
+                }
+                else {
+                    //Silently ignore for an invalid connection.
+                    //TODO: Maybe submit this to a logging system or something?
+                }
+            },
+            InternalEngineToThreadEvent::SetParseLimits(token,parse_limits) => {
+                if let Some(connection) = self.connections.get_mut(&token) {
+                    connection.parser.set_parse_limits(parse_limits);
+                }
+                else {
+                    //Silently ignore for an invalid connection.
+                    //TODO: Maybe submit this to a logging system or something?
+                }
+            },
+            InternalEngineToThreadEvent::SetDataDictionary(token,data_dictionary) => {
+                if let Some(connection) = self.connections.get_mut(&token) {
+                    connection.parser.set_data_dictionary(data_dictionary);
+                }
+                else {
+                    //Silently ignore for an invalid connection.
+                    //TODO: Maybe submit this to a logging system or something?
+                }
+            },
+            InternalEngineToThreadEvent::SetLogonAuthenticator(token,authenticator) => {
+                if let Some(listener) = self.listeners.get_mut(&token) {
+                    listener.logon_authenticator = authenticator;
+                }
+                else {
+                    //Silently ignore for an invalid listener.
+                    //TODO: Maybe submit this to a logging system or something?
+                }
+            },
+            InternalEngineToThreadEvent::SetListenerValidationConfig(token,validation_config) => {
+                if let Some(listener) = self.listeners.get_mut(&token) {
+                    listener.validation_config = validation_config;
+                }
+                else {
+                    //Silently ignore for an invalid listener.
+                    //TODO: Maybe submit this to a logging system or something?
+                }
+            },
+            InternalEngineToThreadEvent::SetListenerParseLimits(token,parse_limits) => {
+                if let Some(listener) = self.listeners.get_mut(&token) {
+                    listener.parse_limits = parse_limits;
+                }
+                else {
+                    //Silently ignore for an invalid listener.
+                    //TODO: Maybe submit this to a logging system or something?
+                }
+            },
+            InternalEngineToThreadEvent::SetListenerDataDictionary(token,data_dictionary) => {
+                if let Some(listener) = self.listeners.get_mut(&token) {
+                    listener.data_dictionary = data_dictionary;
+                }
+                else {
+                    //Silently ignore for an invalid listener.
+                    //TODO: Maybe submit this to a logging system or something?
+                }
+            },
             //Engine wants to approve logon of a connection that was accepted by a listener.
-            InternalEngineToThreadEvent::ApproveNewConnection(connection,message,inbound_msg_seq_num) => {
+            InternalEngineToThreadEvent::ApproveNewConnection(connection,mut message,inbound_msg_seq_num) => {
                 if let Entry::Occupied(mut connection_entry) = self.connections.entry(Token(connection.0)) {
                     {
                         let connection = connection_entry.get_mut();
@@ -859,6 +1055,12 @@ impl InternalThread {
 
                         connection.status = ConnectionStatus::Established;
 
+                        //Let remote know the outcome of authentication unless the response already
+                        //says otherwise.
+                        if message.session_status.is_none() {
+                            message.session_status = connection.logon_session_status.take();
+                        }
+
                         //Setup the version messages should be serialized against by default when
                         //being sent. Only FIXT 1.1 makes this adjustable and it MUST be set by the
                         //response Logon message in the DefaultApplVerID field.
@@ -1068,9 +1270,9 @@ impl InternalThread {
                     for message in messages {
                         let result = match message {
                             ConnectionReadMessage::Message(message) =>
-                                InternalThread::on_network_message(connection_entry.get_mut(),message,&self.tx,&mut self.timer),
-                            ConnectionReadMessage::Error(parse_error) =>
-                                InternalThread::on_network_parse_error(connection_entry.get_mut(),parse_error,&self.tx),
+                                InternalThread::on_network_message(connection_entry.get_mut(),message,&mut self.listeners,&self.tx,&mut self.timer),
+                            ConnectionReadMessage::Error(parse_error,error_context,bytes) =>
+                                InternalThread::on_network_parse_error(connection_entry.get_mut(),parse_error,error_context,bytes,&self.tx,&mut self.timer),
                         };
 
                         if let Err(e) = result {
@@ -1144,6 +1346,12 @@ impl InternalThread {
                                                                      listener_entry.get().sender_comp_id.clone(),
                                                                      Vec::new());
                         connection.is_connected = true; //Accepted connections don't have to wait for connect().
+
+                        //Use the listener's parser settings so they apply to the Logon message
+                        //too.
+                        connection.parser.set_validation_config(listener_entry.get().validation_config.clone());
+                        connection.parser.set_parse_limits(listener_entry.get().parse_limits.clone());
+                        connection.parser.set_data_dictionary(listener_entry.get().data_dictionary.clone());
                         let timeout = self.timer.set_timeout(
                             Duration::from_secs(AUTO_DISCONNECT_AFTER_NO_LOGON_RECEIVED_SECONDS),
                             (TimeoutType::NoLogon,token)).unwrap();
@@ -1171,7 +1379,27 @@ impl InternalThread {
         Ok(())
     }
 
-    fn on_network_message(connection: &mut InternalConnection,mut message: Box<FIXTMessage + Send>,tx: &Sender<EngineEvent>,timer: &mut Timer<(TimeoutType,Token)>) -> Result<(),ConnectionTerminatedReason>  {
+    fn on_network_message(connection: &mut InternalConnection,message: Box<FIXTMessage + Send>,listeners: &mut HashMap<Token,InternalListener>,tx: &Sender<EngineEvent>,timer: &mut Timer<(TimeoutType,Token)>) -> Result<(),ConnectionTerminatedReason>  {
+        try!(InternalThread::process_network_message(connection,message,UTC::now(),tx,timer));
+
+        //Process any queued out of order messages that are now next in sequence. Queued messages
+        //that were skipped over (ie. by a SequenceReset-GapFill or because the resent copy was
+        //already processed) are dropped.
+        while let Some(msg_seq_num) = connection.inbound_out_of_order_messages.keys().next().cloned() {
+            if msg_seq_num > connection.inbound_msg_seq_num || connection.status.is_logging_out_with_error() {
+                break;
+            }
+
+            let (message,received_time) = connection.inbound_out_of_order_messages.remove(&msg_seq_num).unwrap();
+            if msg_seq_num == connection.inbound_msg_seq_num {
+                try!(InternalThread::process_network_message(connection,message,received_time,tx,timer));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn process_network_message(connection: &mut InternalConnection,mut message: Box<FIXTMessage + Send>,received_time: DateTime<UTC>,tx: &Sender<EngineEvent>,timer: &mut Timer<(TimeoutType,Token)>) -> Result<(),ConnectionTerminatedReason>  {
         //Perform book keeping needed to maintain the FIX connection and then pass off the message
         //to the engine.
 
@@ -1265,7 +1493,47 @@ impl InternalThread {
             tx.send(EngineEvent::MessageRejected(connection.as_connection(),message)).unwrap();
         }
 
-        fn on_greater_than_expected_msg_seq_num(connection: &mut InternalConnection,mut message: Box<FIXTMessage + Send>,msg_seq_num: MsgSeqNumType,tx: &Sender<EngineEvent>,timer: &mut Timer<(TimeoutType,Token)>) -> Option<Box<FIXTMessage + Send>> {
+        fn is_orig_sending_time_accurate(connection: &InternalConnection,message: &FIXTMessage,msg_seq_num: MsgSeqNumType,tx: &Sender<EngineEvent>) -> bool {
+            if !message.is_poss_dup() || message.orig_sending_time() <= message.sending_time() {
+                return true;
+            }
+
+            match connection.inbound_orig_sending_time_policy {
+                SendingTimePolicy::Reject => false,
+                SendingTimePolicy::Warn => {
+                    tx.send(EngineEvent::OrigSendingTimeAfterSendingTime(connection.as_connection(),msg_seq_num)).unwrap();
+                    true
+                },
+                SendingTimePolicy::Ignore => true,
+            }
+        }
+
+        fn is_sending_time_accurate(connection: &InternalConnection,message: &FIXTMessage,msg_seq_num: MsgSeqNumType,received_time: DateTime<UTC>,tx: &Sender<EngineEvent>) -> bool {
+            if let SendingTimePolicy::Ignore = connection.inbound_sending_time_policy {
+                return true;
+            }
+
+            //Drift is positive when SendingTime is in the past. Clocks can be off in either
+            //direction so only the magnitude is compared against the tolerance. Messages from the
+            //out of order queue are compared using when they were received so time spent waiting
+            //for the gap to be filled doesn't count.
+            let drift = received_time.signed_duration_since(message.sending_time());
+            let tolerance_ms = connection.inbound_sending_time_tolerance.as_secs().saturating_mul(1000) + (connection.inbound_sending_time_tolerance.subsec_nanos() / 1_000_000) as u64;
+            if drift.num_milliseconds().abs() as u64 <= tolerance_ms {
+                return true;
+            }
+
+            match connection.inbound_sending_time_policy {
+                SendingTimePolicy::Reject => false,
+                SendingTimePolicy::Warn => {
+                    tx.send(EngineEvent::SendingTimeOutsideTolerance(connection.as_connection(),msg_seq_num,drift)).unwrap();
+                    true
+                },
+                SendingTimePolicy::Ignore => unreachable!(),
+            }
+        }
+
+        fn on_greater_than_expected_msg_seq_num(connection: &mut InternalConnection,mut message: Box<FIXTMessage + Send>,msg_seq_num: MsgSeqNumType,tx: &Sender<EngineEvent>,timer: &mut Timer<(TimeoutType,Token)>) -> Option<(Box<FIXTMessage + Send>,bool)> {
             //FIXT v1.1, page 13: We should reply to ResendRequest first when MsgSeqNum is higher
             //than expected. Afterwards, we should send our own ResendRequest.
             message = match if_on_resend_request(connection,message,msg_seq_num,tx,timer) {
@@ -1273,14 +1541,41 @@ impl InternalThread {
                 None => return None,
             };
 
+            //Decide whether the message will be queued or discarded before anything below
+            //changes the queue. The caller must go by this decision.
+            let should_queue = connection.can_queue_out_of_order_message(&*message,msg_seq_num);
+
             //Fetch the messages the remote says were sent but we never received using
             //ResendRequest. The one exception is if we are _receiving_ a ResendRequest message
             //because then we're suppose to defer until after we respond.
             if message.as_any().downcast_ref::<ResendRequest>().is_none() {
-                let mut resend_request = ResendRequest::new();
-                resend_request.begin_seq_no = connection.inbound_msg_seq_num;
-                resend_request.end_seq_no = 0;
-                connection.outbound_messages.push(OutboundMessage::from(resend_request));
+                if should_queue {
+                    //Message will be queued so only request the messages missing just before it
+                    //that haven't already been requested or queued.
+                    let begin_seq_no = cmp::max(
+                        cmp::max(connection.inbound_msg_seq_num,connection.inbound_out_of_order_resend_request_end_seq_no + 1),
+                        connection.inbound_out_of_order_messages.keys().next_back().map_or(0,|queued_msg_seq_num| *queued_msg_seq_num + 1)
+                    );
+                    if begin_seq_no < msg_seq_num {
+                        let mut resend_request = ResendRequest::new();
+                        resend_request.begin_seq_no = begin_seq_no;
+                        resend_request.end_seq_no = msg_seq_num - 1;
+                        connection.outbound_messages.push(OutboundMessage::from(resend_request));
+
+                        connection.inbound_out_of_order_resend_request_end_seq_no = msg_seq_num - 1;
+                    }
+                }
+                else {
+                    //Either queueing is disabled or the queue is full. Fall back to discarding
+                    //the message and requesting everything. Anything already queued will be
+                    //resent so it's no longer needed.
+                    connection.clear_out_of_order_messages();
+
+                    let mut resend_request = ResendRequest::new();
+                    resend_request.begin_seq_no = connection.inbound_msg_seq_num;
+                    resend_request.end_seq_no = 0;
+                    connection.outbound_messages.push(OutboundMessage::from(resend_request));
+                }
             }
 
             //Keep track of the newest msg_seq_num that's been seen so we know when the message gap has
@@ -1350,7 +1645,7 @@ impl InternalThread {
                 }
             }
 
-            Some(message)
+            Some((message,should_queue))
         }
 
         fn on_less_than_expected_msg_seq_num(connection: &mut InternalConnection,message: Box<FIXTMessage + Send>,msg_seq_num: MsgSeqNumType,tx: &Sender<EngineEvent>,timer: &mut Timer<(TimeoutType,Token)>) {
@@ -1358,8 +1653,9 @@ impl InternalThread {
             //either duplicates (as indicated) or an unrecoverable error where one side fell
             //out of sync.
             if message.is_poss_dup() {
-                if message.orig_sending_time() <= message.sending_time() {
-                    //Duplicate message that otherwise seems correct.
+                if is_orig_sending_time_accurate(connection,&*message,msg_seq_num,tx) {
+                    //Duplicate message that otherwise seems correct (or is allowed to be
+                    //inaccurate by the OrigSendingTime policy).
                     tx.send(EngineEvent::MessageReceivedDuplicate(connection.as_connection(),message)).unwrap();
                 }
                 else {
@@ -1378,13 +1674,14 @@ impl InternalThread {
             }
         }
 
-        fn on_expected_msg_seq_num(connection: &mut InternalConnection,mut message: Box<FIXTMessage + Send>,msg_seq_num: MsgSeqNumType,tx: &Sender<EngineEvent>,timer: &mut Timer<(TimeoutType,Token)>) -> Result<Option<Box<FIXTMessage + Send>>,ConnectionTerminatedReason> {
+        fn on_expected_msg_seq_num(connection: &mut InternalConnection,mut message: Box<FIXTMessage + Send>,msg_seq_num: MsgSeqNumType,received_time: DateTime<UTC>,tx: &Sender<EngineEvent>,timer: &mut Timer<(TimeoutType,Token)>) -> Result<Option<Box<FIXTMessage + Send>>,ConnectionTerminatedReason> {
             //Start by incrementing expected inbound MsgSeqNum since the message is at least
             //formatted correctly and matches the expected MsgSeqNum.
             try!(connection.increment_inbound_msg_seq_num());
 
             //Handle general FIXT message validation.
-            if message.is_poss_dup() && message.orig_sending_time() > message.sending_time() {
+            if !is_orig_sending_time_accurate(connection,&*message,msg_seq_num,tx) ||
+               !is_sending_time_accurate(connection,&*message,msg_seq_num,received_time,tx) {
                 reject_for_sending_time_accuracy(connection,message,msg_seq_num,tx);
                 return Ok(None);
             }
@@ -1597,6 +1894,23 @@ impl InternalThread {
                     }
                 }
 
+                //Let the listener's authenticator decide if the connection can continue before
+                //the application is asked to approve it.
+                let result = match listeners.get_mut(&Token(listener.0)).and_then(|internal_listener| internal_listener.logon_authenticator.as_mut()) {
+                    Some(authenticator) => Some(authenticator.authenticate(listener,connection.as_connection(),message)),
+                    None => None,
+                };
+                if let Some(result) = result {
+                    match result {
+                        LogonAuthenticationResult::Accept => connection.logon_session_status = Some(SessionStatus::SessionActive),
+                        LogonAuthenticationResult::PasswordChanged => connection.logon_session_status = Some(SessionStatus::SessionPasswordChanged),
+                        LogonAuthenticationResult::Reject(session_status) => {
+                            connection.initiate_logout_with_session_status(timer,LoggingOutType::Error(ConnectionTerminatedReason::LogonAuthenticationFailedError),Some(session_status),b"Logon rejected");
+                            return Ok(());
+                        },
+                    }
+                }
+
                 //Block reading of new messages until connection has been approved. This will be
                 //automatically unblocked when the Logon response is sent.
                 connection.begin_blocking_inbound(timer);
@@ -1646,15 +1960,24 @@ impl InternalThread {
             //Special case where MsgSeqNum does not matter. Handled above.
         }
         else if msg_seq_num > connection.inbound_msg_seq_num {
+            let should_queue;
             message = match on_greater_than_expected_msg_seq_num(connection,message,msg_seq_num,tx,timer) {
-                Some(message) => message,
+                Some((message,message_should_queue)) => {
+                    should_queue = message_should_queue;
+                    message
+                },
                 None => return Ok(()),
             };
 
             //The only message that can be processed out of order is the Logon message. Every other
-            //one will be discarded and we'll wait for the in-order resend.
+            //one will be queued until the gap is filled (if enabled and there's room) or discarded
+            //and we'll wait for the in-order resend.
             if !just_logged_on {
-                //Message is discarded.
+                if should_queue {
+                    connection.inbound_out_of_order_messages.insert(msg_seq_num,(message,received_time));
+                }
+
+                //Otherwise, message is discarded.
                 return Ok(());
             }
         }
@@ -1663,7 +1986,7 @@ impl InternalThread {
             return Ok(());
         }
         else {
-            message = match try!(on_expected_msg_seq_num(connection,message,msg_seq_num,tx,timer)) {
+            message = match try!(on_expected_msg_seq_num(connection,message,msg_seq_num,received_time,tx,timer)) {
                 Some(message) => message,
                 None => return Ok(()),
             };
@@ -1689,7 +2012,7 @@ impl InternalThread {
         Ok(())
     }
 
-    fn on_network_parse_error(connection: &mut InternalConnection,parse_error: ParseError,tx: &Sender<EngineEvent>)-> Result<(),ConnectionTerminatedReason> {
+    fn on_network_parse_error(connection: &mut InternalConnection,parse_error: ParseError,error_context: ParseErrorContext,bytes: Vec<u8>,tx: &Sender<EngineEvent>,timer: &mut Timer<(TimeoutType,Token)>)-> Result<(),ConnectionTerminatedReason> {
         fn push_reject<T: Into<Vec<u8>>>(connection: &mut InternalConnection,ref_msg_type: &[u8],ref_tag_id: T,session_reject_reason: SessionRejectReason,text: &[u8]) -> Result<(),ConnectionTerminatedReason> {
             let mut reject = Reject::new();
             reject.ref_msg_type = ref_msg_type.to_vec();
@@ -1711,6 +2034,8 @@ impl InternalThread {
             },
             //Handle parse error as normal. Usually just respond with a Reject
             _ => {
+                //Only messages that are ignored without a Reject are truly garbled.
+                let mut is_garbled = false;
                 match parse_error {
                     ParseError::MissingRequiredTag(ref tag,_) => {
                         try!(push_reject(connection,b"",*tag,SessionRejectReason::RequiredTagMissing,b"Required tag missing"));
@@ -1744,6 +2069,21 @@ impl InternalThread {
                         error_text.extend_from_slice(connection.parser.max_message_size().to_string().as_bytes());
                         try!(push_reject(connection,b"",Vec::new(),SessionRejectReason::Other,&error_text[..]));
                     },
+                    ParseError::RepeatingGroupCountTooLarge(ref tag) => {
+                        let mut error_text = b"NumInGroup count exceeds MaxGroupCount=".to_vec();
+                        error_text.extend_from_slice(connection.parser.parse_limits().max_group_count.to_string().as_bytes());
+                        try!(push_reject(connection,b"",*tag,SessionRejectReason::IncorrectNumInGroupCountForRepeatingGroup,&error_text[..]));
+                    },
+                    ParseError::RepeatingGroupNestedTooDeep(ref tag) => {
+                        let mut error_text = b"Repeating group nesting exceeds MaxGroupDepth=".to_vec();
+                        error_text.extend_from_slice(connection.parser.parse_limits().max_group_depth.to_string().as_bytes());
+                        try!(push_reject(connection,b"",*tag,SessionRejectReason::Other,&error_text[..]));
+                    },
+                    ParseError::TooManyFields => {
+                        let mut error_text = b"Number of fields exceeds MaxFieldCount=".to_vec();
+                        error_text.extend_from_slice(connection.parser.parse_limits().max_field_count.to_string().as_bytes());
+                        try!(push_reject(connection,b"",Vec::new(),SessionRejectReason::Other,&error_text[..]));
+                    },
                     ParseError::BeginStrNotFirstTag |
                     ParseError::BodyLengthNotSecondTag |
                     ParseError::MsgTypeNotThirdTag |
@@ -1793,15 +2133,52 @@ impl InternalThread {
                             try!(push_reject(connection,&msg_type[..],&msg_type[..],SessionRejectReason::InvalidMsgType,b"Invalid MsgType"));
                         }
                     },
+                    ParseError::BodyLengthNotNumber |
+                    ParseError::ChecksumDoesNotMatch(_,_) |
+                    ParseError::ChecksumWrongFormat => {
+                        //Truly garbled messages are ignored without a Reject. See FIXT 1.1, page
+                        //40. These are what count towards the garbled message threshold below.
+                        is_garbled = true;
+                    },
                     _ => {}, //TODO: Support other errors as appropriate.
                 };
 
-                //Always increment expected inbound MsgSeqNum after encountering a message that is
-                //garbled, cannot be parsed, or is otherwise invalid. See FIXT 1.1, page 26.
-                try!(connection.increment_inbound_msg_seq_num());
+                //Increment expected inbound MsgSeqNum after encountering a message that cannot be
+                //parsed or is otherwise invalid. See FIXT 1.1, page 26. Garbled messages are
+                //ignored as if they were never received so they don't use up a MsgSeqNum. See
+                //FIXT 1.1, page 40.
+                if !is_garbled {
+                    try!(connection.increment_inbound_msg_seq_num());
+                }
 
                 //Tell user about the garbled message just in case they care.
-                tx.send(EngineEvent::MessageReceivedGarbled(connection.as_connection(),parse_error)).unwrap();
+                if is_garbled {
+                    connection.inbound_garbled_count += 1;
+                }
+                tx.send(EngineEvent::MessageReceivedGarbled(connection.as_connection(),parse_error,error_context,bytes,connection.inbound_garbled_count)).unwrap();
+                if !is_garbled {
+                    return Ok(());
+                }
+
+                //Logout if the line appears to be degrading. Only the times of garbled messages
+                //still within the window are kept.
+                if let Some((count,window)) = connection.inbound_garbled_threshold {
+                    let now = Instant::now();
+                    connection.inbound_garbled_times.push_back(now);
+                    while connection.inbound_garbled_times.len() > count ||
+                          connection.inbound_garbled_times.front().map_or(false,|time| now.duration_since(*time) > window) {
+                        connection.inbound_garbled_times.pop_front();
+                    }
+
+                    if connection.inbound_garbled_times.len() >= count && count > 0 && !connection.status.is_logging_out_with_error() {
+                        let mut text = b"Received ".to_vec();
+                        text.extend_from_slice(connection.inbound_garbled_times.len().to_string().as_bytes());
+                        text.extend_from_slice(b" garbled messages within ");
+                        text.extend_from_slice(window.as_secs().to_string().as_bytes());
+                        text.extend_from_slice(b" seconds");
+                        connection.initiate_logout(timer,LoggingOutType::Error(ConnectionTerminatedReason::InboundGarbledMessageThresholdExceededError),&text[..]);
+                    }
+                }
             },
         };
 
@@ -14,9 +14,8 @@ use std::fmt;
 use dictionary::fields::{MsgSeqNum,OrigSendingTime,SenderCompID,SendingTime,TargetCompID};
 use field::Field;
 use field_type::FieldType;
-use fix_version::FIXVersion;
 use message::{BuildMessage,Message};
-use message_version::MessageVersion;
+use pretty_print::{message_versions,pretty_print};
 
 pub trait BuildFIXTMessage: BuildMessage {
     fn new_into_box(&self) -> Box<BuildFIXTMessage + Send>;
@@ -29,7 +28,9 @@ pub trait FIXTMessageBuildable {
 
 pub trait FIXTMessage: Message {
     fn new_into_box(&self) -> Box<FIXTMessage + Send>;
-    fn msg_type(&self) -> &'static [u8];
+
+    //Borrowed from the message like Message::msg_type_header().
+    fn msg_type(&self) -> &[u8];
     fn msg_seq_num(&self) -> <<MsgSeqNum as Field>::Type as FieldType>::Type;
     fn sender_comp_id(&self) -> &<<SenderCompID as Field>::Type as FieldType>::Type;
     fn target_comp_id(&self) -> &<<TargetCompID as Field>::Type as FieldType>::Type;
@@ -46,13 +47,25 @@ pub trait FIXTMessage: Message {
 
 impl fmt::Debug for FIXTMessage {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f,"{}",Message::debug(self,FIXVersion::FIXT_1_1,MessageVersion::FIX50SP2))
+        //Use {:#?} for a listing with one field per line.
+        if f.alternate() {
+            return write!(f,"{}",pretty_print(self));
+        }
+
+        let (fix_version,message_version) = message_versions(self);
+        write!(f,"{}",Message::debug(self,fix_version,message_version))
     }
 }
 
 impl fmt::Debug for FIXTMessage + Send {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f,"{}",Message::debug(self,FIXVersion::FIXT_1_1,MessageVersion::FIX50SP2))
+        //Use {:#?} for a listing with one field per line.
+        if f.alternate() {
+            return write!(f,"{}",pretty_print(self));
+        }
+
+        let (fix_version,message_version) = message_versions(self);
+        write!(f,"{}",Message::debug(self,fix_version,message_version))
     }
 }
 
@@ -18,6 +18,7 @@ pub mod tests {
     pub use super::engine_thread::{
         AUTO_DISCONNECT_AFTER_INBOUND_RESEND_REQUEST_LOOP_COUNT,
         AUTO_DISCONNECT_AFTER_NO_LOGON_RECEIVED_SECONDS,
+        DEFAULT_SENDING_TIME_TOLERANCE_SECS,
         INBOUND_MESSAGES_BUFFER_LEN_MAX,
         INBOUND_BYTES_BUFFER_CAPACITY
     };
@@ -0,0 +1,573 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self,Write};
+use std::iter::Peekable;
+use std::mem;
+use std::vec::IntoIter;
+
+use constant::{TAG_END,VALUE_END};
+use dictionary::fields::{ApplVerID,MsgSeqNum,OrigSendingTime,PossDupFlag,SenderCompID,SendingTime,TargetCompID};
+use encoder::write_integer;
+use field::Field;
+use field_tag::FieldTag;
+use field_type::FieldType;
+use fix::{ParseError,ParseLimits};
+use fix_version::FIXVersion;
+use fixt::message::FIXTMessage;
+use hash::BuildFieldHasher;
+use message::{FieldHashSet,FieldIter,GroupIter,InvalidFieldValue,InvalidValuePolicy,Message,MessageField,Meta,MissingRequiredField,SetValueError,sanitize_bytes};
+use message_version::MessageVersion;
+use message_view::MessageViewTables;
+use pretty_print::message_versions;
+
+#[derive(Clone,PartialEq)]
+pub enum GenericFieldValue {
+    Value(Vec<u8>),
+    Groups(Vec<Vec<GenericField>>), //Each group is its own ordered list of fields.
+}
+
+#[derive(Clone,PartialEq)]
+pub struct GenericField {
+    pub tag: FieldTag,
+    pub value: GenericFieldValue,
+}
+
+impl GenericField {
+    pub fn new<T: Into<Vec<u8>>>(tag: FieldTag,value: T) -> GenericField {
+        GenericField {
+            tag: tag,
+            value: GenericFieldValue::Value(value.into()),
+        }
+    }
+
+    pub fn new_groups(tag: FieldTag,groups: Vec<Vec<GenericField>>) -> GenericField {
+        GenericField {
+            tag: tag,
+            value: GenericFieldValue::Groups(groups),
+        }
+    }
+}
+
+impl fmt::Debug for GenericField {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            GenericFieldValue::Value(ref value) => write!(f,"{}={}",self.tag,String::from_utf8_lossy(value)),
+            GenericFieldValue::Groups(ref groups) => write!(f,"{}={:?}",self.tag,groups),
+        }
+    }
+}
+
+//A message that isn't described by any dictionary. The standard header fields needed by the
+//engine are decoded like any other message. Everything else is kept as raw bytes in the order it
+//was received so the message can be inspected and sent back out unchanged.
+pub struct GenericMessage {
+    pub meta: Option<Meta>,
+    msg_type_header: Vec<u8>, //35=<MsgType><SOH>
+    pub sender_comp_id: <<SenderCompID as Field>::Type as FieldType>::Type,
+    pub target_comp_id: <<TargetCompID as Field>::Type as FieldType>::Type,
+    pub msg_seq_num: <<MsgSeqNum as Field>::Type as FieldType>::Type,
+    pub poss_dup_flag: <<PossDupFlag as Field>::Type as FieldType>::Type,
+    pub sending_time: <<SendingTime as Field>::Type as FieldType>::Type,
+    pub orig_sending_time: <<OrigSendingTime as Field>::Type as FieldType>::Type,
+    pub fields: Vec<GenericField>, //All other fields, including ApplVerID, in wire order.
+    pub data_tags: FieldHashSet, //Tags of data fields, which can hold any byte. Filled in from the dictionary when parsed.
+}
+
+impl GenericMessage {
+    pub fn new(msg_type: &[u8]) -> GenericMessage {
+        let mut msg_type_header = b"35=".to_vec();
+        msg_type_header.extend_from_slice(msg_type);
+        msg_type_header.push(VALUE_END);
+
+        GenericMessage {
+            meta: None,
+            msg_type_header: msg_type_header,
+            sender_comp_id: <SenderCompID as Field>::Type::default_value(),
+            target_comp_id: <TargetCompID as Field>::Type::default_value(),
+            msg_seq_num: <MsgSeqNum as Field>::Type::default_value(),
+            poss_dup_flag: <PossDupFlag as Field>::Type::default_value(),
+            sending_time: <SendingTime as Field>::Type::default_value(),
+            orig_sending_time: <OrigSendingTime as Field>::Type::default_value(),
+            fields: Vec::new(),
+            data_tags: HashSet::with_hasher(BuildFieldHasher),
+        }
+    }
+
+    pub fn get(&self,tag: FieldTag) -> Option<&GenericFieldValue> {
+        self.fields.iter().find(|field| field.tag == tag).map(|field| &field.value)
+    }
+
+    pub fn get_value(&self,tag: FieldTag) -> Option<&[u8]> {
+        match self.get(tag) {
+            Some(&GenericFieldValue::Value(ref value)) => Some(&value[..]),
+            _ => None,
+        }
+    }
+
+    pub fn get_groups(&self,number_of_groups_tag: FieldTag) -> Option<&Vec<Vec<GenericField>>> {
+        match self.get(number_of_groups_tag) {
+            Some(&GenericFieldValue::Groups(ref groups)) => Some(groups),
+            _ => None,
+        }
+    }
+
+    pub fn push(&mut self,field: GenericField) {
+        self.fields.push(field);
+    }
+
+    pub fn nest_groups(&mut self,tables: &MessageViewTables,parse_limits: &ParseLimits) -> Result<(),ParseError> {
+        //The parser stores every field in a flat list because it doesn't know the layout of the
+        //message ahead of time. Fold any repeating groups found in the dictionary back into their
+        //number of groups field. The same ParseLimits apply as when parsing into a message. Data
+        //fields are recorded too so sanitize_values() leaves them alone.
+        let flat_fields = mem::replace(&mut self.fields,Vec::new());
+        for field in &flat_fields {
+            if let Some(data_tag) = tables.data_tag(field.tag) {
+                self.data_tags.insert(data_tag);
+            }
+        }
+        self.fields = try!(nest_fields(tables,parse_limits,&mut flat_fields.into_iter().peekable(),None,0));
+        Ok(())
+    }
+}
+
+fn nest_fields<'a>(tables: &'a MessageViewTables,parse_limits: &ParseLimits,flat_fields: &mut Peekable<IntoIter<GenericField>>,group: Option<(FieldTag,&'a FieldHashSet)>,group_depth: usize) -> Result<Vec<GenericField>,ParseError> {
+    let mut fields = Vec::new();
+
+    loop {
+        //A group ends when the first tag of the next group is found or when a tag that can't
+        //belong to the group is found.
+        match flat_fields.peek() {
+            Some(field) => if let Some((first_tag,group_tags)) = group {
+                if (!fields.is_empty() && field.tag == first_tag) || !group_tags.contains(&field.tag) {
+                    break;
+                }
+            },
+            None => break,
+        }
+
+        let mut field = flat_fields.next().unwrap();
+        if let Some(group_tags) = tables.group_tags(field.tag) {
+            let group_count = match field.value {
+                GenericFieldValue::Value(ref value) => String::from_utf8_lossy(value).parse::<usize>().unwrap_or(0),
+                GenericFieldValue::Groups(_) => 0,
+            };
+            let first_tag = match flat_fields.peek() {
+                Some(first_field) if group_tags.contains(&first_field.tag) => first_field.tag,
+                _ => FieldTag::empty(),
+            };
+
+            if group_count > parse_limits.max_group_count {
+                return Err(ParseError::RepeatingGroupCountTooLarge(field.tag));
+            }
+            if group_count > 0 && !first_tag.is_empty() {
+                if group_depth + 1 > parse_limits.max_group_depth {
+                    return Err(ParseError::RepeatingGroupNestedTooDeep(field.tag));
+                }
+
+                let mut groups = Vec::new();
+                while groups.len() < group_count && flat_fields.peek().map_or(false,|next_field| next_field.tag == first_tag) {
+                    groups.push(try!(nest_fields(tables,parse_limits,flat_fields,Some((first_tag,group_tags)),group_depth + 1)));
+                }
+                field.value = GenericFieldValue::Groups(groups);
+            }
+        }
+
+        fields.push(field);
+    }
+
+    Ok(fields)
+}
+
+fn sanitize_fields(fields: &mut Vec<GenericField>,data_tags: &FieldHashSet,policy: InvalidValuePolicy) -> Result<(),InvalidFieldValue> {
+    //Data fields are allowed to contain any byte.
+    for field in fields.iter_mut() {
+        let tag = field.tag;
+        match field.value {
+            GenericFieldValue::Value(ref mut value) => {
+                if !data_tags.contains(&tag) && !sanitize_bytes(value,policy) {
+                    return Err(InvalidFieldValue {
+                        tag: tag,
+                        name: "",
+                    });
+                }
+            },
+            GenericFieldValue::Groups(ref mut groups) => {
+                for group in groups.iter_mut() {
+                    try!(sanitize_fields(group,data_tags,policy));
+                }
+            },
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_fields(fields: &Vec<GenericField>) -> Result<(),MissingRequiredField> {
+    //Every field was added on purpose so none of them should be sent without a value.
+    for field in fields {
+        match field.value {
+            GenericFieldValue::Value(ref value) => {
+                if value.is_empty() {
+                    return Err(MissingRequiredField {
+                        tag: field.tag,
+                        name: "",
+                    });
+                }
+            },
+            GenericFieldValue::Groups(ref groups) => {
+                for group in groups {
+                    try!(validate_fields(group));
+                }
+            },
+        }
+    }
+
+    Ok(())
+}
+
+fn read_field(field: &GenericField,buf: &mut Vec<u8>) -> usize {
+    let mut byte_count = 2;
+
+    byte_count += buf.write(&field.tag.to_bytes()[..]).unwrap();
+    buf.push(TAG_END);
+    match field.value {
+        GenericFieldValue::Value(ref value) => {
+            byte_count += buf.write(&value[..]).unwrap();
+            buf.push(VALUE_END);
+        },
+        GenericFieldValue::Groups(ref groups) => {
+            byte_count += buf.write(groups.len().to_string().as_bytes()).unwrap();
+            buf.push(VALUE_END);
+            for group in groups {
+                for group_field in group {
+                    byte_count += read_field(group_field,buf);
+                }
+            }
+        },
+    }
+
+    byte_count
+}
+
+fn write_field(field: &GenericField,writer: &mut Write) -> io::Result<usize> {
+    //Same as read_field() but written straight to writer.
+    let mut byte_count = try!(write_integer(field.tag,writer)) + 2;
+    try!(writer.write_all(&[TAG_END]));
+    match field.value {
+        GenericFieldValue::Value(ref value) => {
+            try!(writer.write_all(&value[..]));
+            try!(writer.write_all(&[VALUE_END]));
+            byte_count += value.len();
+        },
+        GenericFieldValue::Groups(ref groups) => {
+            byte_count += try!(write_integer(groups.len(),writer));
+            try!(writer.write_all(&[VALUE_END]));
+            for group in groups {
+                for group_field in group {
+                    byte_count += try!(write_field(group_field,writer));
+                }
+            }
+        },
+    }
+
+    Ok(byte_count)
+}
+
+pub fn read_message_field(field: &GenericField) -> MessageField {
+    match field.value {
+        GenericFieldValue::Value(ref value) => MessageField {
+            tag: field.tag,
+            value: Cow::Borrowed(&value[..]),
+            groups: GroupIter::empty(),
+        },
+        GenericFieldValue::Groups(ref groups) => MessageField {
+            tag: field.tag,
+            value: Cow::Owned(groups.len().to_string().into_bytes()),
+            groups: GroupIter::from_generic_groups(&groups[..]),
+        },
+    }
+}
+
+impl Clone for GenericMessage {
+    fn clone(&self) -> Self {
+        GenericMessage {
+            meta: self.meta.clone(),
+            msg_type_header: self.msg_type_header.clone(),
+            sender_comp_id: self.sender_comp_id.clone(),
+            target_comp_id: self.target_comp_id.clone(),
+            msg_seq_num: self.msg_seq_num,
+            poss_dup_flag: self.poss_dup_flag,
+            sending_time: self.sending_time,
+            orig_sending_time: self.orig_sending_time,
+            fields: self.fields.clone(),
+            data_tags: self.data_tags.clone(),
+        }
+    }
+}
+
+impl PartialEq for GenericMessage {
+    fn eq(&self,other: &GenericMessage) -> bool {
+        //Note: Meta is not compared for the same reason as messages made using define_message!.
+        //Data tags describe the dictionary the message was parsed with, not the message itself.
+        self.msg_type_header == other.msg_type_header &&
+        self.sender_comp_id == other.sender_comp_id &&
+        self.target_comp_id == other.target_comp_id &&
+        self.msg_seq_num == other.msg_seq_num &&
+        self.poss_dup_flag == other.poss_dup_flag &&
+        self.sending_time == other.sending_time &&
+        self.orig_sending_time == other.orig_sending_time &&
+        self.fields == other.fields
+    }
+}
+
+impl fmt::Debug for GenericMessage {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        let (fix_version,message_version) = match self.meta {
+            Some(ref meta) => (meta.begin_string,meta.message_version),
+            None => (FIXVersion::FIXT_1_1,MessageVersion::FIX50SP2),
+        };
+
+        write!(f,"{}",Message::debug(self,fix_version,message_version))
+    }
+}
+
+impl Message for GenericMessage {
+    fn conditional_required_fields(&self,_version: MessageVersion) -> Vec<FieldTag> {
+        if self.poss_dup_flag {
+            vec![OrigSendingTime::tag()]
+        }
+        else {
+            Vec::new()
+        }
+    }
+
+    fn meta(&self) -> &Option<Meta> {
+        &self.meta
+    }
+
+    fn set_meta(&mut self,meta: Meta) {
+        self.meta = Some(meta);
+    }
+
+    fn set_value(&mut self,key: FieldTag,value: &[u8]) -> Result<(),SetValueError> {
+        if key == SenderCompID::tag() { <SenderCompID as Field>::Type::set_value(&mut self.sender_comp_id,value) }
+        else if key == TargetCompID::tag() { <TargetCompID as Field>::Type::set_value(&mut self.target_comp_id,value) }
+        else if key == MsgSeqNum::tag() { <MsgSeqNum as Field>::Type::set_value(&mut self.msg_seq_num,value) }
+        else if key == PossDupFlag::tag() { <PossDupFlag as Field>::Type::set_value(&mut self.poss_dup_flag,value) }
+        else if key == SendingTime::tag() { <SendingTime as Field>::Type::set_value(&mut self.sending_time,value) }
+        else if key == OrigSendingTime::tag() { <OrigSendingTime as Field>::Type::set_value(&mut self.orig_sending_time,value) }
+        else {
+            self.fields.push(GenericField::new(key,value));
+            Ok(())
+        }
+    }
+
+    fn set_groups(&mut self,_key: FieldTag,_groups: Vec<Box<Message>>) -> bool {
+        //Groups are built from the flat list of fields using nest_groups() instead.
+        false
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut Any {
+        self
+    }
+
+    fn new_into_box(&self) -> Box<Message + Send> {
+        Box::new(GenericMessage::new(self.msg_type()))
+    }
+
+    fn msg_type_header(&self) -> &[u8] {
+        &self.msg_type_header[..]
+    }
+
+    fn field_name(&self,tag: FieldTag) -> Option<&'static str> {
+        //There is no dictionary to look up the other fields with so they're only known by tag.
+        if tag == SenderCompID::tag() { Some(SenderCompID::name()) }
+        else if tag == TargetCompID::tag() { Some(TargetCompID::name()) }
+        else if tag == MsgSeqNum::tag() { Some(MsgSeqNum::name()) }
+        else if tag == PossDupFlag::tag() { Some(PossDupFlag::name()) }
+        else if tag == SendingTime::tag() { Some(SendingTime::name()) }
+        else if tag == OrigSendingTime::tag() { Some(OrigSendingTime::name()) }
+        else { None }
+    }
+
+    fn sanitize_values(&mut self,policy: InvalidValuePolicy) -> Result<(),InvalidFieldValue> {
+        try!(SenderCompID::sanitize(&mut self.sender_comp_id,policy));
+        try!(TargetCompID::sanitize(&mut self.target_comp_id,policy));
+        sanitize_fields(&mut self.fields,&self.data_tags,policy)
+    }
+
+    fn validate(&self,message_version: MessageVersion) -> Result<(),MissingRequiredField> {
+        //Only the standard header is known. Like every other message, the header fields the engine
+        //fills in are skipped.
+        let required = self.conditional_required_fields(message_version).contains(&OrigSendingTime::tag());
+        try!(OrigSendingTime::validate(&self.orig_sending_time,message_version,required));
+        validate_fields(&self.fields)
+    }
+
+    fn read_body(&self,fix_version: FIXVersion,message_version: MessageVersion,buf: &mut Vec<u8>) -> usize {
+        let mut byte_count = 0;
+
+        //SenderCompID and TargetCompID must be the fourth and fifth fields. If ApplVerID is
+        //specified, it must be the sixth field and only exists in FIXT.1.1.
+        byte_count += SenderCompID::read(&self.sender_comp_id,fix_version,message_version,buf,true);
+        byte_count += TargetCompID::read(&self.target_comp_id,fix_version,message_version,buf,true);
+        for field in &self.fields {
+            if field.tag == ApplVerID::tag() && fix_version == FIXVersion::FIXT_1_1 {
+                byte_count += read_field(field,buf);
+            }
+        }
+
+        byte_count += MsgSeqNum::read(&self.msg_seq_num,fix_version,message_version,buf,true);
+        byte_count += PossDupFlag::read(&self.poss_dup_flag,fix_version,message_version,buf,false);
+        byte_count += SendingTime::read(&self.sending_time,fix_version,message_version,buf,true);
+        byte_count += OrigSendingTime::read(&self.orig_sending_time,fix_version,message_version,buf,false);
+
+        for field in &self.fields {
+            if field.tag != ApplVerID::tag() {
+                byte_count += read_field(field,buf);
+            }
+        }
+
+        byte_count
+    }
+
+    fn write_body(&self,fix_version: FIXVersion,message_version: MessageVersion,writer: &mut Write,scratch: &mut Vec<u8>) -> io::Result<usize> {
+        //Same order as read_body().
+        let mut byte_count = 0;
+
+        byte_count += try!(SenderCompID::write(&self.sender_comp_id,fix_version,message_version,writer,scratch,true));
+        byte_count += try!(TargetCompID::write(&self.target_comp_id,fix_version,message_version,writer,scratch,true));
+        for field in &self.fields {
+            if field.tag == ApplVerID::tag() && fix_version == FIXVersion::FIXT_1_1 {
+                byte_count += try!(write_field(field,writer));
+            }
+        }
+
+        byte_count += try!(MsgSeqNum::write(&self.msg_seq_num,fix_version,message_version,writer,scratch,true));
+        byte_count += try!(PossDupFlag::write(&self.poss_dup_flag,fix_version,message_version,writer,scratch,false));
+        byte_count += try!(SendingTime::write(&self.sending_time,fix_version,message_version,writer,scratch,true));
+        byte_count += try!(OrigSendingTime::write(&self.orig_sending_time,fix_version,message_version,writer,scratch,false));
+
+        for field in &self.fields {
+            if field.tag != ApplVerID::tag() {
+                byte_count += try!(write_field(field,writer));
+            }
+        }
+
+        Ok(byte_count)
+    }
+
+    fn get_value(&self,tag: FieldTag) -> Option<Cow<[u8]>> {
+        let (fix_version,message_version) = message_versions(self);
+        if tag == SenderCompID::tag() { SenderCompID::get_value(&self.sender_comp_id,fix_version,message_version) }
+        else if tag == TargetCompID::tag() { TargetCompID::get_value(&self.target_comp_id,fix_version,message_version) }
+        else if tag == MsgSeqNum::tag() { MsgSeqNum::get_value(&self.msg_seq_num,fix_version,message_version) }
+        else if tag == PossDupFlag::tag() { PossDupFlag::get_value(&self.poss_dup_flag,fix_version,message_version) }
+        else if tag == SendingTime::tag() { SendingTime::get_value(&self.sending_time,fix_version,message_version) }
+        else if tag == OrigSendingTime::tag() { OrigSendingTime::get_value(&self.orig_sending_time,fix_version,message_version) }
+        else {
+            self.fields.iter().find(|field| field.tag == tag).map(|field| read_message_field(field).value)
+        }
+    }
+
+    fn fields_with_version(&self,fix_version: FIXVersion,message_version: MessageVersion) -> FieldIter {
+        FieldIter::new(self,fix_version,message_version)
+    }
+
+    fn message_fields_at<'a>(&'a self,index: usize,fix_version: FIXVersion,message_version: MessageVersion,fields: &mut Vec<MessageField<'a>>) -> bool {
+        //Same order as write_body(): the header fields with ApplVerID moved up for FIXT, then
+        //every other field in the order it was parsed.
+        match index {
+            0 => SenderCompID::read_message_fields(&self.sender_comp_id,fix_version,message_version,fields),
+            1 => TargetCompID::read_message_fields(&self.target_comp_id,fix_version,message_version,fields),
+            2 => if fix_version == FIXVersion::FIXT_1_1 {
+                fields.extend(self.fields.iter().filter(|field| field.tag == ApplVerID::tag()).map(read_message_field));
+            },
+            3 => MsgSeqNum::read_message_fields(&self.msg_seq_num,fix_version,message_version,fields),
+            4 => PossDupFlag::read_message_fields(&self.poss_dup_flag,fix_version,message_version,fields),
+            5 => SendingTime::read_message_fields(&self.sending_time,fix_version,message_version,fields),
+            6 => OrigSendingTime::read_message_fields(&self.orig_sending_time,fix_version,message_version,fields),
+            _ => match self.fields.get(index - 7) {
+                Some(field) => if field.tag != ApplVerID::tag() {
+                    fields.push(read_message_field(field));
+                },
+                None => return false,
+            },
+        }
+
+        true
+    }
+}
+
+impl FIXTMessage for GenericMessage {
+    fn new_into_box(&self) -> Box<FIXTMessage + Send> {
+        Box::new(GenericMessage::new(self.msg_type()))
+    }
+
+    fn msg_type(&self) -> &[u8] {
+        //Strip the "35=" prefix and SOH suffix.
+        &self.msg_type_header[3..self.msg_type_header.len() - 1]
+    }
+
+    fn msg_seq_num(&self) -> <<MsgSeqNum as Field>::Type as FieldType>::Type {
+        self.msg_seq_num
+    }
+
+    fn sender_comp_id(&self) -> &<<SenderCompID as Field>::Type as FieldType>::Type {
+        &self.sender_comp_id
+    }
+
+    fn target_comp_id(&self) -> &<<TargetCompID as Field>::Type as FieldType>::Type {
+        &self.target_comp_id
+    }
+
+    fn is_poss_dup(&self) -> bool {
+        self.poss_dup_flag
+    }
+
+    fn set_is_poss_dup(&mut self,is_poss_dup: bool) {
+        self.poss_dup_flag = is_poss_dup;
+    }
+
+    fn sending_time(&self) -> <<SendingTime as Field>::Type as FieldType>::Type {
+        self.sending_time
+    }
+
+    fn orig_sending_time(&self) -> <<OrigSendingTime as Field>::Type as FieldType>::Type {
+        self.orig_sending_time
+    }
+
+    fn set_orig_sending_time(&mut self,orig_sending_time: <<OrigSendingTime as Field>::Type as FieldType>::Type) {
+        self.orig_sending_time = orig_sending_time;
+    }
+
+    fn setup_fixt_session_header(&mut self,
+                                 msg_seq_num: Option<<<MsgSeqNum as Field>::Type as FieldType>::Type>,
+                                 sender_comp_id: <<SenderCompID as Field>::Type as FieldType>::Type,
+                                 target_comp_id: <<TargetCompID as Field>::Type as FieldType>::Type) {
+        if let Some(msg_seq_num) = msg_seq_num {
+            self.msg_seq_num = msg_seq_num;
+        }
+        self.sender_comp_id = sender_comp_id;
+        self.target_comp_id = target_comp_id;
+        self.sending_time = <SendingTime as Field>::Type::new_now();
+    }
+}
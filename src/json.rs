@@ -0,0 +1,565 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//FIX JSON encoding as described by the FIX Trading Community's JSON encoding guidelines. A
+//message is an object with separate "Header", "Body", and "Trailer" objects. Fields are keyed by
+//their dictionary name, every value is a string holding the same value that would be sent on the
+//wire, and repeating groups are arrays of objects keyed by the NumInGroup field's name.
+//BodyLength, CheckSum, and the length fields in front of data fields are implied and left out.
+
+use std::char;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::str;
+
+use field_tag::FieldTag;
+use fix_version::FIXVersion;
+use fixt::message::{BuildFIXTMessage,FIXTMessage};
+use message::{BuildMessage,FieldIter,Message,SetValueError};
+use message_version::MessageVersion;
+use rule::Rule;
+
+//Standard header fields. Everything else except the trailer fields is part of the body.
+const HEADER_TAGS: &'static [FieldTag] = &[
+    FieldTag(49),   //SenderCompID
+    FieldTag(56),   //TargetCompID
+    FieldTag(1128), //ApplVerID
+    FieldTag(1156), //ApplExtID
+    FieldTag(1129), //CstmApplVerID
+    FieldTag(115),  //OnBehalfOfCompID
+    FieldTag(128),  //DeliverToCompID
+    FieldTag(91),   //SecureData
+    FieldTag(34),   //MsgSeqNum
+    FieldTag(50),   //SenderSubID
+    FieldTag(142),  //SenderLocationID
+    FieldTag(57),   //TargetSubID
+    FieldTag(143),  //TargetLocationID
+    FieldTag(116),  //OnBehalfOfSubID
+    FieldTag(144),  //OnBehalfOfLocationID
+    FieldTag(129),  //DeliverToSubID
+    FieldTag(145),  //DeliverToLocationID
+    FieldTag(43),   //PossDupFlag
+    FieldTag(97),   //PossResend
+    FieldTag(52),   //SendingTime
+    FieldTag(122),  //OrigSendingTime
+    FieldTag(213),  //XmlData
+    FieldTag(347),  //MessageEncoding
+    FieldTag(369),  //LastMsgSeqNumProcessed
+    FieldTag(370),  //OnBehalfOfSendingTime
+    FieldTag(627),  //NoHops
+];
+
+const TRAILER_TAGS: &'static [FieldTag] = &[
+    FieldTag(89), //Signature
+];
+
+//Deepest objects and arrays can be nested. Far more than any message with nested repeating groups
+//needs.
+const MAX_NESTING_DEPTH: usize = 64;
+
+//Fields that are built into the parser and never stored in a message.
+const BUILT_IN_NAMES: &'static [&'static str] = &["BeginString","BodyLength","MsgType","CheckSum"];
+
+#[derive(Clone,Debug,PartialEq)]
+pub enum JsonValue {
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String,JsonValue)>), //Members are kept in the order they were written.
+}
+
+impl JsonValue {
+    pub fn write(&self,buf: &mut String) {
+        match *self {
+            JsonValue::String(ref value) => write_string(value,buf),
+            JsonValue::Array(ref values) => {
+                buf.push('[');
+                for (index,value) in values.iter().enumerate() {
+                    if index > 0 {
+                        buf.push(',');
+                    }
+                    value.write(buf);
+                }
+                buf.push(']');
+            },
+            JsonValue::Object(ref members) => {
+                buf.push('{');
+                for (index,&(ref name,ref value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        buf.push(',');
+                    }
+                    write_string(name,buf);
+                    buf.push(':');
+                    value.write(buf);
+                }
+                buf.push('}');
+            },
+        }
+    }
+
+    pub fn parse(json: &str) -> Result<JsonValue,JsonError> {
+        let mut parser = JsonParser {
+            bytes: json.as_bytes(),
+            index: 0,
+            depth: 0,
+        };
+        let value = try!(parser.parse_value());
+        parser.skip_whitespace();
+        if parser.index != parser.bytes.len() {
+            return Err(JsonError::Syntax(parser.index,"Unexpected data after value"));
+        }
+
+        Ok(value)
+    }
+}
+
+impl fmt::Display for JsonValue {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        let mut buf = String::new();
+        self.write(&mut buf);
+        write!(f,"{}",buf)
+    }
+}
+
+//A field of a message ready to be placed into a JSON object.
+#[derive(Clone,Debug,PartialEq)]
+pub struct JsonField {
+    pub tag: FieldTag,
+    pub name: String,
+    pub value: JsonValue,
+}
+
+pub enum JsonError {
+    Syntax(usize,&'static str), //Byte offset, Description
+    MissingSection(&'static str), //Header, Body, or Trailer is not an object.
+    MissingMsgType,
+    MsgTypeUnknown(Vec<u8>), //Message type not in dictionary passed to from_fix_json().
+    UnknownField(String), //Field name isn't defined for the message and isn't a tag number.
+    WrongFormat(String), //Field name
+    OutOfRange(String), //Field name
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JsonError::Syntax(offset,description) => write!(f,"JsonError::Syntax({},{})",offset,description),
+            JsonError::MissingSection(section) => write!(f,"JsonError::MissingSection({})",section),
+            JsonError::MissingMsgType => write!(f,"JsonError::MissingMsgType"),
+            JsonError::MsgTypeUnknown(ref msg_type) => write!(f,"JsonError::MsgTypeUnknown({})",String::from_utf8_lossy(msg_type)),
+            JsonError::UnknownField(ref name) => write!(f,"JsonError::UnknownField({})",name),
+            JsonError::WrongFormat(ref name) => write!(f,"JsonError::WrongFormat({})",name),
+            JsonError::OutOfRange(ref name) => write!(f,"JsonError::OutOfRange({})",name),
+        }
+    }
+}
+
+impl fmt::Debug for JsonError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        <JsonError as fmt::Display>::fmt(self,f)
+    }
+}
+
+fn write_string(value: &str,buf: &mut String) {
+    buf.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if (c as u32) < 0x20 => buf.push_str(&format!("\\u{:04x}",c as u32)),
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+}
+
+struct JsonParser<'a> {
+    bytes: &'a [u8],
+    index: usize,
+    depth: usize, //Number of values the parser is currently nested inside of.
+}
+
+impl<'a> JsonParser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.index < self.bytes.len() {
+            match self.bytes[self.index] {
+                b' ' | b'\t' | b'\r' | b'\n' => self.index += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn expect(&mut self,byte: u8,description: &'static str) -> Result<(),JsonError> {
+        self.skip_whitespace();
+        if self.index < self.bytes.len() && self.bytes[self.index] == byte {
+            self.index += 1;
+            Ok(())
+        }
+        else {
+            Err(JsonError::Syntax(self.index,description))
+        }
+    }
+
+    fn next_is(&mut self,byte: u8) -> bool {
+        self.skip_whitespace();
+        if self.index < self.bytes.len() && self.bytes[self.index] == byte {
+            self.index += 1;
+            return true;
+        }
+
+        false
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue,JsonError> {
+        //Objects and arrays are parsed recursively so limit how deep they can go instead of
+        //overflowing the stack on hostile input.
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(JsonError::Syntax(self.index,"Nested too deeply"));
+        }
+
+        self.depth += 1;
+        let result = self.parse_nested_value();
+        self.depth -= 1;
+
+        result
+    }
+
+    fn parse_nested_value(&mut self) -> Result<JsonValue,JsonError> {
+        self.skip_whitespace();
+        if self.index >= self.bytes.len() {
+            return Err(JsonError::Syntax(self.index,"Expected value"));
+        }
+
+        match self.bytes[self.index] {
+            b'{' => {
+                self.index += 1;
+                let mut members = Vec::new();
+                if self.next_is(b'}') {
+                    return Ok(JsonValue::Object(members));
+                }
+                loop {
+                    try!(self.expect(b'"',"Expected member name"));
+                    let name = try!(self.parse_string());
+                    try!(self.expect(b':',"Expected ':'"));
+                    let value = try!(self.parse_value());
+                    members.push((name,value));
+
+                    if self.next_is(b',') {
+                        continue;
+                    }
+                    try!(self.expect(b'}',"Expected ',' or '}'"));
+                    return Ok(JsonValue::Object(members));
+                }
+            },
+            b'[' => {
+                self.index += 1;
+                let mut values = Vec::new();
+                if self.next_is(b']') {
+                    return Ok(JsonValue::Array(values));
+                }
+                loop {
+                    values.push(try!(self.parse_value()));
+
+                    if self.next_is(b',') {
+                        continue;
+                    }
+                    try!(self.expect(b']',"Expected ',' or ']'"));
+                    return Ok(JsonValue::Array(values));
+                }
+            },
+            b'"' => {
+                self.index += 1;
+                Ok(JsonValue::String(try!(self.parse_string())))
+            },
+            b'-' | b'0'...b'9' => {
+                //Values are supposed to be strings but numbers are accepted as their text to be
+                //forgiving.
+                let start = self.index;
+                while self.index < self.bytes.len() {
+                    match self.bytes[self.index] {
+                        b'-' | b'+' | b'.' | b'e' | b'E' | b'0'...b'9' => self.index += 1,
+                        _ => break,
+                    }
+                }
+                Ok(JsonValue::String(String::from_utf8_lossy(&self.bytes[start..self.index]).into_owned()))
+            },
+            _ => Err(JsonError::Syntax(self.index,"Expected object, array, string, or number")),
+        }
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32,JsonError> {
+        if self.index + 4 > self.bytes.len() {
+            return Err(JsonError::Syntax(self.index,"Incomplete unicode escape"));
+        }
+
+        let value = try!(str::from_utf8(&self.bytes[self.index..self.index + 4]).ok()
+            .and_then(|hex| u32::from_str_radix(hex,16).ok())
+            .ok_or(JsonError::Syntax(self.index,"Invalid unicode escape")));
+        self.index += 4;
+
+        Ok(value)
+    }
+
+    fn parse_string(&mut self) -> Result<String,JsonError> {
+        //Opening quote has already been consumed.
+        let mut result = String::new();
+        let mut start = self.index;
+        while self.index < self.bytes.len() {
+            match self.bytes[self.index] {
+                b'"' => {
+                    result.push_str(&String::from_utf8_lossy(&self.bytes[start..self.index]));
+                    self.index += 1;
+                    return Ok(result);
+                },
+                b'\\' => {
+                    result.push_str(&String::from_utf8_lossy(&self.bytes[start..self.index]));
+                    self.index += 1;
+                    if self.index >= self.bytes.len() {
+                        break;
+                    }
+
+                    let escape = self.bytes[self.index];
+                    self.index += 1;
+                    match escape {
+                        b'"' => result.push('"'),
+                        b'\\' => result.push('\\'),
+                        b'/' => result.push('/'),
+                        b'b' => result.push('\x08'),
+                        b'f' => result.push('\x0C'),
+                        b'n' => result.push('\n'),
+                        b'r' => result.push('\r'),
+                        b't' => result.push('\t'),
+                        b'u' => {
+                            let mut code_point = try!(self.parse_hex4());
+                            if code_point >= 0xD800 && code_point < 0xDC00 {
+                                //Surrogate pair.
+                                if self.bytes[self.index..].starts_with(b"\\u") {
+                                    self.index += 2;
+                                    let low_surrogate = try!(self.parse_hex4());
+                                    code_point = 0x10000 + ((code_point - 0xD800) << 10) + (low_surrogate.wrapping_sub(0xDC00) & 0x3FF);
+                                }
+                            }
+                            result.push(char::from_u32(code_point).unwrap_or('\u{FFFD}'));
+                        },
+                        _ => return Err(JsonError::Syntax(self.index - 1,"Invalid escape")),
+                    }
+                    start = self.index;
+                },
+                _ => self.index += 1,
+            }
+        }
+
+        Err(JsonError::Syntax(self.index,"Unterminated string"))
+    }
+}
+
+//Collects fields in wire order. Fields without a name, ie. extra fields and fields of a
+//GenericMessage, are keyed by tag number. The NumInGroup count of a repeating group is implied by
+//the length of its array and a field repeated without a repeating group becomes an array of its
+//values.
+pub fn read_json_fields(fields: FieldIter) -> Vec<JsonField> {
+    let message = fields.message();
+    let mut result: Vec<JsonField> = Vec::new();
+    for field in fields {
+        let value = if field.is_repeating_group() {
+            JsonValue::Array(field.groups.map(|group| {
+                JsonValue::Object(read_json_fields(group).into_iter().map(|group_field| (group_field.name,group_field.value)).collect())
+            }).collect())
+        }
+        else {
+            JsonValue::String(String::from_utf8_lossy(&field.value).into_owned())
+        };
+
+        if let Some(previous_field) = result.last_mut() {
+            if previous_field.tag == field.tag {
+                let mut values = match mem::replace(&mut previous_field.value,JsonValue::Array(Vec::new())) {
+                    JsonValue::Array(values) => values,
+                    previous_value => vec![previous_value],
+                };
+                values.push(value);
+                previous_field.value = JsonValue::Array(values);
+                continue;
+            }
+        }
+
+        result.push(JsonField {
+            tag: field.tag,
+            name: match message.and_then(|message| message.field_name(field.tag)) {
+                Some(name) => name.to_string(),
+                None => field.tag.to_string(),
+            },
+            value: value,
+        });
+    }
+
+    result
+}
+
+pub fn to_fix_json<T: FIXTMessage + ?Sized>(message: &T,fix_version: FIXVersion,message_version: MessageVersion) -> String {
+    let fields = read_json_fields(message.fields_with_version(fix_version,message_version));
+
+    let mut header = vec![
+        (String::from("BeginString"),JsonValue::String(String::from_utf8_lossy(fix_version.begin_string()).into_owned())),
+        (String::from("MsgType"),JsonValue::String(String::from_utf8_lossy(message.msg_type()).into_owned())),
+    ];
+    let mut body = Vec::new();
+    let mut trailer = Vec::new();
+    for field in fields {
+        if HEADER_TAGS.contains(&field.tag) {
+            header.push((field.name,field.value));
+        }
+        else if TRAILER_TAGS.contains(&field.tag) {
+            trailer.push((field.name,field.value));
+        }
+        else {
+            body.push((field.name,field.value));
+        }
+    }
+
+    let root = JsonValue::Object(vec![
+        (String::from("Header"),JsonValue::Object(header)),
+        (String::from("Body"),JsonValue::Object(body)),
+        (String::from("Trailer"),JsonValue::Object(trailer)),
+    ]);
+
+    root.to_string()
+}
+
+//Sets the fields of a message from members keyed by dictionary name. Numeric names that aren't
+//part of the message are stored as extra fields.
+pub fn set_json_fields<T: Message + ?Sized>(message: &mut T,builder: &mut BuildMessage,members: &[(String,JsonValue)]) -> Result<(),JsonError> {
+    fn set_value<T: Message + ?Sized>(message: &mut T,tag: FieldTag,name: &str,value: &str) -> Result<(),JsonError> {
+        match message.set_value(tag,value.as_bytes()) {
+            Ok(()) => Ok(()),
+            Err(SetValueError::WrongFormat) => Err(JsonError::WrongFormat(name.to_string())),
+            Err(SetValueError::OutOfRange) => Err(JsonError::OutOfRange(name.to_string())),
+        }
+    }
+
+    //Map names to tags using every message version so the JSON doesn't need to say which version
+    //it was written with.
+    let mut fields = HashMap::new();
+    for message_version in MessageVersion::all() {
+        for (tag,rule) in builder.fields(message_version) {
+            if let Some(name) = message.field_name(tag) {
+                fields.insert(name,(tag,rule));
+            }
+        }
+    }
+
+    for &(ref name,ref value) in members {
+        if BUILT_IN_NAMES.iter().any(|built_in_name| built_in_name == name) {
+            continue;
+        }
+
+        let (tag,rule) = match fields.get(&name[..]) {
+            Some(&(tag,ref rule)) => (tag,rule.clone()),
+            None => {
+                //Fields that aren't part of the message can be given by tag number.
+                let tag = match name.parse::<u64>() {
+                    Ok(tag) => FieldTag(tag),
+                    Err(_) => return Err(JsonError::UnknownField(name.clone())),
+                };
+                let value = match *value {
+                    JsonValue::String(ref value) => value,
+                    _ => return Err(JsonError::WrongFormat(name.clone())),
+                };
+                match message.extra_fields_mut() {
+                    Some(extra_fields) => extra_fields.push((tag,value.as_bytes().to_vec())),
+                    None => return Err(JsonError::UnknownField(name.clone())),
+                };
+                continue;
+            },
+        };
+
+        match (value,rule) {
+            //Length is implied by the data field that follows.
+            (&JsonValue::String(_),Rule::PrepareForBytes{ .. }) => {},
+            (&JsonValue::String(ref value),_) => try!(set_value(message,tag,name,value)),
+            (&JsonValue::Array(ref groups),Rule::BeginGroup{ builder_func }) => {
+                let mut group_builder = builder_func();
+                let mut group_messages: Vec<Box<Message>> = Vec::with_capacity(groups.len());
+                for group in groups {
+                    let group_members = match *group {
+                        JsonValue::Object(ref group_members) => group_members,
+                        _ => return Err(JsonError::WrongFormat(name.clone())),
+                    };
+
+                    let mut group_message = group_builder.build();
+                    try!(set_json_fields(&mut *group_message,&mut *group_builder,group_members));
+                    group_messages.push(group_message);
+                }
+                if !message.set_groups(tag,group_messages) {
+                    return Err(JsonError::WrongFormat(name.clone()));
+                }
+            },
+            (&JsonValue::Array(ref values),Rule::RepeatableField) => {
+                for value in values {
+                    match *value {
+                        JsonValue::String(ref value) => try!(set_value(message,tag,name,value)),
+                        _ => return Err(JsonError::WrongFormat(name.clone())),
+                    }
+                }
+            },
+            _ => return Err(JsonError::WrongFormat(name.clone())),
+        }
+    }
+
+    Ok(())
+}
+
+pub fn from_fix_json(json: &str,message_dictionary: &HashMap<&'static [u8],Box<BuildFIXTMessage + Send>>) -> Result<Box<FIXTMessage + Send>,JsonError> {
+    fn find_section<'a>(sections: &'a [(String,JsonValue)],section_name: &'static str) -> Result<Option<&'a [(String,JsonValue)]>,JsonError> {
+        for &(ref name,ref value) in sections {
+            if name == section_name {
+                return match *value {
+                    JsonValue::Object(ref members) => Ok(Some(&members[..])),
+                    _ => Err(JsonError::MissingSection(section_name)),
+                };
+            }
+        }
+
+        Ok(None)
+    }
+
+    let root = try!(JsonValue::parse(json));
+    let sections = match root {
+        JsonValue::Object(ref sections) => &sections[..],
+        _ => return Err(JsonError::Syntax(0,"Expected object")),
+    };
+
+    let header = match try!(find_section(sections,"Header")) {
+        Some(header) => header,
+        None => return Err(JsonError::MissingSection("Header")),
+    };
+    let msg_type = match header.iter().find(|&&(ref name,_)| name == "MsgType") {
+        Some(&(_,JsonValue::String(ref msg_type))) => msg_type.as_bytes(),
+        _ => return Err(JsonError::MissingMsgType),
+    };
+    let builder = match message_dictionary.get(msg_type) {
+        Some(builder) => builder,
+        None => return Err(JsonError::MsgTypeUnknown(msg_type.to_vec())),
+    };
+
+    let mut message = builder.build();
+    let mut builder = BuildMessage::new_into_box(&**builder);
+    try!(set_json_fields(&mut *message,&mut *builder,header));
+    if let Some(body) = try!(find_section(sections,"Body")) {
+        try!(set_json_fields(&mut *message,&mut *builder,body));
+    }
+    if let Some(trailer) = try!(find_section(sections,"Trailer")) {
+        try!(set_json_fields(&mut *message,&mut *builder,trailer));
+    }
+
+    Ok(message)
+}
@@ -41,20 +41,33 @@ extern crate time;
 pub mod byte_buffer;
 #[macro_use]
 pub mod fixt;
+pub mod codegen;
 pub mod constant;
+pub mod data_dictionary;
+pub mod encoder;
+pub mod fast;
 #[macro_use]
 pub mod field;
 pub mod field_tag;
 pub mod field_type;
 pub mod fix;
 pub mod fix_version;
+pub mod fixml;
+pub mod generic_message;
 pub mod hash;
+pub mod json;
 #[macro_use]
 pub mod message;
+pub mod message_diff;
 pub mod message_version;
+pub mod message_view;
 mod network_read_retry;
+pub mod pretty_print;
 pub mod rule;
+#[macro_use]
+pub mod sbe;
 mod token_generator;
+mod xml;
 
 //Dictionary is put last because it needs the above macros.
 #[macro_use]
@@ -10,21 +10,26 @@
 // except according to those terms.
 
 use std::any::Any;
+use std::borrow::Cow;
 use std::collections::{HashMap,HashSet};
 use std::mem;
-use std::io::Write;
+use std::io::{self,Write};
 use std::ptr;
 
 use byte_buffer::ByteBuffer;
-use constant::VALUE_END;
+use constant::{TAG_END,VALUE_END};
+use encoder::{LengthCounter,write_integer};
 use field_tag::FieldTag;
 use fix_version::FIXVersion;
+use generic_message::{GenericField,read_message_field};
 use hash::BuildFieldHasher;
 use message_version::MessageVersion;
+use pretty_print::message_versions;
 use rule::Rule;
 
 pub type FieldHashMap = HashMap<FieldTag,Rule,BuildFieldHasher>;
 pub type FieldHashSet = HashSet<FieldTag,BuildFieldHasher>;
+pub type ExtraFieldMap = Vec<(FieldTag,Vec<u8>)>; //User-defined tags that aren't part of the message definition in the order they were received.
 
 pub trait BuildMessage {
     fn first_field(&self,version: MessageVersion) -> FieldTag;
@@ -58,6 +63,275 @@ pub enum SetValueError {
     OutOfRange,
 }
 
+//What to do with a value that contains an invalid byte when it's about to be sent.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum InvalidValueAction {
+    Reject, //Refuse to send the message.
+    Strip, //Remove the offending bytes.
+    Replace(u8), //Replace the offending bytes with the given byte. It must not be an invalid byte itself.
+}
+
+//How to handle a value that contains a byte that would corrupt the message when it's about to be
+//sent. Only SOH is invalid by default. '=' can't corrupt a message because only the first '=' of a
+//field separates the tag from the value, but some counterparties refuse it anyway so rejecting it
+//too can be turned on. Data fields, which are preceded by a length field, are allowed to contain
+//any byte and are never changed.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct InvalidValuePolicy {
+    pub action: InvalidValueAction,
+    pub include_tag_end: bool, //Treat '=' as an invalid byte too.
+}
+
+impl InvalidValuePolicy {
+    pub fn new(action: InvalidValueAction) -> InvalidValuePolicy {
+        InvalidValuePolicy {
+            action: action,
+            include_tag_end: false,
+        }
+    }
+
+    pub fn is_invalid_value_byte(&self,byte: u8) -> bool {
+        byte == VALUE_END || (self.include_tag_end && byte == TAG_END)
+    }
+}
+
+impl Default for InvalidValuePolicy {
+    fn default() -> Self {
+        InvalidValuePolicy::new(InvalidValueAction::Reject)
+    }
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct InvalidFieldValue {
+    pub tag: FieldTag,
+    pub name: &'static str, //Empty for extra fields.
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct MissingRequiredField {
+    pub tag: FieldTag,
+    pub name: &'static str,
+}
+
+//Field as it appears on the wire. Repeating groups hold the NumInGroup count as their value and
+//list the fields of each group through groups.
+pub struct MessageField<'a> {
+    pub tag: FieldTag,
+    pub value: Cow<'a,[u8]>,
+    pub groups: GroupIter<'a>,
+}
+
+impl<'a> MessageField<'a> {
+    pub fn is_repeating_group(&self) -> bool {
+        //Repeating groups are listed through groups even when there are none.
+        match self.groups.source {
+            GroupSource::Empty => false,
+            _ => true,
+        }
+    }
+}
+
+enum FieldSource<'a> {
+    Message(&'a Message,FIXVersion,MessageVersion),
+    GenericFields(&'a [GenericField]),
+    Empty,
+}
+
+//Lists fields in wire order one field definition at a time. Nothing is serialized or collected
+//ahead of time and repeating groups are only walked when their GroupIter is.
+pub struct FieldIter<'a> {
+    source: FieldSource<'a>,
+    index: usize,
+    pending: Vec<MessageField<'a>>, //Fields left over from the previous index in reverse order.
+}
+
+impl<'a> FieldIter<'a> {
+    pub fn new(message: &'a Message,fix_version: FIXVersion,message_version: MessageVersion) -> FieldIter<'a> {
+        FieldIter::with_source(FieldSource::Message(message,fix_version,message_version))
+    }
+
+    pub fn from_generic_fields(fields: &'a [GenericField]) -> FieldIter<'a> {
+        FieldIter::with_source(FieldSource::GenericFields(fields))
+    }
+
+    pub fn empty() -> FieldIter<'a> {
+        FieldIter::with_source(FieldSource::Empty)
+    }
+
+    pub fn message(&self) -> Option<&'a Message> {
+        //Message the fields belong to so they can be named. Fields of a GenericMessage's
+        //repeating groups don't belong to any message.
+        match self.source {
+            FieldSource::Message(message,_,_) => Some(message),
+            _ => None,
+        }
+    }
+
+    fn with_source(source: FieldSource<'a>) -> FieldIter<'a> {
+        FieldIter {
+            source: source,
+            index: 0,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<'a> Iterator for FieldIter<'a> {
+    type Item = MessageField<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(field) = self.pending.pop() {
+                return Some(field);
+            }
+
+            let has_more = match self.source {
+                FieldSource::Message(message,fix_version,message_version) => message.message_fields_at(self.index,fix_version,message_version,&mut self.pending),
+                FieldSource::GenericFields(fields) => match fields.get(self.index) {
+                    Some(field) => {
+                        self.pending.push(read_message_field(field));
+                        true
+                    },
+                    None => false,
+                },
+                FieldSource::Empty => false,
+            };
+            if !has_more {
+                self.source = FieldSource::Empty;
+                return None;
+            }
+
+            self.index += 1;
+            self.pending.reverse();
+        }
+    }
+}
+
+//Repeating groups that can be listed without knowing the type of group.
+pub trait MessageGroups {
+    fn group_count(&self) -> usize;
+    fn group(&self,index: usize) -> &Message;
+}
+
+impl<T: Message> MessageGroups for Vec<Box<T>> {
+    fn group_count(&self) -> usize {
+        self.len()
+    }
+
+    fn group(&self,index: usize) -> &Message {
+        &*self[index]
+    }
+}
+
+enum GroupSource<'a> {
+    Messages(&'a MessageGroups,FIXVersion,MessageVersion),
+    GenericGroups(&'a [Vec<GenericField>]),
+    Empty,
+}
+
+//Iterates over each repeating group following a number of groups field. Each group lists its own
+//fields using a FieldIter.
+pub struct GroupIter<'a> {
+    source: GroupSource<'a>,
+    index: usize,
+}
+
+impl<'a> GroupIter<'a> {
+    pub fn new(groups: &'a MessageGroups,fix_version: FIXVersion,message_version: MessageVersion) -> GroupIter<'a> {
+        GroupIter {
+            source: GroupSource::Messages(groups,fix_version,message_version),
+            index: 0,
+        }
+    }
+
+    pub fn from_generic_groups(groups: &'a [Vec<GenericField>]) -> GroupIter<'a> {
+        GroupIter {
+            source: GroupSource::GenericGroups(groups),
+            index: 0,
+        }
+    }
+
+    pub fn empty() -> GroupIter<'a> {
+        GroupIter {
+            source: GroupSource::Empty,
+            index: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self.source {
+            GroupSource::Messages(groups,_,_) => groups.group_count(),
+            GroupSource::GenericGroups(groups) => groups.len(),
+            GroupSource::Empty => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<'a> Iterator for GroupIter<'a> {
+    type Item = FieldIter<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.len() {
+            return None;
+        }
+
+        let group = match self.source {
+            GroupSource::Messages(groups,fix_version,message_version) => FieldIter::new(groups.group(self.index),fix_version,message_version),
+            GroupSource::GenericGroups(groups) => FieldIter::from_generic_fields(&groups[self.index][..]),
+            GroupSource::Empty => return None,
+        };
+        self.index += 1;
+
+        Some(group)
+    }
+}
+
+//Standard header fields that are filled in by the engine right before a message is sent:
+//MsgSeqNum, SenderCompID, SendingTime, and TargetCompID.
+pub const ENGINE_HEADER_TAGS: &'static [FieldTag] = &[FieldTag(34),FieldTag(49),FieldTag(52),FieldTag(56)];
+
+pub fn sanitize_bytes(value: &mut Vec<u8>,policy: InvalidValuePolicy) -> bool {
+    //Returns false if the value contains an invalid byte and the policy can't fix it.
+    if !value.iter().any(|byte| policy.is_invalid_value_byte(*byte)) {
+        return true;
+    }
+
+    match policy.action {
+        InvalidValueAction::Reject => false,
+        InvalidValueAction::Strip => {
+            value.retain(|byte| !policy.is_invalid_value_byte(*byte));
+            true
+        },
+        InvalidValueAction::Replace(replacement) => {
+            if policy.is_invalid_value_byte(replacement) {
+                return false;
+            }
+
+            for byte in value.iter_mut().filter(|byte| policy.is_invalid_value_byte(**byte)) {
+                *byte = replacement;
+            }
+            true
+        },
+    }
+}
+
+pub fn sanitize_extra_fields(extra_fields: &mut ExtraFieldMap,policy: InvalidValuePolicy) -> Result<(),InvalidFieldValue> {
+    for &mut (tag,ref mut value) in extra_fields.iter_mut() {
+        if !sanitize_bytes(value,policy) {
+            return Err(InvalidFieldValue {
+                tag: tag,
+                name: "",
+            });
+        }
+    }
+
+    Ok(())
+}
+
 pub trait Message {
     fn conditional_required_fields(&self,version: MessageVersion) -> Vec<FieldTag>;
     fn meta(&self) -> &Option<Meta>;
@@ -67,9 +341,89 @@ pub trait Message {
     fn as_any(&self) -> &Any;
     fn as_any_mut(&mut self) -> &mut Any;
     fn new_into_box(&self) -> Box<Message + Send>;
-    fn msg_type_header(&self) -> &'static [u8];
+
+    //MsgType is borrowed from the message so a GenericMessage can hold any MsgType. Use
+    //MessageDetails::msg_type() when a &'static [u8] is needed, ie. as a message dictionary key.
+    fn msg_type_header(&self) -> &[u8];
     fn read_body(&self,fix_version: FIXVersion,message_version: MessageVersion,buf: &mut Vec<u8>) -> usize;
 
+    //Same bytes as read_body() but written straight to writer. Returns the number of bytes
+    //written. Messages that can't write their fields directly fall back to read_body() into the
+    //scratch buffer.
+    fn write_body(&self,fix_version: FIXVersion,message_version: MessageVersion,writer: &mut Write,scratch: &mut Vec<u8>) -> io::Result<usize> {
+        scratch.clear();
+        self.read_body(fix_version,message_version,scratch);
+        try!(writer.write_all(&scratch[..]));
+        Ok(scratch.len())
+    }
+
+    //Number of bytes written by read_body() and write_body(). Found by writing the body somewhere
+    //that only counts bytes.
+    fn body_len(&self,fix_version: FIXVersion,message_version: MessageVersion) -> usize {
+        let mut counter = LengthCounter::new();
+        self.write_body(fix_version,message_version,&mut counter,&mut Vec::new()).unwrap(); //LengthCounter never fails.
+        counter.len
+    }
+
+    fn field_name(&self,_tag: FieldTag) -> Option<&'static str> {
+        None
+    }
+
+    fn value_description(&self,_tag: FieldTag) -> Option<String> {
+        //Human readable name of the field's value for enumerated fields. Ie. "Buy" for a Side of 1.
+        None
+    }
+
+    fn extra_fields(&self) -> Option<&ExtraFieldMap> {
+        None
+    }
+
+    fn extra_fields_mut(&mut self) -> Option<&mut ExtraFieldMap> {
+        None
+    }
+
+    fn sanitize_values(&mut self,_policy: InvalidValuePolicy) -> Result<(),InvalidFieldValue> {
+        //Make sure no value will corrupt the message once serialized. Every field is checked, even
+        //the ones that won't be serialized with the message version used to send the message.
+        Ok(())
+    }
+
+    fn validate(&self,_message_version: MessageVersion) -> Result<(),MissingRequiredField> {
+        //Make sure every required and conditionally required field for the message version is set,
+        //including those inside repeating groups. Header fields in ENGINE_HEADER_TAGS are skipped
+        //because the engine always fills them in when sending.
+        Ok(())
+    }
+
+    fn get_value(&self,_tag: FieldTag) -> Option<Cow<[u8]>> {
+        //Value as it would be serialized using the version stored in Meta. Fields that are empty,
+        //not part of that version, or are length fields in front of data fields return None.
+        None
+    }
+
+    fn has_field(&self,tag: FieldTag) -> bool {
+        self.get_value(tag).is_some()
+    }
+
+    fn fields(&self) -> FieldIter {
+        //Every field that would be serialized by read_body() in the same order using the version
+        //stored in Meta.
+        let (fix_version,message_version) = message_versions(self);
+        self.fields_with_version(fix_version,message_version)
+    }
+
+    fn fields_with_version(&self,_fix_version: FIXVersion,_message_version: MessageVersion) -> FieldIter {
+        //Implemented as FieldIter::new(self,fix_version,message_version) so message_fields_at() is
+        //used to list each field.
+        FieldIter::empty()
+    }
+
+    fn message_fields_at<'a>(&'a self,_index: usize,_fix_version: FIXVersion,_message_version: MessageVersion,_fields: &mut Vec<MessageField<'a>>) -> bool {
+        //Add the fields of the index-th field definition that would be serialized by read_body().
+        //Returns false once index is past the last field.
+        false
+    }
+
     fn read(&self,fix_version: FIXVersion,message_version: MessageVersion,buf: &mut ByteBuffer) -> usize {
         const HEADER_PADDING_LEN: usize = 32;
 
@@ -166,6 +520,34 @@ pub trait Message {
     }
 }
 
+pub fn read_extra_fields(extra_fields: &ExtraFieldMap,buf: &mut Vec<u8>) -> usize {
+    //Extra fields are always written after every defined field in the order they were added.
+    let mut byte_count = 0;
+    for &(tag,ref value) in extra_fields {
+        byte_count += buf.write(&tag.to_bytes()[..]).unwrap();
+        buf.push(TAG_END);
+        byte_count += buf.write(&value[..]).unwrap();
+        buf.push(VALUE_END);
+        byte_count += 2;
+    }
+
+    byte_count
+}
+
+pub fn write_extra_fields(extra_fields: &ExtraFieldMap,writer: &mut Write) -> io::Result<usize> {
+    //Same as read_extra_fields() but written straight to writer.
+    let mut byte_count = 0;
+    for &(tag,ref value) in extra_fields {
+        byte_count += try!(write_integer(tag,writer));
+        try!(writer.write_all(&[TAG_END]));
+        try!(writer.write_all(&value[..]));
+        try!(writer.write_all(&[VALUE_END]));
+        byte_count += value.len() + 2;
+    }
+
+    Ok(byte_count)
+}
+
 pub const REQUIRED: bool = true;
 pub const NOT_REQUIRED: bool = false;
 
@@ -221,6 +603,7 @@ macro_rules! define_message {
         #[derive(BuildMessage)]
         pub struct $message_name {
             pub meta: Option<$crate::message::Meta>,
+            pub extra_fields: $crate::message::ExtraFieldMap,
             $( pub $field_name: <<$field_type as $crate::field::Field>::Type as $crate::field_type::FieldType>::Type, )*
             $( #[message_type=$message_type] )*
             _message_type_gen: ::std::marker::PhantomData<()>,
@@ -230,6 +613,7 @@ macro_rules! define_message {
             fn clone(&self) -> Self {
                 $message_name {
                     meta: self.meta.clone(),
+                    extra_fields: self.extra_fields.clone(),
                     $( $field_name: self.$field_name.clone(), )*
                     _message_type_gen: ::std::marker::PhantomData,
                 }
@@ -240,6 +624,7 @@ macro_rules! define_message {
             pub fn new() -> $message_name {
                 $message_name {
                     meta: None,
+                    extra_fields: $crate::message::ExtraFieldMap::new(),
                     $( $field_name: <<$field_type as $crate::field::Field>::Type as $crate::field_type::FieldType>::default_value(), )*
                     _message_type_gen: ::std::marker::PhantomData,
                 }
@@ -375,9 +760,118 @@ macro_rules! define_message {
                 $( if match_message_version!(message_version,$( $version )*) {
                     byte_count += <$field_type as $crate::field::Field>::read(&self.$field_name,fix_version,message_version,buf,$field_required);
                 } )*
+                byte_count += $crate::message::read_extra_fields(&self.extra_fields,buf);
 
                 byte_count
             }
+
+            fn write_body(&self,fix_version: $crate::fix_version::FIXVersion,message_version: $crate::message_version::MessageVersion,writer: &mut ::std::io::Write,scratch: &mut Vec<u8>) -> ::std::io::Result<usize> {
+                let mut byte_count: usize = 0;
+                $( if match_message_version!(message_version,$( $version )*) {
+                    byte_count += try!(<$field_type as $crate::field::Field>::write(&self.$field_name,fix_version,message_version,writer,scratch,$field_required));
+                } )*
+                byte_count += try!($crate::message::write_extra_fields(&self.extra_fields,writer));
+
+                Ok(byte_count)
+            }
+
+            fn field_name(&self,tag: $crate::field_tag::FieldTag) -> Option<&'static str> {
+                use $crate::field::Field;
+
+                $( if tag == <$field_type as Field>::tag() { return Some(<$field_type as Field>::name()); } )*
+                None
+            }
+
+            fn value_description(&self,tag: $crate::field_tag::FieldTag) -> Option<String> {
+                use $crate::field::Field;
+                use $crate::field_type::FieldType;
+
+                $( if tag == <$field_type as Field>::tag() { return <<$field_type as Field>::Type as FieldType>::value_description(&self.$field_name); } )*
+                None
+            }
+
+            fn extra_fields(&self) -> Option<&$crate::message::ExtraFieldMap> {
+                Some(&self.extra_fields)
+            }
+
+            fn extra_fields_mut(&mut self) -> Option<&mut $crate::message::ExtraFieldMap> {
+                Some(&mut self.extra_fields)
+            }
+
+            fn sanitize_values(&mut self,policy: $crate::message::InvalidValuePolicy) -> Result<(),$crate::message::InvalidFieldValue> {
+                use $crate::field::Field;
+
+                $( try!(<$field_type as Field>::sanitize(&mut self.$field_name,policy)); )*
+                $crate::message::sanitize_extra_fields(&mut self.extra_fields,policy)
+            }
+
+            fn validate(&self,message_version: $crate::message_version::MessageVersion) -> Result<(),$crate::message::MissingRequiredField> {
+                use $crate::field::Field;
+                use $crate::message::Message;
+
+                let conditional_required_fields = self.conditional_required_fields(message_version);
+                $( if match_message_version!(message_version,$( $version )*) && !$crate::message::ENGINE_HEADER_TAGS.contains(&<$field_type as Field>::tag()) {
+                    let required = $field_required || conditional_required_fields.contains(&<$field_type as Field>::tag());
+                    try!(<$field_type as Field>::validate(&self.$field_name,message_version,required));
+                } )*
+
+                Ok(())
+            }
+
+            fn get_value(&self,tag: $crate::field_tag::FieldTag) -> Option<::std::borrow::Cow<[u8]>> {
+                use $crate::field::Field;
+
+                let (fix_version,message_version) = $crate::pretty_print::message_versions(self);
+                $( if tag == <$field_type as Field>::tag() && match_message_version!(message_version,$( $version )*) {
+                    return <$field_type as Field>::get_value(&self.$field_name,fix_version,message_version);
+                } )*
+
+                self.extra_fields.iter().find(|&&(extra_tag,_)| extra_tag == tag).map(|&(_,ref value)| ::std::borrow::Cow::Borrowed(&value[..]))
+            }
+
+            fn has_field(&self,tag: $crate::field_tag::FieldTag) -> bool {
+                use $crate::field::Field;
+
+                let (fix_version,message_version) = $crate::pretty_print::message_versions(self);
+                $( if tag == <$field_type as Field>::tag() && match_message_version!(message_version,$( $version )*) {
+                    return <$field_type as Field>::is_set(&self.$field_name,fix_version);
+                } )*
+
+                self.extra_fields.iter().any(|&(extra_tag,_)| extra_tag == tag)
+            }
+
+            fn fields_with_version(&self,fix_version: $crate::fix_version::FIXVersion,message_version: $crate::message_version::MessageVersion) -> $crate::message::FieldIter {
+                $crate::message::FieldIter::new(self,fix_version,message_version)
+            }
+
+            fn message_fields_at<'a>(&'a self,index: usize,fix_version: $crate::fix_version::FIXVersion,message_version: $crate::message_version::MessageVersion,fields: &mut Vec<$crate::message::MessageField<'a>>) -> bool {
+                use $crate::field::Field;
+
+                //Each defined field has its own index followed by each extra field.
+                let mut field_index = 0;
+                $(
+                    if index == field_index {
+                        if match_message_version!(message_version,$( $version )*) {
+                            <$field_type as Field>::read_message_fields(&self.$field_name,fix_version,message_version,fields);
+                        }
+                        return true;
+                    }
+                    field_index += 1;
+                )*
+
+                match self.extra_fields.get(index - field_index) {
+                    Some(&(tag,ref value)) => {
+                        fields.push($crate::message::MessageField {
+                            tag: tag,
+                            value: ::std::borrow::Cow::Borrowed(&value[..]),
+                            groups: $crate::message::GroupIter::empty(),
+                        });
+                        true
+                    },
+                    None => false,
+                }
+            }
+
         }
 
         impl PartialEq for $message_name {
@@ -387,7 +881,7 @@ macro_rules! define_message {
                 //a repeating group count is specified with 0, the field could have been optionally
                 //(and recommended to be) left out.
                 $( self.$field_name == other.$field_name && )*
-                true
+                self.extra_fields == other.extra_fields
             }
         }
     };
@@ -0,0 +1,313 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//Field-by-field comparison of two messages. Fields are collected from each message's field
+//iterator so the values compared are exactly what would be sent on the wire. Fields are
+//matched by tag at each level and repeating groups are compared group by group in order. Every
+//difference includes the path of NumInGroup tags and group indexes that lead to it.
+
+use std::cmp;
+use std::fmt;
+use std::io::{self,Write};
+
+use data_dictionary::DataDictionary;
+use field_tag::FieldTag;
+use fix_version::FIXVersion;
+use fixt::message::FIXTMessage;
+use message::FieldIter;
+use message_version::MessageVersion;
+use pretty_print::message_versions;
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum FieldDiffKind {
+    Added, //Only in the right message.
+    Removed, //Only in the left message.
+    Changed, //In both messages with different values.
+}
+
+#[derive(Clone,PartialEq)]
+pub struct FieldDiff {
+    pub path: Vec<(FieldTag,usize)>, //NumInGroup tag and group index for each repeating group the field is nested in.
+    pub tag: FieldTag,
+    pub name: Option<&'static str>, //None when the field is not part of the message definition.
+    pub left: Option<Vec<u8>>,
+    pub right: Option<Vec<u8>>,
+}
+
+impl FieldDiff {
+    pub fn kind(&self) -> FieldDiffKind {
+        match (&self.left,&self.right) {
+            (&None,_) => FieldDiffKind::Added,
+            (_,&None) => FieldDiffKind::Removed,
+            _ => FieldDiffKind::Changed,
+        }
+    }
+
+    pub fn with_name<'a>(&'a self,name: Option<&'a str>) -> NamedFieldDiff<'a> {
+        //Displays the same as the FieldDiff but using name instead, ie. a name from a data
+        //dictionary for a field without a definition.
+        NamedFieldDiff {
+            diff: self,
+            name: name,
+        }
+    }
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.with_name(self.name),f)
+    }
+}
+
+impl fmt::Debug for FieldDiff {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self,f)
+    }
+}
+
+pub struct NamedFieldDiff<'a> {
+    diff: &'a FieldDiff,
+    name: Option<&'a str>,
+}
+
+impl<'a> fmt::Display for NamedFieldDiff<'a> {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        //Written as "~ 78[1].80 AllocQty = 60 -> 40" where the leading character is +, -, or ~
+        //for added, removed, or changed.
+        let diff = self.diff;
+        let prefix = match diff.kind() {
+            FieldDiffKind::Added => '+',
+            FieldDiffKind::Removed => '-',
+            FieldDiffKind::Changed => '~',
+        };
+        try!(write!(f,"{} ",prefix));
+        for &(number_of_groups_tag,index) in &diff.path {
+            try!(write!(f,"{}[{}].",number_of_groups_tag,index));
+        }
+        try!(write!(f,"{}",diff.tag));
+        if let Some(name) = self.name {
+            try!(write!(f," {}",name));
+        }
+
+        match (&diff.left,&diff.right) {
+            (&Some(ref left),&Some(ref right)) => write!(f," = {} -> {}",String::from_utf8_lossy(left),String::from_utf8_lossy(right)),
+            (&Some(ref value),&None) | (&None,&Some(ref value)) => write!(f," = {}",String::from_utf8_lossy(value)),
+            (&None,&None) => Ok(()),
+        }
+    }
+}
+
+enum DiffValue {
+    Values(Vec<Vec<u8>>), //More than one value when the field is repeated without a repeating group.
+    Groups(Vec<Vec<DiffField>>),
+}
+
+struct DiffField {
+    tag: FieldTag,
+    name: Option<&'static str>,
+    value: DiffValue,
+}
+
+fn read_diff_fields(fields: FieldIter) -> Vec<DiffField> {
+    let message = fields.message();
+    let mut result: Vec<DiffField> = Vec::new();
+    for field in fields {
+        if field.is_repeating_group() {
+            result.push(DiffField {
+                tag: field.tag,
+                name: message.and_then(|message| message.field_name(field.tag)),
+                value: DiffValue::Groups(field.groups.map(read_diff_fields).collect()),
+            });
+            continue;
+        }
+
+        if let Some(previous_field) = result.last_mut() {
+            if previous_field.tag == field.tag {
+                if let DiffValue::Values(ref mut values) = previous_field.value {
+                    values.push(field.value.into_owned());
+                    continue;
+                }
+            }
+        }
+
+        result.push(DiffField {
+            tag: field.tag,
+            name: message.and_then(|message| message.field_name(field.tag)),
+            value: DiffValue::Values(vec![field.value.into_owned()]),
+        });
+    }
+
+    result
+}
+
+pub fn diff_messages<L: FIXTMessage + ?Sized,R: FIXTMessage + ?Sized>(left: &L,right: &R) -> Vec<FieldDiff> {
+    //Both messages are read using the version the left message was received with so fields
+    //that only differ because of the version aren't reported.
+    let (fix_version,message_version) = message_versions(left);
+    diff_messages_with_version(left,right,fix_version,message_version)
+}
+
+pub fn diff_messages_with_version<L: FIXTMessage + ?Sized,R: FIXTMessage + ?Sized>(left: &L,right: &R,fix_version: FIXVersion,message_version: MessageVersion) -> Vec<FieldDiff> {
+    let left_fields = read_diff_fields(left.fields_with_version(fix_version,message_version));
+    let right_fields = read_diff_fields(right.fields_with_version(fix_version,message_version));
+
+    let mut diffs = Vec::new();
+    if left.msg_type() != right.msg_type() {
+        diffs.push(FieldDiff {
+            path: Vec::new(),
+            tag: FieldTag(35),
+            name: Some("MsgType"),
+            left: Some(left.msg_type().to_vec()),
+            right: Some(right.msg_type().to_vec()),
+        });
+    }
+
+    let mut path = Vec::new();
+    diff_fields(&left_fields,&right_fields,&mut path,&mut diffs);
+
+    diffs
+}
+
+fn new_diff(path: &[(FieldTag,usize)],field: &DiffField,left: Option<&[u8]>,right: Option<&[u8]>) -> FieldDiff {
+    FieldDiff {
+        path: path.to_vec(),
+        tag: field.tag,
+        name: field.name,
+        left: left.map(|value| value.to_vec()),
+        right: right.map(|value| value.to_vec()),
+    }
+}
+
+fn diff_fields(left_fields: &[DiffField],right_fields: &[DiffField],path: &mut Vec<(FieldTag,usize)>,diffs: &mut Vec<FieldDiff>) {
+    for left_field in left_fields {
+        match right_fields.iter().find(|right_field| right_field.tag == left_field.tag) {
+            Some(right_field) => diff_field(left_field,right_field,path,diffs),
+            None => push_one_sided(left_field,true,path,diffs),
+        }
+    }
+
+    for right_field in right_fields {
+        if !left_fields.iter().any(|left_field| left_field.tag == right_field.tag) {
+            push_one_sided(right_field,false,path,diffs);
+        }
+    }
+}
+
+fn diff_field(left_field: &DiffField,right_field: &DiffField,path: &mut Vec<(FieldTag,usize)>,diffs: &mut Vec<FieldDiff>) {
+    match (&left_field.value,&right_field.value) {
+        (&DiffValue::Values(ref left_values),&DiffValue::Values(ref right_values)) => {
+            for index in 0..cmp::max(left_values.len(),right_values.len()) {
+                match (left_values.get(index),right_values.get(index)) {
+                    (Some(left_value),Some(right_value)) if left_value != right_value => {
+                        diffs.push(new_diff(path,left_field,Some(&left_value[..]),Some(&right_value[..])));
+                    },
+                    (Some(left_value),None) => diffs.push(new_diff(path,left_field,Some(&left_value[..]),None)),
+                    (None,Some(right_value)) => diffs.push(new_diff(path,right_field,None,Some(&right_value[..]))),
+                    _ => {},
+                }
+            }
+        },
+        (&DiffValue::Groups(ref left_groups),&DiffValue::Groups(ref right_groups)) => {
+            if left_groups.len() != right_groups.len() {
+                diffs.push(new_diff(path,left_field,Some(left_groups.len().to_string().as_bytes()),Some(right_groups.len().to_string().as_bytes())));
+            }
+
+            for index in 0..cmp::max(left_groups.len(),right_groups.len()) {
+                path.push((left_field.tag,index));
+                match (left_groups.get(index),right_groups.get(index)) {
+                    (Some(left_group),Some(right_group)) => diff_fields(left_group,right_group,path,diffs),
+                    (Some(left_group),None) => {
+                        for field in left_group {
+                            push_one_sided(field,true,path,diffs);
+                        }
+                    },
+                    (None,Some(right_group)) => {
+                        for field in right_group {
+                            push_one_sided(field,false,path,diffs);
+                        }
+                    },
+                    (None,None) => {},
+                }
+                path.pop();
+            }
+        },
+        _ => {
+            //Same tag but a different structure, ie. a repeating group in a GenericMessage that was
+            //parsed without knowing about the group. Nothing lines up so report both in full.
+            push_one_sided(left_field,true,path,diffs);
+            push_one_sided(right_field,false,path,diffs);
+        },
+    }
+}
+
+fn one_sided_diff(path: &[(FieldTag,usize)],field: &DiffField,value: &[u8],is_left: bool) -> FieldDiff {
+    if is_left {
+        new_diff(path,field,Some(value),None)
+    }
+    else {
+        new_diff(path,field,None,Some(value))
+    }
+}
+
+fn push_one_sided(field: &DiffField,is_left: bool,path: &mut Vec<(FieldTag,usize)>,diffs: &mut Vec<FieldDiff>) {
+    match field.value {
+        DiffValue::Values(ref values) => {
+            for value in values {
+                diffs.push(one_sided_diff(path,field,value,is_left));
+            }
+        },
+        DiffValue::Groups(ref groups) => {
+            diffs.push(one_sided_diff(path,field,groups.len().to_string().as_bytes(),is_left));
+
+            for (index,group) in groups.iter().enumerate() {
+                path.push((field.tag,index));
+                for group_field in group {
+                    push_one_sided(group_field,is_left,path,diffs);
+                }
+                path.pop();
+            }
+        },
+    }
+}
+
+pub fn write_message_diffs(left_messages: &[Box<FIXTMessage + Send>],right_messages: &[Box<FIXTMessage + Send>],data_dictionary: Option<&DataDictionary>,writer: &mut Write) -> io::Result<bool> {
+    //Report printed by fix-rs-diff. The Nth left message is compared to the Nth right message.
+    //Fields without a definition are named using the data dictionary when available. Returns
+    //true if anything is different.
+    let mut is_different = false;
+    for (index,(left_message,right_message)) in left_messages.iter().zip(right_messages.iter()).enumerate() {
+        let diffs = diff_messages(&**left_message,&**right_message);
+        if diffs.is_empty() {
+            continue;
+        }
+
+        if is_different {
+            try!(writeln!(writer,""));
+        }
+        try!(writeln!(writer,"Message {} (35={})",index + 1,String::from_utf8_lossy(left_message.msg_type())));
+        for diff in &diffs {
+            let name: Option<&str> = diff.name;
+            let name = name.or_else(|| data_dictionary.and_then(|data_dictionary| data_dictionary.field(diff.tag)).map(|field| &field.name[..]));
+            try!(writeln!(writer,"{}",diff.with_name(name)));
+        }
+        is_different = true;
+    }
+
+    if left_messages.len() != right_messages.len() {
+        if is_different {
+            try!(writeln!(writer,""));
+        }
+        try!(writeln!(writer,"Left has {} message(s) and right has {} message(s)",left_messages.len(),right_messages.len()));
+        is_different = true;
+    }
+
+    Ok(is_different)
+}
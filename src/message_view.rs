@@ -0,0 +1,525 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::collections::{HashMap,HashSet};
+use std::fmt;
+
+use constant::{FIX_4_0_BEGIN_STRING,FIX_4_1_BEGIN_STRING,FIX_4_2_BEGIN_STRING,FIX_4_3_BEGIN_STRING,FIX_4_4_BEGIN_STRING,FIXT_1_1_BEGIN_STRING,TAG_END,VALUE_END};
+use field::Field;
+use field_tag::FieldTag;
+use field_type::FieldType;
+use fix::{ParseError,ParseLimits,ValidationConfig};
+use fix_version::FIXVersion;
+use fixt::message::BuildFIXTMessage;
+use hash::BuildFieldHasher;
+use message::{FieldHashMap,FieldHashSet,SetValueError};
+use message_version::MessageVersion;
+use rule::Rule;
+
+const BEGINSTR_TAG: FieldTag = FieldTag(8);
+const CHECKSUM_LEN: usize = 7; //10=XXX<SOH>
+
+//Lookup tables needed to walk a message without knowing its type ahead of time. These are built
+//once from the message dictionary so parsing a view never has to allocate.
+pub struct MessageViewTables {
+    data_tags: HashMap<FieldTag,FieldTag,BuildFieldHasher>, //Length tag -> Data tag.
+    group_tags: HashMap<FieldTag,FieldHashSet,BuildFieldHasher>, //Number of groups tag -> All tags that can appear within a group, including nested groups.
+    grouped_tags: FieldHashSet, //Every tag that can appear within any group.
+}
+
+impl MessageViewTables {
+    pub fn new(message_dictionary: &mut HashMap<&'static [u8],Box<BuildFIXTMessage + Send>>) -> MessageViewTables {
+        let mut tables = MessageViewTables {
+            data_tags: HashMap::with_hasher(BuildFieldHasher),
+            group_tags: HashMap::with_hasher(BuildFieldHasher),
+            grouped_tags: HashSet::with_hasher(BuildFieldHasher),
+        };
+
+        //Views are not tied to a message version so every version is merged together.
+        for builder in message_dictionary.values_mut() {
+            for message_version in MessageVersion::all() {
+                tables.add_fields(builder.fields(message_version),message_version);
+            }
+        }
+
+        tables
+    }
+
+    pub fn data_tag(&self,length_tag: FieldTag) -> Option<FieldTag> {
+        self.data_tags.get(&length_tag).cloned()
+    }
+
+    pub fn group_tags(&self,number_of_groups_tag: FieldTag) -> Option<&FieldHashSet> {
+        self.group_tags.get(&number_of_groups_tag)
+    }
+
+    pub fn is_group_tag(&self,tag: FieldTag) -> bool {
+        self.grouped_tags.contains(&tag)
+    }
+
+    pub fn insert_data_tag(&mut self,length_tag: FieldTag,data_tag: FieldTag) {
+        self.data_tags.insert(length_tag,data_tag);
+    }
+
+    pub fn insert_group_tags(&mut self,number_of_groups_tag: FieldTag,group_tags: FieldHashSet) {
+        self.grouped_tags.extend(group_tags.iter());
+        self.group_tags.entry(number_of_groups_tag).or_insert_with(|| HashSet::with_hasher(BuildFieldHasher)).extend(group_tags);
+    }
+
+    fn add_fields(&mut self,fields: FieldHashMap,message_version: MessageVersion) -> FieldHashSet {
+        let mut tags = HashSet::with_hasher(BuildFieldHasher);
+        for (tag,rule) in fields {
+            tags.insert(tag);
+
+            match rule {
+                Rule::PrepareForBytes{ bytes_tag } => {
+                    self.data_tags.insert(tag,bytes_tag);
+                },
+                Rule::BeginGroup{ builder_func } => {
+                    let group_tags = self.add_fields(builder_func().fields(message_version),message_version);
+                    tags.extend(group_tags.iter());
+                    self.insert_group_tags(tag,group_tags);
+                },
+                _ => {},
+            }
+        }
+
+        tags
+    }
+}
+
+//A framed message borrowed straight from the buffer it was parsed from. Only the framing,
+//BodyLength, and CheckSum have been validated. Fields are decoded on demand. Only get(), fields(),
+//and groups() are allocation free. get_field() decodes into an owned value.
+pub struct MessageView<'a> {
+    bytes: &'a [u8],
+    fix_version: FIXVersion,
+    body_length: u64,
+    checksum: u8,
+    msg_type: &'a [u8],
+    body: FieldsView<'a>,
+}
+
+impl<'a> MessageView<'a> {
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn fix_version(&self) -> FIXVersion {
+        self.fix_version
+    }
+
+    pub fn body_length(&self) -> u64 {
+        self.body_length
+    }
+
+    pub fn checksum(&self) -> u8 {
+        self.checksum
+    }
+
+    pub fn msg_type(&self) -> &'a [u8] {
+        self.msg_type
+    }
+
+    pub fn body(&self) -> FieldsView<'a> {
+        self.body
+    }
+
+    pub fn fields(&self) -> FieldIter<'a> {
+        self.body.fields()
+    }
+
+    pub fn get(&self,tag: FieldTag) -> Option<&'a [u8]> {
+        self.body.get(tag)
+    }
+
+    pub fn get_field<F: Field>(&self) -> Option<Result<<<F as Field>::Type as FieldType>::Type,SetValueError>>
+        where <F as Field>::Type: FieldType {
+        self.body.get_field::<F>()
+    }
+
+    pub fn groups(&self,number_of_groups_tag: FieldTag) -> Option<GroupIter<'a>> {
+        self.body.groups(number_of_groups_tag)
+    }
+}
+
+impl<'a> fmt::Debug for MessageView<'a> {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"MessageView({})",String::from_utf8_lossy(self.bytes).replace("\x01","|"))
+    }
+}
+
+//A run of fields within a message. Used for the message body and for each repeating group.
+#[derive(Clone,Copy)]
+pub struct FieldsView<'a> {
+    bytes: &'a [u8],
+    tables: &'a MessageViewTables,
+}
+
+impl<'a> FieldsView<'a> {
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn fields(&self) -> FieldIter<'a> {
+        FieldIter {
+            bytes: self.bytes,
+            offset: 0,
+            tables: self.tables,
+            data_length: None,
+        }
+    }
+
+    pub fn get(&self,tag: FieldTag) -> Option<&'a [u8]> {
+        //First occurrence wins. This includes fields nested in repeating groups.
+        self.fields().find(|&(field_tag,_)| field_tag == tag).map(|(_,value)| value)
+    }
+
+    pub fn get_field<F: Field>(&self) -> Option<Result<<<F as Field>::Type as FieldType>::Type,SetValueError>>
+        where <F as Field>::Type: FieldType {
+        //Decodes a copy of the value the same way parse() would so it can allocate, ie. for
+        //strings and data. Use get() to borrow the raw bytes instead.
+        self.get(F::tag()).map(|bytes| {
+            let mut value = <<F as Field>::Type as FieldType>::default_value();
+            try!(<<F as Field>::Type as FieldType>::set_value(&mut value,bytes));
+            Ok(value)
+        })
+    }
+
+    pub fn groups(&self,number_of_groups_tag: FieldTag) -> Option<GroupIter<'a>> {
+        let group_tags = match self.tables.group_tags.get(&number_of_groups_tag) {
+            Some(group_tags) => group_tags,
+            None => return None,
+        };
+
+        let mut fields = self.fields();
+        while let Some((tag,value)) = fields.next() {
+            if tag == number_of_groups_tag {
+                return Some(GroupIter {
+                    bytes: &self.bytes[fields.offset..],
+                    group_count: bytes_to_usize(value).unwrap_or(0),
+                    group_index: 0,
+                    first_tag: None,
+                    group_tags: group_tags,
+                    tables: self.tables,
+                });
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a> fmt::Debug for FieldsView<'a> {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,"FieldsView({})",String::from_utf8_lossy(self.bytes).replace("\x01","|"))
+    }
+}
+
+//Iterates over every field in wire order as (tag,value) pairs. Length fields for data fields are
+//included as is.
+pub struct FieldIter<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    tables: &'a MessageViewTables,
+    data_length: Option<(FieldTag,usize)>, //Data tag and its length when the previous field was a length field.
+}
+
+impl<'a> FieldIter<'a> {
+    fn next_field(&mut self) -> Result<Option<(FieldTag,&'a [u8])>,ParseError> {
+        if self.offset >= self.bytes.len() {
+            return Ok(None);
+        }
+
+        //Read tag.
+        let bytes = &self.bytes[self.offset..];
+        let tag_end = match bytes.iter().position(|c| *c == TAG_END) {
+            Some(tag_end) => tag_end,
+            None => return Err(ParseError::WrongFormatTag(FieldTag::empty())),
+        };
+        let tag_bytes = &bytes[..tag_end];
+        let tag = FieldTag::from(tag_bytes);
+        if tag_bytes.is_empty() || tag_bytes.iter().any(|c| *c < b'0' || *c > b'9') {
+            return Err(ParseError::WrongFormatTag(tag));
+        }
+
+        //Read value. Data fields can contain VALUE_END so use the preceding length instead.
+        let bytes = &bytes[tag_end + 1..];
+        let value_end = match self.data_length.take() {
+            Some((data_tag,length)) if data_tag == tag => {
+                if bytes.len() <= length || bytes[length] != VALUE_END {
+                    return Err(ParseError::MissingFollowingLengthTag(tag));
+                }
+                length
+            },
+            _ => match bytes.iter().position(|c| *c == VALUE_END) {
+                Some(value_end) => value_end,
+                None => return Err(ParseError::NoValueAfterTag(tag)),
+            },
+        };
+        let value = &bytes[..value_end];
+
+        if let Some(data_tag) = self.tables.data_tags.get(&tag) {
+            if let Some(length) = bytes_to_usize(value) {
+                self.data_length = Some((*data_tag,length));
+            }
+        }
+
+        self.offset += tag_end + 1 + value_end + 1;
+        Ok(Some((tag,value)))
+    }
+}
+
+impl<'a> Iterator for FieldIter<'a> {
+    type Item = (FieldTag,&'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        //Messages are validated when the view is created so this can't fail for a field within
+        //a MessageView.
+        self.next_field().unwrap_or(None)
+    }
+}
+
+//Iterates over each repeating group following a number of groups field.
+pub struct GroupIter<'a> {
+    bytes: &'a [u8],
+    group_count: usize,
+    group_index: usize,
+    first_tag: Option<FieldTag>, //Every group starts with the same tag.
+    group_tags: &'a FieldHashSet,
+    tables: &'a MessageViewTables,
+}
+
+impl<'a> GroupIter<'a> {
+    pub fn len(&self) -> usize {
+        self.group_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.group_count == 0
+    }
+}
+
+impl<'a> Iterator for GroupIter<'a> {
+    type Item = FieldsView<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.group_index >= self.group_count {
+            return None;
+        }
+
+        let mut fields = FieldIter {
+            bytes: self.bytes,
+            offset: 0,
+            tables: self.tables,
+            data_length: None,
+        };
+        let mut group_end = 0;
+        while let Some((tag,_)) = fields.next() {
+            if group_end == 0 {
+                //Group must start with the same tag as every other group.
+                match self.first_tag {
+                    Some(first_tag) => if first_tag != tag {
+                        return None;
+                    },
+                    None => self.first_tag = Some(tag),
+                }
+            }
+            else if Some(tag) == self.first_tag || !self.group_tags.contains(&tag) {
+                break;
+            }
+
+            group_end = fields.offset;
+        }
+
+        if group_end == 0 {
+            return None;
+        }
+
+        let group = FieldsView {
+            bytes: &self.bytes[..group_end],
+            tables: self.tables,
+        };
+        self.bytes = &self.bytes[group_end..];
+        self.group_index += 1;
+
+        Some(group)
+    }
+}
+
+fn bytes_to_usize(bytes: &[u8]) -> Option<usize> {
+    //Avoid going through a String so no allocation is needed.
+    if bytes.is_empty() {
+        return None;
+    }
+
+    let mut result: usize = 0;
+    for c in bytes {
+        if *c < b'0' || *c > b'9' {
+            return None;
+        }
+        result = match result.checked_mul(10).and_then(|result| result.checked_add((*c - b'0') as usize)) {
+            Some(result) => result,
+            None => return None,
+        };
+    }
+
+    Some(result)
+}
+
+fn find_value_end(bytes: &[u8],offset: usize) -> Option<usize> {
+    bytes[offset..].iter().position(|c| *c == VALUE_END).map(|position| offset + position)
+}
+
+pub fn parse_message_view<'a>(tables: &'a MessageViewTables,
+                              max_message_length: u64,
+                              validation_config: &ValidationConfig,
+                              parse_limits: &ParseLimits,
+                              message_bytes: &'a [u8]) -> (usize,Result<Option<MessageView<'a>>,ParseError>) {
+    //Skip garbage until the start of the next message. A trailing '8' is kept in case the rest of
+    //BeginStr hasn't arrived yet.
+    let start = match message_bytes.windows(2).position(|window| window == b"8=") {
+        Some(start) => start,
+        None => return (if message_bytes.last() == Some(&b'8') { message_bytes.len() - 1 } else { message_bytes.len() },Ok(None)),
+    };
+    let bytes = &message_bytes[start..];
+    let incomplete = |bytes_read: usize| {
+        //Bail out on runaway messages that never finish instead of waiting on them forever.
+        if bytes_read as u64 > max_message_length {
+            (start + bytes_read,Err(ParseError::MessageSizeTooBig))
+        }
+        else {
+            (start,Ok(None))
+        }
+    };
+
+    //BeginStr
+    let begin_str_end = match find_value_end(bytes,2) {
+        Some(begin_str_end) => begin_str_end,
+        None => return incomplete(bytes.len()),
+    };
+    let fix_version = match &bytes[2..begin_str_end] {
+        FIX_4_0_BEGIN_STRING => FIXVersion::FIX_4_0,
+        FIX_4_1_BEGIN_STRING => FIXVersion::FIX_4_1,
+        FIX_4_2_BEGIN_STRING => FIXVersion::FIX_4_2,
+        FIX_4_3_BEGIN_STRING => FIXVersion::FIX_4_3,
+        FIX_4_4_BEGIN_STRING => FIXVersion::FIX_4_4,
+        FIXT_1_1_BEGIN_STRING => FIXVersion::FIXT_1_1,
+        _ => return (start + begin_str_end + 1,Err(ParseError::WrongFormatTag(BEGINSTR_TAG))),
+    };
+
+    //BodyLength
+    let body_length_start = begin_str_end + 1;
+    if bytes.len() < body_length_start + 2 {
+        return incomplete(bytes.len());
+    }
+    if &bytes[body_length_start..body_length_start + 2] != b"9=" {
+        return (start + body_length_start,Err(ParseError::BodyLengthNotSecondTag));
+    }
+    let body_length_end = match find_value_end(bytes,body_length_start + 2) {
+        Some(body_length_end) => body_length_end,
+        None => return incomplete(bytes.len()),
+    };
+    let body_length = match bytes_to_usize(&bytes[body_length_start + 2..body_length_end]) {
+        Some(body_length) => body_length,
+        None => return (start + body_length_end + 1,Err(ParseError::BodyLengthNotNumber)),
+    };
+
+    //BodyLength comes straight from the wire so check it before doing any arithmetic with it.
+    let body_start = body_length_end + 1;
+    if body_length as u64 > max_message_length {
+        return (start + body_start,Err(ParseError::MessageSizeTooBig));
+    }
+    let body_end = body_start + body_length;
+    let message_length = body_end + CHECKSUM_LEN;
+    if message_length as u64 > max_message_length {
+        return (start + body_start,Err(ParseError::MessageSizeTooBig));
+    }
+    if bytes.len() < message_length {
+        return (start,Ok(None));
+    }
+    let bytes = &bytes[..message_length];
+
+    //CheckSum
+    let checksum_bytes = &bytes[body_end..];
+    if body_length == 0 || bytes[body_end - 1] != VALUE_END || &checksum_bytes[..3] != b"10=" {
+        return (start + message_length,Err(ParseError::ChecksumNotLastTag));
+    }
+    if checksum_bytes[6] != VALUE_END {
+        return (start + message_length,Err(ParseError::ChecksumWrongFormat));
+    }
+    let stated_checksum = match bytes_to_usize(&checksum_bytes[3..6]) {
+        Some(stated_checksum) if stated_checksum <= 255 => stated_checksum as u8,
+        _ => return (start + message_length,Err(ParseError::ChecksumWrongFormat)),
+    };
+    let checksum = bytes[..body_end].iter().fold(0u8,|checksum,c| checksum.wrapping_add(*c));
+    if checksum != stated_checksum && !validation_config.skip_checksum_verification {
+        return (start + message_length,Err(ParseError::ChecksumDoesNotMatch(checksum,stated_checksum)));
+    }
+
+    //Walk every field once so accessors never have to deal with malformed fields.
+    let body = FieldsView {
+        bytes: &bytes[body_start..body_end],
+        tables: tables,
+    };
+    let mut fields = body.fields();
+    let msg_type = match fields.next_field() {
+        Ok(Some((FieldTag(35),msg_type))) if !msg_type.is_empty() => msg_type,
+        _ => return (start + message_length,Err(ParseError::MsgTypeNotThirdTag)),
+    };
+    //Repeating groups are tracked the same way GroupIter finds them so the same ParseLimits apply
+    //as when parsing into a message. BeginString, BodyLength, MsgType, and CheckSum count as
+    //fields too.
+    let mut field_count = 4;
+    let mut open_groups: Vec<&FieldHashSet> = Vec::new();
+    loop {
+        let (tag,value) = match fields.next_field() {
+            Ok(Some((tag,value))) => (tag,value),
+            Ok(None) => break,
+            Err(e) => return (start + message_length,Err(e)),
+        };
+        if value.is_empty() && !validation_config.allow_empty_values {
+            return (start + message_length,Err(ParseError::NoValueAfterTag(tag)));
+        }
+
+        field_count += 1;
+        if field_count > parse_limits.max_field_count {
+            return (start + message_length,Err(ParseError::TooManyFields));
+        }
+
+        while open_groups.last().map_or(false,|group_tags| !group_tags.contains(&tag)) {
+            open_groups.pop();
+        }
+        if let Some(group_tags) = tables.group_tags.get(&tag) {
+            match bytes_to_usize(value) {
+                Some(group_count) if group_count > parse_limits.max_group_count => {
+                    return (start + message_length,Err(ParseError::RepeatingGroupCountTooLarge(tag)));
+                },
+                Some(group_count) if group_count > 0 => {
+                    open_groups.push(group_tags);
+                    if open_groups.len() > parse_limits.max_group_depth {
+                        return (start + message_length,Err(ParseError::RepeatingGroupNestedTooDeep(tag)));
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    (start + message_length,Ok(Some(MessageView {
+        bytes: bytes,
+        fix_version: fix_version,
+        body_length: body_length as u64,
+        checksum: checksum,
+        msg_type: msg_type,
+        body: body,
+    })))
+}
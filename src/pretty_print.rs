@@ -0,0 +1,101 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//Human readable listing of a message with one field per line. Each line holds the tag number,
+//the field's name, the value as it would be sent on the wire, and the name of the value for
+//enumerated fields. Repeating groups are indented below their NumInGroup field. For example:
+//
+//  8 BeginString = FIX.4.4
+//  35 MsgType = D
+//  ...
+//  54 Side = 1 (Buy)
+//  78 NoAllocs = 2
+//    79 AllocAccount = acc1
+//    80 AllocQty = 40
+//    79 AllocAccount = acc2
+//    80 AllocQty = 60
+//
+//BodyLength, CheckSum, and the length fields in front of data fields are implied and left out.
+
+use std::fmt::Write;
+
+use field_tag::FieldTag;
+use fix_version::FIXVersion;
+use fixt::message::FIXTMessage;
+use message::{FieldIter,Message};
+use message_version::MessageVersion;
+
+const INDENT: &'static str = "  ";
+
+//FIX version and message version the message was received with. Messages that were built
+//locally fall back to the newest versions.
+pub fn message_versions<T: Message + ?Sized>(message: &T) -> (FIXVersion,MessageVersion) {
+    match *message.meta() {
+        Some(ref meta) => (meta.begin_string,meta.message_version),
+        None => (FIXVersion::FIXT_1_1,MessageVersion::FIX50SP2),
+    }
+}
+
+pub fn pretty_print<T: FIXTMessage + ?Sized>(message: &T) -> String {
+    let (fix_version,message_version) = message_versions(message);
+    pretty_print_with_version(message,fix_version,message_version)
+}
+
+pub fn pretty_print_with_version<T: FIXTMessage + ?Sized>(message: &T,fix_version: FIXVersion,message_version: MessageVersion) -> String {
+    let mut result = String::new();
+    write_line(FieldTag(8),Some("BeginString"),fix_version.begin_string(),None,0,&mut result);
+    write_line(FieldTag(35),Some("MsgType"),message.msg_type(),None,0,&mut result);
+    write_fields(message.fields_with_version(fix_version,message_version),0,&mut result);
+
+    result
+}
+
+fn write_fields(fields: FieldIter,depth: usize,result: &mut String) {
+    //Fields are named and described by the message they belong to. Fields without a definition,
+    //ie. extra fields and fields of a GenericMessage, only have their tag number.
+    let message = fields.message();
+    for field in fields {
+        let name = message.and_then(|message| message.field_name(field.tag));
+        let description = message.and_then(|message| message.value_description(field.tag));
+        write_line(field.tag,name,&field.value,description.as_ref().map(|description| &description[..]),depth,result);
+
+        for group in field.groups {
+            write_fields(group,depth + 1,result);
+        }
+    }
+}
+
+fn write_line(tag: FieldTag,name: Option<&str>,value: &[u8],description: Option<&str>,depth: usize,result: &mut String) {
+    for _ in 0..depth {
+        result.push_str(INDENT);
+    }
+
+    write!(result,"{}",tag).unwrap();
+    if let Some(name) = name {
+        write!(result," {}",name).unwrap();
+    }
+    result.push_str(" = ");
+
+    //Data fields can hold anything so escape bytes that would mess up the layout.
+    for c in String::from_utf8_lossy(value).chars() {
+        if c.is_control() {
+            write!(result,"\\x{:02X}",c as u32).unwrap();
+        }
+        else {
+            result.push(c);
+        }
+    }
+
+    if let Some(description) = description {
+        write!(result," ({})",description).unwrap();
+    }
+    result.push('\n');
+}
@@ -22,5 +22,6 @@ pub enum Rule {
     PrepareForBytes{bytes_tag: FieldTag},
     ConfirmPreviousTag{previous_tag: FieldTag}, //TODO: Probably redundant to the PrepareForBytes definition. Should be automatically inferred.
     RequiresFIXVersion{fix_version: FIXVersion}, //Used during serialization only.
+    RepeatableField, //Legacy field that can be specified more than once without a repeating group.
 }
 
@@ -0,0 +1,883 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//Simple Binary Encoding (SBE) for messages made using define_message!. An SBE schema is described
+//with define_sbe_schema! and each message of the schema is mapped onto an existing message struct
+//with define_sbe_message!, using the same layout as the <message> element of the XML schema:
+//
+//  define_sbe_schema!(VenueSchema: 1, 0);
+//  define_sbe_message!(VenueSchema, NewOrderSingle: 14 => {
+//      FIELDS {
+//          cl_ord_id: ClOrdID => SBEType::CharArray(20),
+//          price: Price => SBEType::Decimal(-4),
+//      }
+//      GROUPS {
+//          allocs: NoAllocs<PreAllocGrp>,
+//      }
+//      DATA {
+//          text: Text,
+//      }
+//  });
+//  define_sbe_block!(VenueSchema, PreAllocGrp => { ... });
+//
+//These can be written by hand or generated from the XML schema using fix-rs-codegen --sbe, see
+//codegen/sbe.rs for which schema features are supported.
+//
+//Fixed-layout fields are converted directly between their binary form and the field's value using
+//SBEFieldType, which every field type in this crate implements. Integer, sequence number,
+//timestamp, char, string, data, and enumerated field types convert without any intermediate text.
+//Other field types, including ones from outside of this crate with an empty SBEFieldType impl,
+//fall back to the same value that would be sent using tag=value encoding. Empty fields are encoded
+//using the SBE null value of their type.
+//Repeating groups use a groupSizeEncoding of uint16 blockLength and uint16 numInGroup. Var-data
+//uses a uint16 length prefix. Integers are little-endian.
+//
+//Message layouts are tied to a schema type so the same message struct can be mapped onto more than
+//one schema, including from outside of this crate.
+
+use chrono::{Datelike,NaiveDate,Timelike};
+use chrono::datetime::DateTime;
+use chrono::naive::datetime::NaiveDateTime;
+use chrono::offset::utc::UTC;
+use std::any::Any;
+use std::fmt;
+use std::io::Write;
+use std::str;
+
+use dictionary::field_types::generic::{BoolTrueOrBlankFieldType,CharFieldType,DataFieldType,DayOfMonthFieldType,IntFieldType,LocalMktDateFieldType,MonthYearFieldType,NoneFieldType,RepeatableStringFieldType,RepeatingGroupFieldType,SeqNumFieldType,StringFieldType,UTCTimeOnlyFieldType,UTCTimestampFieldType};
+use dictionary::field_types::other::{ApplVerIDFieldType,DefaultApplVerIDFieldType,SettlTypeFieldType};
+use field::Field;
+use field_type::FieldType;
+use fix_version::FIXVersion;
+use message::{Message,MessageBuildable,SetValueError};
+use message_version::MessageVersion;
+
+pub const HEADER_LEN: usize = 8;
+const GROUP_HEADER_LEN: usize = 4;
+const DATA_HEADER_LEN: usize = 2;
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum SBEType {
+    Char,
+    CharArray(usize), //Padded with NUL bytes.
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    Decimal(i8), //Int64 mantissa with a constant exponent.
+    UTCTimestampNanos, //UInt64 nanoseconds since the UNIX epoch.
+}
+
+impl SBEType {
+    pub fn len(&self) -> usize {
+        match *self {
+            SBEType::Char | SBEType::Int8 | SBEType::UInt8 => 1,
+            SBEType::CharArray(len) => len,
+            SBEType::Int16 | SBEType::UInt16 => 2,
+            SBEType::Int32 | SBEType::UInt32 => 4,
+            SBEType::Int64 | SBEType::UInt64 | SBEType::Decimal(_) | SBEType::UTCTimestampNanos => 8,
+        }
+    }
+}
+
+pub enum SBEError {
+    BufferTooSmall, //Buffer ends before the message does.
+    SchemaMismatch(u16), //Schema ID
+    TemplateMismatch(u16), //Template ID
+    BlockTooShort(u16), //Block length
+    InvalidValue(&'static str), //Field name
+}
+
+impl fmt::Display for SBEError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SBEError::BufferTooSmall => write!(f,"SBEError::BufferTooSmall"),
+            SBEError::SchemaMismatch(schema_id) => write!(f,"SBEError::SchemaMismatch({})",schema_id),
+            SBEError::TemplateMismatch(template_id) => write!(f,"SBEError::TemplateMismatch({})",template_id),
+            SBEError::BlockTooShort(block_length) => write!(f,"SBEError::BlockTooShort({})",block_length),
+            SBEError::InvalidValue(name) => write!(f,"SBEError::InvalidValue({})",name),
+        }
+    }
+}
+
+impl fmt::Debug for SBEError {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        <SBEError as fmt::Display>::fmt(self,f)
+    }
+}
+
+//Value of a single field while it's being converted to or from SBE.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum SBEValue<'a> {
+    Null,
+    Bytes(&'a [u8]),
+    Int(i64),
+    UInt(u64),
+    Decimal(i64,i8), //Mantissa, Exponent
+    Timestamp(u64), //Nanoseconds since the UNIX epoch.
+}
+
+impl<'a> SBEValue<'a> {
+    //Writes the value as it would appear using tag=value encoding.
+    pub fn write_text(&self,buf: &mut Vec<u8>) {
+        match *self {
+            SBEValue::Null => {},
+            SBEValue::Bytes(bytes) => buf.extend_from_slice(bytes),
+            SBEValue::Int(value) => write!(buf,"{}",value).unwrap(),
+            SBEValue::UInt(value) => write!(buf,"{}",value).unwrap(),
+            SBEValue::Decimal(mantissa,exponent) => format_decimal(mantissa,exponent,buf),
+            SBEValue::Timestamp(nanoseconds) => format_timestamp(nanoseconds,buf),
+        }
+    }
+}
+
+//Sets a field using the same text that would be received using tag=value encoding.
+pub fn set_sbe_value_as_text<T: FieldType + ?Sized>(field: &mut <T as FieldType>::Type,value: SBEValue,scratch: &mut Vec<u8>) -> Result<(),SetValueError> {
+    match value {
+        SBEValue::Null => Ok(()),
+        SBEValue::Bytes(bytes) => T::set_value(field,bytes),
+        value => {
+            scratch.clear();
+            value.write_text(scratch);
+            T::set_value(field,&scratch[..])
+        },
+    }
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct SBEHeader {
+    pub block_length: u16,
+    pub template_id: u16,
+    pub schema_id: u16,
+    pub version: u16,
+}
+
+pub trait SBESchema {
+    fn schema_id() -> u16;
+    fn schema_version() -> u16;
+}
+
+pub trait SBEBlock<S: SBESchema> {
+    fn block_length() -> u16;
+    fn encode_block(&self,scratch: &mut Vec<u8>,buf: &mut Vec<u8>) -> Result<(),SBEError>;
+    fn encode_groups_and_data(&self,scratch: &mut Vec<u8>,buf: &mut Vec<u8>) -> Result<(),SBEError>;
+
+    //Block is always at least block_length() bytes long.
+    fn decode_block(&mut self,block: &[u8],scratch: &mut Vec<u8>) -> Result<(),SBEError>;
+
+    //Returns the number of bytes used.
+    fn decode_groups_and_data(&mut self,buf: &[u8],scratch: &mut Vec<u8>) -> Result<usize,SBEError>;
+}
+
+pub trait SBEMessage<S: SBESchema>: SBEBlock<S> {
+    fn template_id() -> u16;
+}
+
+pub trait SBEGroupFieldType<S: SBESchema>: FieldType {
+    fn encode_groups(field: &<Self as FieldType>::Type,scratch: &mut Vec<u8>,buf: &mut Vec<u8>) -> Result<(),SBEError>;
+    fn decode_groups(field: &mut <Self as FieldType>::Type,buf: &[u8],scratch: &mut Vec<u8>) -> Result<usize,SBEError>;
+}
+
+impl<S: SBESchema,T: SBEBlock<S> + Message + MessageBuildable + Any + Clone + Default + PartialEq + Send + Sized> SBEGroupFieldType<S> for RepeatingGroupFieldType<T> {
+    fn encode_groups(field: &<Self as FieldType>::Type,scratch: &mut Vec<u8>,buf: &mut Vec<u8>) -> Result<(),SBEError> {
+        if field.len() > u16::max_value() as usize {
+            return Err(SBEError::InvalidValue("NumInGroup"));
+        }
+
+        write_uint(<T as SBEBlock<S>>::block_length() as u64,2,buf);
+        write_uint(field.len() as u64,2,buf);
+        for group in field {
+            try!(<T as SBEBlock<S>>::encode_block(group,scratch,buf));
+            try!(<T as SBEBlock<S>>::encode_groups_and_data(group,scratch,buf));
+        }
+
+        Ok(())
+    }
+
+    fn decode_groups(field: &mut <Self as FieldType>::Type,buf: &[u8],scratch: &mut Vec<u8>) -> Result<usize,SBEError> {
+        if buf.len() < GROUP_HEADER_LEN {
+            return Err(SBEError::BufferTooSmall);
+        }
+
+        //Groups written with a newer version of the schema can have a longer block. The extra
+        //fields are skipped.
+        let block_length = read_uint(&buf[0..2]) as usize;
+        let group_count = read_uint(&buf[2..4]) as usize;
+        if block_length < <T as SBEBlock<S>>::block_length() as usize {
+            return Err(SBEError::BlockTooShort(block_length as u16));
+        }
+
+        field.clear();
+        let mut offset = GROUP_HEADER_LEN;
+        for _ in 0..group_count {
+            if buf.len() < offset + block_length {
+                return Err(SBEError::BufferTooSmall);
+            }
+
+            let mut group = T::default();
+            try!(<T as SBEBlock<S>>::decode_block(&mut group,&buf[offset..offset + block_length],scratch));
+            offset += block_length;
+            offset += try!(<T as SBEBlock<S>>::decode_groups_and_data(&mut group,&buf[offset..],scratch));
+            field.push(Box::new(group));
+        }
+
+        Ok(offset)
+    }
+}
+
+//Conversion between a field type's value and SBE. Field types that can't hand over their value
+//directly use the default methods, which fall back to the text that would be sent using tag=value
+//encoding.
+pub trait SBEFieldType: FieldType {
+    fn read_sbe<'a>(field: &'a <Self as FieldType>::Type,scratch: &'a mut Vec<u8>) -> SBEValue<'a> {
+        if Self::is_empty(field) {
+            return SBEValue::Null;
+        }
+
+        scratch.clear();
+        Self::read(field,FIXVersion::FIXT_1_1,MessageVersion::FIX50SP2,scratch);
+        SBEValue::Bytes(&scratch[..])
+    }
+
+    fn set_sbe_value(field: &mut <Self as FieldType>::Type,value: SBEValue,scratch: &mut Vec<u8>) -> Result<(),SetValueError> {
+        set_sbe_value_as_text::<Self>(field,value,scratch)
+    }
+}
+
+impl SBEFieldType for ApplVerIDFieldType {}
+impl SBEFieldType for BoolTrueOrBlankFieldType {}
+impl SBEFieldType for DayOfMonthFieldType {}
+impl SBEFieldType for DefaultApplVerIDFieldType {}
+impl SBEFieldType for LocalMktDateFieldType {}
+impl SBEFieldType for MonthYearFieldType {}
+impl SBEFieldType for NoneFieldType {}
+impl SBEFieldType for RepeatableStringFieldType {}
+impl SBEFieldType for SettlTypeFieldType {}
+impl SBEFieldType for UTCTimeOnlyFieldType {}
+
+impl SBEFieldType for CharFieldType {
+    fn read_sbe<'a>(field: &'a Self::Type,_scratch: &'a mut Vec<u8>) -> SBEValue<'a> {
+        if *field == 0 {
+            return SBEValue::Null;
+        }
+
+        SBEValue::UInt(*field as u64)
+    }
+}
+
+impl SBEFieldType for DataFieldType {
+    fn read_sbe<'a>(field: &'a Self::Type,_scratch: &'a mut Vec<u8>) -> SBEValue<'a> {
+        SBEValue::Bytes(&field[..])
+    }
+
+    fn set_sbe_value(field: &mut Self::Type,value: SBEValue,_scratch: &mut Vec<u8>) -> Result<(),SetValueError> {
+        field.clear();
+        value.write_text(field);
+        Ok(())
+    }
+}
+
+impl SBEFieldType for IntFieldType {
+    fn read_sbe<'a>(field: &'a Self::Type,_scratch: &'a mut Vec<u8>) -> SBEValue<'a> {
+        SBEValue::Int(*field)
+    }
+
+    fn set_sbe_value(field: &mut Self::Type,value: SBEValue,scratch: &mut Vec<u8>) -> Result<(),SetValueError> {
+        match value {
+            SBEValue::Int(value) => *field = value,
+            SBEValue::UInt(value) if value <= i64::max_value() as u64 => *field = value as i64,
+            SBEValue::UInt(_) => return Err(SetValueError::OutOfRange),
+            value => return set_sbe_value_as_text::<Self>(field,value,scratch),
+        }
+
+        Ok(())
+    }
+}
+
+impl SBEFieldType for SeqNumFieldType {
+    fn read_sbe<'a>(field: &'a Self::Type,_scratch: &'a mut Vec<u8>) -> SBEValue<'a> {
+        if *field == 0 {
+            return SBEValue::Null;
+        }
+
+        SBEValue::UInt(*field)
+    }
+
+    fn set_sbe_value(field: &mut Self::Type,value: SBEValue,scratch: &mut Vec<u8>) -> Result<(),SetValueError> {
+        match value {
+            SBEValue::UInt(value) => *field = value,
+            SBEValue::Int(value) if value >= 0 => *field = value as u64,
+            SBEValue::Int(_) => return Err(SetValueError::OutOfRange),
+            value => return set_sbe_value_as_text::<Self>(field,value,scratch),
+        }
+
+        Ok(())
+    }
+}
+
+impl SBEFieldType for StringFieldType {
+    fn read_sbe<'a>(field: &'a Self::Type,_scratch: &'a mut Vec<u8>) -> SBEValue<'a> {
+        if field.is_empty() {
+            return SBEValue::Null;
+        }
+
+        SBEValue::Bytes(&field[..])
+    }
+
+    fn set_sbe_value(field: &mut Self::Type,value: SBEValue,_scratch: &mut Vec<u8>) -> Result<(),SetValueError> {
+        //Numbers are written straight into the field since the field stores them as text anyway.
+        field.clear();
+        value.write_text(field);
+        Ok(())
+    }
+}
+
+impl SBEFieldType for UTCTimestampFieldType {
+    fn read_sbe<'a>(field: &'a Self::Type,scratch: &'a mut Vec<u8>) -> SBEValue<'a> {
+        if Self::is_empty(field) {
+            return SBEValue::Null;
+        }
+
+        let naive_utc = field.naive_utc();
+        if naive_utc.timestamp() < 0 {
+            //Can't be represented as nanoseconds since the UNIX epoch. Let the encoder reject it.
+            scratch.clear();
+            Self::read(field,FIXVersion::FIXT_1_1,MessageVersion::FIX50SP2,scratch);
+            return SBEValue::Bytes(&scratch[..]);
+        }
+
+        SBEValue::Timestamp(naive_utc.timestamp() as u64 * 1_000_000_000 + naive_utc.nanosecond() as u64)
+    }
+
+    fn set_sbe_value(field: &mut Self::Type,value: SBEValue,scratch: &mut Vec<u8>) -> Result<(),SetValueError> {
+        match value {
+            SBEValue::Timestamp(nanoseconds) => {
+                //Sub-millisecond precision is truncated because UTCTimestamp does not support it.
+                let nanoseconds_remainder = (nanoseconds % 1_000_000_000) as u32;
+                let naive_utc = NaiveDateTime::from_timestamp((nanoseconds / 1_000_000_000) as i64,nanoseconds_remainder - nanoseconds_remainder % 1_000_000);
+                *field = DateTime::<UTC>::from_utc(naive_utc,UTC);
+                Ok(())
+            },
+            value => set_sbe_value_as_text::<Self>(field,value,scratch),
+        }
+    }
+}
+
+fn write_uint(value: u64,len: usize,buf: &mut Vec<u8>) {
+    for index in 0..len {
+        buf.push((value >> (index * 8)) as u8);
+    }
+}
+
+fn read_uint(bytes: &[u8]) -> u64 {
+    let mut value = 0;
+    for (index,byte) in bytes.iter().enumerate() {
+        value |= (*byte as u64) << (index * 8);
+    }
+
+    value
+}
+
+fn read_int(bytes: &[u8]) -> i64 {
+    //Sign extend from the most significant byte.
+    let shift = 64 - bytes.len() * 8;
+    ((read_uint(bytes) << shift) as i64) >> shift
+}
+
+fn parse_int(value: &[u8]) -> Option<i64> {
+    str::from_utf8(value).ok().and_then(|value| value.parse::<i64>().ok())
+}
+
+fn parse_decimal(value: &[u8],exponent: i8) -> Option<i64> {
+    //Convert to a mantissa without going through floating point so no precision is lost.
+    let value = match str::from_utf8(value) {
+        Ok(value) => value,
+        Err(_) => return None,
+    };
+    let (is_negative,value) = if value.starts_with('-') { (true,&value[1..]) } else { (false,value) };
+    let (integer,fraction) = match value.find('.') {
+        Some(index) => (&value[..index],&value[index + 1..]),
+        None => (value,""),
+    };
+    if integer.is_empty() || !integer.bytes().chain(fraction.bytes()).all(|c| c >= b'0' && c <= b'9') {
+        return None;
+    }
+
+    let mut mantissa: i64 = 0;
+    for c in integer.bytes().chain(fraction.bytes()) {
+        mantissa = match mantissa.checked_mul(10).and_then(|mantissa| mantissa.checked_add((c - b'0') as i64)) {
+            Some(mantissa) => mantissa,
+            None => return None,
+        };
+    }
+
+    let shift = -(exponent as i32) - fraction.len() as i32;
+    if shift >= 0 {
+        for _ in 0..shift {
+            mantissa = match mantissa.checked_mul(10) {
+                Some(mantissa) => mantissa,
+                None => return None,
+            };
+        }
+    }
+    else {
+        //Only trailing zeros can be dropped.
+        for _ in shift..0 {
+            if mantissa % 10 != 0 {
+                return None;
+            }
+            mantissa /= 10;
+        }
+    }
+
+    Some(if is_negative { -mantissa } else { mantissa })
+}
+
+fn format_decimal(mantissa: i64,exponent: i8,buf: &mut Vec<u8>) {
+    if exponent >= 0 {
+        write!(buf,"{}",mantissa).unwrap();
+        for _ in 0..exponent {
+            buf.push(b'0');
+        }
+        return;
+    }
+
+    if mantissa < 0 {
+        buf.push(b'-');
+    }
+    let magnitude = if mantissa < 0 { (mantissa as u64).wrapping_neg() } else { mantissa as u64 };
+    let digits = magnitude.to_string();
+    let fraction_len = -(exponent as i32) as usize;
+    let digits = if digits.len() <= fraction_len {
+        format!("{:0>width$}",digits,width = fraction_len + 1)
+    }
+    else {
+        digits
+    };
+    let (integer,fraction) = digits.split_at(digits.len() - fraction_len);
+    buf.extend_from_slice(integer.as_bytes());
+
+    //Trailing zeros are dropped so values written using fewer decimal places round trip.
+    let fraction = fraction.trim_right_matches('0');
+    if !fraction.is_empty() {
+        buf.push(b'.');
+        buf.extend_from_slice(fraction.as_bytes());
+    }
+}
+
+fn parse_timestamp(value: &[u8]) -> Option<u64> {
+    //Same format as UTCTimestampFieldType: YYYYMMDD-HH:MM:SS[.sss]
+    if value.len() < 17 || value[8] != b'-' || value[11] != b':' || value[14] != b':' {
+        return None;
+    }
+
+    let milliseconds = if value.len() == 21 && value[17] == b'.' { parse_int(&value[18..21]) } else if value.len() == 17 { Some(0) } else { None };
+    let date = match (parse_int(&value[0..4]),parse_int(&value[4..6]),parse_int(&value[6..8])) {
+        (Some(year),Some(month),Some(day)) => NaiveDate::from_ymd_opt(year as i32,month as u32,day as u32),
+        _ => None,
+    };
+    let date_time = match (date,parse_int(&value[9..11]),parse_int(&value[12..14]),parse_int(&value[15..17]),milliseconds) {
+        (Some(date),Some(hours),Some(minutes),Some(seconds),Some(milliseconds)) => date.and_hms_milli_opt(hours as u32,minutes as u32,seconds as u32,milliseconds as u32),
+        _ => None,
+    };
+
+    match date_time {
+        Some(date_time) if date_time.timestamp() >= 0 => Some(date_time.timestamp() as u64 * 1_000_000_000 + date_time.nanosecond() as u64),
+        _ => None,
+    }
+}
+
+fn format_timestamp(nanoseconds: u64,buf: &mut Vec<u8>) {
+    //Sub-millisecond precision is truncated because UTCTimestamp does not support it.
+    let nanoseconds_remainder = (nanoseconds % 1_000_000_000) as u32;
+    let date_time = NaiveDateTime::from_timestamp((nanoseconds / 1_000_000_000) as i64,nanoseconds_remainder - nanoseconds_remainder % 1_000_000);
+    write!(buf,
+           "{:04}{:02}{:02}-{:02}:{:02}:{:02}.{:03}",
+           date_time.year(),
+           date_time.month(),
+           date_time.day(),
+           date_time.hour(),
+           date_time.minute(),
+           date_time.second(),
+           date_time.nanosecond() / 1_000_000).unwrap();
+}
+
+fn rescale(mantissa: i64,from_exponent: i8,to_exponent: i8) -> Option<i64> {
+    //Only trailing zeros can be dropped so no precision is lost.
+    let mut mantissa = mantissa;
+    let mut exponent = from_exponent as i32;
+    while exponent > to_exponent as i32 {
+        mantissa = match mantissa.checked_mul(10) {
+            Some(mantissa) => mantissa,
+            None => return None,
+        };
+        exponent -= 1;
+    }
+    while exponent < to_exponent as i32 {
+        if mantissa % 10 != 0 {
+            return None;
+        }
+        mantissa /= 10;
+        exponent += 1;
+    }
+
+    Some(mantissa)
+}
+
+fn to_int(value: SBEValue) -> Option<i64> {
+    match value {
+        SBEValue::Int(value) => Some(value),
+        SBEValue::UInt(value) if value <= i64::max_value() as u64 => Some(value as i64),
+        SBEValue::Bytes(value) => parse_int(value),
+        _ => None,
+    }
+}
+
+fn to_uint(value: SBEValue) -> Option<u64> {
+    match value {
+        SBEValue::UInt(value) => Some(value),
+        SBEValue::Int(value) if value >= 0 => Some(value as u64),
+        SBEValue::Bytes(value) => str::from_utf8(value).ok().and_then(|value| value.parse::<u64>().ok()),
+        _ => None,
+    }
+}
+
+fn encode_value(sbe_type: SBEType,value: SBEValue,buf: &mut Vec<u8>) -> Result<(),()> {
+    match sbe_type {
+        SBEType::Char => match value {
+            SBEValue::Bytes(value) if value.len() == 1 && value[0] != 0 => buf.push(value[0]),
+            SBEValue::UInt(value) if value > 0 && value <= u8::max_value() as u64 => buf.push(value as u8),
+            SBEValue::Null => buf.push(0),
+            _ => return Err(()),
+        },
+        SBEType::CharArray(len) => {
+            let start_len = buf.len();
+            value.write_text(buf);
+            if buf.len() - start_len > len {
+                buf.truncate(start_len);
+                return Err(());
+            }
+            for _ in buf.len() - start_len..len {
+                buf.push(0);
+            }
+        },
+        SBEType::Int8 | SBEType::Int16 | SBEType::Int32 | SBEType::Int64 => {
+            let bits = sbe_type.len() * 8;
+            let min_value = if bits == 64 { i64::min_value() } else { -(1i64 << (bits - 1)) };
+            let max_value = if bits == 64 { i64::max_value() } else { (1i64 << (bits - 1)) - 1 };
+            let value = match value {
+                SBEValue::Null => min_value,
+                //The minimum value is reserved for null.
+                value => match to_int(value) {
+                    Some(value) if value > min_value && value <= max_value => value,
+                    _ => return Err(()),
+                },
+            };
+            write_uint(value as u64,sbe_type.len(),buf);
+        },
+        SBEType::UInt8 | SBEType::UInt16 | SBEType::UInt32 | SBEType::UInt64 => {
+            let bits = sbe_type.len() * 8;
+            let max_value = if bits == 64 { u64::max_value() } else { (1u64 << bits) - 1 };
+            let value = match value {
+                SBEValue::Null => max_value,
+                //The maximum value is reserved for null.
+                value => match to_uint(value) {
+                    Some(value) if value < max_value => value,
+                    _ => return Err(()),
+                },
+            };
+            write_uint(value,sbe_type.len(),buf);
+        },
+        SBEType::Decimal(exponent) => {
+            let mantissa = match value {
+                SBEValue::Null => None,
+                SBEValue::Bytes(value) => Some(parse_decimal(value,exponent)),
+                SBEValue::Decimal(mantissa,value_exponent) => Some(rescale(mantissa,value_exponent,exponent)),
+                value => Some(to_int(value).and_then(|value| rescale(value,0,exponent))),
+            };
+            let mantissa = match mantissa {
+                None => i64::min_value(),
+                Some(Some(mantissa)) if mantissa != i64::min_value() => mantissa,
+                Some(_) => return Err(()),
+            };
+            write_uint(mantissa as u64,8,buf);
+        },
+        SBEType::UTCTimestampNanos => {
+            let nanoseconds = match value {
+                SBEValue::Null => u64::max_value(),
+                SBEValue::Timestamp(nanoseconds) if nanoseconds != u64::max_value() => nanoseconds,
+                SBEValue::Bytes(value) => try!(parse_timestamp(value).ok_or(())),
+                _ => return Err(()),
+            };
+            write_uint(nanoseconds,8,buf);
+        },
+    }
+
+    Ok(())
+}
+
+fn decode_value(sbe_type: SBEType,bytes: &[u8]) -> SBEValue {
+    match sbe_type {
+        SBEType::Char => {
+            if bytes[0] == 0 {
+                return SBEValue::Null;
+            }
+            SBEValue::Bytes(&bytes[..1])
+        },
+        SBEType::CharArray(_) => {
+            let len = bytes.iter().position(|c| *c == 0).unwrap_or(bytes.len());
+            if len == 0 {
+                return SBEValue::Null;
+            }
+            SBEValue::Bytes(&bytes[..len])
+        },
+        SBEType::Int8 | SBEType::Int16 | SBEType::Int32 | SBEType::Int64 => {
+            let value = read_int(bytes);
+            let null_value = if bytes.len() == 8 { i64::min_value() } else { -(1i64 << (bytes.len() * 8 - 1)) };
+            if value == null_value {
+                return SBEValue::Null;
+            }
+            SBEValue::Int(value)
+        },
+        SBEType::UInt8 | SBEType::UInt16 | SBEType::UInt32 | SBEType::UInt64 => {
+            if bytes.iter().all(|c| *c == 0xFF) {
+                return SBEValue::Null;
+            }
+            SBEValue::UInt(read_uint(bytes))
+        },
+        SBEType::Decimal(exponent) => {
+            let mantissa = read_int(bytes);
+            if mantissa == i64::min_value() {
+                return SBEValue::Null;
+            }
+            SBEValue::Decimal(mantissa,exponent)
+        },
+        SBEType::UTCTimestampNanos => {
+            let nanoseconds = read_uint(bytes);
+            if nanoseconds == u64::max_value() {
+                return SBEValue::Null;
+            }
+            SBEValue::Timestamp(nanoseconds)
+        },
+    }
+}
+
+pub fn encode_field<F: Field>(field: &<<F as Field>::Type as FieldType>::Type,sbe_type: SBEType,scratch: &mut Vec<u8>,buf: &mut Vec<u8>) -> Result<(),SBEError>
+    where <F as Field>::Type: SBEFieldType {
+    let value = <<F as Field>::Type as SBEFieldType>::read_sbe(field,scratch);
+    encode_value(sbe_type,value,buf).map_err(|_| SBEError::InvalidValue(F::name()))
+}
+
+pub fn decode_field<F: Field>(field: &mut <<F as Field>::Type as FieldType>::Type,sbe_type: SBEType,bytes: &[u8],scratch: &mut Vec<u8>) -> Result<(),SBEError>
+    where <F as Field>::Type: SBEFieldType {
+    let value = decode_value(sbe_type,bytes);
+    <<F as Field>::Type as SBEFieldType>::set_sbe_value(field,value,scratch).map_err(|_| SBEError::InvalidValue(F::name()))
+}
+
+pub fn encode_data_field<F: Field>(field: &<<F as Field>::Type as FieldType>::Type,scratch: &mut Vec<u8>,buf: &mut Vec<u8>) -> Result<(),SBEError>
+    where <F as Field>::Type: SBEFieldType {
+    //Length prefix is filled in once the value has been written.
+    let start_len = buf.len();
+    write_uint(0,DATA_HEADER_LEN,buf);
+    <<F as Field>::Type as SBEFieldType>::read_sbe(field,scratch).write_text(buf);
+
+    let len = buf.len() - start_len - DATA_HEADER_LEN;
+    if len > u16::max_value() as usize {
+        buf.truncate(start_len);
+        return Err(SBEError::InvalidValue(F::name()));
+    }
+    buf[start_len] = len as u8;
+    buf[start_len + 1] = (len >> 8) as u8;
+
+    Ok(())
+}
+
+pub fn decode_data_field<F: Field>(field: &mut <<F as Field>::Type as FieldType>::Type,buf: &[u8],scratch: &mut Vec<u8>) -> Result<usize,SBEError>
+    where <F as Field>::Type: SBEFieldType {
+    if buf.len() < DATA_HEADER_LEN {
+        return Err(SBEError::BufferTooSmall);
+    }
+
+    let len = read_uint(&buf[0..DATA_HEADER_LEN]) as usize;
+    if buf.len() < DATA_HEADER_LEN + len {
+        return Err(SBEError::BufferTooSmall);
+    }
+    if len > 0 {
+        let value = SBEValue::Bytes(&buf[DATA_HEADER_LEN..DATA_HEADER_LEN + len]);
+        try!(<<F as Field>::Type as SBEFieldType>::set_sbe_value(field,value,scratch).map_err(|_| SBEError::InvalidValue(F::name())));
+    }
+
+    Ok(DATA_HEADER_LEN + len)
+}
+
+pub fn decode_header(buf: &[u8]) -> Result<SBEHeader,SBEError> {
+    if buf.len() < HEADER_LEN {
+        return Err(SBEError::BufferTooSmall);
+    }
+
+    Ok(SBEHeader {
+        block_length: read_uint(&buf[0..2]) as u16,
+        template_id: read_uint(&buf[2..4]) as u16,
+        schema_id: read_uint(&buf[4..6]) as u16,
+        version: read_uint(&buf[6..8]) as u16,
+    })
+}
+
+//Encodes and decodes SBE messages. The scratch buffer used by field types that fall back to
+//tag=value text is kept between calls so a reused codec stops allocating.
+pub struct SBECodec {
+    scratch: Vec<u8>,
+}
+
+impl SBECodec {
+    pub fn new() -> SBECodec {
+        SBECodec {
+            scratch: Vec::with_capacity(64),
+        }
+    }
+
+    pub fn encode<S: SBESchema,T: SBEMessage<S>>(&mut self,message: &T,buf: &mut Vec<u8>) -> Result<usize,SBEError> {
+        let start_len = buf.len();
+        write_uint(<T as SBEBlock<S>>::block_length() as u64,2,buf);
+        write_uint(<T as SBEMessage<S>>::template_id() as u64,2,buf);
+        write_uint(S::schema_id() as u64,2,buf);
+        write_uint(S::schema_version() as u64,2,buf);
+
+        let result = <T as SBEBlock<S>>::encode_block(message,&mut self.scratch,buf).and_then(|_| {
+            <T as SBEBlock<S>>::encode_groups_and_data(message,&mut self.scratch,buf)
+        });
+        if let Err(err) = result {
+            //Don't leave a partial message behind.
+            buf.truncate(start_len);
+            return Err(err);
+        }
+
+        Ok(buf.len() - start_len)
+    }
+
+    //Returns the message and the number of bytes used.
+    pub fn decode<S: SBESchema,T: SBEMessage<S> + Default>(&mut self,buf: &[u8]) -> Result<(T,usize),SBEError> {
+        let header = try!(decode_header(buf));
+        if header.schema_id != S::schema_id() {
+            return Err(SBEError::SchemaMismatch(header.schema_id));
+        }
+        if header.template_id != <T as SBEMessage<S>>::template_id() {
+            return Err(SBEError::TemplateMismatch(header.template_id));
+        }
+
+        //Messages written with a newer version of the schema can have a longer block. The extra
+        //fields are skipped so older decoders keep working.
+        let block_length = header.block_length as usize;
+        if block_length < <T as SBEBlock<S>>::block_length() as usize {
+            return Err(SBEError::BlockTooShort(header.block_length));
+        }
+        if buf.len() < HEADER_LEN + block_length {
+            return Err(SBEError::BufferTooSmall);
+        }
+
+        let mut message = T::default();
+        try!(<T as SBEBlock<S>>::decode_block(&mut message,&buf[HEADER_LEN..HEADER_LEN + block_length],&mut self.scratch));
+        let len = HEADER_LEN + block_length + try!(<T as SBEBlock<S>>::decode_groups_and_data(&mut message,&buf[HEADER_LEN + block_length..],&mut self.scratch));
+
+        Ok((message,len))
+    }
+}
+
+impl Default for SBECodec {
+    fn default() -> Self {
+        SBECodec::new()
+    }
+}
+
+#[macro_export]
+macro_rules! define_sbe_schema {
+    ( $schema_name:ident : $schema_id:expr , $schema_version:expr ) => {
+        pub struct $schema_name;
+
+        impl $crate::sbe::SBESchema for $schema_name {
+            fn schema_id() -> u16 {
+                $schema_id
+            }
+
+            fn schema_version() -> u16 {
+                $schema_version
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! define_sbe_block {
+    ( $schema:ty , $message_name:ty => {
+        FIELDS { $( $field_name:ident : $field_type:ty => $sbe_type:expr ),* $(),* }
+        GROUPS { $( $group_field_name:ident : $group_field_type:ty ),* $(),* }
+        DATA { $( $data_field_name:ident : $data_field_type:ty ),* $(),* }
+    } ) => {
+        impl $crate::sbe::SBEBlock<$schema> for $message_name {
+            fn block_length() -> u16 {
+                (0 $( + $sbe_type.len() )*) as u16
+            }
+
+            #[allow(unused_variables)]
+            fn encode_block(&self,scratch: &mut Vec<u8>,buf: &mut Vec<u8>) -> Result<(),$crate::sbe::SBEError> {
+                $( try!($crate::sbe::encode_field::<$field_type>(&self.$field_name,$sbe_type,scratch,buf)); )*
+
+                Ok(())
+            }
+
+            #[allow(unused_variables)]
+            fn encode_groups_and_data(&self,scratch: &mut Vec<u8>,buf: &mut Vec<u8>) -> Result<(),$crate::sbe::SBEError> {
+                $( try!(<<$group_field_type as $crate::field::Field>::Type as $crate::sbe::SBEGroupFieldType<$schema>>::encode_groups(&self.$group_field_name,scratch,buf)); )*
+                $( try!($crate::sbe::encode_data_field::<$data_field_type>(&self.$data_field_name,scratch,buf)); )*
+
+                Ok(())
+            }
+
+            #[allow(unused_variables,unused_mut)]
+            fn decode_block(&mut self,block: &[u8],scratch: &mut Vec<u8>) -> Result<(),$crate::sbe::SBEError> {
+                let mut offset = 0;
+                $(
+                    let sbe_type: $crate::sbe::SBEType = $sbe_type;
+                    try!($crate::sbe::decode_field::<$field_type>(&mut self.$field_name,sbe_type,&block[offset..offset + sbe_type.len()],scratch));
+                    offset += sbe_type.len();
+                )*
+
+                Ok(())
+            }
+
+            #[allow(unused_variables,unused_mut)]
+            fn decode_groups_and_data(&mut self,buf: &[u8],scratch: &mut Vec<u8>) -> Result<usize,$crate::sbe::SBEError> {
+                let mut offset = 0;
+                $( offset += try!(<<$group_field_type as $crate::field::Field>::Type as $crate::sbe::SBEGroupFieldType<$schema>>::decode_groups(&mut self.$group_field_name,&buf[offset..],scratch)); )*
+                $( offset += try!($crate::sbe::decode_data_field::<$data_field_type>(&mut self.$data_field_name,&buf[offset..],scratch)); )*
+
+                Ok(offset)
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! define_sbe_message {
+    ( $schema:ty , $message_name:ty : $template_id:expr => { $( $body:tt )* } ) => {
+        define_sbe_block!($schema,$message_name => { $( $body )* });
+
+        impl $crate::sbe::SBEMessage<$schema> for $message_name {
+            fn template_id() -> u16 {
+                $template_id
+            }
+        }
+    };
+}
@@ -0,0 +1,306 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//Just enough XML to read a QuickFIX data dictionary, a FIX Orchestra repository, an SBE schema, or
+//a FIXML message: elements, attributes, text, comments, processing instructions, the predefined
+//entities, and character references. Text is only needed for SBE constants so all of the text
+//directly inside an element is joined and trimmed. DTDs and CDATA sections are not supported.
+
+use std::char;
+
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String,String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    pub fn new<T: Into<String>>(name: T) -> Element {
+        Element {
+            name: name.into(),
+            attributes: Vec::new(),
+            children: Vec::new(),
+            text: String::new(),
+        }
+    }
+
+    pub fn attribute(&self,name: &str) -> Option<&str> {
+        self.attributes.iter().find(|&&(ref attribute_name,_)| attribute_name == name).map(|&(_,ref value)| &value[..])
+    }
+
+    pub fn child(&self,name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    pub fn write(&self,buf: &mut String) {
+        buf.push('<');
+        buf.push_str(&self.name);
+        for &(ref name,ref value) in &self.attributes {
+            buf.push(' ');
+            buf.push_str(name);
+            buf.push_str("=\"");
+            encode_entities(value,buf);
+            buf.push('"');
+        }
+
+        if self.children.is_empty() && self.text.is_empty() {
+            buf.push_str("/>");
+            return;
+        }
+
+        buf.push('>');
+        encode_entities(&self.text,buf);
+        for child in &self.children {
+            child.write(buf);
+        }
+        buf.push_str("</");
+        buf.push_str(&self.name);
+        buf.push('>');
+    }
+}
+
+pub struct XmlError {
+    pub offset: usize, //Byte offset in the document where the error was detected.
+    pub description: &'static str,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error<T>(&self,description: &'static str) -> Result<T,XmlError> {
+        Err(XmlError {
+            offset: self.offset,
+            description: description,
+        })
+    }
+
+    fn starts_with(&self,prefix: &[u8]) -> bool {
+        self.bytes[self.offset..].starts_with(prefix)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.offset).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !is_whitespace(c) {
+                break;
+            }
+            self.offset += 1;
+        }
+    }
+
+    fn skip_past(&mut self,terminator: &[u8],description: &'static str) -> Result<(),XmlError> {
+        while self.offset < self.bytes.len() {
+            if self.starts_with(terminator) {
+                self.offset += terminator.len();
+                return Ok(());
+            }
+            self.offset += 1;
+        }
+
+        self.error(description)
+    }
+
+    fn skip_misc(&mut self,text: &mut Vec<u8>) -> Result<(),XmlError> {
+        //Skip anything that can appear between elements but doesn't describe the document. Text
+        //content is collected so the caller can decide if it matters.
+        loop {
+            if self.starts_with(b"<?") {
+                try!(self.skip_past(b"?>","Unterminated processing instruction"));
+            }
+            else if self.starts_with(b"<!--") {
+                try!(self.skip_past(b"-->","Unterminated comment"));
+            }
+            else if self.starts_with(b"<!") {
+                return self.error("DTD and CDATA sections are not supported");
+            }
+            else if self.offset < self.bytes.len() && !self.starts_with(b"<") {
+                text.push(self.bytes[self.offset]);
+                self.offset += 1;
+            }
+            else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn read_name(&mut self) -> Result<String,XmlError> {
+        let start = self.offset;
+        while let Some(c) = self.peek() {
+            if is_whitespace(c) || c == b'=' || c == b'/' || c == b'>' {
+                break;
+            }
+            self.offset += 1;
+        }
+
+        if start == self.offset {
+            return self.error("Expected name");
+        }
+
+        Ok(String::from_utf8_lossy(&self.bytes[start..self.offset]).into_owned())
+    }
+
+    fn read_attribute_value(&mut self) -> Result<String,XmlError> {
+        let quote = match self.peek() {
+            Some(c) if c == b'"' || c == b'\'' => c,
+            _ => return self.error("Expected quoted attribute value"),
+        };
+        self.offset += 1;
+
+        let start = self.offset;
+        while let Some(c) = self.peek() {
+            if c == quote {
+                let value = try!(decode_entities(&self.bytes[start..self.offset]).or_else(|_| self.error("Unknown entity in attribute value")));
+                self.offset += 1;
+                return Ok(value);
+            }
+            self.offset += 1;
+        }
+
+        self.error("Unterminated attribute value")
+    }
+
+    fn read_element(&mut self) -> Result<Element,XmlError> {
+        if !self.starts_with(b"<") {
+            return self.error("Expected element");
+        }
+        self.offset += 1;
+
+        let mut element = Element {
+            name: try!(self.read_name()),
+            attributes: Vec::new(),
+            children: Vec::new(),
+            text: String::new(),
+        };
+
+        //Attributes.
+        loop {
+            self.skip_whitespace();
+            if self.starts_with(b"/>") {
+                self.offset += 2;
+                return Ok(element);
+            }
+            else if self.starts_with(b">") {
+                self.offset += 1;
+                break;
+            }
+
+            let name = try!(self.read_name());
+            self.skip_whitespace();
+            if !self.starts_with(b"=") {
+                return self.error("Expected '=' after attribute name");
+            }
+            self.offset += 1;
+            self.skip_whitespace();
+            let value = try!(self.read_attribute_value());
+            element.attributes.push((name,value));
+        }
+
+        //Children until the matching end tag.
+        let mut text = Vec::new();
+        loop {
+            try!(self.skip_misc(&mut text));
+            if self.offset >= self.bytes.len() {
+                return self.error("Unterminated element");
+            }
+            else if self.starts_with(b"</") {
+                self.offset += 2;
+                let name = try!(self.read_name());
+                if name != element.name {
+                    return self.error("End tag does not match start tag");
+                }
+                self.skip_whitespace();
+                if !self.starts_with(b">") {
+                    return self.error("Expected '>' after end tag");
+                }
+                self.offset += 1;
+
+                element.text = try!(decode_entities(&text[..]).or_else(|_| self.error("Unknown entity in text"))).trim().to_string();
+                return Ok(element);
+            }
+
+            element.children.push(try!(self.read_element()));
+        }
+    }
+}
+
+fn is_whitespace(c: u8) -> bool {
+    c == b' ' || c == b'\t' || c == b'\r' || c == b'\n'
+}
+
+fn decode_entities(bytes: &[u8]) -> Result<String,()> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut result = String::with_capacity(text.len());
+    let mut remaining = &text[..];
+    while let Some(start) = remaining.find('&') {
+        result.push_str(&remaining[..start]);
+        let end = match remaining[start..].find(';') {
+            Some(end) => start + end,
+            None => return Err(()),
+        };
+        let entity = &remaining[start + 1..end];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if entity.starts_with("#x") => u32::from_str_radix(&entity[2..],16).ok().and_then(char::from_u32),
+            _ if entity.starts_with('#') => u32::from_str_radix(&entity[1..],10).ok().and_then(char::from_u32),
+            _ => None,
+        };
+        match c {
+            Some(c) => result.push(c),
+            None => return Err(()),
+        }
+        remaining = &remaining[end + 1..];
+    }
+    result.push_str(remaining);
+
+    Ok(result)
+}
+
+fn encode_entities(value: &str,buf: &mut String) {
+    for c in value.chars() {
+        match c {
+            '&' => buf.push_str("&amp;"),
+            '<' => buf.push_str("&lt;"),
+            '>' => buf.push_str("&gt;"),
+            '"' => buf.push_str("&quot;"),
+            //Control characters, such as SOH in a data field, are written as character references
+            //so they survive being read back.
+            c if (c as u32) < 0x20 => buf.push_str(&format!("&#x{:X};",c as u32)),
+            c => buf.push(c),
+        }
+    }
+}
+
+pub fn parse(document: &str) -> Result<Element,XmlError> {
+    let mut reader = Reader {
+        bytes: document.as_bytes(),
+        offset: 0,
+    };
+
+    try!(reader.skip_misc(&mut Vec::new()));
+    let root = try!(reader.read_element());
+    try!(reader.skip_misc(&mut Vec::new()));
+    if reader.offset < reader.bytes.len() {
+        return reader.error("Unexpected content after root element");
+    }
+
+    Ok(root)
+}
@@ -12,6 +12,7 @@
 #![feature(attr_literals)]
 #![feature(const_fn)]
 
+extern crate chrono;
 #[macro_use]
 extern crate fix_rs;
 #[macro_use]
@@ -38,10 +39,10 @@ use fix_rs::field_tag::{self,FieldTag};
 use fix_rs::fix::ParseError;
 use fix_rs::fix_version::FIXVersion;
 use fix_rs::fixt;
-use fix_rs::fixt::engine::{EngineEvent,ConnectionTerminatedReason,ResendResponse};
+use fix_rs::fixt::engine::{EngineEvent,ConnectionTerminatedReason,ResendResponse,SendMessageError,SendingTimePolicy};
 use fix_rs::fixt::tests::{AUTO_DISCONNECT_AFTER_INBOUND_RESEND_REQUEST_LOOP_COUNT,INBOUND_MESSAGES_BUFFER_LEN_MAX,INBOUND_BYTES_BUFFER_CAPACITY};
 use fix_rs::fixt::message::FIXTMessage;
-use fix_rs::message::{self,NOT_REQUIRED,REQUIRED,Message};
+use fix_rs::message::{self,InvalidValueAction,InvalidValuePolicy,NOT_REQUIRED,REQUIRED,Message};
 use fix_rs::message_version::{self,MessageVersion};
 
 fn serialize_and_append_message<T: FIXTMessage>(message: &T,fix_version: FIXVersion,message_version: MessageVersion,buffer: &mut Vec<u8>) {
@@ -397,7 +398,7 @@ fn test_wrong_sender_comp_id_in_logon_response() {
     let (mut test_server,mut client,connection) = TestStream::setup_test_server(build_dictionary());
 
     let message = new_logon_message();
-    client.send_message(connection,message);
+    client.send_message(connection,message).unwrap();
     let _ = test_server.recv_message::<Logon>();
 
     //Respond with a logon messaging containing the wrong SenderCompID.
@@ -441,7 +442,7 @@ fn test_wrong_target_comp_id_in_logon_response() {
     let (mut test_server,mut client,connection) = TestStream::setup_test_server(build_dictionary());
 
     let message = new_logon_message();
-    client.send_message(connection,message);
+    client.send_message(connection,message).unwrap();
     let _ = test_server.recv_message::<Logon>();
 
     //Respond with a logon messaging containing the wrong TargetCompID.
@@ -551,7 +552,7 @@ fn test_sender_comp_id() {
         assert_eq!(message.session_reject_reason.expect("SessionRejectReason must be provided"),SessionRejectReason::TagSpecifiedOutOfRequiredOrder);
         assert_eq!(message.text,b"SenderCompID must be the 4th tag".to_vec());
 
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::SenderCompIDNotFourthTag = parse_error { true } else { false });
         });
@@ -566,7 +567,7 @@ fn test_sender_comp_id() {
         assert_eq!(message.session_reject_reason.expect("SessionRejectReason must be provided"),SessionRejectReason::TagSpecifiedOutOfRequiredOrder);
         assert_eq!(message.text,b"SenderCompID must be the 4th tag".to_vec());
 
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::SenderCompIDNotFourthTag = parse_error { true } else { false });
         });
@@ -604,7 +605,7 @@ fn test_sender_comp_id() {
         assert_eq!(message.msg_seq_num,2);
         assert_eq!(message.text,b"Required tag missing".to_vec());
 
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::MissingRequiredTag(ref tag,_) = parse_error { *tag == SenderCompID::tag() } else { false });
         });
@@ -647,7 +648,7 @@ fn test_target_comp_id() {
         assert_eq!(message.session_reject_reason.expect("SessionRejectReason must be provided"),SessionRejectReason::TagSpecifiedOutOfRequiredOrder);
         assert_eq!(message.text,b"TargetCompID must be the 5th tag".to_vec());
 
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::TargetCompIDNotFifthTag = parse_error { true } else { false });
         });
@@ -662,7 +663,7 @@ fn test_target_comp_id() {
         assert_eq!(message.session_reject_reason.expect("SessionRejectReason must be provided"),SessionRejectReason::TagSpecifiedOutOfRequiredOrder);
         assert_eq!(message.text,b"TargetCompID must be the 5th tag".to_vec());
 
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::TargetCompIDNotFifthTag = parse_error { true } else { false });
         });
@@ -700,7 +701,7 @@ fn test_target_comp_id() {
         assert_eq!(message.msg_seq_num,2);
         assert_eq!(message.text,b"Required tag missing".to_vec());
 
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::MissingRequiredTag(ref tag,_) = parse_error { *tag == TargetCompID::tag() } else { false });
         });
@@ -730,7 +731,7 @@ fn test_default_appl_ver_id() {
         //Make client send a TestMessage.
         let mut message = new_fixt_message!(TestMessage);
         message.text = b"text".to_vec();
-        client.send_message(connection,message);
+        client.send_message(connection,message).unwrap();
 
         //Confirm text field was excluded by server due to requiring >= FIX50 but default is FIX40.
         let message = test_server.recv_message::<TestMessage>();
@@ -756,7 +757,7 @@ fn test_default_appl_ver_id() {
         test_server.send_message(message);
 
         //Make sure message is considered invalid.
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::UnknownTag(ref tag) = parse_error { *tag == FieldTag(58) } else { false });
         });
@@ -792,7 +793,7 @@ fn test_appl_ver_id() {
         assert_eq!(message.text,b"ApplVerID must be the 6th tag if specified".to_vec());
 
         //Make sure Engine indicates that it rejected the message.
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::ApplVerIDNotSixthTag = parse_error { true } else { false });
         });
@@ -826,7 +827,7 @@ fn test_appl_ver_id() {
         assert_eq!(message.session_reject_reason.unwrap(),SessionRejectReason::TagNotDefinedForThisMessageType);
         assert_eq!(message.text,b"Tag not defined for this message type".to_vec());
 
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::UnexpectedTag(ref tag) = parse_error { *tag == Text::tag()  } else { false });
         });
@@ -851,7 +852,7 @@ fn test_message_type_default_application_version() {
     //Logon.
     let mut logon_message = new_logon_message();
     logon_message.default_appl_ver_id = MessageVersion::FIX50;
-    client.send_message_box_with_message_version(connection,MessageVersion::FIX50SP2,Box::new(logon_message));
+    client.send_message_box_with_message_version(connection,MessageVersion::FIX50SP2,Box::new(logon_message)).unwrap();
     let message = test_server.recv_message::<Logon>();
     assert_eq!(message.msg_seq_num,1);
 
@@ -918,7 +919,7 @@ fn test_respond_to_test_request_immediately_after_logon() {
     let (mut test_server,mut client,connection) = TestStream::setup_test_server(build_dictionary());
 
     //Have client send Logon.
-    client.send_message_box(connection,Box::new(new_logon_message()));
+    client.send_message_box(connection,Box::new(new_logon_message())).unwrap();
     let message = test_server.recv_message::<Logon>();
     assert_eq!(message.msg_seq_num,1);
 
@@ -973,7 +974,7 @@ fn test_respect_default_appl_ver_id_in_test_request_immediately_after_logon() {
     //Have client send Logon.
     let mut logon_message = new_logon_message();
     logon_message.default_appl_ver_id = MessageVersion::FIX50SP2;
-    client.send_message_box(connection,Box::new(logon_message));
+    client.send_message_box(connection,Box::new(logon_message)).unwrap();
     let message = test_server.recv_message::<Logon>();
     assert_eq!(message.msg_seq_num,1);
 
@@ -1023,7 +1024,7 @@ fn test_logout_and_terminate_wrong_versioned_test_request_immediately_after_logo
     let (mut test_server,mut client,connection) = TestStream::setup_test_server(build_dictionary());
 
     //Have client send Logon.
-    client.send_message_box(connection,Box::new(new_logon_message()));
+    client.send_message_box(connection,Box::new(new_logon_message())).unwrap();
     let message = test_server.recv_message::<Logon>();
     assert_eq!(message.msg_seq_num,1);
 
@@ -1097,7 +1098,7 @@ fn test_max_message_size() {
         //Have client send Logon.
         let mut message = new_logon_message();
         message.max_message_size = MAX_MESSAGE_SIZE;
-        client.send_message_box(connection,Box::new(message));
+        client.send_message_box(connection,Box::new(message)).unwrap();
         let message = test_server.recv_message::<Logon>();
         assert_eq!(message.msg_seq_num,1);
         assert_eq!(message.max_message_size,MAX_MESSAGE_SIZE);
@@ -1140,7 +1141,7 @@ fn test_max_message_size() {
         //Have client send Logon.
         let mut message = new_logon_message();
         message.max_message_size = MAX_MESSAGE_SIZE;
-        client.send_message_box(connection,Box::new(message));
+        client.send_message_box(connection,Box::new(message)).unwrap();
         let message = test_server.recv_message::<Logon>();
         assert_eq!(message.msg_seq_num,1);
         assert_eq!(message.max_message_size,MAX_MESSAGE_SIZE);
@@ -1359,3 +1360,438 @@ fn test_inbound_resend_loop_detection() {
     });
     assert!(test_server.is_stream_closed(Duration::from_secs(3)));
 }
+
+#[test]
+fn test_sending_time_tolerance() {
+    define_dictionary!(
+        Logon,
+        Reject,
+        TestRequest,
+    );
+
+    //Connect and logon.
+    let (mut test_server,mut client,connection) = TestStream::setup_test_server_and_logon(build_dictionary());
+
+    //SendingTime is not checked by default.
+    let mut message = new_fixt_message!(TestRequest);
+    message.msg_seq_num = 2;
+    message.test_req_id = b"2".to_vec();
+    message.sending_time = message.sending_time - chrono::Duration::minutes(10);
+    test_server.send_message(message);
+    let message = engine_poll_message!(client,connection,TestRequest);
+    assert_eq!(message.msg_seq_num,2);
+    let _ = test_server.recv_message::<Heartbeat>();
+
+    //When warning, the message should still be accepted but the drift should be reported first.
+    client.set_sending_time_tolerance(connection,Duration::from_secs(5),SendingTimePolicy::Warn);
+    let mut message = new_fixt_message!(TestRequest);
+    message.msg_seq_num = 3;
+    message.test_req_id = b"3".to_vec();
+    message.sending_time = message.sending_time - chrono::Duration::minutes(10);
+    test_server.send_message(message);
+    engine_poll_event!(client,EngineEvent::SendingTimeOutsideTolerance(msg_connection,msg_seq_num,drift) => {
+        assert_eq!(msg_connection,connection);
+        assert_eq!(msg_seq_num,3);
+        assert!(drift >= chrono::Duration::minutes(10));
+    });
+    let message = engine_poll_message!(client,connection,TestRequest);
+    assert_eq!(message.msg_seq_num,3);
+    let _ = test_server.recv_message::<Heartbeat>();
+
+    //When rejecting, the message should be rejected but the inbound MsgSeqNum still incremented.
+    client.set_sending_time_tolerance(connection,Duration::from_secs(5),SendingTimePolicy::Reject);
+    let mut message = new_fixt_message!(TestRequest);
+    message.msg_seq_num = 4;
+    message.test_req_id = b"4".to_vec();
+    message.sending_time = message.sending_time + chrono::Duration::minutes(10);
+    test_server.send_message(message);
+
+    let message = test_server.recv_message::<Reject>();
+    assert_eq!(message.ref_seq_num,4);
+    assert_eq!(message.session_reject_reason.unwrap(),SessionRejectReason::SendingTimeAccuracyProblem);
+    engine_poll_event!(client,EngineEvent::MessageRejected(msg_connection,rejected_message) => {
+        assert_eq!(msg_connection,connection);
+        assert_eq!(rejected_message.msg_seq_num(),4);
+    });
+
+    //Messages within tolerance should be accepted as normal.
+    let mut message = new_fixt_message!(TestRequest);
+    message.msg_seq_num = 5;
+    message.test_req_id = b"5".to_vec();
+    test_server.send_message(message);
+    let message = engine_poll_message!(client,connection,TestRequest);
+    assert_eq!(message.msg_seq_num,5);
+}
+
+#[test]
+fn test_orig_sending_time_policy() {
+    define_dictionary!(
+        Logon,
+        Reject,
+        TestRequest,
+    );
+
+    //Connect and logon.
+    let (mut test_server,mut client,connection) = TestStream::setup_test_server_and_logon(build_dictionary());
+
+    //When warning, the PossDup message should be accepted after the problem is reported.
+    client.set_orig_sending_time_policy(connection,SendingTimePolicy::Warn);
+    let mut message = new_fixt_message!(TestRequest);
+    message.msg_seq_num = 2;
+    message.test_req_id = b"2".to_vec();
+    message.poss_dup_flag = true;
+    message.orig_sending_time = message.sending_time + chrono::Duration::seconds(1);
+    test_server.send_message(message);
+    engine_poll_event!(client,EngineEvent::OrigSendingTimeAfterSendingTime(msg_connection,msg_seq_num) => {
+        assert_eq!(msg_connection,connection);
+        assert_eq!(msg_seq_num,2);
+    });
+    let message = engine_poll_message!(client,connection,TestRequest);
+    assert_eq!(message.msg_seq_num,2);
+    let _ = test_server.recv_message::<Heartbeat>();
+
+    //When ignoring, the PossDup message should be accepted silently.
+    client.set_orig_sending_time_policy(connection,SendingTimePolicy::Ignore);
+    let mut message = new_fixt_message!(TestRequest);
+    message.msg_seq_num = 3;
+    message.test_req_id = b"3".to_vec();
+    message.poss_dup_flag = true;
+    message.orig_sending_time = message.sending_time + chrono::Duration::seconds(1);
+    test_server.send_message(message);
+    let message = engine_poll_message!(client,connection,TestRequest);
+    assert_eq!(message.msg_seq_num,3);
+}
+
+#[test]
+fn test_garbled_message_threshold() {
+    define_dictionary!(
+        Logon,
+        Logout,
+        Reject,
+        TestRequest,
+    );
+
+    fn send_garbled_test_request(test_server: &mut TestStream,msg_seq_num: u64) -> Vec<u8> {
+        let mut message = new_fixt_message!(TestRequest);
+        message.msg_seq_num = msg_seq_num;
+        message.test_req_id = b"test".to_vec();
+
+        //Corrupt the checksum so the message is garbled.
+        let mut buffer = Vec::new();
+        serialize_and_append_message(&message,FIXVersion::FIXT_1_1,MessageVersion::FIX50SP2,&mut buffer);
+        let checksum_index = buffer.len() - 2;
+        buffer[checksum_index] = if buffer[checksum_index] == b'0' { b'1' } else { b'0' };
+        let _ = test_server.stream.write(&buffer[..]);
+
+        buffer
+    }
+
+    //Connect and logon.
+    let (mut test_server,mut client,connection) = TestStream::setup_test_server_and_logon(build_dictionary());
+    client.set_garbled_message_threshold(connection,(2,Duration::from_secs(60)));
+
+    //First garbled message should be reported with the raw bytes and then ignored without using
+    //up its MsgSeqNum.
+    let bytes = send_garbled_test_request(&mut test_server,2);
+    engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,error_context,garbled_bytes,garbled_count) => {
+        assert_eq!(msg_connection,connection);
+        assert!(if let ParseError::ChecksumDoesNotMatch(_,_) = parse_error { true } else { false });
+        assert_eq!(error_context.msg_type,Some(b"1".to_vec()));
+        assert_eq!(error_context.msg_seq_num,Some(2));
+        assert_eq!(garbled_bytes,bytes);
+        assert_eq!(garbled_count,1);
+    });
+
+    //Messages answered with a Reject are not garbled so they don't count towards the threshold.
+    let mut message = new_fixt_message!(TestRequest);
+    message.msg_seq_num = 2;
+    test_server.send_message(message);
+    let message = test_server.recv_message::<Reject>();
+    assert_eq!(message.ref_seq_num,2);
+    engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,garbled_count) => {
+        assert_eq!(msg_connection,connection);
+        assert!(if let ParseError::MissingRequiredTag(_,_) = parse_error { true } else { false });
+        assert_eq!(garbled_count,1);
+    });
+
+    //Second garbled message within the window should trigger a logout.
+    send_garbled_test_request(&mut test_server,3);
+    engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,_,_,_,garbled_count) => {
+        assert_eq!(msg_connection,connection);
+        assert_eq!(garbled_count,2);
+    });
+
+    let message = test_server.recv_message::<Logout>();
+    assert_eq!(message.text,b"Received 2 garbled messages within 60 seconds".to_vec());
+
+    engine_poll_event!(client,EngineEvent::ConnectionTerminated(terminated_connection,reason) => {
+        assert_eq!(terminated_connection,connection);
+        assert!(if let ConnectionTerminatedReason::InboundGarbledMessageThresholdExceededError = reason { true } else { false });
+    });
+    assert!(test_server.is_stream_closed(Duration::from_secs(3)));
+}
+
+#[test]
+fn test_inbound_out_of_order_queue() {
+    define_dictionary!(
+        Heartbeat,
+        Logon,
+        Reject,
+        ResendRequest,
+        SequenceReset,
+        TestRequest,
+    );
+
+    //Make sure out of order messages are held onto and delivered in order once the gap is filled.
+    {
+        //Connect and logon.
+        let (mut test_server,mut client,connection) = TestStream::setup_test_server_and_logon(build_dictionary());
+        client.set_inbound_out_of_order_queue_len_max(connection,10);
+
+        //Send messages with MsgSeqNum higher than expected. Only the missing messages should be
+        //requested and only once.
+        for msg_seq_num in 4..6 {
+            let mut message = new_fixt_message!(TestRequest);
+            message.msg_seq_num = msg_seq_num;
+            message.test_req_id = msg_seq_num.to_string().into_bytes();
+            test_server.send_message(message);
+        }
+
+        let message = test_server.recv_message::<ResendRequest>();
+        assert_eq!(message.msg_seq_num,2);
+        assert_eq!(message.begin_seq_no,2);
+        assert_eq!(message.end_seq_no,3);
+        engine_poll_no_event!(client);
+
+        //Fill the gap. The queued messages should then be delivered in order.
+        let mut message = new_fixt_message!(SequenceReset);
+        message.gap_fill_flag = true;
+        message.new_seq_no = 4;
+        message.msg_seq_num = 2;
+        test_server.send_message(message);
+
+        let message = engine_poll_message!(client,connection,SequenceReset);
+        assert_eq!(message.msg_seq_num,2);
+        let message = engine_poll_message!(client,connection,TestRequest);
+        assert_eq!(message.msg_seq_num,4);
+        let message = engine_poll_message!(client,connection,TestRequest);
+        assert_eq!(message.msg_seq_num,5);
+
+        let message = test_server.recv_message::<Heartbeat>();
+        assert_eq!(message.test_req_id,b"4".to_vec());
+        let message = test_server.recv_message::<Heartbeat>();
+        assert_eq!(message.test_req_id,b"5".to_vec());
+    }
+
+    //Make sure exceeding the queue falls back to discarding and requesting everything.
+    {
+        //Connect and logon.
+        let (mut test_server,mut client,connection) = TestStream::setup_test_server_and_logon(build_dictionary());
+        client.set_inbound_out_of_order_queue_len_max(connection,1);
+
+        //Fill the queue.
+        let mut message = new_fixt_message!(TestRequest);
+        message.msg_seq_num = 4;
+        message.test_req_id = b"4".to_vec();
+        test_server.send_message(message);
+
+        let message = test_server.recv_message::<ResendRequest>();
+        assert_eq!(message.begin_seq_no,2);
+        assert_eq!(message.end_seq_no,3);
+
+        //One more message than fits.
+        let mut message = new_fixt_message!(TestRequest);
+        message.msg_seq_num = 5;
+        message.test_req_id = b"5".to_vec();
+        test_server.send_message(message);
+
+        let message = test_server.recv_message::<ResendRequest>();
+        assert_eq!(message.begin_seq_no,2);
+        assert_eq!(message.end_seq_no,0);
+
+        //Nothing was kept, including the message that didn't fit, so filling the gap up to it
+        //should not deliver anything else.
+        let mut message = new_fixt_message!(SequenceReset);
+        message.gap_fill_flag = true;
+        message.new_seq_no = 5;
+        message.msg_seq_num = 2;
+        test_server.send_message(message);
+
+        let message = engine_poll_message!(client,connection,SequenceReset);
+        assert_eq!(message.msg_seq_num,2);
+        engine_poll_no_event!(client);
+
+        //The resent copy is delivered exactly once.
+        let mut message = new_fixt_message!(TestRequest);
+        message.msg_seq_num = 5;
+        message.test_req_id = b"5".to_vec();
+        test_server.send_message(message);
+
+        let message = engine_poll_message!(client,connection,TestRequest);
+        assert_eq!(message.msg_seq_num,5);
+        let message = test_server.recv_message::<Heartbeat>();
+        assert_eq!(message.test_req_id,b"5".to_vec());
+        engine_poll_no_event!(client);
+    }
+
+    //Make sure queued messages are not rejected for their SendingTime growing stale while
+    //waiting for the gap to be filled.
+    {
+        //Connect and logon.
+        let (mut test_server,mut client,connection) = TestStream::setup_test_server_and_logon(build_dictionary());
+        client.set_inbound_out_of_order_queue_len_max(connection,10);
+        client.set_sending_time_tolerance(connection,Duration::from_secs(1),SendingTimePolicy::Reject);
+
+        let mut message = new_fixt_message!(TestRequest);
+        message.msg_seq_num = 3;
+        message.test_req_id = b"3".to_vec();
+        test_server.send_message(message);
+
+        let message = test_server.recv_message::<ResendRequest>();
+        assert_eq!(message.begin_seq_no,2);
+        assert_eq!(message.end_seq_no,2);
+
+        thread::sleep(Duration::from_millis(1500));
+
+        let mut message = new_fixt_message!(SequenceReset);
+        message.gap_fill_flag = true;
+        message.new_seq_no = 3;
+        message.msg_seq_num = 2;
+        test_server.send_message(message);
+
+        let message = engine_poll_message!(client,connection,SequenceReset);
+        assert_eq!(message.msg_seq_num,2);
+        let message = engine_poll_message!(client,connection,TestRequest);
+        assert_eq!(message.msg_seq_num,3);
+        let message = test_server.recv_message::<Heartbeat>();
+        assert_eq!(message.test_req_id,b"3".to_vec());
+    }
+
+    //Make sure queued messages whose SendingTime was already stale when they were received are
+    //still rejected once the gap is filled.
+    {
+        //Connect and logon.
+        let (mut test_server,mut client,connection) = TestStream::setup_test_server_and_logon(build_dictionary());
+        client.set_inbound_out_of_order_queue_len_max(connection,10);
+        client.set_sending_time_tolerance(connection,Duration::from_secs(5),SendingTimePolicy::Reject);
+
+        let mut message = new_fixt_message!(TestRequest);
+        message.msg_seq_num = 3;
+        message.test_req_id = b"3".to_vec();
+        message.sending_time = message.sending_time - chrono::Duration::minutes(10);
+        test_server.send_message(message);
+
+        let message = test_server.recv_message::<ResendRequest>();
+        assert_eq!(message.begin_seq_no,2);
+        assert_eq!(message.end_seq_no,2);
+
+        let mut message = new_fixt_message!(SequenceReset);
+        message.gap_fill_flag = true;
+        message.new_seq_no = 3;
+        message.msg_seq_num = 2;
+        test_server.send_message(message);
+
+        let message = engine_poll_message!(client,connection,SequenceReset);
+        assert_eq!(message.msg_seq_num,2);
+        engine_poll_event!(client,EngineEvent::MessageRejected(msg_connection,rejected_message) => {
+            assert_eq!(msg_connection,connection);
+
+            let message = rejected_message.as_any().downcast_ref::<TestRequest>().expect("Not expected message type").clone();
+            assert_eq!(message.msg_seq_num,3);
+        });
+        let message = test_server.recv_message::<Reject>();
+        assert_eq!(message.ref_seq_num,3);
+        assert_eq!(message.session_reject_reason.unwrap(),SessionRejectReason::SendingTimeAccuracyProblem);
+    }
+}
+
+#[test]
+fn test_invalid_value_policy() {
+    define_dictionary!(
+        Heartbeat,
+        Logon,
+        TestRequest,
+    );
+
+    //Connect and logon.
+    let (mut test_server,mut client,connection) = TestStream::setup_test_server_and_logon(build_dictionary());
+
+    //Values containing SOH are refused by default and nothing is sent.
+    let mut message = new_fixt_message!(TestRequest);
+    message.test_req_id = b"a\x01b=c".to_vec();
+    match client.send_message(connection,message) {
+        Err(SendMessageError::InvalidFieldValue(invalid_field_value)) => {
+            assert_eq!(invalid_field_value.tag,FieldTag(112));
+            assert_eq!(invalid_field_value.name,"TestReqID");
+        },
+        _ => assert!(false),
+    }
+
+    //'=' is allowed by default.
+    let mut message = new_fixt_message!(TestRequest);
+    message.test_req_id = b"b=c".to_vec();
+    client.send_message(connection,message).unwrap();
+    let message = test_server.recv_message::<TestRequest>();
+    assert_eq!(message.msg_seq_num,2);
+    assert_eq!(message.test_req_id,b"b=c".to_vec());
+
+    //Strip the offending bytes.
+    client.set_invalid_value_policy(InvalidValuePolicy::new(InvalidValueAction::Strip));
+    let mut message = new_fixt_message!(TestRequest);
+    message.test_req_id = b"a\x01b=c".to_vec();
+    client.send_message(connection,message).unwrap();
+    let message = test_server.recv_message::<TestRequest>();
+    assert_eq!(message.msg_seq_num,3);
+    assert_eq!(message.test_req_id,b"ab=c".to_vec());
+
+    //Replace the offending bytes, including '=' when asked to.
+    client.set_invalid_value_policy(InvalidValuePolicy {
+        action: InvalidValueAction::Replace(b' '),
+        include_tag_end: true,
+    });
+    let mut message = new_fixt_message!(TestRequest);
+    message.test_req_id = b"a\x01b=c".to_vec();
+    client.send_message(connection,message).unwrap();
+    let message = test_server.recv_message::<TestRequest>();
+    assert_eq!(message.msg_seq_num,4);
+    assert_eq!(message.test_req_id,b"a b c".to_vec());
+}
+
+#[test]
+fn test_missing_required_field() {
+    define_dictionary!(
+        Heartbeat,
+        Logon,
+        TestRequest,
+    );
+
+    //Connect and logon.
+    let (mut test_server,mut client,connection) = TestStream::setup_test_server_and_logon(build_dictionary());
+
+    //Messages sent with a message version are checked right away.
+    let message = new_fixt_message!(TestRequest);
+    match client.send_message_box_with_message_version(connection,MessageVersion::FIX50SP2,Box::new(message)) {
+        Err(SendMessageError::MissingRequiredField(missing_required_field)) => {
+            assert_eq!(missing_required_field.tag,FieldTag(112));
+            assert_eq!(missing_required_field.name,"TestReqID");
+        },
+        _ => assert!(false),
+    }
+
+    //Otherwise, they are checked against the connection's default message version when they're
+    //serialized and dropped.
+    let message = new_fixt_message!(TestRequest);
+    client.send_message(connection,message).unwrap();
+    engine_poll_event!(client,EngineEvent::MessageNotSent(msg_connection,message,error) => {
+        assert_eq!(msg_connection,connection);
+        assert!(message.as_any().is::<TestRequest>());
+        assert!(if let SendMessageError::MissingRequiredField(ref missing_required_field) = error { missing_required_field.tag == FieldTag(112) } else { false });
+    });
+
+    //Dropped messages don't use up a MsgSeqNum.
+    let mut message = new_fixt_message!(TestRequest);
+    message.test_req_id = b"1".to_vec();
+    client.send_message(connection,message).unwrap();
+    let message = test_server.recv_message::<TestRequest>();
+    assert_eq!(message.msg_seq_num,2);
+}
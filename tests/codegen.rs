@@ -0,0 +1,136 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate fix_rs;
+
+use fix_rs::codegen::{Codegen,range_mask,snake_case,versions_for_data_dictionary,versions_to_range};
+use fix_rs::codegen::sbe::SBESchemaError;
+use fix_rs::data_dictionary::DataDictionary;
+use fix_rs::field_tag::FieldTag;
+
+//FIX42.xml is a QuickFIX data dictionary and FIX44.xml is a FIX Orchestra repository. NoOrders
+//holds different fields in NewOrderList and ListStatus so it must become a context dependent
+//group.
+const FIX42_XML: &'static str = include_str!("codegen/FIX42.xml");
+const FIX44_XML: &'static str = include_str!("codegen/FIX44.xml");
+const SBE_SCHEMA_XML: &'static str = include_str!("codegen/venue.xml");
+
+fn new_codegen() -> Codegen {
+    let mut codegen = Codegen::new();
+    for &(source,document) in &[("FIX42.xml",FIX42_XML),("FIX44.xml",FIX44_XML)] {
+        let data_dictionary = DataDictionary::from_xml(document).unwrap();
+        let versions = versions_for_data_dictionary(&data_dictionary).unwrap();
+        codegen.add_data_dictionary(source.to_string(),&data_dictionary,versions);
+    }
+
+    codegen
+}
+
+#[test]
+fn snake_case_test() {
+    assert_eq!(snake_case("ClOrdID"),"cl_ord_id");
+    assert_eq!(snake_case("SecurityIDSource"),"security_id_source");
+    assert_eq!(snake_case("NoPartyIDs"),"no_party_ids");
+    assert_eq!(snake_case("MDEntryPx"),"md_entry_px");
+    assert_eq!(snake_case("NoMDEntries"),"no_md_entries");
+    assert_eq!(snake_case("Price2"),"price2");
+    assert_eq!(snake_case("Type"),"type_");
+}
+
+#[test]
+fn versions_test() {
+    //Bit N is MessageVersion::all()[N], ie. 0x04 is FIX42 and 0x10 is FIX44.
+    assert_eq!(versions_to_range(0x01),"FIX40");
+    assert_eq!(versions_to_range(0x14),"FIX42..FIX44");
+    assert_eq!(versions_to_range(0xFF),"FIX40..");
+    assert_eq!(versions_to_range(0xE0),"FIX50..");
+    assert_eq!(versions_to_range(0x80),"FIX50SP2..");
+
+    assert_eq!(range_mask(0x14),0x1C);
+    assert_eq!(range_mask(0x81),0xFF);
+    assert_eq!(range_mask(0x20),0x20);
+
+    assert_eq!(versions_for_data_dictionary(&DataDictionary::from_xml(FIX42_XML).unwrap()),Some(0x04));
+    assert_eq!(versions_for_data_dictionary(&DataDictionary::from_xml(FIX44_XML).unwrap()),Some(0x10));
+}
+
+#[test]
+fn orchestra_test() {
+    let data_dictionary = DataDictionary::from_xml(FIX44_XML).unwrap();
+    assert_eq!(data_dictionary.begin_string(),b"FIX.4.4");
+
+    //Code sets become the values of the field using them.
+    let side = data_dictionary.field_by_name("Side").unwrap();
+    assert_eq!(side.field_type,"CHAR");
+    assert_eq!(side.values.len(),3);
+    assert_eq!(side.values[2].value,b"5".to_vec());
+    assert_eq!(side.values[2].description,"SellShort");
+
+    //The standard header and trailer are not part of each message.
+    let header_tags: Vec<FieldTag> = data_dictionary.header().iter().map(|field| field.tag).collect();
+    assert_eq!(header_tags,vec![FieldTag(8),FieldTag(9),FieldTag(35)]);
+    assert!(data_dictionary.message(b"0").unwrap().is_admin);
+    let new_order_list = data_dictionary.message(b"E").unwrap();
+    assert!(!new_order_list.is_admin);
+    let tags: Vec<FieldTag> = new_order_list.fields.iter().map(|field| field.tag).collect();
+    assert_eq!(tags,vec![FieldTag(66),FieldTag(390),FieldTag(73)]);
+
+    //Components inside groups are flattened and are only required when the component is.
+    let group = new_order_list.fields[2].group.as_ref().unwrap();
+    let group_tags: Vec<FieldTag> = group.iter().map(|field| field.tag).collect();
+    assert_eq!(group_tags,vec![FieldTag(11),FieldTag(54),FieldTag(453)]);
+    assert!(new_order_list.fields[2].required);
+    assert!(!group[2].required);
+    assert!(group[2].group.as_ref().unwrap()[0].required);
+}
+
+#[test]
+fn generate_test() {
+    let codegen = new_codegen();
+    assert_eq!(codegen.generate_field_types(),include_str!("codegen/field_types.golden"));
+    assert_eq!(codegen.generate_fields(),include_str!("codegen/fields.golden"));
+    assert_eq!(codegen.generate_messages(),include_str!("codegen/messages.golden"));
+}
+
+#[test]
+fn generate_sbe_test() {
+    //Blocks use the same group types as the generated messages and shared groups are only
+    //defined once.
+    let codegen = new_codegen();
+    assert_eq!(codegen.generate_sbe("venue.xml",SBE_SCHEMA_XML).unwrap(),include_str!("codegen/sbe.golden"));
+
+    //Layouts that SBECodec can't encode are rejected.
+    let schema = SBE_SCHEMA_XML.replace("byteOrder=\"littleEndian\"","byteOrder=\"bigEndian\"");
+    match codegen.generate_sbe("venue.xml",&schema[..]) {
+        Err(SBESchemaError::UnsupportedEncoding(name)) => assert_eq!(name,"VenueSchema"),
+        result => panic!("Unexpected result: {:?}",result),
+    }
+
+    let schema = SBE_SCHEMA_XML.replace("presence=\"constant\">-2","presence=\"optional\">-2");
+    match codegen.generate_sbe("venue.xml",&schema[..]) {
+        Err(SBESchemaError::UnsupportedType(name)) => assert_eq!(name,"qtyEncoding"),
+        result => panic!("Unexpected result: {:?}",result),
+    }
+
+    let schema = SBE_SCHEMA_XML.replace("offset=\"16\"","offset=\"20\"");
+    match codegen.generate_sbe("venue.xml",&schema[..]) {
+        Err(SBESchemaError::UnsupportedOffset(name)) => assert_eq!(name,"BidID"),
+        result => panic!("Unexpected result: {:?}",result),
+    }
+
+    let party_id_source = "<field name=\"PartyIDSource\" id=\"447\" type=\"char\"/>";
+    let index = SBE_SCHEMA_XML.rfind(party_id_source).unwrap();
+    let schema = format!("{}<field name=\"PartyIDSource\" id=\"447\" type=\"int8\"/>{}",&SBE_SCHEMA_XML[..index],&SBE_SCHEMA_XML[index + party_id_source.len()..]);
+    match codegen.generate_sbe("venue.xml",&schema[..]) {
+        Err(SBESchemaError::ConflictingGroup(name)) => assert_eq!(name,"PartyIDsGrp"),
+        result => panic!("Unexpected result: {:?}",result),
+    }
+}
@@ -279,7 +279,7 @@ impl TestStream {
         //Logon.
         let mut logon_message = new_logon_message();
         logon_message.default_appl_ver_id = message_version;
-        client.send_message_box_with_message_version(connection,fix_version.max_message_version(),Box::new(logon_message));
+        client.send_message_box_with_message_version(connection,fix_version.max_message_version(),Box::new(logon_message)).unwrap();
         let message = test_server.recv_message::<Logon>();
         assert_eq!(message.msg_seq_num,1);
 
@@ -304,11 +304,18 @@ impl TestStream {
     }
 
     pub fn setup_test_client_with_ver(fix_version: FIXVersion,message_version: MessageVersion,message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>>) -> (TestStream,Engine,Listener,Connection) {
+        Self::setup_test_client_with_listener_setup(fix_version,message_version,message_dictionary,|_,_| {})
+    }
+
+    pub fn setup_test_client_with_listener_setup<F: FnOnce(&mut Engine,Listener)>(fix_version: FIXVersion,message_version: MessageVersion,message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>>,setup_listener: F) -> (TestStream,Engine,Listener,Connection) {
         //Setup client and listener.
         let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127,0,0,1),SOCKET_PORT.fetch_add(1,Ordering::SeqCst) as u16));
         let mut client = Engine::new(message_dictionary.clone(),MAX_MESSAGE_SIZE).unwrap();
         let listener = client.add_listener(SERVER_SENDER_COMP_ID,&addr).unwrap().unwrap();
 
+        //Let the test configure the listener before anything connects to it.
+        setup_listener(&mut client,listener);
+
         //Setup a client socket and connect to server.
         let stream = TcpStream::connect(&addr).unwrap();
 
@@ -0,0 +1,240 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate chrono;
+extern crate fix_rs;
+
+use chrono::offset::utc::UTC;
+use chrono::TimeZone;
+use std::collections::HashMap;
+
+use fix_rs::dictionary::messages::NewOrderSingle;
+use fix_rs::fast::{FASTDecoder,FASTError,FASTTemplates};
+use fix_rs::field_tag::FieldTag;
+use fix_rs::fixt::message::{BuildFIXTMessage,FIXTMessage,FIXTMessageBuildable};
+use fix_rs::generic_message::{GenericField,GenericMessage};
+use fix_rs::message::{Message,MessageDetails};
+
+#[test]
+fn fast_test() {
+    let templates = FASTTemplates::from_xml(r#"<?xml version="1.0" encoding="UTF-8"?>
+        <templates xmlns="http://www.fixprotocol.org/ns/fast/td/1.1">
+            <template name="MDIncRefresh" id="1">
+                <string name="MessageType" id="35"><constant value="X"/></string>
+                <uInt32 name="MsgSeqNum" id="34"><increment/></uInt32>
+                <sequence name="MDEntries">
+                    <length name="NoMDEntries" id="268"/>
+                    <uInt32 name="MDUpdateAction" id="279"><copy value="0"/></uInt32>
+                    <string name="Symbol" id="55"><copy/></string>
+                    <decimal name="MDEntryPx" id="270">
+                        <exponent><copy value="-2"/></exponent>
+                        <mantissa><delta/></mantissa>
+                    </decimal>
+                    <decimal name="MDEntrySize" id="271" presence="optional"><delta/></decimal>
+                    <string name="QuoteCondition" id="276" presence="optional"><default/></string>
+                </sequence>
+            </template>
+            <template name="NewOrderSingle" id="2">
+                <string name="MessageType" id="35"><constant value="D"/></string>
+                <uInt32 name="MsgSeqNum" id="34"><increment/></uInt32>
+                <string name="SendingTime" id="52"><tail value="20090107-18:15:16.000"/></string>
+                <string name="ClOrdID" id="11"/>
+                <sequence name="Allocs" presence="optional">
+                    <length name="NoAllocs" id="78"/>
+                    <string name="AllocAccount" id="79"/>
+                    <decimal name="AllocQty" id="80"/>
+                </sequence>
+                <string name="Symbol" id="55"><copy/></string>
+                <string name="Side" id="54"><default value="1"/></string>
+                <decimal name="OrderQty" id="38"/>
+                <string name="OrdType" id="40"><constant value="2"/></string>
+                <decimal name="Price" id="44"/>
+                <uInt32 name="UserDefined" id="5000" presence="optional"/>
+            </template>
+        </templates>"#).unwrap();
+
+    let mut message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> = HashMap::new();
+    message_dictionary.insert(<NewOrderSingle as MessageDetails>::msg_type(),<NewOrderSingle as Default>::default().builder());
+    let mut decoder = FASTDecoder::new(templates,message_dictionary);
+
+    //Messages without a static type are decoded into a GenericMessage. The first message includes
+    //the template ID while the second relies on the previous one.
+    let bytes = b"\xE0\x81\xE4\x82\xA8\x41\x42\xC3\x08\x9A\x81\x03\xF4\xC1\xC0\x81\xFB\x80\x80\x81\x80\x8A\x81\x00\xE4";
+    let (message,bytes_read) = decoder.decode(bytes).unwrap();
+    assert_eq!(bytes_read,18);
+    assert_eq!(message.msg_type(),b"X");
+    let message = message.as_any().downcast_ref::<GenericMessage>().unwrap();
+    assert_eq!(message.msg_seq_num,100);
+    assert_eq!(*message.get_groups(FieldTag(268)).unwrap(),vec![
+        vec![
+            GenericField::new(FieldTag(279),"0"),
+            GenericField::new(FieldTag(55),"ABC"),
+            GenericField::new(FieldTag(270),"10.50"),
+            GenericField::new(FieldTag(271),"500"),
+            GenericField::new(FieldTag(276),"A"),
+        ],
+        vec![
+            GenericField::new(FieldTag(279),"1"),
+            GenericField::new(FieldTag(55),"ABC"),
+            GenericField::new(FieldTag(270),"10.45"),
+        ],
+    ]);
+
+    let (message,bytes_read) = decoder.decode(&bytes[18..]).unwrap();
+    assert_eq!(bytes_read,7);
+    let message = message.as_any().downcast_ref::<GenericMessage>().unwrap();
+    assert_eq!(message.msg_seq_num,101);
+    assert_eq!(*message.get_groups(FieldTag(268)).unwrap(),vec![
+        vec![
+            GenericField::new(FieldTag(279),"1"),
+            GenericField::new(FieldTag(55),"ABC"),
+            GenericField::new(FieldTag(270),"10.55"),
+            GenericField::new(FieldTag(271),"600"),
+        ],
+    ]);
+
+    //Messages in the dictionary are decoded into their static type. MsgSeqNum and Symbol share
+    //global dictionary entries with the previous template. Unknown fields become extra fields.
+    let bytes = b"\xD0\x82\x37\x2E\x31\x32\xB3\x31\x32\xB3\x83\x41\xB1\x80\xBC\x41\xB2\x80\xA8\x80\x00\xE4\xFF\x00\xE9\x88";
+
+    //An incomplete message leaves the dictionaries untouched so it can be decoded again once the
+    //rest of the message arrives.
+    match decoder.decode(&bytes[..bytes.len() - 1]) {
+        Err(FASTError::BufferTooSmall) => {},
+        _ => assert!(false),
+    }
+
+    let (message,bytes_read) = decoder.decode(bytes).unwrap();
+    assert_eq!(bytes_read,bytes.len());
+    let message = message.as_any().downcast_ref::<NewOrderSingle>().unwrap();
+    assert_eq!(message.msg_seq_num,102);
+    assert_eq!(message.sending_time,UTC.ymd(2009,1,7).and_hms_milli(18,15,17,123));
+    assert_eq!(message.cl_ord_id,b"123".to_vec());
+    assert_eq!(message.allocs.len(),2);
+    assert_eq!(message.allocs[0].alloc_account,b"A1".to_vec());
+    assert_eq!(message.allocs[0].alloc_qty,b"60".to_vec());
+    assert_eq!(message.allocs[1].alloc_account,b"A2".to_vec());
+    assert_eq!(message.allocs[1].alloc_qty,b"40".to_vec());
+    assert_eq!(message.symbol,b"ABC".to_vec());
+    assert_eq!(message.order_qty,b"100".to_vec());
+    assert_eq!(message.price,b"10.5".to_vec());
+    assert_eq!(message.extra_fields,vec![(FieldTag(5000),b"7".to_vec())]);
+
+    //Resetting the dictionaries forgets the previous template ID.
+    decoder.reset();
+    match decoder.decode(b"\x80\x81\x80\x8A\x81\x00\xE4") {
+        Err(FASTError::MissingTemplateID) => {},
+        _ => assert!(false),
+    }
+
+    match decoder.decode(b"\xC0\x89") {
+        Err(FASTError::TemplateIDUnknown(9)) => {},
+        _ => assert!(false),
+    }
+
+    //Template files are checked for problems up front.
+    match FASTTemplates::from_xml(r#"<templates><template name="A" id="1"><templateRef name="B"/></template></templates>"#) {
+        Err(FASTError::UnknownTemplate(name)) => assert_eq!(name,"B"),
+        _ => assert!(false),
+    }
+
+    match FASTTemplates::from_xml(r#"<templates><template name="A" id="1"><templateRef name="A"/></template></templates>"#) {
+        Err(FASTError::RecursiveTemplate(name)) => assert_eq!(name,"A"),
+        _ => assert!(false),
+    }
+
+    match FASTTemplates::from_xml(r#"<templates><template name="A" id="1"><uInt32 name="B"><constant/></uInt32></template></templates>"#) {
+        Err(FASTError::MissingAttribute(element,attribute)) => {
+            assert_eq!(element,"constant");
+            assert_eq!(attribute,"value");
+        },
+        _ => assert!(false),
+    }
+
+    //Sequence lengths and dynamic template references come from the wire so they are limited.
+    let templates = FASTTemplates::from_xml(r#"
+        <templates>
+            <template name="Constants" id="3">
+                <sequence name="Items">
+                    <length name="NoItems" id="9000"/>
+                    <string name="Item" id="9001"><constant value="A"/></string>
+                </sequence>
+            </template>
+            <template name="Nested" id="4"><templateRef/></template>
+        </templates>"#).unwrap();
+    let mut decoder = FASTDecoder::new(templates,HashMap::new());
+    match decoder.decode(b"\xC0\x83\x27\x88") {
+        Err(FASTError::SequenceTooLong(name,len)) => {
+            assert_eq!(name,"NoItems");
+            assert_eq!(len,5000);
+        },
+        _ => assert!(false),
+    }
+
+    decoder.set_max_template_depth(2);
+    match decoder.decode(b"\xC0\x84\xC0\x84\xC0\x84\xC0\x84") {
+        Err(FASTError::TemplateDepthExceeded) => {},
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn fast_invalid_value_test() {
+    let templates = FASTTemplates::from_xml(r#"
+        <templates>
+            <template name="Values" id="5">
+                <uInt32 name="Count" id="9002"/>
+                <uInt64 name="Total" id="9003"/>
+                <string name="Text" id="58"><delta/></string>
+            </template>
+        </templates>"#).unwrap();
+    let mut decoder = FASTDecoder::new(templates,HashMap::new());
+
+    //Stop bit encoded integers that don't fit their type are rejected.
+    match decoder.decode_fields(b"\xC0\x85\x10\x00\x00\x00\x80\x80\x80\x80") {
+        Err(FASTError::IntegerOverflow(name)) => assert_eq!(name,"Count"),
+        _ => assert!(false),
+    }
+
+    match decoder.decode_fields(b"\xC0\x85\x80\x7F\x7F\x7F\x7F\x7F\x7F\x7F\x7F\x7F\x7F\xFF\x80\x80") {
+        Err(FASTError::IntegerOverflow(name)) => assert_eq!(name,"Total"),
+        _ => assert!(false),
+    }
+
+    //A value that never sets the stop bit runs off the end of the buffer.
+    match decoder.decode_fields(b"\xC0\x85\x01\x02\x03") {
+        Err(FASTError::BufferTooSmall) => {},
+        _ => assert!(false),
+    }
+
+    //Deltas are applied to the previous value. A negative subtraction length works from the front.
+    let (_,fields,len) = decoder.decode_fields(b"\xC0\x85\x80\x80\x80\x41\xC2").unwrap();
+    assert_eq!(len,7);
+    assert_eq!(fields.last().unwrap(),&GenericField::new(FieldTag(58),"AB"));
+
+    let (_,fields,_) = decoder.decode_fields(b"\xC0\x85\x80\x80\xFE\xDA").unwrap();
+    assert_eq!(fields.last().unwrap(),&GenericField::new(FieldTag(58),"ZB"));
+
+    //Subtraction lengths longer than the previous value are rejected from either end and the
+    //previous value is kept.
+    match decoder.decode_fields(b"\xC0\x85\x80\x80\x83\x80") {
+        Err(FASTError::InvalidValue(name)) => assert_eq!(name,"Text"),
+        _ => assert!(false),
+    }
+
+    match decoder.decode_fields(b"\xC0\x85\x80\x80\xFC\x80") {
+        Err(FASTError::InvalidValue(name)) => assert_eq!(name,"Text"),
+        _ => assert!(false),
+    }
+
+    let (_,fields,_) = decoder.decode_fields(b"\xC0\x85\x80\x80\x82\x80").unwrap();
+    assert_eq!(fields.last().unwrap(),&GenericField::new(FieldTag(58),""));
+}
@@ -0,0 +1,157 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate fix_rs;
+
+use std::any::Any;
+use std::collections::HashMap;
+
+use fix_rs::byte_buffer::ByteBuffer;
+use fix_rs::dictionary::messages::{Logon,NewOrderSingle};
+use fix_rs::fix::Parser;
+use fix_rs::fix_version::FIXVersion;
+use fix_rs::fixml::{FIXMLError,from_fixml,to_fixml};
+use fix_rs::fixt::message::{BuildFIXTMessage,FIXTMessage,FIXTMessageBuildable};
+use fix_rs::message::{Message,MessageDetails};
+use fix_rs::message_version::MessageVersion;
+
+const MAX_MESSAGE_SIZE: u64 = 4096;
+
+//FIX 4.4 NewOrderSingle with a PreAllocGrp repeating group.
+const NEW_ORDER_SINGLE_MESSAGE: &'static [u8] = b"8=FIX.4.4\x019=179\x0135=D\x0149=CLIENT\x0156=SERVER\x0134=2\x0152=20090107-18:15:16.000\x0111=123\x011=ACCT\x0178=2\x0179=A1\x0180=60\x0179=A2\x0180=40\x0121=1\x0155=TEST\x0148=459200101\x0122=1\x0154=1\x0160=20090107-18:15:16.000\x0138=100\x0140=2\x0144=10.5\x0110=192\x01";
+
+//FIX 4.2 Logon with a NoMsgTypes repeating group.
+const LOGON_MESSAGE: &'static [u8] = b"8=FIX.4.2\x019=93\x0135=A\x0149=SERVER\x0156=CLIENT\x0134=1\x0152=20090107-18:15:16\x0198=0\x01108=30\x01384=2\x01372=A\x01385=S\x01372=0\x01385=R\x0110=102\x01";
+
+fn parse_message<T: FIXTMessage + FIXTMessageBuildable + MessageDetails + Default + Any + Clone + Send>(message: &[u8]) -> T {
+    let mut message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> = HashMap::new();
+    message_dictionary.insert(<T as MessageDetails>::msg_type(),<T as Default>::default().builder());
+    let mut parser = Parser::new(message_dictionary,MAX_MESSAGE_SIZE);
+
+    let (_,result) = parser.parse(message);
+    assert!(result.is_ok());
+    assert_eq!(parser.messages.len(),1);
+    parser.messages.remove(0).as_any().downcast_ref::<T>().unwrap().clone()
+}
+
+fn new_order_single_dictionary() -> HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> {
+    let mut message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> = HashMap::new();
+    message_dictionary.insert(<NewOrderSingle as MessageDetails>::msg_type(),<NewOrderSingle as Default>::default().builder());
+    message_dictionary
+}
+
+#[test]
+fn fixml_test() {
+    let message = parse_message::<NewOrderSingle>(NEW_ORDER_SINGLE_MESSAGE);
+    let message_dictionary = new_order_single_dictionary();
+
+    //Header and component blocks become child elements and each repeating group becomes its own
+    //element.
+    let fixml = to_fixml(&message,FIXVersion::FIX_4_4,MessageVersion::FIX44);
+    assert_eq!(fixml,concat!(
+        r#"<FIXML v="4.4"><Order ID="123" Acct="ACCT" HandlInst="1" Side="1" TxnTm="20090107-18:15:16.000" Typ="2" Px="10.5">"#,
+        r#"<Hdr SID="CLIENT" TID="SERVER" SeqNum="2" Snt="20090107-18:15:16.000"/>"#,
+        r#"<Alloc Acct="A1" Qty="60"/><Alloc Acct="A2" Qty="40"/>"#,
+        r#"<Instrmt Sym="TEST" ID="459200101" Src="1"/>"#,
+        r#"<OrdQty Qty="100"/>"#,
+        r#"</Order></FIXML>"#
+    ));
+
+    //Decoding gives back the same message and serializes to the same tag=value message.
+    let decoded_message = from_fixml(&fixml,&message_dictionary).unwrap();
+    let decoded_message = decoded_message.as_any().downcast_ref::<NewOrderSingle>().unwrap();
+    assert!(*decoded_message == message);
+
+    let mut expected_bytes = ByteBuffer::new();
+    message.read(FIXVersion::FIX_4_4,MessageVersion::FIX44,&mut expected_bytes);
+    let mut decoded_bytes = ByteBuffer::new();
+    decoded_message.read(FIXVersion::FIX_4_4,MessageVersion::FIX44,&mut decoded_bytes);
+    assert_eq!(decoded_bytes.bytes(),expected_bytes.bytes());
+
+    //XML declarations, comments, whitespace, single quotes, and entities are accepted.
+    let fixml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <!-- Comment -->
+        <FIXML v="4.4">
+            <Order ID='A&amp;B' Side="2" TxnTm="20090107-18:15:16.000" Typ="1" Acct="&lt;&#x41;&#66;&gt;">
+                <Hdr SID="CLIENT" TID="SERVER" SeqNum="3" Snt="20090107-18:15:16.000"/>
+                <Instrmt Sym="TEST" ID="459200101" Src="1"></Instrmt>
+                <OrdQty Qty="100"/>
+            </Order>
+        </FIXML>"#;
+    let decoded_message = from_fixml(fixml,&message_dictionary).unwrap();
+    let decoded_message = decoded_message.as_any().downcast_ref::<NewOrderSingle>().unwrap();
+    assert_eq!(decoded_message.cl_ord_id,b"A&B".to_vec());
+    assert_eq!(decoded_message.account,b"<AB>".to_vec());
+    assert_eq!(decoded_message.msg_seq_num,3);
+
+    //Fields without an abbreviation fall back to their standard FIX name.
+    let message = parse_message::<Logon>(LOGON_MESSAGE);
+    let fixml = to_fixml(&message,FIXVersion::FIX_4_2,MessageVersion::FIX42);
+    assert!(fixml.contains(r#"<NoMsgTypes RefMsgTyp="A" MsgDirection="S"/><NoMsgTypes RefMsgTyp="0" MsgDirection="R"/>"#));
+}
+
+#[test]
+fn fixml_unknown_abbreviation_test() {
+    let message_dictionary = new_order_single_dictionary();
+
+    //Unknown attributes, elements, and messages are rejected.
+    match from_fixml(r#"<FIXML><Order Bogus="1"/></FIXML>"#,&message_dictionary) {
+        Err(FIXMLError::UnknownField(name)) => assert_eq!(name,"Bogus"),
+        _ => assert!(false),
+    }
+
+    match from_fixml(r#"<FIXML><Order><Bogus/></Order></FIXML>"#,&message_dictionary) {
+        Err(FIXMLError::UnknownField(name)) => assert_eq!(name,"Bogus"),
+        _ => assert!(false),
+    }
+
+    match from_fixml(r#"<FIXML><ExecRpt/></FIXML>"#,&message_dictionary) {
+        Err(FIXMLError::MessageUnknown(name)) => assert_eq!(name,"ExecRpt"),
+        _ => assert!(false),
+    }
+
+    //Abbreviations only apply within the element they belong to.
+    match from_fixml(r#"<FIXML><Order Sym="TEST"/></FIXML>"#,&message_dictionary) {
+        Err(FIXMLError::UnknownField(name)) => assert_eq!(name,"Sym"),
+        _ => assert!(false),
+    }
+
+    match from_fixml(r#"<FIXML><Order SID="CLIENT"/></FIXML>"#,&message_dictionary) {
+        Err(FIXMLError::UnknownField(name)) => assert_eq!(name,"SID"),
+        _ => assert!(false),
+    }
+
+    match from_fixml(r#"<FIXML><Order><Instrmt Bogus="1"/></Order></FIXML>"#,&message_dictionary) {
+        Err(FIXMLError::UnknownField(name)) => assert_eq!(name,"Bogus"),
+        _ => assert!(false),
+    }
+
+    match from_fixml(r#"<FIXML><Order><Instrmt><Bogus/></Instrmt></Order></FIXML>"#,&message_dictionary) {
+        Err(FIXMLError::UnknownField(name)) => assert_eq!(name,"Bogus"),
+        _ => assert!(false),
+    }
+
+    match from_fixml(r#"<FIXML><Order><Alloc Acct="A1" Bogus="1"/></Order></FIXML>"#,&message_dictionary) {
+        Err(FIXMLError::UnknownField(name)) => assert_eq!(name,"Bogus"),
+        _ => assert!(false),
+    }
+
+    //Malformed documents are rejected too.
+    match from_fixml(r#"<FIXML><Order></Ord></FIXML>"#,&message_dictionary) {
+        Err(FIXMLError::Syntax(..)) => {},
+        _ => assert!(false),
+    }
+
+    match from_fixml(r#"<FIXML></FIXML>"#,&message_dictionary) {
+        Err(FIXMLError::MissingMessage) => {},
+        _ => assert!(false),
+    }
+}
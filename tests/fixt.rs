@@ -35,7 +35,7 @@ mod common;
 use common::{CLIENT_SENDER_COMP_ID,CLIENT_TARGET_COMP_ID,SERVER_SENDER_COMP_ID,SERVER_TARGET_COMP_ID,TestStream,new_logon_message,recv_bytes_with_timeout,send_message};
 use fix_rs::dictionary::standard_msg_types;
 use fix_rs::dictionary::field_types::generic::{CharFieldType,NoneFieldType,StringFieldType};
-use fix_rs::dictionary::field_types::other::{BusinessRejectReason,OrdType,SecurityIDSource,SessionRejectReason,Side};
+use fix_rs::dictionary::field_types::other::{BusinessRejectReason,OrdType,SecurityIDSource,SessionRejectReason,SessionStatus,Side};
 use fix_rs::dictionary::fields::{TestReqID,HeartBtInt,EndSeqNo,SideField,OrigSendingTime,NoHops,HopCompID};
 use fix_rs::dictionary::messages::{Logon,Logout,NewOrderSingle,ResendRequest,TestRequest,Heartbeat,SequenceReset,Reject,BusinessMessageReject};
 use fix_rs::field::Field;
@@ -68,7 +68,7 @@ fn test_1B() {
         let (mut test_server,mut client,connection) = TestStream::setup_test_server(build_dictionary());
 
         let logon_message = new_logon_message();
-        client.send_message(connection,logon_message.clone());
+        client.send_message(connection,logon_message.clone()).unwrap();
 
         let message = test_server.recv_message::<Logon>();
         server_response_func(&mut test_server,message.clone());
@@ -394,7 +394,7 @@ fn test_2B() {
 
         let mut message = new_fixt_message!(TestRequest);
         message.test_req_id = b"1".to_vec();
-        client.send_message(connection,message);
+        client.send_message(connection,message).unwrap();
         let message = test_server.recv_message::<TestRequest>();
         assert_eq!(message.msg_seq_num,2);
 
@@ -406,7 +406,7 @@ fn test_2B() {
         assert_eq!(message.msg_seq_num,2);
 
         let message = new_fixt_message!(Logout);
-        client.send_message(connection,message);
+        client.send_message(connection,message).unwrap();
         let message = test_server.recv_message::<Logout>();
         assert_eq!(message.msg_seq_num,3);
 
@@ -478,18 +478,26 @@ fn test_2B() {
         let bytes_written = test_server.stream.write(garbled_test_request).unwrap();
         assert_eq!(bytes_written,garbled_test_request.len());
 
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,_) => {
+        //Messages with a bad BodyLength or CheckSum are ignored without using up a MsgSeqNum. The
+        //rest are rejected and do.
+        let msg_seq_num = engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error,_,_,_) => {
             assert_eq!(connection,gm_connection);
+            match parse_error {
+                ParseError::BodyLengthNotNumber |
+                ParseError::ChecksumDoesNotMatch(_,_) |
+                ParseError::ChecksumWrongFormat => 2,
+                _ => 3,
+            }
         });
 
         //Send valid message.
         let mut message = new_fixt_message!(TestRequest);
-        message.msg_seq_num = 3;
+        message.msg_seq_num = msg_seq_num;
         message.test_req_id = b"1".to_vec();
         test_server.send_message(message);
 
         let message = engine_poll_message!(client,connection,TestRequest);
-        assert_eq!(message.msg_seq_num,3);
+        assert_eq!(message.msg_seq_num,msg_seq_num);
     }
 
     //e. Logon, send message with PossDupFlag set to Y, MsgSeqNum lower than expected, and:
@@ -609,7 +617,7 @@ fn test_2B() {
         assert_eq!(message.session_reject_reason.unwrap(),SessionRejectReason::RequiredTagMissing);
         assert_eq!(message.text,b"Conditionally required tag missing".to_vec());
 
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
 
             match parse_error {
@@ -782,7 +790,7 @@ fn test_2B() {
         assert_eq!(message.text,b"Invalid MsgType".to_vec());
 
         //Confirm Engine issued warning.
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::MsgTypeUnknown(_) = parse_error { true } else { false });
         });
@@ -833,7 +841,7 @@ fn test_2B() {
         assert_eq!(message.text,b"Unsupported Message Type".to_vec());
 
         //Confirm Engine issued warning.
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::MsgTypeUnknown(_) = parse_error { true } else { false });
         });
@@ -894,7 +902,7 @@ fn test_4B() {
         //Send message to reset Engine's output heartbeat.
         let mut message = new_fixt_message!(TestRequest);
         message.test_req_id = b"1".to_vec();
-        client.send_message(connection,message);
+        client.send_message(connection,message).unwrap();
         let _ = test_server.recv_message::<TestRequest>();
 
         //Sleep a little bit and make sure clienent sends a TestRequest because it didn't receive
@@ -1434,7 +1442,7 @@ fn test_12B() {
 
         let mut message = new_fixt_message!(Logout);
         message.msg_seq_num = 2;
-        message.session_status = b"4".to_vec(); //Session logout complete.
+        message.session_status = Some(SessionStatus::SessionLogoutComplete);
         test_server.send_message(message);
 
         //Give client thread a chance to disconnect.
@@ -1625,7 +1633,7 @@ fn test_14B() {
         test_server.send_message(message);
 
         //Make sure client issued an error.
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error,_,_,_) => {
             assert_eq!(gm_connection,connection);
             match parse_error {
                 ParseError::UnknownTag(tag) => assert_eq!(tag,UndefinedField::tag()),
@@ -1643,7 +1651,7 @@ fn test_14B() {
         test_server.send_message(message);
 
         //Make sure client issued an error.
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error,_,_,_) => {
             assert_eq!(gm_connection,connection);
             match parse_error {
                 ParseError::MissingRequiredTag(tag,message) => {
@@ -1666,7 +1674,7 @@ fn test_14B() {
         test_server.send_message(message);
 
         //Make sure client issued an error.
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error,_,_,_) => {
             assert_eq!(gm_connection,connection);
             match parse_error {
                 ParseError::UnexpectedTag(tag) => assert_eq!(tag,HeartBtInt::tag()),
@@ -1684,7 +1692,7 @@ fn test_14B() {
         test_server.send_message(message);
 
         //Make sure client issued an error.
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error,_,_,_) => {
             assert_eq!(gm_connection,connection);
             match parse_error {
                 ParseError::NoValueAfterTag(tag) => assert_eq!(tag,TestReqIDEmpty::tag()),
@@ -1725,7 +1733,7 @@ fn test_14B() {
             test_server.send_message(message);
 
             //Make sure client issued an error.
-            engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error) => {
+            engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error,_,_,_) => {
                 assert_eq!(gm_connection,connection);
                 match parse_error {
                     ParseError::OutOfRangeTag(tag) => assert_eq!(tag,SideField::tag()),
@@ -1746,7 +1754,7 @@ fn test_14B() {
         test_server.send_message(message);
 
         //Make sure client issued an error.
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error,_,_,_) => {
             assert_eq!(gm_connection,connection);
             match parse_error {
                 ParseError::WrongFormatTag(tag) => assert_eq!(tag,BeginSeqNoString::tag()),
@@ -1773,7 +1781,7 @@ fn test_14B() {
             assert_eq!(bytes_written,message_bytes.len());
 
             //Make sure client issued an error.
-            engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error) => {
+            engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error,_,_,_) => {
                 assert_eq!(gm_connection,connection);
                 match parse_error {
                     ParseError::BeginStrNotFirstTag => {},
@@ -1797,7 +1805,7 @@ fn test_14B() {
         test_server.send_message(message);
 
         //Make sure client issued an error.
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error,_,_,_) => {
             assert_eq!(gm_connection,connection);
             match parse_error {
                 ParseError::DuplicateTag(tag) => assert_eq!(tag,TestReqID::tag()),
@@ -1820,7 +1828,7 @@ fn test_14B() {
                 assert_eq!(bytes_written,message_bytes.len());
 
                 //Make sure client issued an error.
-                engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error) => {
+                engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error,_,_,_) => {
                     assert_eq!(gm_connection,connection);
                     match parse_error {
                         ParseError::NonRepeatingGroupTagInRepeatingGroup(tag) => assert_eq!(tag,ref_tag_id),
@@ -1868,7 +1876,7 @@ fn test_14B() {
         test_server.send_message(message);
 
         //Make sure client issued an error.
-        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error) => {
+        engine_poll_event!(client,EngineEvent::MessageReceivedGarbled(gm_connection,parse_error,_,_,_) => {
             assert_eq!(gm_connection,connection);
             match parse_error {
                 ParseError::MissingConditionallyRequiredTag(tag,_) => assert_eq!(tag,OrigSendingTime::tag()),
@@ -1948,7 +1956,7 @@ fn test_20B() {
     for x in 2..6 {
         let mut message = new_fixt_message!(TestRequest);
         message.test_req_id = x.to_string().as_bytes().to_vec();
-        client.send_message(connection,message);
+        client.send_message(connection,message).unwrap();
 
         let message = test_server.recv_message::<TestRequest>();
         assert_eq!(message.msg_seq_num,x);
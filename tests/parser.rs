@@ -22,25 +22,37 @@ use chrono::offset::utc::UTC;
 use chrono::TimeZone;
 use std::any::Any;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use fix_rs::byte_buffer::ByteBuffer;
-use fix_rs::dictionary::field_types::generic::RepeatingGroupFieldType;
-use fix_rs::dictionary::field_types::other::{EncryptMethod,RateSource,RateSourceType};
-use fix_rs::dictionary::fields::{EncryptMethod as EncryptMethodField,HeartBtInt,MsgSeqNum,SendingTime,SenderCompID,TargetCompID,NoMsgTypeGrp,RawData,RawDataLength,Symbol,TestReqID,Text,OrigSendingTime,ClOrdID,AllocAccount,RateSource as RateSourceField,RateSourceType as RateSourceTypeField,ReferencePage as ReferencePageField};
-use fix_rs::dictionary::messages::Heartbeat;
+use fix_rs::data_dictionary::{DataDictionary,DataDictionaryError};
+use fix_rs::dictionary::field_types::generic::{CharFieldType,RepeatingGroupFieldType};
+use fix_rs::dictionary::field_types::other::{EncryptMethod,RateSource,RateSourceType,SecurityIDSource as SecurityIDSourceValue};
+use fix_rs::dictionary::fields::{Account,AllocQty,HandlInst,NoAllocs,Price,SecurityIDSource,SideField,EncryptMethod as EncryptMethodField,HeartBtInt,MsgSeqNum,SendingTime,SenderCompID,TargetCompID,NoMsgTypeGrp,RawData,RawDataLength,Symbol,TestReqID,Text,OrigSendingTime,ClOrdID,AllocAccount,RateSource as RateSourceField,RateSourceType as RateSourceTypeField,ReferencePage as ReferencePageField};
+use fix_rs::dictionary::messages::{Email,Heartbeat,ListStatus,News,NewOrderList,NewOrderSingle};
+use fix_rs::encoder::{EncodeError,Encoder};
 use fix_rs::field::Field;
 use fix_rs::field_tag::{self,FieldTag};
 use fix_rs::field_type::FieldType;
-use fix_rs::fix::{Parser,ParseError};
+use fix_rs::fix::{FieldDelimiter,Parser,ParseError,ParseLimits,ValidationConfig};
 use fix_rs::fix_version::FIXVersion;
 use fix_rs::fixt;
 use fix_rs::fixt::message::{BuildFIXTMessage,FIXTMessage,FIXTMessageBuildable};
-use fix_rs::message::{self,MessageDetails,REQUIRED,NOT_REQUIRED};
+use fix_rs::json::{JsonError,from_fix_json,to_fix_json};
+use fix_rs::generic_message::{GenericField,GenericMessage};
+use fix_rs::message::{self,InvalidValueAction,InvalidValuePolicy,Message,MessageDetails,REQUIRED,NOT_REQUIRED};
+use fix_rs::message_diff::{FieldDiffKind,diff_messages,write_message_diffs};
 use fix_rs::message_version::{self,MessageVersion};
+use fix_rs::pretty_print::{pretty_print,pretty_print_with_version};
 
 const PARSE_MESSAGE_BY_STREAM: bool = true;
 const MAX_MESSAGE_SIZE: u64 = 4096;
 
+//LogonTest with a NoMsgTypeGrp repeating group.
+const LOGON_TEST_MESSAGE: &'static [u8] = b"8=FIX.4.2\x019=95\x0135=L\x0149=SERVER\x0156=CLIENT\x0134=177\x0152=20090107-18:15:16\x0198=0\x01108=30\x01384=2\x01372=A\x01385=S\x01372=0\x01385=R\x0110=225\x01";
+//FIX 4.4 NewOrderSingle with a PreAllocGrp repeating group.
+const NEW_ORDER_SINGLE_MESSAGE: &'static [u8] = b"8=FIX.4.4\x019=179\x0135=D\x0149=CLIENT\x0156=SERVER\x0134=2\x0152=20090107-18:15:16.000\x0111=123\x011=ACCT\x0178=2\x0179=A1\x0180=60\x0179=A2\x0180=40\x0121=1\x0155=TEST\x0148=459200101\x0122=1\x0154=1\x0160=20090107-18:15:16.000\x0138=100\x0140=2\x0144=10.5\x0110=192\x01";
+
 define_message!(LogonTest: b"L" => {
     REQUIRED, encrypt_method: EncryptMethodField [FIX40..],
     REQUIRED, heart_bt_int: HeartBtInt [FIX40..],
@@ -933,3 +945,1135 @@ fn no_value_after_tag_test() {
     }
 }
 
+
+#[test]
+fn garbled_bytes_test() {
+    let mut message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> = HashMap::new();
+    message_dictionary.insert(<LogonTest as MessageDetails>::msg_type(),<LogonTest as Default>::default().builder());
+
+    //Garbage before the message should not be included in the garbled bytes.
+    let incorrect_checksum_tag_message = b"8=FIX.4.2\x019=65\x0135=L\x0149=SERVER\x0156=CLIENT\x0134=177\x0152=20090107-18:15:16\x0198=0\x01108=30\x0110=000\x01";
+    let mut message = b"garbage".to_vec();
+    message.extend_from_slice(incorrect_checksum_tag_message);
+
+    let mut parser = Parser::new(message_dictionary,MAX_MESSAGE_SIZE);
+    let (bytes_parsed,result) = parser.parse(&message[..]);
+    assert_eq!(bytes_parsed,message.len());
+    match result.err().unwrap() {
+        fix_rs::fix::ParseError::ChecksumDoesNotMatch(_,_) => {},
+        _ => assert!(false),
+    }
+    assert_eq!(parser.garbled_bytes(),&incorrect_checksum_tag_message[..]);
+
+    //Errors in the middle of a message should only include the bytes up to the error.
+    let (_,result) = parser.parse(b"8=FIX.4.2\x019=TST\x0135=L\x01");
+    match result.err().unwrap() {
+        fix_rs::fix::ParseError::BodyLengthNotNumber => {},
+        _ => assert!(false),
+    }
+    assert_eq!(parser.garbled_bytes(),b"8=FIX.4.2\x019=TST\x01");
+}
+
+#[test]
+fn error_context_test() {
+    let mut message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> = HashMap::new();
+    message_dictionary.insert(<LogonTest as MessageDetails>::msg_type(),<LogonTest as Default>::default().builder());
+    let mut parser = Parser::new(message_dictionary,MAX_MESSAGE_SIZE);
+
+    //Bytes consumed by earlier calls count towards the byte offset.
+    let (bytes_parsed,result) = parser.parse(b"garbage");
+    assert_eq!(bytes_parsed,7);
+    assert!(result.is_ok());
+
+    //Error inside of the second repeating group.
+    let message = b"8=FIX.4.2\x019=100\x0135=L\x0134=177\x0198=0\x01108=30\x01384=3\x01372=A\x01385=S\x01372=0\x019999=x\x01";
+    let (_,result) = parser.parse(&message[..]);
+    match result.err().unwrap() {
+        ParseError::NonRepeatingGroupTagInRepeatingGroup(tag) => assert_eq!(tag,FieldTag(9999)),
+        _ => assert!(false),
+    }
+    {
+        let error_context = parser.error_context();
+        assert_eq!(error_context.byte_offset,77);
+        assert_eq!(error_context.field_ordinal,11);
+        assert_eq!(error_context.group_path.len(),1);
+        assert_eq!(error_context.group_path[0].number_of_groups_tag,FieldTag(384));
+        assert_eq!(error_context.group_path[0].index,1);
+        assert_eq!(error_context.msg_type,Some(b"L".to_vec()));
+        assert_eq!(error_context.msg_seq_num,Some(177));
+        assert_eq!(error_context.to_string(),"byte 77, field 11, group NoMsgTypeGrp[1], MsgType L, MsgSeqNum 177");
+    }
+
+    //Nothing past the header is known when the header itself is wrong.
+    let message = b"8=FIX.4.2\x0135=L\x01";
+    let (_,result) = parser.parse(&message[..]);
+    match result.err().unwrap() {
+        ParseError::BodyLengthNotSecondTag => {},
+        _ => assert!(false),
+    }
+    {
+        let error_context = parser.error_context();
+        assert_eq!(error_context.byte_offset,92);
+        assert_eq!(error_context.field_ordinal,2);
+        assert!(error_context.group_path.is_empty());
+        assert!(error_context.msg_type.is_none());
+        assert!(error_context.msg_seq_num.is_none());
+    }
+}
+
+fn parse_message_with_validation_config<T: FIXTMessage + FIXTMessageBuildable + MessageDetails + Default + Any + Clone + PartialEq + Send>(validation_config: ValidationConfig,message: &[u8]) -> Result<T,ParseError> {
+    let mut message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> = HashMap::new();
+    message_dictionary.insert(<T as MessageDetails>::msg_type(),<T as Default>::default().builder());
+
+    let mut parser = Parser::new(message_dictionary,MAX_MESSAGE_SIZE);
+    parser.set_validation_config(validation_config);
+
+    let (bytes_parsed,result) = parser.parse(message);
+    if let Err(err) = result {
+        return Err(err);
+    }
+    assert_eq!(bytes_parsed,message.len());
+    assert_eq!(parser.messages.len(),1);
+
+    Ok(parser.messages.first().unwrap().as_any().downcast_ref::<T>().unwrap().clone())
+}
+
+#[test]
+fn validation_config_test() {
+    //All of the messages below have an incorrect checksum so it must be skipped for each check.
+    let skip_checksum_config = ValidationConfig {
+        skip_checksum_verification: true,
+        ..ValidationConfig::strict()
+    };
+
+    //Skip checksum verification.
+    {
+        let message = b"8=FIX.4.2\x019=65\x0135=L\x0149=SERVER\x0156=CLIENT\x0134=177\x0152=20090107-18:15:16\x0198=0\x01108=30\x0110=000\x01";
+        let result = parse_message_with_validation_config::<LogonTest>(ValidationConfig::strict(),message);
+        match result.err().unwrap() {
+            fix_rs::fix::ParseError::ChecksumDoesNotMatch(calculated_checksum,stated_checksum) => {
+                assert_eq!(calculated_checksum,73);
+                assert_eq!(stated_checksum,0);
+            },
+            _ => assert!(false),
+        }
+
+        let message = parse_message_with_validation_config::<LogonTest>(skip_checksum_config.clone(),message).unwrap();
+        assert_eq!(message.msg_seq_num,177);
+
+        //CheckSum must still end the message with a value.
+        let message = b"8=FIX.4.2\x019=28\x0135=L\x0134=177\x0198=0\x01108=30\x0158=\x0110=\x01";
+        let result = parse_message_with_validation_config::<LogonTest>(ValidationConfig::lenient(),message);
+        match result.err().unwrap() {
+            fix_rs::fix::ParseError::NoValueAfterTag(tag) => assert_eq!(tag,FieldTag(10)),
+            _ => assert!(false),
+        }
+    }
+
+    //Ignore unknown fields.
+    {
+        let message = b"8=FIX.4.2\x019=38\x0135=L\x0134=177\x0198=0\x01108=30\x019999=X\x0155=IBM\x0110=000\x01";
+        let result = parse_message_with_validation_config::<LogonTest>(skip_checksum_config.clone(),message);
+        match result.err().unwrap() {
+            fix_rs::fix::ParseError::UnknownTag(tag) => assert_eq!(tag,FieldTag(9999)),
+            _ => assert!(false),
+        }
+
+        let validation_config = ValidationConfig {
+            ignore_unknown_fields: true,
+            ..skip_checksum_config.clone()
+        };
+        let message = parse_message_with_validation_config::<LogonTest>(validation_config,message).unwrap();
+        assert_eq!(message.msg_seq_num,177);
+        assert_eq!(message.heart_bt_int,30);
+    }
+
+    //Relax header ordering.
+    {
+        let message = b"8=FIXT.1.1\x019=52\x0135=0\x0156=CLIENT\x0149=SERVER\x0134=10\x0152=20170105-01:01:01\x0110=000\x01";
+        let result = parse_message_with_validation_config::<Heartbeat>(skip_checksum_config.clone(),message);
+        match result.err().unwrap() {
+            fix_rs::fix::ParseError::SenderCompIDNotFourthTag => {},
+            _ => assert!(false),
+        }
+
+        let validation_config = ValidationConfig {
+            relax_header_ordering: true,
+            ..skip_checksum_config.clone()
+        };
+        let message = parse_message_with_validation_config::<Heartbeat>(validation_config,message).unwrap();
+        assert_eq!(message.sender_comp_id,b"SERVER".to_vec());
+        assert_eq!(message.target_comp_id,b"CLIENT".to_vec());
+        assert_eq!(message.msg_seq_num,10);
+    }
+
+    //Allow empty values.
+    {
+        let message = b"8=FIX.4.2\x019=28\x0135=L\x0134=177\x0198=0\x01108=30\x0158=\x0110=000\x01";
+        let result = parse_message_with_validation_config::<LogonTest>(skip_checksum_config.clone(),message);
+        match result.err().unwrap() {
+            fix_rs::fix::ParseError::NoValueAfterTag(tag) => assert_eq!(tag,FieldTag(58)),
+            _ => assert!(false),
+        }
+
+        let validation_config = ValidationConfig {
+            allow_empty_values: true,
+            ..skip_checksum_config.clone()
+        };
+        let message = parse_message_with_validation_config::<LogonTest>(validation_config,message).unwrap();
+        assert!(message.text.is_empty());
+    }
+
+    //Allow fields from other message versions.
+    {
+        define_fixt_message!(TestMessage: b"9999" => {
+            NOT_REQUIRED, test_req_id: TestReqID [FIX50..],
+        });
+
+        let message = b"8=FIX.4.2\x019=64\x0135=9999\x0149=SERVER\x0156=CLIENT\x0134=10\x0152=20170105-01:01:01\x01112=Test\x0110=000\x01";
+        let result = parse_message_with_validation_config::<TestMessage>(skip_checksum_config.clone(),message);
+        match result.err().unwrap() {
+            fix_rs::fix::ParseError::UnknownTag(tag) => assert_eq!(tag,FieldTag(112)),
+            _ => assert!(false),
+        }
+
+        let validation_config = ValidationConfig {
+            allow_fields_from_other_message_versions: true,
+            ..skip_checksum_config.clone()
+        };
+        let message = parse_message_with_validation_config::<TestMessage>(validation_config,message).unwrap();
+        assert_eq!(message.test_req_id,b"Test".to_vec());
+    }
+}
+
+#[test]
+fn extra_fields_test() {
+    let capture_config = ValidationConfig {
+        skip_checksum_verification: true,
+        capture_unknown_fields: true,
+        ..ValidationConfig::strict()
+    };
+
+    //Unknown tags are stored with the message, or with the repeating group they were found in
+    //when more groups are expected.
+    let message = b"8=FIX.4.2\x019=86\x0135=L\x0134=177\x0198=0\x01108=30\x015001=abc\x01384=2\x01372=A\x01385=S\x019999=x\x01372=0\x01385=R\x0120001=y\x0158=done\x0110=000\x01";
+    let result = parse_message_with_validation_config::<LogonTest>(ValidationConfig { skip_checksum_verification: true,..ValidationConfig::strict() },message);
+    match result.err().unwrap() {
+        fix_rs::fix::ParseError::UnknownTag(tag) => assert_eq!(tag,FieldTag(5001)),
+        _ => assert!(false),
+    }
+
+    let message = parse_message_with_validation_config::<LogonTest>(capture_config.clone(),message).unwrap();
+    assert_eq!(message.text,b"done".to_vec());
+    assert_eq!(message.extra_fields,vec![(FieldTag(5001),b"abc".to_vec()),(FieldTag(20001),b"y".to_vec())]);
+    assert_eq!(message.msg_type_grp.len(),2);
+    assert_eq!(message.msg_type_grp[0].extra_fields,vec![(FieldTag(9999),b"x".to_vec())]);
+    assert!(message.msg_type_grp[1].extra_fields.is_empty());
+
+    //Extra fields are serialized so they survive being sent back out.
+    let mut message_bytes = ByteBuffer::new();
+    message.read(FIXVersion::FIX_4_2,MessageVersion::FIX42,&mut message_bytes);
+    let new_message = parse_message_with_validation_config::<LogonTest>(capture_config.clone(),message_bytes.bytes()).unwrap();
+    assert!(new_message == message);
+
+    //Extra fields are sent back out in the order they were received instead of by tag.
+    let unordered_message = b"8=FIX.4.2\x019=38\x0135=L\x0134=177\x0198=0\x01108=30\x019000=b\x015001=a\x0110=000\x01";
+    let message = parse_message_with_validation_config::<LogonTest>(capture_config.clone(),unordered_message).unwrap();
+    assert_eq!(message.extra_fields,vec![(FieldTag(9000),b"b".to_vec()),(FieldTag(5001),b"a".to_vec())]);
+    let mut message_bytes = ByteBuffer::new();
+    message.read(FIXVersion::FIX_4_2,MessageVersion::FIX42,&mut message_bytes);
+    assert!(message_bytes.bytes().windows(14).any(|bytes| bytes == b"9000=b\x015001=a\x01"));
+
+    //An extra field can still only be specified once.
+    let duplicate_message = b"8=FIX.4.2\x019=42\x0135=L\x0134=177\x0198=0\x01108=30\x015001=abc\x015001=def\x0110=000\x01";
+    let result = parse_message_with_validation_config::<LogonTest>(capture_config.clone(),duplicate_message);
+    match result.err().unwrap() {
+        fix_rs::fix::ParseError::DuplicateTag(tag) => assert_eq!(tag,FieldTag(5001)),
+        _ => assert!(false),
+    }
+
+    //A message tag that was already found is a duplicate inside of a repeating group instead of
+    //an unknown tag.
+    let duplicate_message = b"8=FIX.4.2\x019=61\x0135=L\x0134=177\x0198=0\x01108=30\x01384=2\x01372=A\x01385=S\x01108=31\x01372=0\x01385=R\x0110=000\x01";
+    let result = parse_message_with_validation_config::<LogonTest>(capture_config,duplicate_message);
+    match result.err().unwrap() {
+        fix_rs::fix::ParseError::DuplicateTag(tag) => assert_eq!(tag,FieldTag(108)),
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn message_view_test() {
+    let mut message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> = HashMap::new();
+    message_dictionary.insert(<LogonTest as MessageDetails>::msg_type(),<LogonTest as Default>::default().builder());
+    let mut parser = Parser::new(message_dictionary,MAX_MESSAGE_SIZE);
+
+    let message = b"8=FIX.4.2\x019=85\x0135=L\x0134=177\x0198=0\x01108=30\x0195=13\x0196=This\x01is=atest\x01384=2\x01372=A\x01385=S\x01372=0\x01385=R\x0158=done\x0110=200\x01";
+
+    //Incomplete messages should wait for more bytes.
+    {
+        let (bytes_parsed,result) = parser.parse_view(&message[..message.len() - 1]);
+        assert_eq!(bytes_parsed,0);
+        assert!(result.unwrap().is_none());
+    }
+
+    //Fields should be accessible directly from the buffer.
+    {
+        let (bytes_parsed,result) = parser.parse_view(&message[..]);
+        assert_eq!(bytes_parsed,message.len());
+        let view = result.unwrap().unwrap();
+        assert_eq!(view.bytes(),&message[..]);
+        assert_eq!(view.fix_version(),FIXVersion::FIX_4_2);
+        assert_eq!(view.body_length(),85);
+        assert_eq!(view.checksum(),200);
+        assert_eq!(view.msg_type(),b"L");
+        assert_eq!(view.get(FieldTag(34)).unwrap(),b"177");
+        assert_eq!(view.get(FieldTag(96)).unwrap(),b"This\x01is=atest");
+        assert_eq!(view.get_field::<HeartBtInt>().unwrap().unwrap(),30);
+        assert!(view.get(FieldTag(55)).is_none());
+
+        let tags: Vec<FieldTag> = view.fields().map(|(tag,_)| tag).collect();
+        assert_eq!(tags,vec![FieldTag(35),FieldTag(34),FieldTag(98),FieldTag(108),FieldTag(95),FieldTag(96),FieldTag(384),FieldTag(372),FieldTag(385),FieldTag(372),FieldTag(385),FieldTag(58)]);
+
+        let groups: Vec<_> = view.groups(FieldTag(384)).unwrap().collect();
+        assert_eq!(groups.len(),2);
+        assert_eq!(groups[0].get(FieldTag(372)).unwrap(),b"A");
+        assert_eq!(groups[0].get(FieldTag(385)).unwrap(),b"S");
+        assert_eq!(groups[1].get(FieldTag(372)).unwrap(),b"0");
+        assert_eq!(groups[1].get(FieldTag(385)).unwrap(),b"R");
+        assert!(groups[1].get(FieldTag(58)).is_none());
+    }
+
+    //Garbage before the message should be skipped.
+    {
+        let mut garbage_message = b"garbage".to_vec();
+        garbage_message.extend_from_slice(&message[..]);
+        let (bytes_parsed,result) = parser.parse_view(&garbage_message[..]);
+        assert_eq!(bytes_parsed,garbage_message.len());
+        assert_eq!(result.unwrap().unwrap().bytes(),&message[..]);
+    }
+
+    //CheckSum must still be correct.
+    {
+        let incorrect_checksum_message = b"8=FIX.4.2\x019=29\x0135=L\x0134=177\x0198=0\x01108=30\x0158=A\x0110=000\x01";
+        let (bytes_parsed,result) = parser.parse_view(&incorrect_checksum_message[..]);
+        assert_eq!(bytes_parsed,incorrect_checksum_message.len());
+        match result.err().unwrap() {
+            fix_rs::fix::ParseError::ChecksumDoesNotMatch(_,stated_checksum) => assert_eq!(stated_checksum,0),
+            _ => assert!(false),
+        }
+    }
+
+    //A BodyLength near the largest possible value must not overflow.
+    {
+        let huge_body_length_message = b"8=FIX.4.2\x019=18446744073709551610\x0135=L\x0110=000\x01";
+        let (_,result) = parser.parse_view(&huge_body_length_message[..]);
+        match result.err().unwrap() {
+            fix_rs::fix::ParseError::MessageSizeTooBig => {},
+            _ => assert!(false),
+        }
+    }
+}
+
+#[test]
+fn generic_message_test() {
+    let mut message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> = HashMap::new();
+    message_dictionary.insert(<LogonTest as MessageDetails>::msg_type(),<LogonTest as Default>::default().builder());
+    let mut parser = Parser::new(message_dictionary,MAX_MESSAGE_SIZE);
+
+    let message = b"8=FIX.4.2\x019=107\x0135=ZZ\x0149=S\x0156=T\x0134=5\x0152=20170101-12:00:00.000\x0195=13\x0196=This\x01is=atest\x01384=2\x01372=A\x01385=S\x01372=0\x01385=R\x0158=done\x0110=134\x01";
+
+    //Unknown message types are rejected by default.
+    {
+        let (_,result) = parser.parse(&message[..]);
+        match result.err().unwrap() {
+            ParseError::MsgTypeUnknown(msg_type) => assert_eq!(msg_type,b"ZZ"),
+            _ => assert!(false),
+        }
+    }
+
+    //Fall back to a GenericMessage that keeps every field in order.
+    parser.set_generic_message_fallback(true);
+    let (bytes_parsed,result) = parser.parse(&message[..]);
+    assert!(result.is_ok());
+    assert_eq!(bytes_parsed,message.len());
+    assert_eq!(parser.messages.len(),1);
+    let generic_message = parser.messages.remove(0).as_any().downcast_ref::<GenericMessage>().unwrap().clone();
+    assert_eq!(generic_message.msg_type(),b"ZZ");
+    assert_eq!(generic_message.sender_comp_id,b"S");
+    assert_eq!(generic_message.target_comp_id,b"T");
+    assert_eq!(generic_message.msg_seq_num,5);
+    assert_eq!(generic_message.sending_time,UTC.ymd(2017,1,1).and_hms(12,0,0));
+
+    let tags: Vec<FieldTag> = generic_message.fields.iter().map(|field| field.tag).collect();
+    assert_eq!(tags,vec![FieldTag(95),FieldTag(96),FieldTag(384),FieldTag(58)]);
+    assert_eq!(generic_message.get_value(FieldTag(96)).unwrap(),b"This\x01is=atest");
+    assert_eq!(generic_message.get_value(FieldTag(58)).unwrap(),b"done");
+    assert!(generic_message.get_value(FieldTag(384)).is_none());
+
+    let groups = generic_message.get_groups(FieldTag(384)).unwrap();
+    assert_eq!(groups.len(),2);
+    assert!(groups[0] == vec![GenericField::new(FieldTag(372),b"A".to_vec()),GenericField::new(FieldTag(385),b"S".to_vec())]);
+    assert!(groups[1] == vec![GenericField::new(FieldTag(372),b"0".to_vec()),GenericField::new(FieldTag(385),b"R".to_vec())]);
+
+    //Serializing should produce the original message which parses back into the same
+    //GenericMessage.
+    let mut message_bytes = ByteBuffer::new();
+    generic_message.read(FIXVersion::FIX_4_2,MessageVersion::FIX42,&mut message_bytes);
+    assert_eq!(message_bytes.bytes(),&message[..]);
+
+    let (_,result) = parser.parse(message_bytes.bytes());
+    assert!(result.is_ok());
+    assert_eq!(parser.messages.len(),1);
+    assert!(*parser.messages[0].as_any().downcast_ref::<GenericMessage>().unwrap() == generic_message);
+
+    //Data fields found in the dictionary are left alone when sanitizing. Every other value is
+    //checked, even when the field before it happens to hold its length.
+    assert!(generic_message.data_tags.contains(&FieldTag(96)));
+    let mut sanitized_message = generic_message.clone();
+    assert!(sanitized_message.sanitize_values(InvalidValuePolicy::default()).is_ok());
+    assert!(sanitized_message == generic_message);
+    sanitized_message.push(GenericField::new(FieldTag(5000),b"a\x01b".to_vec()));
+    match sanitized_message.sanitize_values(InvalidValuePolicy::default()) {
+        Err(invalid_field_value) => assert_eq!(invalid_field_value.tag,FieldTag(5000)),
+        _ => assert!(false),
+    }
+    sanitized_message.sanitize_values(InvalidValuePolicy::new(InvalidValueAction::Strip)).unwrap();
+    assert_eq!(sanitized_message.get_value(FieldTag(5000)).unwrap(),b"ab");
+    assert_eq!(sanitized_message.get_value(FieldTag(96)).unwrap(),b"This\x01is=atest");
+    sanitized_message.push(GenericField::new(FieldTag(5001),b"3".to_vec()));
+    sanitized_message.push(GenericField::new(FieldTag(5002),b"a\x01b".to_vec()));
+    sanitized_message.sanitize_values(InvalidValuePolicy::new(InvalidValueAction::Strip)).unwrap();
+    assert_eq!(sanitized_message.get_value(FieldTag(5002)).unwrap(),b"ab");
+    sanitized_message.data_tags.insert(FieldTag(5003));
+    sanitized_message.push(GenericField::new(FieldTag(5003),b"a\x01b".to_vec()));
+    assert!(sanitized_message.sanitize_values(InvalidValuePolicy::default()).is_ok());
+
+    //Fields without a value and a missing OrigSendingTime on a possible duplicate fail validation.
+    assert!(generic_message.validate(MessageVersion::FIX42).is_ok());
+    let mut invalid_message = generic_message.clone();
+    invalid_message.poss_dup_flag = true;
+    match invalid_message.validate(MessageVersion::FIX42) {
+        Err(missing_required_field) => assert_eq!(missing_required_field.tag,OrigSendingTime::tag()),
+        _ => assert!(false),
+    }
+    let mut invalid_message = generic_message.clone();
+    invalid_message.push(GenericField::new(FieldTag(5000),b"".to_vec()));
+    match invalid_message.validate(MessageVersion::FIX42) {
+        Err(missing_required_field) => assert_eq!(missing_required_field.tag,FieldTag(5000)),
+        _ => assert!(false),
+    }
+
+    //Standard header fields are still required.
+    {
+        let missing_sending_time_message = b"8=FIX.4.2\x019=26\x0135=ZZ\x0149=S\x0156=T\x0134=5\x0158=A\x0110=000\x01";
+        parser.set_validation_config(ValidationConfig { skip_checksum_verification: true,..ValidationConfig::default() });
+        let (_,result) = parser.parse(&missing_sending_time_message[..]);
+        match result.err().unwrap() {
+            ParseError::MissingRequiredTag(tag,_) => assert_eq!(tag,SendingTime::tag()),
+            _ => assert!(false),
+        }
+    }
+
+    //Only tags that can belong to a repeating group may be repeated.
+    {
+        let duplicate_tag_message = b"8=FIX.4.2\x019=68\x0135=ZZ\x0149=S\x0156=T\x0134=6\x0152=20170101-12:00:00.000\x0158=A\x01372=A\x01372=B\x0158=B\x0110=000\x01";
+        let (_,result) = parser.parse(&duplicate_tag_message[..]);
+        match result.err().unwrap() {
+            ParseError::DuplicateTag(tag) => assert_eq!(tag,FieldTag(58)),
+            _ => assert!(false),
+        }
+    }
+}
+
+#[test]
+fn sanitize_byte_values_test() {
+    //Char fields and reserved enum values hold bytes as is so they are checked like strings.
+    let mut value = b'\x01';
+    assert!(!<CharFieldType as FieldType>::sanitize_value(&mut value,InvalidValuePolicy::default()));
+    assert!(<CharFieldType as FieldType>::sanitize_value(&mut value,InvalidValuePolicy::new(InvalidValueAction::Replace(b'?'))));
+    assert_eq!(value,b'?');
+
+    let mut message = NewOrderSingle::default();
+    message.security_id_source = Some(SecurityIDSourceValue::Other(b"a\x01b".to_vec()));
+    match message.sanitize_values(InvalidValuePolicy::default()) {
+        Err(invalid_field_value) => assert_eq!(invalid_field_value.tag,SecurityIDSource::tag()),
+        _ => assert!(false),
+    }
+    message.sanitize_values(InvalidValuePolicy::new(InvalidValueAction::Strip)).unwrap();
+    assert_eq!(message.security_id_source,Some(SecurityIDSourceValue::Other(b"ab".to_vec())));
+}
+
+#[test]
+fn context_dependent_repeating_groups_test() {
+    //NoOrders is a ListOrdGrp in NewOrderList but an OrdListStatGrp in ListStatus.
+    let message = b"8=FIX.4.2\x019=127\x0135=E\x0149=S\x0156=T\x0134=1\x0152=20170101-12:00:00\x0166=L1\x01394=3\x0168=2\x0173=2\x0111=A\x0167=1\x0178=1\x0179=ACC\x0180=100\x0155=IBM\x0154=1\x0111=B\x0167=2\x0155=MSFT\x0154=2\x0110=234\x01";
+    let message = parse_message::<NewOrderList>(message).unwrap();
+    assert_eq!(message.list_id,b"L1");
+    assert_eq!(message.orders.len(),2);
+    assert_eq!(message.orders[0].cl_ord_id,b"A");
+    assert_eq!(message.orders[0].list_seq_no,1);
+    assert_eq!(message.orders[0].symbol,b"IBM");
+    assert_eq!(message.orders[0].allocs.len(),1);
+    assert_eq!(message.orders[0].allocs[0].alloc_account,b"ACC");
+    assert_eq!(message.orders[0].allocs[0].alloc_qty,b"100");
+    assert_eq!(message.orders[1].cl_ord_id,b"B");
+    assert_eq!(message.orders[1].list_seq_no,2);
+    assert!(message.orders[1].allocs.is_empty());
+
+    let message = b"8=FIX.4.2\x019=146\x0135=N\x0149=S\x0156=T\x0134=2\x0152=20170101-12:00:00\x0166=L1\x01429=2\x0182=1\x01431=3\x0183=1\x0168=2\x0173=2\x0111=A\x0114=0\x0139=0\x01151=100\x0184=0\x016=0\x0111=B\x0114=50\x0139=1\x01151=50\x0184=0\x016=10.5\x0110=025\x01";
+    let message = parse_message::<ListStatus>(message).unwrap();
+    assert_eq!(message.orders.len(),2);
+    assert_eq!(message.orders[0].cl_ord_id,b"A");
+    assert_eq!(message.orders[1].cum_qty,b"50");
+    assert_eq!(message.orders[1].ord_status,b'1');
+    assert_eq!(message.orders[1].avg_px,b"10.5");
+
+    //Tags from a different message's NoOrders group don't belong.
+    let message = b"8=FIX.4.2\x019=151\x0135=N\x0149=S\x0156=T\x0134=2\x0152=20170101-12:00:00\x0166=L1\x01429=2\x0182=1\x01431=3\x0183=1\x0168=2\x0173=2\x0111=A\x0167=1\x0114=0\x0139=0\x01151=100\x0184=0\x016=0\x0111=B\x0114=50\x0139=1\x01151=50\x0184=0\x016=10.5\x0110=241\x01";
+    let result = parse_message::<ListStatus>(message);
+    match result.err().unwrap() {
+        ParseError::NonRepeatingGroupTagInRepeatingGroup(tag) => assert_eq!(tag,FieldTag(67)),
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn encoder_test() {
+    let message = LOGON_TEST_MESSAGE;
+    let mut message = parse_message::<LogonTest>(message).unwrap();
+    message.raw_data = b"a\x01b".to_vec();
+
+    let mut expected_bytes = ByteBuffer::new();
+    message.read(FIXVersion::FIX_4_2,MessageVersion::FIX42,&mut expected_bytes);
+
+    //The length pass agrees with the body that's written, including the data field's length
+    //field.
+    let mut body = Vec::new();
+    message.read_body(FIXVersion::FIX_4_2,MessageVersion::FIX42,&mut body);
+    assert_eq!(message.body_len(FIXVersion::FIX_4_2,MessageVersion::FIX42),body.len());
+
+    //Encode into a slice that's big enough.
+    let mut encoder = Encoder::new();
+    let mut buf = [0u8;256];
+    let len = encoder.encode_to_slice(&message,FIXVersion::FIX_4_2,MessageVersion::FIX42,&mut buf[..]).unwrap();
+    assert_eq!(&buf[..len],expected_bytes.bytes());
+
+    //Encode into an io::Write.
+    let mut writer = Vec::new();
+    let len = encoder.encode_to_writer(&message,FIXVersion::FIX_4_2,MessageVersion::FIX42,&mut writer).unwrap();
+    assert_eq!(len,writer.len());
+    assert_eq!(&writer[..],expected_bytes.bytes());
+
+    //Slice that's too small is left untouched.
+    let mut buf = [0u8;64];
+    match encoder.encode_to_slice(&message,FIXVersion::FIX_4_2,MessageVersion::FIX42,&mut buf[..]) {
+        Err(EncodeError::BufferTooSmall(required_len)) => assert_eq!(required_len,expected_bytes.len()),
+        _ => assert!(false),
+    }
+    assert!(buf.iter().all(|byte| *byte == 0));
+}
+
+#[test]
+fn fix_json_test() {
+    let message = LOGON_TEST_MESSAGE;
+    let message = parse_message::<LogonTest>(message).unwrap();
+
+    let mut message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> = HashMap::new();
+    message_dictionary.insert(<LogonTest as MessageDetails>::msg_type(),<LogonTest as Default>::default().builder());
+
+    //Header, Body, and Trailer are kept apart, values are written as they appear on the wire,
+    //repeating groups become arrays of objects, and fields are named using their standard FIX
+    //names.
+    let json = to_fix_json(&message,FIXVersion::FIX_4_2,MessageVersion::FIX42);
+    assert_eq!(json,concat!(
+        r#"{"Header":{"BeginString":"FIX.4.2","MsgType":"L","MsgSeqNum":"177","SendingTime":"20090107-18:15:16.000","SenderCompID":"SERVER","TargetCompID":"CLIENT"},"#,
+        r#""Body":{"EncryptMethod":"0","HeartBtInt":"30","NoMsgTypes":[{"RefMsgType":"A","MsgDirection":"S"},{"RefMsgType":"0","MsgDirection":"R"}]},"#,
+        r#""Trailer":{}}"#
+    ));
+
+    //Decoding gives back the same message.
+    let decoded_message = from_fix_json(&json,&message_dictionary).unwrap();
+    let decoded_message = decoded_message.as_any().downcast_ref::<LogonTest>().unwrap();
+    assert!(*decoded_message == message);
+
+    //Generic messages name the header fields they know about and key every other field by tag
+    //number.
+    let mut generic_message = GenericMessage::new(b"ZZ");
+    generic_message.sender_comp_id = b"CLIENT".to_vec();
+    generic_message.push(GenericField::new(FieldTag(58),b"A".to_vec()));
+    generic_message.push(GenericField::new_groups(FieldTag(384),vec![vec![GenericField::new(FieldTag(372),b"A".to_vec())]]));
+    let json = to_fix_json(&generic_message,FIXVersion::FIX_4_2,MessageVersion::FIX42);
+    assert!(json.contains(r#""SenderCompID":"CLIENT""#));
+    assert!(json.contains(r#""Body":{"58":"A","384":[{"372":"A"}]}"#));
+
+    //Whitespace is allowed, tag numbers can be used for fields that aren't part of the message, and
+    //data fields don't need their length field.
+    let json = r#"{
+        "Header": { "MsgType": "L", "MsgSeqNum": "1", "SendingTime": "20090107-18:15:16.000" },
+        "Body": { "EncryptMethod": "0", "HeartBtInt": "30", "RawData": "a\u0001b", "9999": "Extra" }
+    }"#;
+    let decoded_message = from_fix_json(json,&message_dictionary).unwrap();
+    let decoded_message = decoded_message.as_any().downcast_ref::<LogonTest>().unwrap();
+    assert_eq!(decoded_message.msg_seq_num,1);
+    assert_eq!(decoded_message.raw_data,b"a\x01b".to_vec());
+    assert_eq!(decoded_message.get_value(FieldTag(9999)).unwrap().as_ref(),b"Extra");
+
+    //Unknown names, malformed values, and unknown message types are rejected.
+    let json = r#"{"Header":{"MsgType":"L"},"Body":{"NotAField":"1"}}"#;
+    match from_fix_json(json,&message_dictionary) {
+        Err(JsonError::UnknownField(name)) => assert_eq!(name,"NotAField"),
+        _ => assert!(false),
+    }
+
+    let json = r#"{"Header":{"MsgType":"L"},"Body":{"HeartBtInt":"abc"}}"#;
+    match from_fix_json(json,&message_dictionary) {
+        Err(JsonError::WrongFormat(name)) => assert_eq!(name,"HeartBtInt"),
+        _ => assert!(false),
+    }
+
+    let json = r#"{"Header":{"MsgType":"Z"},"Body":{}}"#;
+    match from_fix_json(json,&message_dictionary) {
+        Err(JsonError::MsgTypeUnknown(msg_type)) => assert_eq!(msg_type,b"Z".to_vec()),
+        _ => assert!(false),
+    }
+
+    match from_fix_json(r#"{"Header":{"MsgType":"L"}"#,&message_dictionary) {
+        Err(JsonError::Syntax(..)) => {},
+        _ => assert!(false),
+    }
+
+    //Deeply nested input is rejected instead of overflowing the stack.
+    let json = ::std::iter::repeat("[").take(100000).collect::<String>();
+    match from_fix_json(&json,&message_dictionary) {
+        Err(JsonError::Syntax(_,description)) => assert_eq!(description,"Nested too deeply"),
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn pretty_print_test() {
+    let message = NEW_ORDER_SINGLE_MESSAGE;
+    let message = parse_message_with_ver::<NewOrderSingle>(FIXVersion::FIX_4_4,MessageVersion::FIX44,message).unwrap();
+
+    //The version stored in the message's Meta is used, enumerated values are described, and
+    //repeating groups are indented below their NumInGroup field.
+    let output = pretty_print(&message);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0],"8 BeginString = FIX.4.4");
+    assert_eq!(lines[1],"35 MsgType = D");
+    assert!(lines.contains(&"49 SenderCompID = CLIENT"));
+    assert!(lines.contains(&"54 Side = 1 (Buy)"));
+    assert!(lines.contains(&"40 OrdType = 2 (Limit)"));
+    assert!(lines.contains(&"21 HandlInst = 1 (AutomatedExecutionOrderPrivateNoBrokerIntervention)"));
+    assert!(lines.contains(&"38 OrderQty = 100"));
+    assert!(output.contains("78 NoAllocs = 2\n  79 AllocAccount = A1\n  80 AllocQty = 60\n  79 AllocAccount = A2\n  80 AllocQty = 40\n"));
+    assert!(!lines.iter().any(|line| line.starts_with("1128 ")));
+
+    //Debug uses the same version and {:#?} gives the same listing.
+    assert!(format!("{:?}",&message as &FIXTMessage).starts_with("8=FIX.4.4|"));
+    assert_eq!(format!("{:#?}",&message as &FIXTMessage),output);
+
+    //A different version can be requested explicitly.
+    let output = pretty_print_with_version(&message,FIXVersion::FIX_4_2,MessageVersion::FIX42);
+    assert!(output.starts_with("8 BeginString = FIX.4.2\n"));
+
+    //Fields without a definition are listed by tag number and control characters are escaped.
+    let mut message = NewOrderSingle::default();
+    message.extra_fields.push((FieldTag(9999),b"a\x01b".to_vec()));
+    assert!(pretty_print(&message).contains("\n9999 = a\\x01b\n"));
+
+    //Generic messages only name the header fields they know about.
+    let mut message = GenericMessage::new(b"ZZ");
+    message.sender_comp_id = b"CLIENT".to_vec();
+    message.push(GenericField::new_groups(FieldTag(384),vec![vec![GenericField::new(FieldTag(372),b"A".to_vec())]]));
+    let output = pretty_print(&message);
+    assert!(output.contains("\n49 SenderCompID = CLIENT\n"));
+    assert!(output.ends_with("\n384 = 1\n  372 = A\n"));
+}
+
+#[test]
+fn message_diff_test() {
+    let message = NEW_ORDER_SINGLE_MESSAGE;
+    let left_message = parse_message_with_ver::<NewOrderSingle>(FIXVersion::FIX_4_4,MessageVersion::FIX44,message).unwrap();
+
+    //Identical messages have no differences.
+    assert!(diff_messages(&left_message,&left_message.clone()).is_empty());
+
+    //Changed, removed, and added fields are found at the top level and inside repeating groups.
+    let mut right_message = left_message.clone();
+    right_message.msg_seq_num = 5;
+    right_message.account = Vec::new();
+    right_message.allocs[1].alloc_qty = b"45".to_vec();
+    right_message.extra_fields.push((FieldTag(9999),b"Extra".to_vec()));
+    let diffs = diff_messages(&left_message,&right_message);
+    assert_eq!(diffs.len(),4);
+    assert_eq!(diffs[0].kind(),FieldDiffKind::Changed);
+    assert_eq!(diffs[0].tag,MsgSeqNum::tag());
+    assert_eq!(diffs[0].name,Some("MsgSeqNum"));
+    assert_eq!(diffs[0].left,Some(b"2".to_vec()));
+    assert_eq!(diffs[0].right,Some(b"5".to_vec()));
+    assert_eq!(diffs[1].kind(),FieldDiffKind::Removed);
+    assert_eq!(diffs[1].tag,Account::tag());
+    assert_eq!(diffs[1].left,Some(b"ACCT".to_vec()));
+    assert_eq!(diffs[1].right,None);
+    assert_eq!(diffs[2].path,vec![(FieldTag(78),1)]);
+    assert_eq!(diffs[2].tag,AllocQty::tag());
+    assert_eq!(format!("{}",diffs[2]),"~ 78[1].80 AllocQty = 40 -> 45");
+    assert_eq!(diffs[3].kind(),FieldDiffKind::Added);
+    assert_eq!(format!("{}",diffs[3]),"+ 9999 = Extra");
+    assert_eq!(diffs[3].with_name(Some("Custom")).to_string(),"+ 9999 Custom = Extra");
+
+    //Removing a whole group reports the NumInGroup change and every field in the group.
+    let mut right_message = left_message.clone();
+    right_message.allocs.pop();
+    let diffs: Vec<String> = diff_messages(&left_message,&right_message).iter().map(|diff| diff.to_string()).collect();
+    assert_eq!(diffs,vec!["~ 78 NoAllocs = 2 -> 1","- 78[1].79 AllocAccount = A2","- 78[1].80 AllocQty = 40"]);
+
+    //Generic messages are compared by tag without names.
+    let mut left_message = GenericMessage::new(b"ZZ");
+    left_message.push(GenericField::new(FieldTag(58),b"A".to_vec()));
+    left_message.push(GenericField::new_groups(FieldTag(384),vec![vec![GenericField::new(FieldTag(372),b"A".to_vec())]]));
+    let mut right_message = GenericMessage::new(b"ZZ");
+    right_message.push(GenericField::new(FieldTag(58),b"B".to_vec()));
+    right_message.push(GenericField::new_groups(FieldTag(384),vec![vec![GenericField::new(FieldTag(372),b"0".to_vec())]]));
+    let diffs: Vec<String> = diff_messages(&left_message,&right_message).iter().map(|diff| diff.to_string()).collect();
+    assert_eq!(diffs,vec!["~ 58 = A -> B","~ 384[0].372 = A -> 0"]);
+
+    //fix-rs-diff's report lists the differences of each pair of messages and names fields using
+    //the data dictionary when given one.
+    let mut left_message = GenericMessage::new(b"ZZ");
+    left_message.push(GenericField::new(FieldTag(55),b"IBM".to_vec()));
+    let mut right_message = left_message.clone();
+    right_message.push(GenericField::new(FieldTag(9999),b"Extra".to_vec()));
+    let left_messages = vec![Box::new(left_message.clone()) as Box<FIXTMessage + Send>,Box::new(left_message.clone()) as Box<FIXTMessage + Send>];
+    let right_messages = vec![Box::new(left_message.clone()) as Box<FIXTMessage + Send>,Box::new(right_message.clone()) as Box<FIXTMessage + Send>];
+    let mut output = Vec::new();
+    assert!(!write_message_diffs(&left_messages[..],&left_messages[..],None,&mut output).unwrap());
+    assert!(output.is_empty());
+
+    let mut right_message = GenericMessage::new(b"ZZ");
+    right_message.push(GenericField::new(FieldTag(55),b"MSFT".to_vec()));
+    let mut right_messages = right_messages;
+    right_messages.push(Box::new(right_message) as Box<FIXTMessage + Send>);
+    let mut left_messages = left_messages;
+    left_messages.push(Box::new(left_message.clone()) as Box<FIXTMessage + Send>);
+    assert!(write_message_diffs(&left_messages[..],&right_messages[..],None,&mut output).unwrap());
+    assert_eq!(String::from_utf8(output).unwrap(),"Message 2 (35=ZZ)\n+ 9999 = Extra\n\nMessage 3 (35=ZZ)\n~ 55 = IBM -> MSFT\n");
+
+    let data_dictionary = DataDictionary::from_xml(DATA_DICTIONARY_XML).unwrap();
+    left_messages.pop();
+    let mut output = Vec::new();
+    assert!(write_message_diffs(&left_messages[..],&right_messages[..],Some(&data_dictionary),&mut output).unwrap());
+    assert_eq!(String::from_utf8(output).unwrap(),"Message 2 (35=ZZ)\n+ 9999 = Extra\n\nLeft has 2 message(s) and right has 3 message(s)\n");
+
+    let mut output = Vec::new();
+    assert!(write_message_diffs(&right_messages[2..],&left_messages[..1],Some(&data_dictionary),&mut output).unwrap());
+    assert_eq!(String::from_utf8(output).unwrap(),"Message 1 (35=ZZ)\n~ 55 Symbol = MSFT -> IBM\n");
+}
+
+#[test]
+fn validate_test() {
+    define_fields!(
+        NoRateSources: RepeatingGroupFieldType<RateSourceGrp> = 1445,
+    );
+
+    define_message!(RateSourceGrp {
+        REQUIRED, rate_source: RateSourceField [FIX40..],
+        REQUIRED, rate_source_type: RateSourceTypeField [FIX40..],
+        NOT_REQUIRED, reference_page: ReferencePageField [FIX40..] => REQUIRED_WHEN |message: &RateSourceGrp,_| { message.rate_source == RateSource::Other },
+    });
+
+    define_message!(ValidateTestMessage: b"L" => {
+        REQUIRED, sender_comp_id: SenderCompID [FIX40..],
+        REQUIRED, sending_time: SendingTime [FIX40..],
+        REQUIRED, test_req_id: TestReqID [FIX40..],
+        NOT_REQUIRED, rate_sources: NoRateSources [FIX40..],
+        REQUIRED, text: Text [FIX50..],
+    });
+
+    //Header fields filled in by the engine are not checked.
+    let mut message = ValidateTestMessage::new();
+    message.test_req_id = b"1".to_vec();
+    assert!(message.validate(MessageVersion::FIX42).is_ok());
+
+    //Required fields are only checked for the message versions they are part of.
+    let missing_required_field = message.validate(MessageVersion::FIX50).err().unwrap();
+    assert_eq!(missing_required_field.tag,Text::tag());
+    assert_eq!(missing_required_field.name,"Text");
+
+    message.test_req_id.clear();
+    let missing_required_field = message.validate(MessageVersion::FIX42).err().unwrap();
+    assert_eq!(missing_required_field.tag,TestReqID::tag());
+
+    //Conditionally required fields inside repeating groups are checked too.
+    message.test_req_id = b"1".to_vec();
+    let mut group = RateSourceGrp::new();
+    group.rate_source = RateSource::Bloomberg;
+    group.rate_source_type = RateSourceType::Primary;
+    message.rate_sources.push(Box::new(group.clone()));
+    assert!(message.validate(MessageVersion::FIX42).is_ok());
+
+    group.rate_source = RateSource::Other;
+    message.rate_sources.push(Box::new(group));
+    let missing_required_field = message.validate(MessageVersion::FIX42).err().unwrap();
+    assert_eq!(missing_required_field.tag,ReferencePageField::tag());
+
+    message.rate_sources[1].reference_page = b"page".to_vec();
+    assert!(message.validate(MessageVersion::FIX42).is_ok());
+}
+
+#[test]
+fn field_access_test() {
+    let message = NEW_ORDER_SINGLE_MESSAGE;
+    let mut message = parse_message_with_ver::<NewOrderSingle>(FIXVersion::FIX_4_4,MessageVersion::FIX44,message).unwrap();
+
+    //Values are returned as they would be sent on the wire.
+    assert_eq!(message.get_value(SideField::tag()).unwrap().as_ref(),b"1");
+    assert_eq!(message.get_value(Account::tag()).unwrap().as_ref(),b"ACCT");
+    assert_eq!(message.get_value(MsgSeqNum::tag()).unwrap().as_ref(),b"2");
+    assert_eq!(message.get_value(NoAllocs::tag()).unwrap().as_ref(),b"2");
+    assert!(message.has_field(Price::tag()));
+
+    //Empty fields, fields not part of the message version, and unknown tags are not set.
+    assert!(message.get_value(Text::tag()).is_none());
+    assert!(!message.has_field(Text::tag()));
+    assert!(!message.has_field(FieldTag(1128)));
+    assert!(!message.has_field(FieldTag(9999)));
+
+    //Fields are listed in wire order with repeating groups nested below their NumInGroup field.
+    let tags: Vec<FieldTag> = message.fields().map(|field| field.tag).collect();
+    let position = |tag: FieldTag| tags.iter().position(|other_tag| *other_tag == tag).unwrap();
+    assert!(position(SenderCompID::tag()) < position(TargetCompID::tag()));
+    assert!(position(TargetCompID::tag()) < position(MsgSeqNum::tag()));
+    assert!(position(Account::tag()) < position(NoAllocs::tag()));
+    assert!(position(NoAllocs::tag()) < position(HandlInst::tag()));
+    assert!(!tags.contains(&AllocAccount::tag()));
+
+    let mut allocs = message.fields().find(|field| field.tag == NoAllocs::tag()).unwrap();
+    assert_eq!(allocs.value.as_ref(),b"2");
+    assert_eq!(allocs.groups.len(),2);
+    let group_fields: Vec<(FieldTag,Vec<u8>)> = allocs.groups.nth(1).unwrap().map(|field| (field.tag,field.value.to_vec())).collect();
+    assert_eq!(group_fields,vec![(AllocAccount::tag(),b"A2".to_vec()),(AllocQty::tag(),b"40".to_vec())]);
+
+    //Fields without a definition are included after the defined fields.
+    message.extra_fields.push((FieldTag(9999),b"Extra".to_vec()));
+    assert_eq!(message.get_value(FieldTag(9999)).unwrap().as_ref(),b"Extra");
+    assert!(message.has_field(FieldTag(9999)));
+    let last_field = message.fields().last().unwrap();
+    assert_eq!(last_field.tag,FieldTag(9999));
+
+    //Generic messages expose their fields the same way.
+    let mut message = GenericMessage::new(b"ZZ");
+    message.sender_comp_id = b"CLIENT".to_vec();
+    message.push(GenericField::new(FieldTag(58),b"A".to_vec()));
+    message.push(GenericField::new_groups(FieldTag(384),vec![vec![GenericField::new(FieldTag(372),b"A".to_vec())]]));
+    let message = &message as &Message;
+    assert_eq!(message.get_value(SenderCompID::tag()).unwrap().as_ref(),b"CLIENT");
+    assert_eq!(message.get_value(FieldTag(58)).unwrap().as_ref(),b"A");
+    assert_eq!(message.get_value(FieldTag(384)).unwrap().as_ref(),b"1");
+    assert!(!message.has_field(FieldTag(372)));
+    let mut groups = message.fields().last().unwrap().groups;
+    assert_eq!(groups.len(),1);
+    assert_eq!(groups.next().unwrap().next().unwrap().tag,FieldTag(372));
+    assert!(groups.next().is_none());
+}
+
+#[test]
+fn parse_limits_test() {
+    fn parse_with_limits(parse_limits: ParseLimits,message: &[u8]) -> Result<(),ParseError> {
+        let mut message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> = HashMap::new();
+        message_dictionary.insert(<LogonTest as MessageDetails>::msg_type(),<LogonTest as Default>::default().builder());
+        message_dictionary.insert(<NewOrderList as MessageDetails>::msg_type(),<NewOrderList as Default>::default().builder());
+        let mut parser = Parser::new(message_dictionary,MAX_MESSAGE_SIZE);
+        parser.set_parse_limits(parse_limits);
+        parser.set_generic_message_fallback(true);
+
+        //Parsing into a view must come to the same conclusion as parsing into a message.
+        let view_result = {
+            let (_,result) = parser.parse_view(message);
+            result.map(|_| ())
+        };
+        let (_,result) = parser.parse(message);
+        assert_eq!(format!("{:?}",view_result),format!("{:?}",result));
+        result
+    }
+
+    //Message has 15 fields and two repeating groups at a depth of one.
+    let message = LOGON_TEST_MESSAGE;
+    let exact_limits = ParseLimits {
+        max_group_count: 2,
+        max_group_depth: 1,
+        max_field_count: 15,
+    };
+    assert!(parse_with_limits(ParseLimits::default(),message).is_ok());
+    assert!(parse_with_limits(exact_limits.clone(),message).is_ok());
+
+    //Too many repeating groups.
+    let result = parse_with_limits(ParseLimits { max_group_count: 1, ..exact_limits.clone() },message);
+    match result.err().unwrap() {
+        ParseError::RepeatingGroupCountTooLarge(tag) => assert_eq!(tag,FieldTag(384)),
+        _ => assert!(false),
+    }
+
+    //Repeating groups nested too deeply.
+    let result = parse_with_limits(ParseLimits { max_group_depth: 0, ..exact_limits.clone() },message);
+    match result.err().unwrap() {
+        ParseError::RepeatingGroupNestedTooDeep(tag) => assert_eq!(tag,FieldTag(384)),
+        _ => assert!(false),
+    }
+
+    //Too many fields.
+    let result = parse_with_limits(ParseLimits { max_field_count: 14, ..exact_limits.clone() },message);
+    match result.err().unwrap() {
+        ParseError::TooManyFields => {},
+        _ => assert!(false),
+    }
+
+    //NoAllocs is nested at a depth of two. The second message has an unknown MsgType so it's
+    //parsed into a GenericMessage using the groups known from NewOrderList.
+    let new_order_list_message = b"8=FIX.4.2\x019=127\x0135=E\x0149=S\x0156=T\x0134=1\x0152=20170101-12:00:00\x0166=L1\x01394=3\x0168=2\x0173=2\x0111=A\x0167=1\x0178=1\x0179=ACC\x0180=100\x0155=IBM\x0154=1\x0111=B\x0167=2\x0155=MSFT\x0154=2\x0110=234\x01";
+    let generic_message = b"8=FIX.4.2\x019=128\x0135=ZZ\x0149=S\x0156=T\x0134=1\x0152=20170101-12:00:00\x0166=L1\x01394=3\x0168=2\x0173=2\x0111=A\x0167=1\x0178=1\x0179=ACC\x0180=100\x0155=IBM\x0154=1\x0111=B\x0167=2\x0155=MSFT\x0154=2\x0110=090\x01";
+    for message in &[&new_order_list_message[..],&generic_message[..]] {
+        assert!(parse_with_limits(ParseLimits { max_group_depth: 2, ..ParseLimits::default() },message).is_ok());
+
+        let result = parse_with_limits(ParseLimits { max_group_depth: 1, ..ParseLimits::default() },message);
+        match result.err().unwrap() {
+            ParseError::RepeatingGroupNestedTooDeep(tag) => assert_eq!(tag,FieldTag(78)),
+            _ => assert!(false),
+        }
+
+        let result = parse_with_limits(ParseLimits { max_group_count: 1, ..ParseLimits::default() },message);
+        match result.err().unwrap() {
+            ParseError::RepeatingGroupCountTooLarge(tag) => assert_eq!(tag,FieldTag(73)),
+            _ => assert!(false),
+        }
+    }
+}
+
+#[test]
+fn repeatable_related_sym_test() {
+    //FIX 4.0 and 4.1 allow RelatedSym to be repeated without a repeating group.
+    let message = b"8=FIX.4.0\x019=75\x0135=C\x0149=S\x0156=T\x0134=1\x0152=20170101-12:00:00\x0194=0\x0146=IBM\x0146=MSFT\x0133=1\x0158=hello\x0110=128\x01";
+    let message = parse_message_with_ver::<Email>(FIXVersion::FIX_4_0,MessageVersion::FIX40,message).unwrap();
+    assert_eq!(message.related_sym,vec![b"IBM".to_vec(),b"MSFT".to_vec()]);
+    assert_eq!(message.no_lines_of_text.len(),1);
+
+    let message = b"8=FIX.4.1\x019=91\x0135=B\x0149=S\x0156=T\x0134=2\x0152=20170101-12:00:00\x01148=Earnings\x0146=IBM\x0146=MSFT\x0146=AAPL\x0133=1\x0158=hello\x0110=124\x01";
+    let message = parse_message_with_ver::<News>(FIXVersion::FIX_4_1,MessageVersion::FIX41,message).unwrap();
+    assert_eq!(message.headline,b"Earnings");
+    assert_eq!(message.related_sym,vec![b"IBM".to_vec(),b"MSFT".to_vec(),b"AAPL".to_vec()]);
+
+    //Every occurrence is written back out when serializing.
+    let mut message_bytes = ByteBuffer::new();
+    message.read(FIXVersion::FIX_4_1,MessageVersion::FIX41,&mut message_bytes);
+    let serialized_message = String::from_utf8_lossy(message_bytes.bytes()).into_owned();
+    assert!(serialized_message.contains("\x0146=IBM\x0146=MSFT\x0146=AAPL\x01"));
+
+    //Other fields still can't be repeated.
+    let message = b"8=FIX.4.0\x019=72\x0135=C\x0149=S\x0156=T\x0134=1\x0152=20170101-12:00:00\x0194=0\x0146=IBM\x0194=1\x0133=1\x0158=hello\x0110=119\x01";
+    let result = parse_message_with_ver::<Email>(FIXVersion::FIX_4_0,MessageVersion::FIX40,message);
+    match result.err().unwrap() {
+        ParseError::DuplicateTag(tag) => assert_eq!(tag,FieldTag(94)),
+        _ => assert!(false),
+    }
+}
+
+#[test]
+fn field_delimiter_test() {
+    let soh_message = b"8=FIX.4.2\x019=65\x0135=L\x0149=SERVER\x0156=CLIENT\x0134=177\x0152=20090107-18:15:16\x0198=0\x01108=30\x0110=073\x01";
+    let pipe_message = b"8=FIX.4.2|9=65|35=L|49=SERVER|56=CLIENT|34=177|52=20090107-18:15:16|98=0|108=30|10=073|";
+    let caret_a_message = b"8=FIX.4.2^A9=65^A35=L^A49=SERVER^A56=CLIENT^A34=177^A52=20090107-18:15:16^A98=0^A108=30^A10=073^A";
+
+    let mut message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> = HashMap::new();
+    message_dictionary.insert(<LogonTest as MessageDetails>::msg_type(),<LogonTest as Default>::default().builder());
+    let mut parser = Parser::new(message_dictionary,MAX_MESSAGE_SIZE);
+
+    let (_,result) = parser.parse(&soh_message[..]);
+    assert!(result.is_ok());
+    let expected_message = parser.messages.remove(0).as_any().downcast_ref::<LogonTest>().unwrap().clone();
+
+    //BodyLength and CheckSum are validated as if the delimiter was SOH.
+    parser.set_field_delimiter(FieldDelimiter::Byte(b'|'));
+    let (bytes_parsed,result) = parser.parse(&pipe_message[..]);
+    assert!(result.is_ok());
+    assert_eq!(bytes_parsed,pipe_message.len());
+    assert_eq!(parser.messages.len(),1);
+    assert!(*parser.messages.remove(0).as_any().downcast_ref::<LogonTest>().unwrap() == expected_message);
+
+    //Auto detect the delimiter even when it's split between calls.
+    parser.set_field_delimiter(FieldDelimiter::AutoDetect);
+    let split_index = caret_a_message.iter().position(|c| *c == b'^').unwrap() + 1;
+    let (bytes_parsed,result) = parser.parse(&caret_a_message[..split_index]);
+    assert!(result.is_ok());
+    assert_eq!(bytes_parsed,split_index);
+    assert_eq!(parser.field_delimiter(),FieldDelimiter::AutoDetect);
+    let (bytes_parsed,result) = parser.parse(&caret_a_message[split_index..]);
+    assert!(result.is_ok());
+    assert_eq!(bytes_parsed,caret_a_message.len() - split_index);
+    assert_eq!(parser.field_delimiter(),FieldDelimiter::CaretA);
+    assert_eq!(parser.messages.len(),1);
+    assert!(*parser.messages.remove(0).as_any().downcast_ref::<LogonTest>().unwrap() == expected_message);
+
+    //A corrupted message is still caught by the CheckSum.
+    parser.set_field_delimiter(FieldDelimiter::AutoDetect);
+    let bad_message = b"8=FIX.4.2|9=65|35=L|49=SERVER|56=CLIENT|34=178|52=20090107-18:15:16|98=0|108=30|10=073|";
+    let (_,result) = parser.parse(&bad_message[..]);
+    match result.err().unwrap() {
+        ParseError::ChecksumDoesNotMatch(_,_) => {},
+        _ => assert!(false),
+    }
+
+    //A trailing caret that was held back isn't lost when parsing stops on it in the next call.
+    //Offsets count the bytes after ^A is translated to SOH.
+    let mut parser = Parser::new(HashMap::new(),MAX_MESSAGE_SIZE);
+    parser.set_field_delimiter(FieldDelimiter::CaretA);
+    let first_bytes = b"8=FIX.4.2^A9=4^A35=^";
+    let (bytes_parsed,result) = parser.parse(&first_bytes[..]);
+    assert!(result.is_ok());
+    assert_eq!(bytes_parsed,first_bytes.len());
+    let second_bytes = b"X8=FIX.4.2^A9=4^A35=L^A";
+    let (bytes_parsed,result) = parser.parse(&second_bytes[..]);
+    match result.err().unwrap() {
+        ParseError::ChecksumNotLastTag => {},
+        _ => assert!(false),
+    }
+    assert_eq!(bytes_parsed,0);
+    assert_eq!(parser.error_context().byte_offset,17);
+    let (_,result) = parser.parse(&second_bytes[bytes_parsed..]);
+    match result.err().unwrap() {
+        ParseError::ChecksumNotLastTag => {},
+        _ => assert!(false),
+    }
+    assert_eq!(parser.error_context().byte_offset,36);
+}
+
+const DATA_DICTIONARY_XML: &'static str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<fix type="FIX" major="4" minor="2" servicepack="0">
+  <header>
+    <field name="BeginString" required="Y"/>
+    <field name="BodyLength" required="Y"/>
+    <field name="MsgType" required="Y"/>
+    <field name="SenderCompID" required="Y"/>
+    <field name="TargetCompID" required="Y"/>
+  </header>
+  <trailer>
+    <field name="CheckSum" required="Y"/>
+  </trailer>
+  <messages>
+    <message name="CustomOrder" msgtype="ZZ" msgcat="app">
+      <field name="Symbol" required="Y"/>
+      <field name="Side" required="Y"/>
+      <component name="Parties" required="N"/>
+    </message>
+  </messages>
+  <components>
+    <component name="Parties">
+      <group name="NoPartyIDs" required="N">
+        <field name="PartyID" required="Y"/>
+        <field name="PartyIDSource" required="N"/>
+      </group>
+    </component>
+  </components>
+  <fields>
+    <field number="8" name="BeginString" type="STRING"/>
+    <field number="9" name="BodyLength" type="LENGTH"/>
+    <field number="10" name="CheckSum" type="STRING"/>
+    <field number="35" name="MsgType" type="STRING"/>
+    <field number="49" name="SenderCompID" type="STRING"/>
+    <field number="56" name="TargetCompID" type="STRING"/>
+    <field number="55" name="Symbol" type="STRING"/>
+    <field number="54" name="Side" type="CHAR">
+      <value enum="1" description="BUY"/>
+      <value enum="2" description="SELL"/>
+    </field>
+    <field number="453" name="NoPartyIDs" type="NUMINGROUP"/>
+    <field number="448" name="PartyID" type="STRING"/>
+    <field number="447" name="PartyIDSource" type="CHAR">
+      <value enum="D" description="PROPRIETARY_CUSTOM_CODE"/>
+    </field>
+  </fields>
+</fix>
+"#;
+
+#[test]
+fn data_dictionary_test() {
+    let data_dictionary = DataDictionary::from_xml(DATA_DICTIONARY_XML).unwrap();
+    assert_eq!(data_dictionary.begin_string(),b"FIX.4.2");
+    assert_eq!(data_dictionary.fix_version(),Some(FIXVersion::FIX_4_2));
+    assert_eq!(data_dictionary.field_by_name("Side").unwrap().tag,FieldTag(54));
+    assert_eq!(data_dictionary.field(FieldTag(54)).unwrap().values.len(),2);
+    {
+        let message_definition = data_dictionary.message(b"ZZ").unwrap();
+        assert_eq!(message_definition.name,"CustomOrder");
+        assert!(!message_definition.is_admin);
+
+        //Components are flattened into the message.
+        let tags: Vec<FieldTag> = message_definition.fields.iter().map(|field| field.tag).collect();
+        assert_eq!(tags,vec![FieldTag(55),FieldTag(54),FieldTag(453)]);
+        assert!(message_definition.fields[2].group.is_some());
+    }
+
+    //Messages in the data dictionary are parsed without the generic message fallback.
+    let mut message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> = HashMap::new();
+    message_dictionary.insert(<LogonTest as MessageDetails>::msg_type(),<LogonTest as Default>::default().builder());
+    let mut parser = Parser::new(message_dictionary,MAX_MESSAGE_SIZE);
+    parser.set_data_dictionary(Some(Arc::new(data_dictionary)));
+    assert!(!parser.generic_message_fallback());
+
+    let message = b"8=FIX.4.2\x019=90\x0135=ZZ\x0149=S\x0156=T\x0134=5\x0152=20170101-12:00:00.000\x0155=ABC\x0154=1\x01453=2\x01448=P1\x01447=D\x01448=P2\x01447=D\x0110=052\x01";
+    let (bytes_parsed,result) = parser.parse(&message[..]);
+    assert!(result.is_ok());
+    assert_eq!(bytes_parsed,message.len());
+    assert_eq!(parser.messages.len(),1);
+    {
+        let generic_message = parser.messages.remove(0);
+        let generic_message = generic_message.as_any().downcast_ref::<GenericMessage>().unwrap();
+        assert_eq!(generic_message.get_value(FieldTag(55)).unwrap(),b"ABC");
+        let groups = generic_message.get_groups(FieldTag(453)).unwrap();
+        assert_eq!(groups.len(),2);
+        assert!(groups[1] == vec![GenericField::new(FieldTag(448),b"P2".to_vec()),GenericField::new(FieldTag(447),b"D".to_vec())]);
+    }
+
+    //Required fields are enforced.
+    {
+        let message = b"8=FIX.4.2\x019=51\x0135=ZZ\x0149=S\x0156=T\x0134=5\x0152=20170101-12:00:00.000\x0154=1\x0110=182\x01";
+        let (_,result) = parser.parse(&message[..]);
+        match result.err().unwrap() {
+            ParseError::MissingRequiredTag(tag,_) => assert_eq!(tag,FieldTag(55)),
+            _ => assert!(false),
+        }
+    }
+
+    //Values must be one of the enumerated values.
+    {
+        let message = b"8=FIX.4.2\x019=58\x0135=ZZ\x0149=S\x0156=T\x0134=5\x0152=20170101-12:00:00.000\x0155=ABC\x0154=9\x0110=051\x01";
+        let (_,result) = parser.parse(&message[..]);
+        match result.err().unwrap() {
+            ParseError::OutOfRangeTag(tag) => assert_eq!(tag,FieldTag(54)),
+            _ => assert!(false),
+        }
+    }
+
+    //Tags that aren't in the data dictionary are rejected unless unknown fields are ignored.
+    {
+        let message = b"8=FIX.4.2\x019=65\x0135=ZZ\x0149=S\x0156=T\x0134=5\x0152=20170101-12:00:00.000\x0155=ABC\x0154=1\x019999=X\x0110=163\x01";
+        let (_,result) = parser.parse(&message[..]);
+        match result.err().unwrap() {
+            ParseError::UnknownTag(tag) => assert_eq!(tag,FieldTag(9999)),
+            _ => assert!(false),
+        }
+
+        parser.set_validation_config(ValidationConfig { ignore_unknown_fields: true,..ValidationConfig::default() });
+        let (_,result) = parser.parse(&message[..]);
+        assert!(result.is_ok());
+        assert_eq!(parser.messages.len(),1);
+    }
+
+    //Data dictionaries that refer to undefined components can't be loaded.
+    let bad_xml = DATA_DICTIONARY_XML.replace(r#"<component name="Parties" required="N"/>"#,r#"<component name="Instrument" required="N"/>"#);
+    match DataDictionary::from_xml(&bad_xml[..]) {
+        Err(DataDictionaryError::UnknownComponent(name)) => assert_eq!(name,"Instrument"),
+        _ => assert!(false),
+    }
+}
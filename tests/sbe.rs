@@ -0,0 +1,212 @@
+// Copyright 2017 James Bendig. See the COPYRIGHT file at the top-level
+// directory of this distribution.
+//
+// Licensed under:
+//   the MIT license
+//     <LICENSE-MIT or https://opensource.org/licenses/MIT>
+//   or the Apache License, Version 2.0
+//     <LICENSE-APACHE or https://www.apache.org/licenses/LICENSE-2.0>,
+// at your option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#[macro_use]
+extern crate fix_rs;
+
+use std::collections::HashMap;
+
+use fix_rs::byte_buffer::ByteBuffer;
+use fix_rs::dictionary::fields::{Account,AllocAccount,AllocQty,ClOrdID,HandlInst,MsgSeqNum,NoAllocs,OrdType,OrderQty,PreAllocGrp,Price,SecurityID,SecurityIDSource,SenderCompID,SendingTime,SideField,Symbol,TargetCompID,TransactTime};
+use fix_rs::dictionary::messages::NewOrderSingle;
+use fix_rs::fix::Parser;
+use fix_rs::fix_version::FIXVersion;
+use fix_rs::fixt::message::{BuildFIXTMessage,FIXTMessageBuildable};
+use fix_rs::message::{Message,MessageDetails};
+use fix_rs::message_version::MessageVersion;
+use fix_rs::sbe::{SBECodec,SBEError,SBEHeader,SBEType,decode_header};
+
+const MAX_MESSAGE_SIZE: u64 = 4096;
+
+//FIX 4.4 NewOrderSingle with a PreAllocGrp repeating group.
+const NEW_ORDER_SINGLE_MESSAGE: &'static [u8] = b"8=FIX.4.4\x019=179\x0135=D\x0149=CLIENT\x0156=SERVER\x0134=2\x0152=20090107-18:15:16.000\x0111=123\x011=ACCT\x0178=2\x0179=A1\x0180=60\x0179=A2\x0180=40\x0121=1\x0155=TEST\x0148=459200101\x0122=1\x0154=1\x0160=20090107-18:15:16.000\x0138=100\x0140=2\x0144=10.5\x0110=192\x01";
+
+define_sbe_schema!(TestSchema: 1, 0);
+
+define_sbe_message!(TestSchema, NewOrderSingle: 14 => {
+    FIELDS {
+        msg_seq_num: MsgSeqNum => SBEType::UInt32,
+        sending_time: SendingTime => SBEType::UTCTimestampNanos,
+        cl_ord_id: ClOrdID => SBEType::CharArray(20),
+        handl_inst: HandlInst => SBEType::Char,
+        symbol: Symbol => SBEType::CharArray(8),
+        security_id: SecurityID => SBEType::CharArray(12),
+        security_id_source: SecurityIDSource => SBEType::Char,
+        side: SideField => SBEType::Char,
+        transact_time: TransactTime => SBEType::UTCTimestampNanos,
+        order_qty: OrderQty => SBEType::Decimal(-4),
+        ord_type: OrdType => SBEType::Char,
+        price: Price => SBEType::Decimal(-4),
+    }
+    GROUPS {
+        allocs: NoAllocs<PreAllocGrp>,
+    }
+    DATA {
+        sender_comp_id: SenderCompID,
+        target_comp_id: TargetCompID,
+        account: Account,
+    }
+});
+
+define_sbe_block!(TestSchema, PreAllocGrp => {
+    FIELDS {
+        alloc_account: AllocAccount => SBEType::CharArray(8),
+        alloc_qty: AllocQty => SBEType::Decimal(-2),
+    }
+    GROUPS {}
+    DATA {}
+});
+
+//Layout of the encoded NEW_ORDER_SINGLE_MESSAGE.
+const GROUP_HEADER_OFFSET: usize = 88;
+const GROUPS_OFFSET: usize = 92;
+const DATA_OFFSET: usize = 124;
+
+fn new_order_single() -> NewOrderSingle {
+    let mut message_dictionary: HashMap<&'static [u8],Box<BuildFIXTMessage + Send>> = HashMap::new();
+    message_dictionary.insert(<NewOrderSingle as MessageDetails>::msg_type(),<NewOrderSingle as Default>::default().builder());
+    let mut parser = Parser::new(message_dictionary,MAX_MESSAGE_SIZE);
+
+    let (_,result) = parser.parse(NEW_ORDER_SINGLE_MESSAGE);
+    assert!(result.is_ok());
+    assert_eq!(parser.messages.len(),1);
+    parser.messages.remove(0).as_any().downcast_ref::<NewOrderSingle>().unwrap().clone()
+}
+
+#[test]
+fn sbe_test() {
+    let message = new_order_single();
+
+    //Header is followed by the fixed-layout block, each repeating group, and then var-data.
+    let mut codec = SBECodec::new();
+    let mut buf = Vec::new();
+    let len = codec.encode::<TestSchema,NewOrderSingle>(&message,&mut buf).unwrap();
+    assert_eq!(len,buf.len());
+    assert_eq!(decode_header(&buf).unwrap(),SBEHeader { block_length: 80, template_id: 14, schema_id: 1, version: 0 });
+    assert_eq!(&buf[8..12],&[2,0,0,0]);
+    assert_eq!(&buf[GROUP_HEADER_OFFSET..GROUPS_OFFSET],&[16,0,2,0]);
+    assert_eq!(&buf[GROUPS_OFFSET..100],b"A1\0\0\0\0\0\0");
+    assert_eq!(&buf[100..102],&[0x70,0x17]);
+    assert_eq!(&buf[DATA_OFFSET..],b"\x06\x00CLIENT\x06\x00SERVER\x04\x00ACCT");
+
+    //Decoding gives back the same message and serializes to the same tag=value message.
+    let (decoded_message,decoded_len) = codec.decode::<TestSchema,NewOrderSingle>(&buf).unwrap();
+    assert_eq!(decoded_len,len);
+    assert!(decoded_message == message);
+
+    let mut expected_bytes = ByteBuffer::new();
+    message.read(FIXVersion::FIX_4_4,MessageVersion::FIX44,&mut expected_bytes);
+    let mut decoded_bytes = ByteBuffer::new();
+    decoded_message.read(FIXVersion::FIX_4_4,MessageVersion::FIX44,&mut decoded_bytes);
+    assert_eq!(decoded_bytes.bytes(),expected_bytes.bytes());
+
+    //Block written by a newer version of the schema is accepted and the extra bytes are skipped.
+    let mut newer_buf = buf.clone();
+    newer_buf[0] = 82;
+    newer_buf[6] = 1;
+    newer_buf.insert(GROUP_HEADER_OFFSET,0xFF);
+    newer_buf.insert(GROUP_HEADER_OFFSET,0xFF);
+    let (decoded_message,decoded_len) = codec.decode::<TestSchema,NewOrderSingle>(&newer_buf).unwrap();
+    assert_eq!(decoded_len,newer_buf.len());
+    assert!(decoded_message == message);
+
+    //Values that don't fit the layout are rejected without writing a partial message.
+    let mut long_message = message.clone();
+    long_message.cl_ord_id = b"123456789012345678901".to_vec();
+    let mut long_buf = b"ABC".to_vec();
+    match codec.encode::<TestSchema,NewOrderSingle>(&long_message,&mut long_buf) {
+        Err(SBEError::InvalidValue(name)) => assert_eq!(name,"ClOrdID"),
+        _ => assert!(false),
+    }
+    assert_eq!(long_buf,b"ABC".to_vec());
+
+    let mut long_message = message.clone();
+    long_message.price = b"10.00001".to_vec();
+    match codec.encode::<TestSchema,NewOrderSingle>(&long_message,&mut long_buf) {
+        Err(SBEError::InvalidValue(name)) => assert_eq!(name,"Price"),
+        _ => assert!(false),
+    }
+
+    //Empty fields are encoded using null values and decoded as empty.
+    let mut empty_message = message.clone();
+    empty_message.symbol = Vec::new();
+    empty_message.account = Vec::new();
+    empty_message.price = Vec::new();
+    buf.clear();
+    codec.encode::<TestSchema,NewOrderSingle>(&empty_message,&mut buf).unwrap();
+    assert_eq!(&buf[41..49],&[0,0,0,0,0,0,0,0]);
+    assert_eq!(&buf[80..88],&[0,0,0,0,0,0,0,0x80]);
+    let (decoded_message,_) = codec.decode::<TestSchema,NewOrderSingle>(&buf).unwrap();
+    assert!(decoded_message == empty_message);
+}
+
+#[test]
+fn sbe_invalid_buffer_test() {
+    let message = new_order_single();
+    let mut codec = SBECodec::new();
+    let mut buf = Vec::new();
+    codec.encode::<TestSchema,NewOrderSingle>(&message,&mut buf).unwrap();
+
+    //Mismatched headers are rejected.
+    let mut wrong_buf = buf.clone();
+    wrong_buf[2] = 15;
+    match codec.decode::<TestSchema,NewOrderSingle>(&wrong_buf) {
+        Err(SBEError::TemplateMismatch(15)) => {},
+        _ => assert!(false),
+    }
+
+    let mut wrong_buf = buf.clone();
+    wrong_buf[4] = 2;
+    match codec.decode::<TestSchema,NewOrderSingle>(&wrong_buf) {
+        Err(SBEError::SchemaMismatch(2)) => {},
+        _ => assert!(false),
+    }
+
+    let mut wrong_buf = buf.clone();
+    wrong_buf[0] = 79;
+    match codec.decode::<TestSchema,NewOrderSingle>(&wrong_buf) {
+        Err(SBEError::BlockTooShort(79)) => {},
+        _ => assert!(false),
+    }
+
+    let mut wrong_buf = buf.clone();
+    wrong_buf[GROUP_HEADER_OFFSET] = 9;
+    match codec.decode::<TestSchema,NewOrderSingle>(&wrong_buf) {
+        Err(SBEError::BlockTooShort(9)) => {},
+        _ => assert!(false),
+    }
+
+    //Truncating the message anywhere is caught: in the header, the block, a group header, a
+    //group, a data header, and a data value.
+    for &len in &[0,7,8,87,GROUP_HEADER_OFFSET,GROUPS_OFFSET - 1,GROUPS_OFFSET,GROUPS_OFFSET + 9,DATA_OFFSET - 1,DATA_OFFSET,DATA_OFFSET + 1,DATA_OFFSET + 7,buf.len() - 1] {
+        match codec.decode::<TestSchema,NewOrderSingle>(&buf[..len]) {
+            Err(SBEError::BufferTooSmall) => {},
+            _ => assert!(false),
+        }
+    }
+
+    //Group counts and data lengths come from the buffer so they can't be trusted.
+    let mut wrong_buf = buf.clone();
+    wrong_buf[GROUP_HEADER_OFFSET + 2] = 0xFF;
+    wrong_buf[GROUP_HEADER_OFFSET + 3] = 0xFF;
+    match codec.decode::<TestSchema,NewOrderSingle>(&wrong_buf[..DATA_OFFSET]) {
+        Err(SBEError::BufferTooSmall) => {},
+        _ => assert!(false),
+    }
+
+    let mut wrong_buf = buf.clone();
+    wrong_buf[DATA_OFFSET] = 0xFF;
+    wrong_buf[DATA_OFFSET + 1] = 0xFF;
+    match codec.decode::<TestSchema,NewOrderSingle>(&wrong_buf) {
+        Err(SBEError::BufferTooSmall) => {},
+        _ => assert!(false),
+    }
+}
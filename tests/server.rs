@@ -26,15 +26,15 @@ use std::time::Duration;
 #[macro_use]
 mod common;
 use common::{CLIENT_SENDER_COMP_ID,CLIENT_TARGET_COMP_ID,TestStream,new_logon_message};
-use fix_rs::dictionary::field_types::other::{MsgDirection,SessionRejectReason};
+use fix_rs::dictionary::field_types::other::{MsgDirection,SessionRejectReason,SessionStatus};
 use fix_rs::dictionary::fields::{MsgTypeGrp,Text};
 use fix_rs::dictionary::messages::{Heartbeat,Logon,Logout,Reject,TestRequest};
 use fix_rs::field::Field;
 use fix_rs::field_tag::{self,FieldTag};
-use fix_rs::fix::ParseError;
+use fix_rs::fix::{ParseError,ValidationConfig};
 use fix_rs::fix_version::FIXVersion;
 use fix_rs::fixt;
-use fix_rs::fixt::engine::{EngineEvent,ConnectionTerminatedReason};
+use fix_rs::fixt::engine::{Engine,EngineEvent,Connection,ConnectionTerminatedReason,Listener,LogonAuthenticationResult,LogonAuthenticator};
 use fix_rs::fixt::message::FIXTMessage;
 use fix_rs::fixt::tests::{AUTO_DISCONNECT_AFTER_NO_LOGON_RECEIVED_SECONDS};
 use fix_rs::message::{self,REQUIRED};
@@ -135,7 +135,7 @@ fn test_default_appl_ver_id() {
         let mut message = new_fixt_message!(FROM_CLIENT TestMessage);
         message.msg_seq_num = 2;
         message.text = b"text".to_vec();
-        engine.send_message(connection,message);
+        engine.send_message(connection,message).unwrap();
 
         //Confirm text field was excluded by engine due to requiring >= FIX50 but default is FIX40.
         let message = test_client.recv_message::<TestMessage>();
@@ -161,7 +161,7 @@ fn test_default_appl_ver_id() {
         test_client.send_message(message);
 
         //Make sure message is considered invalid.
-        engine_poll_event!(engine,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(engine,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::UnknownTag(ref tag) = parse_error { *tag == FieldTag(58) } else { false });
         });
@@ -197,7 +197,7 @@ fn test_appl_ver_id() {
         assert_eq!(message.text,b"ApplVerID must be the 6th tag if specified".to_vec());
 
         //Make sure Engine indicates that it rejected the message.
-        engine_poll_event!(engine,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(engine,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::ApplVerIDNotSixthTag = parse_error { true } else { false });
         });
@@ -231,7 +231,7 @@ fn test_appl_ver_id() {
         assert_eq!(message.session_reject_reason.unwrap(),SessionRejectReason::TagNotDefinedForThisMessageType);
         assert_eq!(message.text,b"Tag not defined for this message type".to_vec());
 
-        engine_poll_event!(engine,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error) => {
+        engine_poll_event!(engine,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
             assert_eq!(msg_connection,connection);
             assert!(if let ParseError::UnexpectedTag(ref tag) = parse_error { *tag == Text::tag()  } else { false });
         });
@@ -496,3 +496,133 @@ fn test_heart_bt_int() {
         let _ = test_client.recv_message::<TestRequest>();
     }
 }
+
+#[test]
+fn test_logon_authenticator() {
+    define_dictionary!(
+        Logon,
+        Logout,
+    );
+
+    struct TestLogonAuthenticator;
+
+    impl LogonAuthenticator for TestLogonAuthenticator {
+        fn authenticate(&mut self,_listener: Listener,_connection: Connection,message: &Logon) -> LogonAuthenticationResult {
+            if message.username != b"user".to_vec() || message.password != b"password".to_vec() {
+                return LogonAuthenticationResult::Reject(SessionStatus::InvalidUsernameOrPassword);
+            }
+            else if !message.new_password.is_empty() {
+                return LogonAuthenticationResult::PasswordChanged;
+            }
+
+            LogonAuthenticationResult::Accept
+        }
+    }
+
+    fn logon_with_password(password: &[u8],new_password: &[u8]) -> (TestStream,Engine,Connection) {
+        let (mut test_client,mut engine,listener,connection) = TestStream::setup_test_client(build_dictionary());
+        engine.set_logon_authenticator(listener,Some(Box::new(TestLogonAuthenticator)));
+
+        //Give engine thread a chance to install the authenticator before the Logon arrives.
+        thread::sleep(Duration::from_millis(100));
+
+        let mut logon_message = new_logon_message();
+        logon_message.sender_comp_id = CLIENT_SENDER_COMP_ID.to_vec();
+        logon_message.target_comp_id = CLIENT_TARGET_COMP_ID.to_vec();
+        logon_message.username = b"user".to_vec();
+        logon_message.password = password.to_vec();
+        logon_message.new_password = new_password.to_vec();
+        test_client.send_message(logon_message);
+
+        (test_client,engine,connection)
+    }
+
+    fn approve_logon(engine: &mut Engine,connection: Connection) {
+        engine_poll_event!(engine,EngineEvent::ConnectionLoggingOn(_,some_connection,logon_message) => {
+            assert_eq!(some_connection,connection);
+
+            let mut response_message = new_fixt_message!(Logon);
+            response_message.encrypt_method = logon_message.encrypt_method.clone();
+            response_message.heart_bt_int = logon_message.heart_bt_int;
+            response_message.default_appl_ver_id = logon_message.default_appl_ver_id;
+            engine.approve_new_connection(connection,Box::new(response_message),None);
+        });
+    }
+
+    //Accepted logons should include SessionStatus in the Logon response.
+    {
+        let (mut test_client,mut engine,connection) = logon_with_password(b"password",b"");
+        approve_logon(&mut engine,connection);
+
+        let message = test_client.recv_message::<Logon>();
+        assert_eq!(message.session_status,Some(SessionStatus::SessionActive));
+    }
+
+    //Logons that change the password should say so in the Logon response.
+    {
+        let (mut test_client,mut engine,connection) = logon_with_password(b"password",b"new_password");
+        approve_logon(&mut engine,connection);
+
+        let message = test_client.recv_message::<Logon>();
+        assert_eq!(message.session_status,Some(SessionStatus::SessionPasswordChanged));
+    }
+
+    //Rejected logons should be responded to with a Logout including the SessionStatus and should
+    //never be passed on to be approved.
+    {
+        let (mut test_client,mut engine,connection) = logon_with_password(b"wrong",b"");
+
+        let message = test_client.recv_message::<Logout>();
+        assert_eq!(message.session_status,Some(SessionStatus::InvalidUsernameOrPassword));
+
+        engine_poll_event!(engine,EngineEvent::ConnectionTerminated(terminated_connection,reason) => {
+            assert_eq!(terminated_connection,connection);
+            assert!(if let ConnectionTerminatedReason::LogonAuthenticationFailedError = reason { true } else { false });
+        });
+
+        assert!(test_client.is_stream_closed(Duration::from_secs(5)));
+    }
+}
+
+#[test]
+fn test_listener_parser_settings() {
+    define_dictionary!(
+        Logon,
+    );
+
+    //Logon with SenderCompID and TargetCompID after MsgSeqNum.
+    let logon_message = b"8=FIXT.1.1\x019=67\x0135=A\x0134=1\x0149=TEST\x0152=20170101-00:00:00.000\x0156=TX\x0198=0\x01108=5\x011137=9\x0110=046\x01";
+
+    //Make sure listener validation config is used when parsing the Logon of accepted
+    //connections.
+    {
+        let (mut test_client,mut engine,listener,connection) = TestStream::setup_test_client_with_listener_setup(FIXVersion::FIXT_1_1,MessageVersion::FIX50SP2,build_dictionary(),|engine,listener| {
+            engine.set_listener_validation_config(listener,ValidationConfig { relax_header_ordering: true,..ValidationConfig::strict() });
+
+            //Give engine thread a chance to apply the config before the connection is accepted.
+            thread::sleep(Duration::from_millis(100));
+        });
+
+        let bytes_written = test_client.stream.write(logon_message).unwrap();
+        assert_eq!(bytes_written,logon_message.len());
+
+        engine_poll_event!(engine,EngineEvent::ConnectionLoggingOn(some_listener,some_connection,logon_message) => {
+            assert_eq!(some_listener,listener);
+            assert_eq!(some_connection,connection);
+            assert_eq!(logon_message.sender_comp_id,CLIENT_SENDER_COMP_ID.to_vec());
+        });
+    }
+
+    //Make sure the default strict validation still applies otherwise.
+    {
+        let (mut test_client,mut engine,_,connection) = TestStream::setup_test_client(build_dictionary());
+
+        let bytes_written = test_client.stream.write(logon_message).unwrap();
+        assert_eq!(bytes_written,logon_message.len());
+
+        engine_poll_event!(engine,EngineEvent::MessageReceivedGarbled(msg_connection,parse_error,_,_,_) => {
+            assert_eq!(msg_connection,connection);
+            assert!(if let ParseError::SenderCompIDNotFourthTag = parse_error { true } else { false });
+        });
+    }
+}